@@ -27,6 +27,16 @@ impl ApplyCommand {
                     .required(false)
                     .help("Do not make the state persistent"),
             )
+            .arg(
+                clap::Arg::new("REVERT_AFTER")
+                    .long("revert-after")
+                    .value_parser(clap::value_parser!(u32))
+                    .required(false)
+                    .help(
+                        "Revert the applied state after specified seconds \
+                        unless confirmed by `nipc commit confirm`",
+                    ),
+            )
             .arg(
                 clap::Arg::new("DIFF")
                     .long("diff")
@@ -54,6 +64,9 @@ impl ApplyCommand {
         if matches.get_flag("MEMORY_ONLY") {
             opt.memory_only = true;
         }
+        if let Some(revert_after) = matches.get_one::<u32>("REVERT_AFTER") {
+            opt.revert_after = *revert_after;
+        }
         conn.apply_net_state(state.clone(), opt).await?;
         println!("{}", serde_yaml::to_string(&state)?);
         Ok(())
@@ -92,6 +92,12 @@ impl CommitCommand {
                             .long("full")
                             .action(clap::ArgAction::SetTrue)
                             .help("Show all information of commit"),
+                    )
+                    .arg(
+                        clap::Arg::new("SHOW_SECRETS")
+                            .long("show-secrets")
+                            .action(clap::ArgAction::SetTrue)
+                            .help("Show secrets instead of hiding them"),
                     ),
             )
             .subcommand(
@@ -126,6 +132,14 @@ impl CommitCommand {
                             .help("UUIDs of commit to remove"),
                     ),
             )
+            .subcommand(clap::Command::new("confirm").about(
+                "Confirm the state applied with `--revert-after`, \
+                    so it will not be reverted",
+            ))
+            .subcommand(clap::Command::new("cancel").about(
+                "Revert the state applied with `--revert-after` \
+                    immediately",
+            ))
             .subcommand(
                 clap::Command::new("rollback")
                     .about("Rollback to specified commit")
@@ -157,6 +171,7 @@ impl CommitCommand {
             if let Some(count) = show_matches.get_one::<u32>("COUNT") {
                 opt.count = *count;
             }
+            opt.include_secrets = show_matches.get_flag("SHOW_SECRETS");
             let commits = conn.query_commits(opt).await?;
             let show_full = show_matches.get_flag("FULL");
             if let Some(uuid) = show_matches.get_one::<String>("UUID") {
@@ -204,6 +219,13 @@ impl CommitCommand {
             } else {
                 return Err("UUIDs of commit to remove undefined".into());
             }
+        } else if matches.subcommand_matches("confirm").is_some() {
+            if let Some(commit) = conn.confirm_commit().await? {
+                show_commits([commit].iter(), CommitShowType::Brief)?;
+            }
+        } else if matches.subcommand_matches("cancel").is_some() {
+            let state = conn.cancel_commit().await?;
+            println!("{}", serde_yaml::to_string(&state)?);
         } else if let Some(rollback_matches) =
             matches.subcommand_matches("rollback")
         {
@@ -222,7 +244,9 @@ async fn revert_commit(
     apply_opt: NipartApplyOption,
 ) -> Result<(), CliError> {
     let uuid = NipartUuid::from_str(uuid)?;
-    let opt = NetworkCommitQueryOption::default();
+    let mut opt = NetworkCommitQueryOption::default();
+    // Secrets are required for applying the revert state
+    opt.include_secrets = true;
     let mut conn = NipartConnection::new().await?;
     let commits = conn.query_commits(opt).await?;
     if let Some(commit) = commits.as_slice().iter().find(|c| c.uuid == uuid) {
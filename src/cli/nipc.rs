@@ -6,6 +6,7 @@ mod error;
 mod gen;
 mod show;
 mod state;
+mod wait_online;
 
 use std::str::FromStr;
 
@@ -13,7 +14,7 @@ use nipart::{NipartConnection, NipartEvent, NipartLogLevel};
 
 use crate::{
     apply::ApplyCommand, commit::CommitCommand, error::CliError,
-    gen::GenCommand, show::ShowCommand,
+    gen::GenCommand, show::ShowCommand, wait_online::WaitOnlineCommand,
 };
 
 #[tokio::main]
@@ -79,6 +80,7 @@ async fn main() -> Result<(), CliError> {
                 ),
         )
         .subcommand(CommitCommand::gen_command())
+        .subcommand(WaitOnlineCommand::gen_command())
         .subcommand(
             clap::Command::new("debug")
                 .about(
@@ -124,6 +126,10 @@ async fn main() -> Result<(), CliError> {
         CommitCommand::handle(matches).await?;
     } else if let Some(matches) = matches.subcommand_matches(GenCommand::NAME) {
         GenCommand::handle(matches).await?;
+    } else if let Some(matches) =
+        matches.subcommand_matches(WaitOnlineCommand::NAME)
+    {
+        WaitOnlineCommand::handle(matches).await?;
     } else {
         eprintln!("Error: Invalid argument\n");
         cli_cmd.print_help()?;
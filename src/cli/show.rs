@@ -27,6 +27,12 @@ impl ShowCommand {
                     .action(clap::ArgAction::SetTrue)
                     .help("Show stored state"),
             )
+            .arg(
+                clap::Arg::new("SHOW_SECRETS")
+                    .long("show-secrets")
+                    .action(clap::ArgAction::SetTrue)
+                    .help("Show secrets instead of hiding them"),
+            )
     }
 
     pub(crate) async fn handle(
@@ -39,12 +45,18 @@ impl ShowCommand {
                 .into());
         }
 
+        let include_secrets = matches.get_flag("SHOW_SECRETS");
+
         let net_state = if matches.get_flag("SAVED") {
-            conn.query_net_state(NipartQueryOption::saved()).await?
+            let mut opt = NipartQueryOption::saved();
+            opt.include_secrets = include_secrets;
+            conn.query_net_state(opt).await?
         } else if matches.get_flag("DIFF") {
             Self::get_diff_state(&mut conn).await?
         } else {
-            conn.query_net_state(NipartQueryOption::saved()).await?
+            let mut opt = NipartQueryOption::saved();
+            opt.include_secrets = include_secrets;
+            conn.query_net_state(opt).await?
         };
 
         println!("{}", serde_yaml::to_string(&net_state)?);
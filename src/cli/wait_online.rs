@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io::Read;
+
+use nipart::{NipartConnection, NipartWaitOnlineOption};
+
+use crate::CliError;
+
+pub(crate) struct WaitOnlineCommand;
+
+impl WaitOnlineCommand {
+    pub(crate) const NAME: &str = "wait-online";
+
+    const DEFAULT_CONFIG_PATH: &str = "/etc/nipart/wait-online.yml";
+
+    pub(crate) fn gen_command() -> clap::Command {
+        clap::Command::new("wait-online")
+            .alias("w")
+            .about("Block till network is online")
+            .arg(
+                clap::Arg::new("CONFIG_FILE")
+                    .required(false)
+                    .index(1)
+                    .default_value(Self::DEFAULT_CONFIG_PATH)
+                    .help("Wait online probe config file, `-` for stdin"),
+            )
+    }
+
+    pub(crate) async fn handle(
+        matches: &clap::ArgMatches,
+    ) -> Result<(), CliError> {
+        let file_path = matches
+            .get_one::<String>("CONFIG_FILE")
+            .map(|s| s.as_str())
+            .unwrap_or(Self::DEFAULT_CONFIG_PATH);
+        let opt = wait_online_option_from_file(file_path)?;
+        let mut conn = NipartConnection::new().await?;
+        conn.wait_online(opt).await?;
+        Ok(())
+    }
+}
+
+fn wait_online_option_from_file(
+    file_path: &str,
+) -> Result<NipartWaitOnlineOption, CliError> {
+    let mut content = String::new();
+    if file_path == "-" {
+        std::io::stdin().read_to_string(&mut content)?;
+    } else {
+        std::fs::File::open(file_path)
+            .map_err(|e| format!("Failed to open {file_path}: {e}"))?
+            .read_to_string(&mut content)?;
+    };
+    Ok(serde_yaml::from_str(&content)?)
+}
@@ -193,7 +193,9 @@ async fn send_reply_to_client(
         if let Err(e) = tx.send(event.clone()).await {
             log::warn!("Failed to reply event to user {e}");
         }
-        if event.is_log() {
+        // Log and drift report might be sent multiple times for single
+        // request.
+        if event.is_log() || event.is_drift_report() {
             if let Ok(mut queue) = tracking_queue.lock() {
                 queue.insert(event.uuid, tx);
             }
@@ -1,12 +1,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use nipart::{
-    NipartError, NipartEvent, NipartEventAddress, NipartLogEntry,
-    NipartLogLevel, NipartPluginEvent, NipartRole, NipartUserEvent, NipartUuid,
+    NipartDhcpLease, NipartDriftPolicy, NipartError, NipartEvent,
+    NipartEventAddress, NipartLogEntry, NipartLogLevel, NipartPluginEvent,
+    NipartRole, NipartUserEvent, NipartUuid,
 };
 use tokio::sync::mpsc::{Receiver, Sender};
 
-use super::{WorkFlow, WorkFlowQueue};
+use super::{
+    drift::{gen_drift_report_event, gen_set_drift_policy_reply},
+    revert::{gen_confirm_commit_reply, no_pending_revert_error},
+    WorkFlow, WorkFlowQueue,
+};
 use crate::PluginRoles;
 
 // Check the session queue every 1 second to check whether any workflow expired
@@ -44,6 +49,13 @@ async fn commander_thread(
         WorkFlow::new_daemon_post_start(&plugin_roles, nipart::DEFAULT_TIMEOUT);
     workflow_queue.add_workflow(workflow, share_data);
 
+    register_drift_monitor_rules(
+        &workflow_queue,
+        &plugin_roles,
+        &commander_to_switch,
+    )
+    .await;
+
     let mut workflow_queue_check_interval = tokio::time::interval(
         std::time::Duration::from_millis(WORKFLOW_QUEUE_CHECK_INTERVAL),
     );
@@ -51,6 +63,12 @@ async fn commander_thread(
     loop {
         if let Err(e) = tokio::select! {
             _ = workflow_queue_check_interval.tick() => {
+                check_pending_revert(&mut workflow_queue, &plugin_roles);
+                check_drift(
+                    &mut workflow_queue,
+                    &plugin_roles,
+                    &commander_to_switch,
+                ).await;
                 process_workflow_queue(
                     &mut workflow_queue, &mut commander_to_switch).await
             }
@@ -133,6 +151,13 @@ async fn process_plugin_event(
     commander_to_switch: &mut Sender<NipartEvent>,
     plugin_roles: &PluginRoles,
 ) -> Result<(), NipartError> {
+    if let NipartPluginEvent::GotMonitorEvent(monitor_event) = &event.plugin {
+        if event.uuid == workflow_queue.drift.uuid {
+            log::debug!("Network changed: {monitor_event}");
+            workflow_queue.notify_network_change();
+            return Ok(());
+        }
+    }
     if event.plugin.is_reply() {
         workflow_queue.add_reply(event);
         process_workflow_queue(workflow_queue, commander_to_switch).await
@@ -157,6 +182,30 @@ async fn process_plugin_event(
                 process_workflow_queue(workflow_queue, commander_to_switch)
                     .await?;
             }
+            NipartPluginEvent::LostDhcpLease(lease) => {
+                remove_dhcp_lease(
+                    event.uuid,
+                    *lease,
+                    "lost",
+                    event.timeout,
+                    workflow_queue,
+                    commander_to_switch,
+                    plugin_roles,
+                )
+                .await?;
+            }
+            NipartPluginEvent::ReleasedDhcpLease(lease) => {
+                remove_dhcp_lease(
+                    event.uuid,
+                    *lease,
+                    "released",
+                    event.timeout,
+                    workflow_queue,
+                    commander_to_switch,
+                    plugin_roles,
+                )
+                .await?;
+            }
             _ => {
                 log::error!("Unknown user event {event:?}");
             }
@@ -165,6 +214,29 @@ async fn process_plugin_event(
     }
 }
 
+async fn remove_dhcp_lease(
+    uuid: NipartUuid,
+    lease: NipartDhcpLease,
+    reason: &str,
+    timeout: u32,
+    workflow_queue: &mut WorkFlowQueue,
+    commander_to_switch: &mut Sender<NipartEvent>,
+    plugin_roles: &PluginRoles,
+) -> Result<(), NipartError> {
+    log_to_user(
+        uuid,
+        NipartLogLevel::Info,
+        format!("DHCP lease on {} {reason}: {lease:?}", lease.iface()),
+        commander_to_switch,
+    )
+    .await;
+
+    let (workflow, share_data) =
+        WorkFlow::new_remove_dhcp_lease(uuid, lease, plugin_roles, timeout);
+    workflow_queue.add_workflow(workflow, share_data);
+    process_workflow_queue(workflow_queue, commander_to_switch).await
+}
+
 async fn process_user_event(
     event: NipartEvent,
     workflow_queue: &mut WorkFlowQueue,
@@ -199,6 +271,15 @@ async fn process_user_event(
             event.timeout,
         )?,
         NipartUserEvent::ApplyNetState(des, opt) => {
+            if let Err(e) = workflow_queue.check_apply_allowed(&opt) {
+                let mut reply: NipartEvent = e.into();
+                reply.uuid = event.uuid;
+                send_to_switch(reply, commander_to_switch).await;
+                return Ok(());
+            }
+            if opt.revert_after > 0 {
+                workflow_queue.reserve_revert(event.uuid);
+            }
             WorkFlow::new_apply_net_state(
                 *des,
                 opt,
@@ -223,6 +304,61 @@ async fn process_user_event(
             event.uuid,
             event.timeout,
         ),
+        NipartUserEvent::WaitOnline(opt) => {
+            match WorkFlow::new_wait_online(
+                *opt,
+                event.uuid,
+                plugin_roles,
+                event.timeout,
+            ) {
+                Ok(w) => w,
+                Err(e) => {
+                    let mut reply: NipartEvent = e.into();
+                    reply.uuid = event.uuid;
+                    send_to_switch(reply, commander_to_switch).await;
+                    return Ok(());
+                }
+            }
+        }
+        NipartUserEvent::ConfirmCommit => {
+            let reply = gen_confirm_commit_reply(
+                event.uuid,
+                workflow_queue.take_pending_revert(),
+                event.timeout,
+            );
+            send_to_switch(reply, commander_to_switch).await;
+            return Ok(());
+        }
+        NipartUserEvent::CancelCommit => {
+            if let Some(pending) = workflow_queue.take_pending_revert() {
+                WorkFlow::new_revert_commit(
+                    pending,
+                    plugin_roles,
+                    event.uuid,
+                    event.timeout,
+                    true,
+                )
+            } else {
+                let mut reply: NipartEvent = no_pending_revert_error().into();
+                reply.uuid = event.uuid;
+                send_to_switch(reply, commander_to_switch).await;
+                return Ok(());
+            }
+        }
+        NipartUserEvent::WatchDrift => {
+            workflow_queue.drift.add_watcher(event.uuid);
+            return Ok(());
+        }
+        NipartUserEvent::SetDriftPolicy(policy) => {
+            log::info!("Setting drift policy to {policy}");
+            workflow_queue.drift.policy = policy;
+            send_to_switch(
+                gen_set_drift_policy_reply(event.uuid, event.timeout),
+                commander_to_switch,
+            )
+            .await;
+            return Ok(());
+        }
         _ => {
             log::error!("Unknown user event {event:?}");
             return Ok(());
@@ -232,6 +368,117 @@ async fn process_user_event(
     process_workflow_queue(workflow_queue, commander_to_switch).await
 }
 
+fn check_pending_revert(
+    workflow_queue: &mut WorkFlowQueue,
+    plugin_roles: &PluginRoles,
+) {
+    if let Some(pending) = workflow_queue.take_expired_revert() {
+        log::warn!(
+            "Network state applied by {} is not confirmed before timeout, \
+            reverting",
+            pending.uuid
+        );
+        let (workflow, share_data) = WorkFlow::new_revert_commit(
+            pending,
+            plugin_roles,
+            NipartUuid::new(),
+            nipart::DEFAULT_TIMEOUT,
+            false,
+        );
+        workflow_queue.add_workflow(workflow, share_data);
+    }
+}
+
+async fn register_drift_monitor_rules(
+    workflow_queue: &WorkFlowQueue,
+    plugin_roles: &PluginRoles,
+    sender: &Sender<NipartEvent>,
+) {
+    // OVSDB changes are monitored by OVS plugin
+    if plugin_roles.get_plugin_count(NipartRole::Ovs) > 0 {
+        let event = NipartEvent::new_with_uuid(
+            workflow_queue.drift.uuid,
+            NipartUserEvent::None,
+            NipartPluginEvent::RegisterMonitorRule(Box::new(
+                workflow_queue.drift.gen_ovsdb_monitor_rule(),
+            )),
+            NipartEventAddress::Commander,
+            NipartEventAddress::Group(NipartRole::Ovs),
+            nipart::DEFAULT_TIMEOUT,
+        );
+        send_to_switch(event, sender).await;
+    }
+    if plugin_roles.get_plugin_count(NipartRole::Monitor) == 0 {
+        log::info!("No monitor plugin, kernel drift detection disabled");
+        return;
+    }
+    for rule in workflow_queue.drift.gen_monitor_rules() {
+        let event = NipartEvent::new_with_uuid(
+            workflow_queue.drift.uuid,
+            NipartUserEvent::None,
+            NipartPluginEvent::RegisterMonitorRule(Box::new(rule)),
+            NipartEventAddress::Commander,
+            NipartEventAddress::Group(NipartRole::Monitor),
+            nipart::DEFAULT_TIMEOUT,
+        );
+        send_to_switch(event, sender).await;
+    }
+}
+
+/// Start drift check once network settled down, report drift found by
+/// previous check and revert it if requested by drift policy.
+async fn check_drift(
+    workflow_queue: &mut WorkFlowQueue,
+    plugin_roles: &PluginRoles,
+    sender: &Sender<NipartEvent>,
+) {
+    if let Some(diff) = workflow_queue.take_drift_report() {
+        log::warn!(
+            "Running network state drifted from saved network state: {}",
+            serde_json::to_string(&diff).unwrap_or_default()
+        );
+        for uuid in workflow_queue.drift.watchers() {
+            send_to_switch(gen_drift_report_event(*uuid, &diff), sender).await;
+        }
+        if workflow_queue.drift.policy == NipartDriftPolicy::Revert {
+            log::info!("Reverting network state drift");
+            let (workflow, share_data) = WorkFlow::new_revert_drift(
+                diff,
+                plugin_roles,
+                nipart::DEFAULT_TIMEOUT,
+            );
+            workflow_queue.add_workflow(workflow, share_data);
+        }
+    }
+    if plugin_roles.get_plugin_count(NipartRole::Monitor) > 0 {
+        for plugin_event in workflow_queue.drift.gen_link_monitor_rule_changes()
+        {
+            let event = NipartEvent::new_with_uuid(
+                workflow_queue.drift.uuid,
+                NipartUserEvent::None,
+                plugin_event,
+                NipartEventAddress::Commander,
+                NipartEventAddress::Group(NipartRole::Monitor),
+                nipart::DEFAULT_TIMEOUT,
+            );
+            send_to_switch(event, sender).await;
+        }
+    }
+    if plugin_roles.get_plugin_count(NipartRole::Commit) > 0
+        && workflow_queue.take_due_drift_check()
+    {
+        let (workflow, share_data) =
+            WorkFlow::new_check_drift(plugin_roles, nipart::DEFAULT_TIMEOUT);
+        workflow_queue.add_workflow(workflow, share_data);
+    }
+}
+
+async fn send_to_switch(event: NipartEvent, sender: &Sender<NipartEvent>) {
+    if let Err(e) = sender.send(event).await {
+        log::error!("{e}");
+    }
+}
+
 async fn log_to_user(
     uuid: NipartUuid,
     level: NipartLogLevel,
@@ -127,6 +127,10 @@ fn query_net_commits(
             task.timeout,
         )
     } else {
+        let include_secrets = matches!(
+            &task.kind,
+            TaskKind::QueryCommits(opt) if opt.include_secrets
+        );
         let mut ret_commits: Vec<NetworkCommit> = Vec::new();
         for reply in task.replies.as_slice() {
             if let NipartPluginEvent::QueryCommitsReply(commits) = &reply.plugin
@@ -134,6 +138,11 @@ fn query_net_commits(
                 ret_commits.extend_from_slice(commits.as_slice());
             }
         }
+        if !include_secrets {
+            for commit in ret_commits.iter_mut() {
+                commit.hide_secrets();
+            }
+        }
         NipartEvent::new_with_uuid(
             task.uuid,
             NipartUserEvent::QueryCommitsReply(Box::new(ret_commits)),
@@ -148,7 +157,7 @@ fn query_net_commits(
 
 fn query_net_state_from_commits(
     task: &Task,
-    _share_data: &mut WorkFlowShareData,
+    share_data: &mut WorkFlowShareData,
 ) -> Result<Vec<NipartEvent>, NipartError> {
     let event = if task.replies.is_empty() {
         NipartEvent::new_with_uuid(
@@ -173,6 +182,7 @@ fn query_net_state_from_commits(
                 }
             }
         }
+        share_data.hide_secrets_if_required(&mut net_state);
         NipartEvent::new_with_uuid(
             task.uuid,
             NipartUserEvent::QueryNetStateReply(Box::new(net_state)),
@@ -187,7 +197,7 @@ fn query_net_state_from_commits(
 
 fn handle_query_last_commit_state_reply(
     task: &Task,
-    _share_data: &mut WorkFlowShareData,
+    share_data: &mut WorkFlowShareData,
 ) -> Result<Vec<NipartEvent>, NipartError> {
     let mut net_state = NetworkState::default();
     for reply in task.replies.as_slice() {
@@ -197,6 +207,7 @@ fn handle_query_last_commit_state_reply(
             net_state.update_state(sub_state);
         }
     }
+    share_data.hide_secrets_if_required(&mut net_state);
     Ok(vec![NipartEvent::new_with_uuid(
         task.uuid,
         NipartUserEvent::QueryNetStateReply(Box::new(net_state)),
@@ -16,17 +16,59 @@ impl WorkFlow {
         timeout: u32,
     ) -> (Self, WorkFlowShareData) {
         let plugin_count = plugins.get_plugin_count(NipartRole::ApplyDhcpLease);
-        let tasks = vec![Task::new(
+        let dns_plugin_count = plugins.get_plugin_count(NipartRole::Dns);
+        let has_dns = lease.has_dns();
+        let mut tasks = vec![Task::new(
             uuid,
-            TaskKind::ApplyDhcpLease(lease),
+            TaskKind::ApplyDhcpLease(lease.clone()),
             plugin_count,
             timeout,
             None,
         )];
+        if has_dns && dns_plugin_count > 0 {
+            tasks.push(Task::new(
+                uuid,
+                TaskKind::ApplyDhcpDns(lease),
+                dns_plugin_count,
+                timeout,
+                None,
+            ));
+        }
         let share_data = WorkFlowShareData::default();
 
         (WorkFlow::new("apply_dhcp_lease", uuid, tasks), share_data)
     }
+
+    /// Remove address, routes and DNS of lost or released DHCP lease.
+    pub(crate) fn new_remove_dhcp_lease(
+        uuid: NipartUuid,
+        lease: NipartDhcpLease,
+        plugins: &PluginRoles,
+        timeout: u32,
+    ) -> (Self, WorkFlowShareData) {
+        let plugin_count = plugins.get_plugin_count(NipartRole::ApplyDhcpLease);
+        let dns_plugin_count = plugins.get_plugin_count(NipartRole::Dns);
+        let has_dns = lease.has_dns();
+        let mut tasks = vec![Task::new(
+            uuid,
+            TaskKind::RemoveDhcpLease(lease.clone()),
+            plugin_count,
+            timeout,
+            None,
+        )];
+        if has_dns && dns_plugin_count > 0 {
+            tasks.push(Task::new(
+                uuid,
+                TaskKind::RemoveDhcpDns(lease),
+                dns_plugin_count,
+                timeout,
+                None,
+            ));
+        }
+        let share_data = WorkFlowShareData::default();
+
+        (WorkFlow::new("remove_dhcp_lease", uuid, tasks), share_data)
+    }
 }
 
 impl Task {
@@ -43,4 +85,48 @@ impl Task {
             self.timeout,
         )
     }
+
+    /// Hand DHCP lease to plugins holding [NipartRole::Dns] for DNS resolver
+    /// config.
+    pub(crate) fn gen_apply_dhcp_dns(
+        &self,
+        lease: NipartDhcpLease,
+    ) -> NipartEvent {
+        NipartEvent::new_with_uuid(
+            self.uuid,
+            NipartUserEvent::None,
+            NipartPluginEvent::ApplyDhcpLease(Box::new(lease)),
+            NipartEventAddress::Commander,
+            NipartEventAddress::Group(NipartRole::Dns),
+            self.timeout,
+        )
+    }
+
+    pub(crate) fn gen_remove_dhcp_lease(
+        &self,
+        lease: NipartDhcpLease,
+    ) -> NipartEvent {
+        NipartEvent::new_with_uuid(
+            self.uuid,
+            NipartUserEvent::None,
+            NipartPluginEvent::RemoveDhcpLease(Box::new(lease)),
+            NipartEventAddress::Commander,
+            NipartEventAddress::Group(NipartRole::ApplyDhcpLease),
+            self.timeout,
+        )
+    }
+
+    pub(crate) fn gen_remove_dhcp_dns(
+        &self,
+        lease: NipartDhcpLease,
+    ) -> NipartEvent {
+        NipartEvent::new_with_uuid(
+            self.uuid,
+            NipartUserEvent::None,
+            NipartPluginEvent::RemoveDhcpLease(Box::new(lease)),
+            NipartEventAddress::Commander,
+            NipartEventAddress::Group(NipartRole::Dns),
+            self.timeout,
+        )
+    }
 }
@@ -0,0 +1,387 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use nipart::{
+    ErrorKind, NetworkState, NipartAddressMonitorKind,
+    NipartAddressMonitorRule, NipartApplyOption, NipartDriftPolicy,
+    NipartError, NipartEvent, NipartEventAddress, NipartLinkMonitorKind,
+    NipartLinkMonitorRule, NipartMonitorRule, NipartOvsdbMonitorRule,
+    NipartPluginEvent, NipartRole, NipartRouteMonitorKind,
+    NipartRouteMonitorRule, NipartRouteRuleMonitorRule, NipartUserEvent,
+    NipartUuid, RouteEntry, RouteRuleEntry,
+};
+
+use super::{
+    state::{gen_apply_net_state_tasks, get_state_from_replies},
+    Task, TaskKind, WorkFlow, WorkFlowQueue, WorkFlowShareData,
+};
+use crate::PluginRoles;
+
+/// Only check drift after no network change noticed in this period, so we
+/// will not track intermediate states.
+const DRIFT_DEBOUNCE: Duration = Duration::from_secs(10);
+
+const CHECK_DRIFT_WORKFLOW_KIND: &str = "check_drift";
+
+/// Tracking out-of-band network changes noticed by monitor plugins.
+#[derive(Debug, Clone)]
+pub(crate) struct DriftTracker {
+    /// UUID of monitor rules registered for drift detection
+    pub(crate) uuid: NipartUuid,
+    pub(crate) policy: NipartDriftPolicy,
+    /// UUIDs of `WatchDrift` requests.
+    /// The API listener will discard report to disconnected user.
+    watchers: HashSet<NipartUuid>,
+    /// Time of last network change
+    last_change: Option<Instant>,
+    /// Whether check drift workflow is running
+    checking: bool,
+    /// Drift found by check drift workflow, waiting to be reported
+    report: Option<NetworkState>,
+    /// Interfaces in saved network state
+    managed_ifaces: HashSet<String>,
+    /// Interfaces holding link monitor rule for drift detection
+    monitored_ifaces: HashSet<String>,
+}
+
+impl Default for DriftTracker {
+    fn default() -> Self {
+        Self {
+            uuid: NipartUuid::new(),
+            policy: NipartDriftPolicy::default(),
+            watchers: HashSet::new(),
+            // Check drift once daemon started, which also find out managed
+            // interfaces for link monitor rules.
+            last_change: Some(Instant::now()),
+            checking: false,
+            report: None,
+            managed_ifaces: HashSet::new(),
+            monitored_ifaces: HashSet::new(),
+        }
+    }
+}
+
+impl DriftTracker {
+    pub(crate) fn add_watcher(&mut self, uuid: NipartUuid) {
+        self.watchers.insert(uuid);
+    }
+
+    pub(crate) fn watchers(&self) -> impl Iterator<Item = &NipartUuid> {
+        self.watchers.iter()
+    }
+
+    /// Generate monitor rules notifying commander on any route, route rule
+    /// or IP address removal. IP address adding is noticed by the prefix
+    /// route created by kernel. Link changes of managed interfaces are
+    /// monitored by rules from [DriftTracker::gen_link_monitor_rule_changes].
+    pub(crate) fn gen_monitor_rules(&self) -> Vec<NipartMonitorRule> {
+        let mut ret = Vec::new();
+        for kind in
+            [NipartRouteMonitorKind::Add, NipartRouteMonitorKind::Remove]
+        {
+            ret.push(NipartMonitorRule::Route(NipartRouteMonitorRule::new(
+                kind,
+                NipartEventAddress::Commander,
+                self.uuid,
+                RouteEntry::new(),
+            )));
+            ret.push(NipartMonitorRule::RouteRule(
+                NipartRouteRuleMonitorRule::new(
+                    kind,
+                    NipartEventAddress::Commander,
+                    self.uuid,
+                    RouteRuleEntry::new(),
+                ),
+            ));
+        }
+        ret.push(NipartMonitorRule::Address(NipartAddressMonitorRule::new(
+            NipartAddressMonitorKind::Remove,
+            NipartEventAddress::Commander,
+            self.uuid,
+        )));
+        ret
+    }
+
+    /// Generate register monitor rule events for newly managed interfaces
+    /// and remove monitor rule events for interfaces no longer managed.
+    pub(crate) fn gen_link_monitor_rule_changes(
+        &mut self,
+    ) -> Vec<NipartPluginEvent> {
+        let mut ret = Vec::new();
+        for iface in self.monitored_ifaces.difference(&self.managed_ifaces) {
+            log::debug!("Stop monitoring link change of {iface} for drift");
+            ret.push(NipartPluginEvent::RemoveMonitorRule(Box::new(
+                self.gen_link_monitor_rule(iface),
+            )));
+        }
+        for iface in self.managed_ifaces.difference(&self.monitored_ifaces) {
+            log::debug!("Start monitoring link change of {iface} for drift");
+            ret.push(NipartPluginEvent::RegisterMonitorRule(Box::new(
+                self.gen_link_monitor_rule(iface),
+            )));
+        }
+        self.monitored_ifaces.clone_from(&self.managed_ifaces);
+        ret
+    }
+
+    fn gen_link_monitor_rule(&self, iface: &str) -> NipartMonitorRule {
+        NipartMonitorRule::Link(NipartLinkMonitorRule::new(
+            NipartLinkMonitorKind::Change,
+            NipartEventAddress::Commander,
+            self.uuid,
+            iface.to_string(),
+        ))
+    }
+
+    /// Replace managed interfaces with kernel interfaces in saved state.
+    fn set_managed_ifaces(&mut self, saved_state: &NetworkState) {
+        self.managed_ifaces = saved_state
+            .interfaces
+            .iter()
+            .filter(|i| !i.is_absent() && !i.is_userspace())
+            .map(|i| i.name().to_string())
+            .collect();
+    }
+
+    /// Update managed interfaces with applied state.
+    fn update_managed_ifaces(&mut self, applied_state: &NetworkState) {
+        for iface in applied_state
+            .interfaces
+            .iter()
+            .filter(|i| !i.is_userspace())
+        {
+            if iface.is_absent() {
+                self.managed_ifaces.remove(iface.name());
+            } else {
+                self.managed_ifaces.insert(iface.name().to_string());
+            }
+        }
+    }
+
+    /// Generate monitor rule notifying commander on any change of OVS
+    /// bridges, ports, interfaces and OVSDB global config.
+    pub(crate) fn gen_ovsdb_monitor_rule(&self) -> NipartMonitorRule {
+        NipartMonitorRule::Ovsdb(NipartOvsdbMonitorRule::new(
+            NipartEventAddress::Commander,
+            self.uuid,
+        ))
+    }
+}
+
+impl WorkFlowQueue {
+    /// Whether any workflow is changing network state.
+    pub(crate) fn is_applying(&self) -> bool {
+        self.workflows.values().any(|w| {
+            w.tasks.iter().any(|t| {
+                matches!(
+                    t.kind,
+                    TaskKind::ApplyNetState(_)
+                        | TaskKind::ApplyDhcpConfig
+                        | TaskKind::ApplyDhcpLease(_)
+                        | TaskKind::RemoveDhcpLease(_)
+                )
+            })
+        })
+    }
+
+    /// Record network change noticed by monitor plugin. Changes happened
+    /// during applying are ignored as they are made by ourselves.
+    pub(crate) fn notify_network_change(&mut self) {
+        if self.is_applying() {
+            log::trace!("Ignoring network change during applying");
+        } else {
+            self.drift.last_change = Some(Instant::now());
+        }
+    }
+
+    /// Return true if no network change happened in [DRIFT_DEBOUNCE] since
+    /// last change and no check drift workflow is running.
+    pub(crate) fn take_due_drift_check(&mut self) -> bool {
+        if self.drift.checking || self.is_applying() {
+            return false;
+        }
+        if self
+            .drift
+            .last_change
+            .map(|t| t.elapsed() >= DRIFT_DEBOUNCE)
+            == Some(true)
+        {
+            self.drift.last_change = None;
+            self.drift.checking = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn take_drift_report(&mut self) -> Option<NetworkState> {
+        self.drift.report.take()
+    }
+
+    /// Invoked when workflow removed from queue.
+    pub(crate) fn drift_workflow_finished(
+        &mut self,
+        workflow: &WorkFlow,
+        share_data: Option<&WorkFlowShareData>,
+    ) {
+        if workflow.kind == CHECK_DRIFT_WORKFLOW_KIND {
+            self.drift.checking = false;
+            if workflow.is_done() {
+                self.drift.report =
+                    share_data.and_then(|s| s.drift_report.clone());
+                if let Some(saved_state) =
+                    share_data.and_then(|s| s.desired_state.as_ref())
+                {
+                    self.drift.set_managed_ifaces(saved_state);
+                }
+            }
+        } else if workflow.is_done() {
+            // Only workflows applying network state hold apply option
+            if let Some(applied_state) = share_data
+                .filter(|s| s.apply_option.is_some())
+                .and_then(|s| s.desired_state.as_ref())
+            {
+                self.drift.update_managed_ifaces(applied_state);
+            }
+        }
+    }
+}
+
+impl WorkFlow {
+    /// Compare saved network state with running network state.
+    pub(crate) fn new_check_drift(
+        plugins: &PluginRoles,
+        timeout: u32,
+    ) -> (Self, WorkFlowShareData) {
+        let uuid = NipartUuid::new();
+        let tasks = vec![
+            Task::new(
+                uuid,
+                TaskKind::QueryCommits(Default::default()),
+                plugins.get_plugin_count(NipartRole::Commit),
+                timeout,
+                Some(store_saved_state),
+            ),
+            Task::new(
+                uuid,
+                TaskKind::QueryNetState(Default::default()),
+                plugins.get_plugin_count(NipartRole::QueryAndApply)
+                    + plugins.get_plugin_count(NipartRole::Dhcp),
+                timeout,
+                Some(gen_drift_report),
+            ),
+        ];
+        (
+            WorkFlow::new(CHECK_DRIFT_WORKFLOW_KIND, uuid, tasks),
+            WorkFlowShareData::default(),
+        )
+    }
+
+    /// Re-apply the saved network state which drifted.
+    pub(crate) fn new_revert_drift(
+        diff: NetworkState,
+        plugins: &PluginRoles,
+        timeout: u32,
+    ) -> (Self, WorkFlowShareData) {
+        let uuid = NipartUuid::new();
+        let mut apply_opt = NipartApplyOption::default();
+        apply_opt.memory_only = true;
+
+        let mut tasks =
+            gen_apply_net_state_tasks(&apply_opt, uuid, plugins, timeout);
+        tasks.push(Task::new(
+            uuid,
+            TaskKind::Callback,
+            0,
+            timeout,
+            Some(log_drift_reverted),
+        ));
+
+        let share_data = WorkFlowShareData {
+            desired_state: Some(diff),
+            apply_option: Some(apply_opt),
+            ..Default::default()
+        };
+        (WorkFlow::new("revert_drift", uuid, tasks), share_data)
+    }
+}
+
+fn store_saved_state(
+    task: &Task,
+    share_data: &mut WorkFlowShareData,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    let mut saved_state = NetworkState::default();
+    for reply in task.replies.as_slice() {
+        if let NipartPluginEvent::QueryCommitsReply(commits) = &reply.plugin {
+            for commit in commits.as_slice() {
+                saved_state.update_state(&commit.desired_state);
+            }
+        }
+    }
+    share_data.desired_state = Some(saved_state);
+    Ok(Vec::new())
+}
+
+fn gen_drift_report(
+    task: &Task,
+    share_data: &mut WorkFlowShareData,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    let saved_state = if let Some(s) = share_data.desired_state.as_ref() {
+        s
+    } else {
+        return Err(NipartError::new(
+            ErrorKind::Bug,
+            format!(
+                "gen_drift_report(): Got None for desired_state in \
+                share data {share_data:?}",
+            ),
+        ));
+    };
+    let running_state = get_state_from_replies(task.replies.as_slice());
+    let mut diff = saved_state.gen_diff(&running_state)?;
+    diff.description = String::new();
+    if diff.is_empty() {
+        log::debug!("Running network state matches saved network state");
+    } else {
+        share_data.drift_report = Some(diff);
+    }
+    Ok(Vec::new())
+}
+
+fn log_drift_reverted(
+    task: &Task,
+    _share_data: &mut WorkFlowShareData,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    log::info!("Reverted network state drift in workflow {}", task.uuid);
+    Ok(Vec::new())
+}
+
+pub(crate) fn gen_drift_report_event(
+    uuid: NipartUuid,
+    diff: &NetworkState,
+) -> NipartEvent {
+    NipartEvent::new_with_uuid(
+        uuid,
+        NipartUserEvent::DriftReport(Box::new(diff.clone())),
+        NipartPluginEvent::None,
+        NipartEventAddress::Daemon,
+        NipartEventAddress::User,
+        nipart::DEFAULT_TIMEOUT,
+    )
+}
+
+pub(crate) fn gen_set_drift_policy_reply(
+    uuid: NipartUuid,
+    timeout: u32,
+) -> NipartEvent {
+    NipartEvent::new_with_uuid(
+        uuid,
+        NipartUserEvent::SetDriftPolicyReply,
+        NipartPluginEvent::None,
+        NipartEventAddress::Daemon,
+        NipartEventAddress::User,
+        timeout,
+    )
+}
@@ -3,13 +3,18 @@
 mod commander_thread;
 mod commit;
 mod dhcp;
+mod drift;
 mod log_level;
 mod plugin;
 mod post_start;
+mod revert;
 mod state;
 mod task;
+mod wait_online;
 mod workflow;
 
 pub(crate) use self::commander_thread::start_commander_thread;
+pub(crate) use self::drift::DriftTracker;
+pub(crate) use self::revert::PendingRevert;
 pub(crate) use self::task::{Task, TaskKind};
 pub(crate) use self::workflow::{WorkFlow, WorkFlowQueue, WorkFlowShareData};
@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::SystemTime;
+
+use nipart::{
+    ErrorKind, NetworkCommit, NipartApplyOption, NipartError, NipartEvent,
+    NipartEventAddress, NipartPluginEvent, NipartRole, NipartUserEvent,
+    NipartUuid,
+};
+
+use super::{
+    state::{gen_apply_net_state_tasks, APPLY_NET_STATE},
+    Task, TaskKind, WorkFlow, WorkFlowQueue, WorkFlowShareData,
+};
+use crate::PluginRoles;
+
+/// Applied network state waiting for user confirmation, will be reverted
+/// once deadline reached.
+#[derive(Debug, Clone)]
+pub(crate) struct PendingRevert {
+    /// UUID of the ApplyNetState event
+    pub(crate) uuid: NipartUuid,
+    pub(crate) commit: NetworkCommit,
+    /// Whether commit is stored by plugins or not.
+    pub(crate) memory_only: bool,
+    pub(crate) deadline: SystemTime,
+}
+
+impl PendingRevert {
+    pub(crate) fn is_expired(&self) -> bool {
+        SystemTime::now() >= self.deadline
+    }
+}
+
+impl WorkFlowQueue {
+    /// Any apply is rejected when a network state applied with
+    /// `revert_after` is in progress or waiting for confirmation, as the
+    /// revert would wipe it. An apply with `revert_after` is also rejected
+    /// when other apply is still in progress.
+    pub(crate) fn check_apply_allowed(
+        &self,
+        opt: &NipartApplyOption,
+    ) -> Result<(), NipartError> {
+        if self.pending_revert.is_some() || self.revert_apply_uuid.is_some() {
+            return Err(NipartError::new(
+                ErrorKind::InvalidArgument,
+                "Previous network state applied with revert_after is \
+                still waiting for confirmation"
+                    .into(),
+            ));
+        }
+        if opt.revert_after > 0
+            && self.workflows.values().any(|w| w.kind == APPLY_NET_STATE)
+        {
+            return Err(NipartError::new(
+                ErrorKind::InvalidArgument,
+                "Cannot apply with revert_after when other network state \
+                apply is still in progress"
+                    .into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reserve the pending revert slot for specified ApplyNetState workflow
+    pub(crate) fn reserve_revert(&mut self, uuid: NipartUuid) {
+        self.revert_apply_uuid = Some(uuid);
+    }
+
+    /// Store the pending revert of finished workflow. Refuse to overwrite
+    /// existing pending revert or revert not reserved by
+    /// [WorkFlowQueue::reserve_revert()].
+    pub(crate) fn install_pending_revert(
+        &mut self,
+        pending: PendingRevert,
+    ) -> Result<(), NipartError> {
+        if let Some(existing) = self.pending_revert.as_ref() {
+            return Err(NipartError::new(
+                ErrorKind::Bug,
+                format!(
+                    "Network state applied by {} is already waiting for \
+                    confirmation, cannot install pending revert of {}",
+                    existing.uuid, pending.uuid
+                ),
+            ));
+        }
+        if self.revert_apply_uuid != Some(pending.uuid) {
+            return Err(NipartError::new(
+                ErrorKind::Bug,
+                format!(
+                    "Pending revert of {} is not reserved, reserved is {:?}",
+                    pending.uuid, self.revert_apply_uuid
+                ),
+            ));
+        }
+        self.pending_revert = Some(pending);
+        Ok(())
+    }
+
+    /// Release the reservation once its workflow finished, failed or expired.
+    pub(crate) fn release_revert_reservation(&mut self, uuid: NipartUuid) {
+        if self.revert_apply_uuid == Some(uuid) {
+            self.revert_apply_uuid = None;
+        }
+    }
+
+    pub(crate) fn take_pending_revert(&mut self) -> Option<PendingRevert> {
+        self.pending_revert.take()
+    }
+
+    pub(crate) fn take_expired_revert(&mut self) -> Option<PendingRevert> {
+        if self.pending_revert.as_ref().map(|p| p.is_expired()) == Some(true) {
+            self.pending_revert.take()
+        } else {
+            None
+        }
+    }
+}
+
+impl WorkFlow {
+    /// Apply the revert state of pending commit and remove that commit.
+    /// When `reply_to_user` is false, the result is only logged as nobody is
+    /// waiting for it.
+    pub(crate) fn new_revert_commit(
+        pending: PendingRevert,
+        plugin_roles: &PluginRoles,
+        uuid: NipartUuid,
+        timeout: u32,
+        reply_to_user: bool,
+    ) -> (Self, WorkFlowShareData) {
+        let mut apply_opt = NipartApplyOption::default();
+        apply_opt.memory_only = true;
+
+        let mut tasks =
+            gen_apply_net_state_tasks(&apply_opt, uuid, plugin_roles, timeout);
+
+        if !pending.memory_only {
+            tasks.push(Task::new(
+                uuid,
+                TaskKind::RemoveCommits(vec![pending.commit.uuid]),
+                plugin_roles.get_plugin_count(NipartRole::Commit),
+                timeout,
+                None,
+            ));
+        }
+
+        tasks.push(Task::new(
+            uuid,
+            TaskKind::Callback,
+            0,
+            timeout,
+            Some(if reply_to_user {
+                reply_cancel_commit
+            } else {
+                log_auto_revert
+            }),
+        ));
+
+        let share_data = WorkFlowShareData {
+            desired_state: Some(pending.commit.revert_state),
+            apply_option: Some(apply_opt),
+            ..Default::default()
+        };
+        (WorkFlow::new("revert_commit", uuid, tasks), share_data)
+    }
+}
+
+/// Store the applied commit as pending revert, will be moved to
+/// [WorkFlowQueue] once workflow finished.
+pub(crate) fn schedule_revert(
+    task: &Task,
+    share_data: &mut WorkFlowShareData,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    let (revert_after, memory_only) = match share_data.apply_option.as_ref() {
+        Some(opt) => (opt.revert_after, opt.memory_only),
+        None => {
+            return Err(NipartError::new(
+                ErrorKind::Bug,
+                format!(
+                    "schedule_revert(): Got None for apply_option in \
+                    share data {share_data:?}",
+                ),
+            ));
+        }
+    };
+    let commit = match share_data.commit.as_ref() {
+        Some(c) => c.clone(),
+        None => {
+            return Err(NipartError::new(
+                ErrorKind::Bug,
+                format!(
+                    "schedule_revert(): Got None for commit in \
+                    share data {share_data:?}",
+                ),
+            ));
+        }
+    };
+    let deadline = SystemTime::now()
+        .checked_add(std::time::Duration::from_secs(revert_after.into()))
+        .ok_or_else(|| {
+            NipartError::new(
+                ErrorKind::InvalidArgument,
+                format!("revert_after {revert_after} is too large"),
+            )
+        })?;
+    log::info!(
+        "Network state applied by {} will be reverted in {revert_after} \
+        seconds unless confirmed",
+        task.uuid
+    );
+    share_data.pending_revert = Some(PendingRevert {
+        uuid: task.uuid,
+        commit,
+        memory_only,
+        deadline,
+    });
+    Ok(Vec::new())
+}
+
+fn reply_cancel_commit(
+    task: &Task,
+    share_data: &mut WorkFlowShareData,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    Ok(vec![NipartEvent::new_with_uuid(
+        task.uuid,
+        NipartUserEvent::CancelCommitReply(Box::new(
+            share_data.post_apply_state.clone().unwrap_or_default(),
+        )),
+        NipartPluginEvent::None,
+        NipartEventAddress::Daemon,
+        NipartEventAddress::User,
+        task.timeout,
+    )])
+}
+
+fn log_auto_revert(
+    task: &Task,
+    _share_data: &mut WorkFlowShareData,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    log::warn!(
+        "Reverted unconfirmed network state in workflow {}",
+        task.uuid
+    );
+    Ok(Vec::new())
+}
+
+pub(crate) fn gen_confirm_commit_reply(
+    uuid: NipartUuid,
+    pending: Option<PendingRevert>,
+    timeout: u32,
+) -> NipartEvent {
+    let user_event = match pending {
+        Some(pending) => {
+            log::info!(
+                "Network state applied by {} confirmed by user",
+                pending.uuid
+            );
+            NipartUserEvent::ConfirmCommitReply(Box::new(
+                if pending.memory_only {
+                    None
+                } else {
+                    let mut commit = pending.commit;
+                    commit.hide_secrets();
+                    Some(commit)
+                },
+            ))
+        }
+        None => NipartUserEvent::Error(no_pending_revert_error()),
+    };
+    NipartEvent::new_with_uuid(
+        uuid,
+        user_event,
+        NipartPluginEvent::None,
+        NipartEventAddress::Daemon,
+        NipartEventAddress::User,
+        timeout,
+    )
+}
+
+pub(crate) fn no_pending_revert_error() -> NipartError {
+    NipartError::new(
+        ErrorKind::InvalidArgument,
+        "No network state is waiting for confirmation".into(),
+    )
+}
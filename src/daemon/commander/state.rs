@@ -7,12 +7,16 @@ use nipart::{
     NipartRole, NipartStateKind, NipartUserEvent, NipartUuid,
 };
 
-use super::{Task, TaskKind, WorkFlow, WorkFlowShareData};
+use super::{
+    revert::schedule_revert, Task, TaskKind, WorkFlow, WorkFlowShareData,
+};
 use crate::PluginRoles;
 
 const VERIFY_RETRY_COUNT: u32 = 5;
 const VERIFY_RETRY_INTERVAL: u32 = 1000;
 
+pub(crate) const APPLY_NET_STATE: &str = "apply_net_state";
+
 /// Generate tasks for apply netstate, tasks not will send reply to user upon
 /// finish.
 pub(crate) fn gen_apply_net_state_tasks(
@@ -33,10 +37,19 @@ pub(crate) fn gen_apply_net_state_tasks(
             Some(pre_apply_query_related_state),
         ),
         Task::new(uuid, TaskKind::Lock, 1, timeout, None),
+        // DHCP plugin should release lease before interface changed or
+        // removed.
+        Task::new(
+            uuid,
+            TaskKind::ApplyDhcpConfig,
+            plugins.get_plugin_count(NipartRole::Dhcp),
+            timeout,
+            Some(log_reply_error),
+        ),
         Task::new(
             uuid,
             TaskKind::ApplyNetState(opt.clone()),
-            plugin_count,
+            plugins.get_plugin_count(NipartRole::QueryAndApply),
             timeout,
             Some(apply_net_state),
         ),
@@ -72,6 +85,53 @@ pub(crate) fn gen_apply_net_state_tasks(
     tasks
 }
 
+fn gen_apply_sriov_pf_tasks(
+    uuid: NipartUuid,
+    plugins: &PluginRoles,
+    timeout: u32,
+) -> Vec<Task> {
+    let plugin_count = plugins.get_plugin_count(NipartRole::QueryAndApply)
+        + plugins.get_plugin_count(NipartRole::Dhcp);
+    let mut opt = NipartApplyOption::default();
+    opt.memory_only = true;
+    opt.no_verify = true;
+
+    vec![
+        Task::new(
+            uuid,
+            TaskKind::QueryRelatedNetState,
+            plugin_count,
+            timeout,
+            Some(pre_apply_query_related_state),
+        ),
+        Task::new(uuid, TaskKind::Lock, 1, timeout, None),
+        // DHCP plugin should release lease before interface changed or
+        // removed.
+        Task::new(
+            uuid,
+            TaskKind::ApplyDhcpConfig,
+            plugins.get_plugin_count(NipartRole::Dhcp),
+            timeout,
+            Some(log_reply_error),
+        ),
+        Task::new(
+            uuid,
+            TaskKind::ApplyNetState(opt),
+            plugins.get_plugin_count(NipartRole::QueryAndApply),
+            timeout,
+            Some(apply_net_state),
+        ),
+        Task::new(uuid, TaskKind::Unlock, 1, timeout, None),
+        Task::new(
+            uuid,
+            TaskKind::Callback,
+            0,
+            timeout,
+            Some(use_pending_desired_state),
+        ),
+    ]
+}
+
 impl WorkFlow {
     pub(crate) fn new_query_net_state(
         opt: NipartQueryOption,
@@ -83,42 +143,46 @@ impl WorkFlow {
         let plugin_count = plugins.get_plugin_count(NipartRole::QueryAndApply)
             + plugins.get_plugin_count(NipartRole::Dhcp);
 
-        match opt.kind {
+        let (workflow, mut share_data) = match opt.kind {
             NipartStateKind::RunningNetworkState => {
                 let tasks = vec![Task::new(
                     uuid,
-                    TaskKind::QueryNetState(opt),
+                    TaskKind::QueryNetState(opt.clone()),
                     plugin_count,
                     timeout,
                     Some(query_net_state),
                 )];
                 let share_data = WorkFlowShareData::default();
 
-                Ok((WorkFlow::new("query_net_state", uuid, tasks), share_data))
+                (WorkFlow::new("query_net_state", uuid, tasks), share_data)
             }
             NipartStateKind::SavedNetworkState => {
-                Ok(WorkFlow::new_query_net_state_in_commits(
+                WorkFlow::new_query_net_state_in_commits(
                     plugins.get_plugin_count(NipartRole::Commit),
                     uuid,
                     timeout,
-                ))
+                )
             }
             NipartStateKind::PostLastCommitNetworkState => {
-                Ok(WorkFlow::new_query_post_commit_net_state(
+                WorkFlow::new_query_post_commit_net_state(
                     plugins.get_plugin_count(NipartRole::Commit),
                     uuid,
                     timeout,
-                ))
+                )
             }
-            _ => Err(NipartError::new(
-                ErrorKind::Bug,
-                format!(
-                    "BUG: Commander new_query_net_state got \
+            _ => {
+                return Err(NipartError::new(
+                    ErrorKind::Bug,
+                    format!(
+                        "BUG: Commander new_query_net_state got \
                         unexpected NipartStateKind {}",
-                    opt.kind
-                ),
-            )),
-        }
+                        opt.kind
+                    ),
+                ));
+            }
+        };
+        share_data.query_option = Some(opt);
+        Ok((workflow, share_data))
     }
 
     pub(crate) fn new_apply_net_state(
@@ -128,7 +192,38 @@ impl WorkFlow {
         plugins: &PluginRoles,
         timeout: u32,
     ) -> (Self, WorkFlowShareData) {
-        let mut tasks = gen_apply_net_state_tasks(&opt, uuid, plugins, timeout);
+        let mut tasks = Vec::new();
+        let mut share_data = WorkFlowShareData::default();
+
+        // SR-IOV VF interfaces only show up after PF config applied, hence
+        // apply PF config first, so VF interfaces referred in desired state
+        // could be resolved.
+        match des_state.get_sriov_pf_conf_state() {
+            Some(pf_state)
+                if pf_state.interfaces.iter().count()
+                    < des_state.interfaces.iter().count() =>
+            {
+                log::debug!("Applying SR-IOV PF config first");
+                tasks.extend(gen_apply_sriov_pf_tasks(uuid, plugins, timeout));
+                share_data.desired_state = Some(pf_state);
+                share_data.pending_desired_state = Some(des_state);
+            }
+            _ => {
+                share_data.desired_state = Some(des_state);
+            }
+        }
+
+        tasks.extend(gen_apply_net_state_tasks(&opt, uuid, plugins, timeout));
+
+        if opt.revert_after > 0 {
+            tasks.push(Task::new(
+                uuid,
+                TaskKind::Callback,
+                0,
+                timeout,
+                Some(schedule_revert),
+            ));
+        }
 
         tasks.push(Task::new(
             uuid,
@@ -138,18 +233,14 @@ impl WorkFlow {
             Some(reply_net_state_apply),
         ));
 
-        let share_data = WorkFlowShareData {
-            desired_state: Some(des_state),
-            apply_option: Some(opt),
-            ..Default::default()
-        };
-        (WorkFlow::new("apply_net_state", uuid, tasks), share_data)
+        share_data.apply_option = Some(opt);
+        (WorkFlow::new(APPLY_NET_STATE, uuid, tasks), share_data)
     }
 }
 
 fn query_net_state(
     task: &Task,
-    _share_data: &mut WorkFlowShareData,
+    share_data: &mut WorkFlowShareData,
 ) -> Result<Vec<NipartEvent>, NipartError> {
     let event = if task.replies.is_empty() {
         NipartEvent::new_with_uuid(
@@ -164,7 +255,8 @@ fn query_net_state(
             task.timeout,
         )
     } else {
-        let state = get_state_from_replies(task.replies.as_slice());
+        let mut state = get_state_from_replies(task.replies.as_slice());
+        share_data.hide_secrets_if_required(&mut state);
         NipartEvent::new_with_uuid(
             task.uuid,
             NipartUserEvent::QueryNetStateReply(Box::new(state)),
@@ -196,11 +288,39 @@ fn pre_apply_query_related_state(
         MergedNetworkState::new(des_state, cur_state.clone(), false, false)?;
 
     share_data.merged_state = Some(merged_state);
-    share_data.pre_apply_state = Some(cur_state);
+    // Keep the state before SR-IOV PF config applied
+    let pre_apply_state = match share_data.pre_apply_state.take() {
+        Some(pf_pre_apply_state) => {
+            let mut state = cur_state;
+            state.update_state(&pf_pre_apply_state);
+            state
+        }
+        None => cur_state,
+    };
+    share_data.pre_apply_state = Some(pre_apply_state);
 
     Ok(Vec::new())
 }
 
+fn use_pending_desired_state(
+    _task: &Task,
+    share_data: &mut WorkFlowShareData,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    if let Some(des_state) = share_data.pending_desired_state.take() {
+        share_data.desired_state = Some(des_state);
+        share_data.merged_state = None;
+        Ok(Vec::new())
+    } else {
+        Err(NipartError::new(
+            ErrorKind::Bug,
+            format!(
+                "use_pending_desired_state(): Got None for \
+                pending_desired_state in share data {share_data:?}",
+            ),
+        ))
+    }
+}
+
 // Since we have verification process afterwards, here we only log errors
 // from plugins
 fn apply_net_state(
@@ -401,12 +521,35 @@ impl Task {
         ret
     }
 
+    pub(crate) fn gen_request_apply_dhcp_config(
+        &self,
+        share_data: &WorkFlowShareData,
+    ) -> Vec<NipartEvent> {
+        let dhcp_changes = match share_data.merged_state.as_ref() {
+            Some(s) => s.get_dhcp_changes(),
+            None => {
+                log::error!(
+                    "BUG: gen_request_apply_dhcp_config() got None for \
+                    merged_state in share data {share_data:?}"
+                );
+                Vec::new()
+            }
+        };
+        vec![NipartEvent::new_with_uuid(
+            self.uuid,
+            NipartUserEvent::None,
+            NipartPluginEvent::ApplyDhcpConfig(Box::new(dhcp_changes)),
+            NipartEventAddress::Commander,
+            NipartEventAddress::Dhcp,
+            self.timeout,
+        )]
+    }
+
     pub(crate) fn gen_request_apply(
         &self,
         opt: NipartApplyOption,
         share_data: &WorkFlowShareData,
     ) -> Vec<NipartEvent> {
-        let mut ret = Vec::new();
         let merged_state = match share_data.merged_state.as_ref() {
             Some(s) => s.clone(),
             None => {
@@ -417,24 +560,14 @@ impl Task {
                 MergedNetworkState::default()
             }
         };
-        let dhcp_changes = merged_state.get_dhcp_changes();
-        ret.push(NipartEvent::new_with_uuid(
+        vec![NipartEvent::new_with_uuid(
             self.uuid,
             NipartUserEvent::None,
             NipartPluginEvent::ApplyNetState(Box::new(merged_state), opt),
             NipartEventAddress::Commander,
             NipartEventAddress::Group(NipartRole::QueryAndApply),
             self.timeout,
-        ));
-        ret.push(NipartEvent::new_with_uuid(
-            self.uuid,
-            NipartUserEvent::None,
-            NipartPluginEvent::ApplyDhcpConfig(Box::new(dhcp_changes)),
-            NipartEventAddress::Commander,
-            NipartEventAddress::Dhcp,
-            self.timeout,
-        ));
-        ret
+        )]
     }
 
     pub(crate) fn gen_request_lock(
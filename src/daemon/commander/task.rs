@@ -5,6 +5,7 @@ use std::time::SystemTime;
 use nipart::{
     NetworkCommitQueryOption, NipartApplyOption, NipartDhcpLease, NipartError,
     NipartEvent, NipartLogLevel, NipartQueryOption, NipartUuid,
+    NipartWaitOnlineProbe,
 };
 
 use super::WorkFlowShareData;
@@ -133,6 +134,9 @@ impl Task {
             TaskKind::QueryRelatedNetState => {
                 self.gen_request_query_related(share_data)
             }
+            TaskKind::ApplyDhcpConfig => {
+                self.gen_request_apply_dhcp_config(share_data)
+            }
             TaskKind::ApplyNetState(opt) => {
                 self.gen_request_apply(opt.clone(), share_data)
             }
@@ -143,6 +147,15 @@ impl Task {
             TaskKind::ApplyDhcpLease(lease) => {
                 vec![self.gen_apply_dhcp_lease(lease.clone())]
             }
+            TaskKind::ApplyDhcpDns(lease) => {
+                vec![self.gen_apply_dhcp_dns(lease.clone())]
+            }
+            TaskKind::RemoveDhcpLease(lease) => {
+                vec![self.gen_remove_dhcp_lease(lease.clone())]
+            }
+            TaskKind::RemoveDhcpDns(lease) => {
+                vec![self.gen_remove_dhcp_dns(lease.clone())]
+            }
             TaskKind::QueryCommits(opt) => {
                 self.gen_request_query_commits(opt.clone())
             }
@@ -158,6 +171,7 @@ impl Task {
             TaskKind::QueryLastCommitState => {
                 self.gen_request_query_last_commit_state()
             }
+            TaskKind::WaitOnline(probe) => self.gen_request_wait_online(probe),
         };
         if self.retry_count != 0 {
             for event in &mut events {
@@ -189,10 +203,18 @@ pub(crate) enum TaskKind {
     QueryPluginInfo,
     QueryNetState(NipartQueryOption),
     QueryRelatedNetState,
+    /// Apply DHCP config changes of merged state to DHCP plugin
+    ApplyDhcpConfig,
     ApplyNetState(NipartApplyOption),
     QueryLogLevel,
     ChangeLogLevel(NipartLogLevel),
     ApplyDhcpLease(NipartDhcpLease),
+    /// Hand DNS information in DHCP lease to DNS plugin
+    ApplyDhcpDns(NipartDhcpLease),
+    /// Remove lost or released DHCP lease
+    RemoveDhcpLease(NipartDhcpLease),
+    /// Remove DNS information of lost or released DHCP lease
+    RemoveDhcpDns(NipartDhcpLease),
     Quit,
     QueryCommits(NetworkCommitQueryOption),
     RemoveCommits(Vec<NipartUuid>),
@@ -204,6 +226,8 @@ pub(crate) enum TaskKind {
     QueryLastCommitState,
     Lock,
     Unlock,
+    /// Wait specified probe to pass
+    WaitOnline(NipartWaitOnlineProbe),
 }
 
 impl std::fmt::Display for TaskKind {
@@ -215,10 +239,14 @@ impl std::fmt::Display for TaskKind {
                 Self::QueryPluginInfo => "query_plugin_info",
                 Self::QueryNetState(_) => "query_net_state",
                 Self::QueryRelatedNetState => "query_related_net_state",
+                Self::ApplyDhcpConfig => "apply_dhcp_config",
                 Self::ApplyNetState(_) => "apply_state",
                 Self::QueryLogLevel => "query_log_level",
                 Self::ChangeLogLevel(_) => "change_log_level",
                 Self::ApplyDhcpLease(_) => "apply_dhcp_lease",
+                Self::ApplyDhcpDns(_) => "apply_dhcp_dns",
+                Self::RemoveDhcpLease(_) => "remove_dhcp_lease",
+                Self::RemoveDhcpDns(_) => "remove_dhcp_dns",
                 Self::Quit => "quit",
                 Self::QueryCommits(_) => "query_commits",
                 Self::RemoveCommits(_) => "remove_commits",
@@ -228,6 +256,7 @@ impl std::fmt::Display for TaskKind {
                 Self::Unlock => "unlock",
                 Self::Callback => "callback",
                 Self::QueryLastCommitState => "query_last_commit_state",
+                Self::WaitOnline(_) => "wait_online",
             }
         )
     }
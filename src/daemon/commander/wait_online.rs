@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use nipart::{
+    ErrorKind, NipartError, NipartEvent, NipartEventAddress, NipartPluginEvent,
+    NipartRole, NipartUserEvent, NipartUuid, NipartWaitOnlineOption,
+    NipartWaitOnlineProbe,
+};
+
+use super::{Task, TaskKind, WorkFlow, WorkFlowShareData};
+use crate::PluginRoles;
+
+// Extra milliseconds for plugin to reply probe timeout error before task
+// expired.
+const WAIT_ONLINE_TIMEOUT_MARGIN: u32 = 5000;
+
+impl WorkFlow {
+    pub(crate) fn new_wait_online(
+        opt: NipartWaitOnlineOption,
+        uuid: NipartUuid,
+        plugins: &PluginRoles,
+        timeout: u32,
+    ) -> Result<(Self, WorkFlowShareData), NipartError> {
+        let plugin_count = plugins.get_plugin_count(NipartRole::WaitOnline);
+        if plugin_count == 0 && !opt.probes.is_empty() {
+            return Err(NipartError::new(
+                ErrorKind::NotSupportedError,
+                format!("No plugin is holding role {}", NipartRole::WaitOnline),
+            ));
+        }
+        let mut tasks = Vec::new();
+        // Task deadline is calculated on creation, so we accumulate the
+        // timeout of previous probes.
+        let mut deadline: u32 = 0;
+        for probe in opt.probes {
+            deadline = deadline
+                .saturating_add(
+                    probe
+                        .timeout
+                        .saturating_add(probe.post_sleep)
+                        .saturating_mul(1000),
+                )
+                .saturating_add(WAIT_ONLINE_TIMEOUT_MARGIN);
+            tasks.push(Task::new(
+                uuid,
+                TaskKind::WaitOnline(probe),
+                plugin_count,
+                deadline,
+                Some(check_wait_online_reply),
+            ));
+        }
+        tasks.push(Task::new(
+            uuid,
+            TaskKind::Callback,
+            0,
+            deadline.max(timeout),
+            Some(reply_wait_online),
+        ));
+
+        Ok((
+            WorkFlow::new("wait_online", uuid, tasks),
+            WorkFlowShareData::default(),
+        ))
+    }
+}
+
+fn check_wait_online_reply(
+    task: &Task,
+    _share_data: &mut WorkFlowShareData,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    for reply in task.replies.as_slice() {
+        if let NipartUserEvent::Error(e) = &reply.user {
+            return Err(e.clone());
+        }
+    }
+    if let TaskKind::WaitOnline(probe) = &task.kind {
+        log::info!("Wait online probe {probe} passed");
+    }
+    Ok(Vec::new())
+}
+
+fn reply_wait_online(
+    task: &Task,
+    _share_data: &mut WorkFlowShareData,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    Ok(vec![NipartEvent::new_with_uuid(
+        task.uuid,
+        NipartUserEvent::WaitOnlineReply,
+        NipartPluginEvent::None,
+        NipartEventAddress::Daemon,
+        NipartEventAddress::User,
+        task.timeout,
+    )])
+}
+
+impl Task {
+    pub(crate) fn gen_request_wait_online(
+        &self,
+        probe: &NipartWaitOnlineProbe,
+    ) -> Vec<NipartEvent> {
+        vec![NipartEvent::new_with_uuid(
+            self.uuid,
+            NipartUserEvent::None,
+            NipartPluginEvent::WaitOnline(Box::new(probe.clone())),
+            NipartEventAddress::Commander,
+            NipartEventAddress::Group(NipartRole::WaitOnline),
+            self.timeout,
+        )]
+    }
+}
@@ -5,19 +5,39 @@ use std::collections::HashMap;
 use nipart::{
     ErrorKind, MergedNetworkState, NetworkCommit, NetworkState,
     NipartApplyOption, NipartError, NipartEvent, NipartEventAddress,
-    NipartPluginEvent, NipartUserEvent, NipartUuid, DEFAULT_TIMEOUT,
+    NipartPluginEvent, NipartQueryOption, NipartUserEvent, NipartUuid,
+    DEFAULT_TIMEOUT,
 };
 
-use super::Task;
+use super::{DriftTracker, PendingRevert, Task};
 
 #[derive(Debug, Clone, Default)]
 pub(crate) struct WorkFlowShareData {
     pub(crate) apply_option: Option<NipartApplyOption>,
     pub(crate) desired_state: Option<NetworkState>,
+    /// Full desired state waiting SR-IOV PF config to be applied first
+    pub(crate) pending_desired_state: Option<NetworkState>,
     pub(crate) pre_apply_state: Option<NetworkState>,
     pub(crate) merged_state: Option<MergedNetworkState>,
     pub(crate) post_apply_state: Option<NetworkState>,
     pub(crate) commit: Option<NetworkCommit>,
+    pub(crate) pending_revert: Option<PendingRevert>,
+    /// Difference between saved and running network state
+    pub(crate) drift_report: Option<NetworkState>,
+    pub(crate) query_option: Option<NipartQueryOption>,
+}
+
+impl WorkFlowShareData {
+    /// Hide secrets in network state replying to user unless
+    /// [NipartQueryOption::include_secrets] is set.
+    pub(crate) fn hide_secrets_if_required(
+        &self,
+        net_state: &mut NetworkState,
+    ) {
+        if self.query_option.as_ref().map(|o| o.include_secrets) != Some(true) {
+            net_state.hide_secrets();
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -208,6 +228,11 @@ impl WorkFlow {
 pub(crate) struct WorkFlowQueue {
     pub(crate) workflows: HashMap<NipartUuid, WorkFlow>,
     pub(crate) share_data: HashMap<NipartUuid, WorkFlowShareData>,
+    /// Applied state waiting for user confirmation
+    pub(crate) pending_revert: Option<PendingRevert>,
+    /// ApplyNetState workflow with `revert_after` still in progress
+    pub(crate) revert_apply_uuid: Option<NipartUuid>,
+    pub(crate) drift: DriftTracker,
 }
 
 impl WorkFlowQueue {
@@ -217,6 +242,9 @@ impl WorkFlowQueue {
         Self {
             workflows: HashMap::with_capacity(Self::INIT_CAPACITY),
             share_data: HashMap::with_capacity(Self::INIT_CAPACITY),
+            pending_revert: None,
+            revert_apply_uuid: None,
+            drift: DriftTracker::default(),
         }
     }
 
@@ -270,13 +298,25 @@ impl WorkFlowQueue {
             .collect();
 
         for uuid in pending_removal_workflow_uuids {
+            let share_data = self.share_data.remove(&uuid);
             if let Some(workflow) = self.workflows.remove(&uuid) {
+                self.drift_workflow_finished(&workflow, share_data.as_ref());
                 if workflow.is_done() {
                     log::debug!("Workflow {workflow} finished");
+                    if let Some(pending_revert) =
+                        share_data.and_then(|s| s.pending_revert)
+                    {
+                        if let Err(e) =
+                            self.install_pending_revert(pending_revert)
+                        {
+                            log::error!("{e}");
+                        }
+                    }
                 } else if workflow.is_expired() {
                     log::debug!("Workflow {workflow} expired");
                 }
             }
+            self.release_revert_reservation(uuid);
         }
 
         Ok(ret)
@@ -9,8 +9,11 @@ use nipart::{
     NipartUserEvent,
 };
 use nipart_plugin_baize::NipartPluginBaize;
+use nipart_plugin_dns::NipartPluginDns;
+use nipart_plugin_lldp::NipartPluginLldp;
 use nipart_plugin_mozim::NipartPluginMozim;
 use nipart_plugin_nispor::NipartPluginNispor;
+use nipart_plugin_ovs::NipartPluginOvs;
 use nipart_plugin_sima::NipartPluginSima;
 use nipart_plugin_smith::NipartPluginSmith;
 use tokio::sync::mpsc::{Receiver, Sender};
@@ -161,6 +164,9 @@ impl Plugins {
     async fn load_native_plugins(&mut self) -> Result<(), NipartError> {
         self.insert(start_plugin::<NipartPluginNispor>().await?);
         self.insert(start_plugin::<NipartPluginMozim>().await?);
+        self.insert(start_plugin::<NipartPluginOvs>().await?);
+        self.insert(start_plugin::<NipartPluginLldp>().await?);
+        self.insert(start_plugin::<NipartPluginDns>().await?);
         self.insert(start_plugin::<NipartPluginBaize>().await?);
         self.insert(start_plugin::<NipartPluginSima>().await?);
         self.insert(start_plugin::<NipartPluginSmith>().await?);
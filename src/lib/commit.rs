@@ -16,6 +16,8 @@ pub struct NetworkCommitQueryOption {
     /// Include commits with this UUID only, empty Vec means all commits
     /// When not emtpy, the `NetworkCommitQueryOption.count` will be ignored.
     pub uuids: Vec<NipartUuid>,
+    /// Include secrets in commits instead of hiding them
+    pub include_secrets: bool,
 }
 
 impl std::fmt::Display for NetworkCommitQueryOption {
@@ -99,6 +101,13 @@ impl NetworkCommit {
             desired_state,
         }
     }
+
+    /// Replace secret string in desired and revert state with
+    /// `<_password_hid_by_nmstate>`
+    pub fn hide_secrets(&mut self) {
+        self.desired_state.hide_secrets();
+        self.revert_state.hide_secrets();
+    }
 }
 
 impl NipartConnection {
@@ -154,4 +163,50 @@ impl NipartConnection {
             ))
         }
     }
+
+    /// Confirm the pending network state applied with
+    /// `NipartApplyOption.revert_after`, so it will not be reverted.
+    pub async fn confirm_commit(
+        &mut self,
+    ) -> Result<Option<NetworkCommit>, NipartError> {
+        let request = NipartEvent::new(
+            NipartUserEvent::ConfirmCommit,
+            NipartPluginEvent::None,
+            NipartEventAddress::User,
+            NipartEventAddress::Daemon,
+            self.timeout,
+        );
+        self.send(&request).await?;
+        let event = self.recv_reply(request.uuid, self.timeout).await?;
+        if let NipartUserEvent::ConfirmCommitReply(s) = event.user {
+            Ok(*s)
+        } else {
+            Err(NipartError::new(
+                ErrorKind::Bug,
+                format!("Invalid reply {event:?} for ConfirmCommit"),
+            ))
+        }
+    }
+
+    /// Revert the pending network state applied with
+    /// `NipartApplyOption.revert_after` immediately.
+    pub async fn cancel_commit(&mut self) -> Result<NetworkState, NipartError> {
+        let request = NipartEvent::new(
+            NipartUserEvent::CancelCommit,
+            NipartPluginEvent::None,
+            NipartEventAddress::User,
+            NipartEventAddress::Daemon,
+            self.timeout,
+        );
+        self.send(&request).await?;
+        let event = self.recv_reply(request.uuid, self.timeout).await?;
+        if let NipartUserEvent::CancelCommitReply(s) = event.user {
+            Ok(*s)
+        } else {
+            Err(NipartError::new(
+                ErrorKind::Bug,
+                format!("Invalid reply {event:?} for CancelCommit"),
+            ))
+        }
+    }
 }
@@ -67,6 +67,19 @@ pub struct NipartDhcpConfigV4 {
     pub client_id: Option<String>,
     pub enabled: bool,
     pub timeout: u32,
+    /// Whether to include DNS servers and search domains in lease
+    pub auto_dns: bool,
+    /// Whether to include routes(including default gateway) in lease
+    pub auto_routes: bool,
+    /// Whether to include default gateway in lease
+    pub auto_gateway: bool,
+    /// Route table to hold routes retrieved from DHCP server
+    pub auto_table_id: Option<u32>,
+    /// Metric of routes retrieved from DHCP server
+    pub auto_route_metric: Option<u32>,
+    /// Whether to include interface MTU in lease, should be false when MTU
+    /// is defined by user
+    pub auto_mtu: bool,
 }
 
 impl NipartDhcpConfigV4 {
@@ -86,6 +99,12 @@ impl Default for NipartDhcpConfigV4 {
             client_id: None,
             enabled: false,
             timeout: DEFAULT_DHCP_TIMEOUT,
+            auto_dns: true,
+            auto_routes: true,
+            auto_gateway: true,
+            auto_table_id: None,
+            auto_route_metric: None,
+            auto_mtu: true,
         }
     }
 }
@@ -107,6 +126,22 @@ pub enum NipartDhcpLease {
     V6(NipartDhcpLeaseV6),
 }
 
+impl NipartDhcpLease {
+    pub fn iface(&self) -> &str {
+        match self {
+            Self::V4(l) => l.iface.as_str(),
+            Self::V6(l) => l.iface.as_str(),
+        }
+    }
+
+    pub fn has_dns(&self) -> bool {
+        match self {
+            Self::V4(l) => l.has_dns(),
+            Self::V6(_) => false,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct NipartDhcpLeaseV4 {
@@ -115,6 +150,50 @@ pub struct NipartDhcpLeaseV4 {
     pub prefix_length: u8,
     pub server_ip: Ipv4Addr,
     pub lease_time: u32,
+    pub gateways: Vec<Ipv4Addr>,
+    pub dns_srvs: Vec<Ipv4Addr>,
+    pub search_domains: Vec<String>,
+    pub ntp_srvs: Vec<Ipv4Addr>,
+    pub mtu: Option<u16>,
+    /// Classless static routes(option 121). Once defined, the `gateways`
+    /// should be ignored as RFC 3442 required.
+    pub classless_routes: Vec<NipartDhcpClasslessRouteV4>,
+    /// Route table to hold routes of this lease, None for main table
+    pub route_table_id: Option<u32>,
+    /// Metric of routes of this lease
+    pub route_metric: Option<u32>,
+    /// Address of previous lease on the same interface when DHCP server
+    /// assigned a different one, should be removed from interface
+    pub previous_ip: Option<Ipv4Addr>,
+}
+
+impl NipartDhcpLeaseV4 {
+    pub fn has_dns(&self) -> bool {
+        !self.dns_srvs.is_empty() || !self.search_domains.is_empty()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NipartDhcpClasslessRouteV4 {
+    pub destination: Ipv4Addr,
+    pub prefix_length: u8,
+    /// `0.0.0.0` means the destination is on-link
+    pub router: Ipv4Addr,
+}
+
+impl NipartDhcpClasslessRouteV4 {
+    pub fn new(
+        destination: Ipv4Addr,
+        prefix_length: u8,
+        router: Ipv4Addr,
+    ) -> Self {
+        Self {
+            destination,
+            prefix_length,
+            router,
+        }
+    }
 }
 
 impl NipartDhcpLeaseV4 {
@@ -131,6 +210,15 @@ impl NipartDhcpLeaseV4 {
             prefix_length,
             server_ip,
             lease_time,
+            gateways: Vec::new(),
+            dns_srvs: Vec::new(),
+            search_domains: Vec::new(),
+            ntp_srvs: Vec::new(),
+            mtu: None,
+            classless_routes: Vec::new(),
+            route_table_id: None,
+            route_metric: None,
+            previous_ip: None,
         }
     }
 }
@@ -141,6 +229,33 @@ pub struct NipartDhcpLeaseV6 {
     pub iface: String,
     pub ip: Ipv6Addr,
     pub prefix_length: u8,
-    pub server_ip: Ipv4Addr,
-    pub lease_time: u32,
+    pub server_ip: Ipv6Addr,
+    /// Preferred lifetime in seconds
+    pub preferred_time: u32,
+    /// Valid lifetime in seconds
+    pub valid_time: u32,
+    /// Address of previous lease on the same interface when DHCP server
+    /// assigned a different one, should be removed from interface
+    pub previous_ip: Option<Ipv6Addr>,
+}
+
+impl NipartDhcpLeaseV6 {
+    pub fn new(
+        iface: String,
+        ip: Ipv6Addr,
+        prefix_length: u8,
+        server_ip: Ipv6Addr,
+        preferred_time: u32,
+        valid_time: u32,
+    ) -> Self {
+        Self {
+            iface,
+            ip,
+            prefix_length,
+            server_ip,
+            preferred_time,
+            valid_time,
+            previous_ip: None,
+        }
+    }
 }
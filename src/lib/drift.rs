@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ErrorKind, NetworkState, NipartConnection, NipartError, NipartEvent,
+    NipartEventAddress, NipartPluginEvent, NipartUserEvent, NipartUuid,
+};
+
+/// How daemon reacts when running network state of managed interfaces,
+/// routes or route rules drift away from the saved network state.
+#[derive(
+    Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum NipartDriftPolicy {
+    /// Log the difference and send it to drift watchers
+    #[default]
+    Report,
+    /// Report the difference and re-apply the saved network state
+    Revert,
+}
+
+impl std::fmt::Display for NipartDriftPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Report => "report",
+                Self::Revert => "revert",
+            }
+        )
+    }
+}
+
+impl NipartConnection {
+    pub async fn set_drift_policy(
+        &mut self,
+        policy: NipartDriftPolicy,
+    ) -> Result<(), NipartError> {
+        let request = NipartEvent::new(
+            NipartUserEvent::SetDriftPolicy(policy),
+            NipartPluginEvent::None,
+            NipartEventAddress::User,
+            NipartEventAddress::Daemon,
+            self.timeout,
+        );
+        self.send(&request).await?;
+        let event = self.recv_reply(request.uuid, self.timeout).await?;
+        if let NipartUserEvent::SetDriftPolicyReply = event.user {
+            Ok(())
+        } else {
+            Err(NipartError::new(
+                ErrorKind::Bug,
+                format!("Invalid reply {event:?} for SetDriftPolicy"),
+            ))
+        }
+    }
+
+    /// Subscribe to drift reports. Use the returned UUID with
+    /// [NipartConnection::recv_drift_report] to receive them.
+    pub async fn watch_drift(&mut self) -> Result<NipartUuid, NipartError> {
+        let request = NipartEvent::new(
+            NipartUserEvent::WatchDrift,
+            NipartPluginEvent::None,
+            NipartEventAddress::User,
+            NipartEventAddress::Daemon,
+            self.timeout,
+        );
+        self.send(&request).await?;
+        Ok(request.uuid)
+    }
+
+    /// Block till next drift report arrives. The returned network state only
+    /// contains saved properties which differ from the running network state.
+    pub async fn recv_drift_report(
+        &mut self,
+        uuid: NipartUuid,
+    ) -> Result<NetworkState, NipartError> {
+        loop {
+            let event = match self.buffer.remove(&uuid) {
+                Some(e) => e,
+                None => self.recv::<NipartEvent>().await?,
+            };
+            if event.is_log() {
+                event.emit_log();
+                continue;
+            }
+            if event.uuid != uuid {
+                self.buffer.insert(event.uuid, event);
+                continue;
+            }
+            let event = event.into_result()?;
+            return if let NipartUserEvent::DriftReport(diff) = event.user {
+                Ok(*diff)
+            } else {
+                Err(NipartError::new(
+                    ErrorKind::Bug,
+                    format!("Invalid reply {event:?} for WatchDrift"),
+                ))
+            };
+        }
+    }
+}
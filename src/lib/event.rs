@@ -6,8 +6,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     NetworkCommit, NetworkCommitQueryOption, NetworkState, NipartApplyOption,
-    NipartError, NipartLogEntry, NipartLogLevel, NipartPluginEvent,
-    NipartPluginInfo, NipartQueryOption, NipartRole, NipartUuid,
+    NipartDriftPolicy, NipartError, NipartLogEntry, NipartLogLevel,
+    NipartPluginEvent, NipartPluginInfo, NipartQueryOption, NipartRole,
+    NipartUuid, NipartWaitOnlineOption,
 };
 
 #[derive(
@@ -140,6 +141,10 @@ impl NipartEvent {
         matches!(self.user, NipartUserEvent::Log(_))
     }
 
+    pub fn is_drift_report(&self) -> bool {
+        matches!(self.user, NipartUserEvent::DriftReport(_))
+    }
+
     pub fn emit_log(&self) {
         if let NipartUserEvent::Log(log_entry) = &self.user {
             let log_source = format!("nipart.{}", self.src);
@@ -194,6 +199,31 @@ pub enum NipartUserEvent {
     RemoveCommits(Box<Vec<NipartUuid>>),
     /// Reply with new applied and saved network state after commits removal.
     RemoveCommitsReply(Box<NetworkState>),
+    /// Confirm the network state applied with
+    /// `NipartApplyOption.revert_after` set, so it will not be reverted.
+    ConfirmCommit,
+    /// Reply with confirmed commit, `None` if applied with `memory_only`.
+    ConfirmCommitReply(Box<Option<NetworkCommit>>),
+    /// Revert the network state applied with
+    /// `NipartApplyOption.revert_after` set without waiting for timeout.
+    CancelCommit,
+    /// Reply with network state after revert.
+    CancelCommitReply(Box<NetworkState>),
+
+    /// Block till all specified probes passed.
+    WaitOnline(Box<NipartWaitOnlineOption>),
+    /// Reply when all probes passed.
+    WaitOnlineReply,
+
+    /// Change how daemon reacts on network state drift.
+    SetDriftPolicy(NipartDriftPolicy),
+    /// Reply when drift policy changed.
+    SetDriftPolicyReply,
+    /// Subscribe to drift reports, no reply till drift detected.
+    WatchDrift,
+    /// Saved network state which differs from running network state.
+    /// Will be sent to every drift watcher.
+    DriftReport(Box<NetworkState>),
 
     /// Plugin or daemon logs to user
     Log(NipartLogEntry),
@@ -221,6 +251,16 @@ impl std::fmt::Display for NipartUserEvent {
                 Self::QueryCommitsReply(_) => "query_commits_reply",
                 Self::RemoveCommits(_) => "remove_commits",
                 Self::RemoveCommitsReply(_) => "remove_commits_reply",
+                Self::ConfirmCommit => "confirm_commit",
+                Self::ConfirmCommitReply(_) => "confirm_commit_reply",
+                Self::CancelCommit => "cancel_commit",
+                Self::CancelCommitReply(_) => "cancel_commit_reply",
+                Self::WaitOnline(_) => "wait_online",
+                Self::WaitOnlineReply => "wait_online_reply",
+                Self::SetDriftPolicy(_) => "set_drift_policy",
+                Self::SetDriftPolicyReply => "set_drift_policy_reply",
+                Self::WatchDrift => "watch_drift",
+                Self::DriftReport(_) => "drift_report",
                 Self::Log(_) => "log",
             }
         )
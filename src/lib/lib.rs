@@ -2,6 +2,7 @@
 
 mod commit;
 mod dhcp;
+mod drift;
 mod error;
 mod event;
 mod ipc;
@@ -15,6 +16,7 @@ mod plugin_external;
 mod plugin_ipc;
 mod plugin_native;
 mod state_options;
+mod wait_online;
 // TODO: Currently we are copy code from nmstate, hence suppressed warnings,
 //       Need to clean up the code once detached from nmstate code base
 #[allow(dead_code, unused_imports, unexpected_cfgs)]
@@ -22,17 +24,20 @@ mod state;
 
 pub use self::commit::{NetworkCommit, NetworkCommitQueryOption};
 pub use self::dhcp::{
-    NipartDhcpConfig, NipartDhcpConfigV4, NipartDhcpConfigV6, NipartDhcpLease,
-    NipartDhcpLeaseV4, NipartDhcpLeaseV6,
+    NipartDhcpClasslessRouteV4, NipartDhcpConfig, NipartDhcpConfigV4,
+    NipartDhcpConfigV6, NipartDhcpLease, NipartDhcpLeaseV4, NipartDhcpLeaseV6,
 };
+pub use self::drift::NipartDriftPolicy;
 pub use self::error::{ErrorKind, NipartError};
 pub use self::event::{NipartEvent, NipartEventAddress, NipartUserEvent};
 pub use self::ipc::{NipartConnection, DEFAULT_TIMEOUT};
 pub use self::lock::{NipartLockEntry, NipartLockOption};
 pub use self::logging::{NipartLogEntry, NipartLogLevel};
 pub use self::monitor::{
-    NipartAddressMonitorKind, NipartAddressMonitorRule, NipartLinkMonitorKind,
-    NipartLinkMonitorRule, NipartMonitorEvent, NipartMonitorRule,
+    NipartAddressMonitorKind, NipartAddressMonitorRule, NipartAddressScope,
+    NipartLinkMonitorKind, NipartLinkMonitorRule, NipartMonitorEvent,
+    NipartMonitorRule, NipartOvsdbMonitorRule, NipartRouteMonitorKind,
+    NipartRouteMonitorRule, NipartRouteRuleMonitorRule,
 };
 pub use self::nipart_uuid::NipartUuid;
 pub use self::plugin::{
@@ -44,6 +49,9 @@ pub use self::plugin_native::NipartNativePlugin;
 pub use self::state_options::{
     NipartApplyOption, NipartQueryOption, NipartStateKind,
 };
+pub use self::wait_online::{
+    NipartWaitOnlineOption, NipartWaitOnlineProbe, NipartWaitOnlineProbeKind,
+};
 
 // TODO Please remove this * once we detached from nmstate code base
 pub use self::state::*;
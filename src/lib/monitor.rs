@@ -4,13 +4,16 @@ use std::net::IpAddr;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{NipartEventAddress, NipartUuid};
+use crate::{NipartEventAddress, NipartUuid, RouteEntry, RouteRuleEntry};
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum NipartMonitorRule {
     Link(NipartLinkMonitorRule),
     Address(NipartAddressMonitorRule),
+    Route(NipartRouteMonitorRule),
+    RouteRule(NipartRouteRuleMonitorRule),
+    Ovsdb(NipartOvsdbMonitorRule),
 }
 
 impl std::fmt::Display for NipartMonitorRule {
@@ -18,6 +21,9 @@ impl std::fmt::Display for NipartMonitorRule {
         match self {
             Self::Link(rule) => write!(f, "{rule}"),
             Self::Address(rule) => write!(f, "{rule}"),
+            Self::Route(rule) => write!(f, "{rule}"),
+            Self::RouteRule(rule) => write!(f, "{rule}"),
+            Self::Ovsdb(rule) => write!(f, "{rule}"),
         }
     }
 }
@@ -29,8 +35,28 @@ pub enum NipartMonitorEvent {
     LinkUp(String),
     /// Interface down
     LinkDown(String),
+    /// Interface created, removed or any of its property changed
+    LinkChange(String),
+    /// IP address been added or finished duplicate address detection
+    AddressAdd(IpAddr),
     /// IP address been removed
     AddressRemove(IpAddr),
+    /// Route been added or changed
+    RouteAdd(Box<RouteEntry>),
+    /// Route been removed
+    RouteRemove(Box<RouteEntry>),
+    /// Route rule been added
+    RouteRuleAdd(Box<RouteRuleEntry>),
+    /// Route rule been removed
+    RouteRuleRemove(Box<RouteRuleEntry>),
+    /// OVS bridge been added, removed or changed in OVSDB
+    OvsBridgeChange(String),
+    /// OVS port been added, removed or changed in OVSDB
+    OvsPortChange(String),
+    /// OVS interface been added, removed or changed in OVSDB
+    OvsIfaceChange(String),
+    /// OVSDB global configuration (`Open_vSwitch` table) changed
+    OvsGlobalConfigChange,
 }
 
 impl std::fmt::Display for NipartMonitorEvent {
@@ -38,7 +64,23 @@ impl std::fmt::Display for NipartMonitorEvent {
         match self {
             Self::LinkUp(iface) => write!(f, "link_up:{iface}"),
             Self::LinkDown(iface) => write!(f, "link_down:{iface}"),
+            Self::LinkChange(iface) => write!(f, "link_change:{iface}"),
+            Self::AddressAdd(ip) => write!(f, "addr_add:{ip}"),
             Self::AddressRemove(ip) => write!(f, "addr_remove:{ip}"),
+            Self::RouteAdd(route) => write!(f, "route_add:{route}"),
+            Self::RouteRemove(route) => write!(f, "route_remove:{route}"),
+            Self::RouteRuleAdd(rule) => write!(f, "route_rule_add:{rule}"),
+            Self::RouteRuleRemove(rule) => {
+                write!(f, "route_rule_remove:{rule}")
+            }
+            Self::OvsBridgeChange(name) => {
+                write!(f, "ovs_bridge_change:{name}")
+            }
+            Self::OvsPortChange(name) => write!(f, "ovs_port_change:{name}"),
+            Self::OvsIfaceChange(name) => write!(f, "ovs_iface_change:{name}"),
+            Self::OvsGlobalConfigChange => {
+                write!(f, "ovs_global_config_change")
+            }
         }
     }
 }
@@ -59,6 +101,8 @@ impl std::fmt::Display for NipartMonitorEvent {
 pub enum NipartLinkMonitorKind {
     Up,
     Down,
+    /// Any change of link, e.g. MTU, state, controller or link removal
+    Change,
 }
 
 impl std::fmt::Display for NipartLinkMonitorKind {
@@ -69,13 +113,14 @@ impl std::fmt::Display for NipartLinkMonitorKind {
             match self {
                 Self::Up => "up",
                 Self::Down => "down",
+                Self::Change => "change",
             }
         )
     }
 }
 
-/// Monitor on the link state(`IFLA_OPERSTATE`) up/down event of specified
-/// interface
+/// Monitor on the link state(`IFLA_OPERSTATE`) up/down event or any link
+/// change of specified interface
 #[derive(
     Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash,
 )]
@@ -116,8 +161,10 @@ impl NipartLinkMonitorRule {
     }
 }
 
-/// Monitor on the IP address (`IFLA_OPERSTATE`) add/remove event of specified
-/// interface
+/// Monitor on the IP address add/remove event. The `None` properties are
+/// treated as wildcard, for example, setting `iface` to `eth1` and `scope` to
+/// [NipartAddressScope::Link] with `Add` kind will notify when IPv6 link
+/// local address of `eth1` is ready for use.
 #[derive(
     Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash,
 )]
@@ -129,18 +176,47 @@ pub struct NipartAddressMonitorRule {
     /// Event ID for tracing the source of this request
     pub uuid: NipartUuid,
     /// Interface to monitor
-    pub ip: IpAddr,
-    // TODO: this is for DHCPv6 notification for link local address change,
-    // hence we should `AddressScope::Link` here.
+    pub iface: Option<String>,
+    /// IP address to monitor
+    pub ip: Option<IpAddr>,
+    /// Scope of IP address to monitor
+    pub scope: Option<NipartAddressScope>,
 }
 
 impl std::fmt::Display for NipartAddressMonitorRule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "address_monitor: uuid:{}, kind:{}, requester:{}, ip:{}",
-            self.uuid, self.kind, self.requester, self.ip
-        )
+            "address_monitor: uuid:{}, kind:{}, requester:{}",
+            self.uuid, self.kind, self.requester
+        )?;
+        if let Some(iface) = self.iface.as_ref() {
+            write!(f, ", iface:{iface}")?;
+        }
+        if let Some(ip) = self.ip.as_ref() {
+            write!(f, ", ip:{ip}")?;
+        }
+        if let Some(scope) = self.scope.as_ref() {
+            write!(f, ", scope:{scope}")?;
+        }
+        Ok(())
+    }
+}
+
+impl NipartAddressMonitorRule {
+    pub fn new(
+        kind: NipartAddressMonitorKind,
+        requester: NipartEventAddress,
+        uuid: NipartUuid,
+    ) -> Self {
+        Self {
+            kind,
+            requester,
+            uuid,
+            iface: None,
+            ip: None,
+            scope: None,
+        }
     }
 }
 
@@ -158,7 +234,12 @@ impl std::fmt::Display for NipartAddressMonitorRule {
 )]
 #[non_exhaustive]
 pub enum NipartAddressMonitorKind {
+    /// Address added and not in IPv6 duplicate address detection
+    Add,
     Remove,
+    /// Any address added(after duplicate address detection) or removed.
+    /// Existing addresses are not notified on registration.
+    Change,
 }
 
 impl std::fmt::Display for NipartAddressMonitorKind {
@@ -167,8 +248,216 @@ impl std::fmt::Display for NipartAddressMonitorKind {
             f,
             "{}",
             match self {
+                Self::Add => "add",
                 Self::Remove => "remove",
+                Self::Change => "change",
             }
         )
     }
 }
+
+#[derive(
+    Deserialize,
+    Serialize,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Copy,
+)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum NipartAddressScope {
+    /// `RT_SCOPE_UNIVERSE`
+    Global,
+    /// `RT_SCOPE_LINK`, e.g. IPv6 link local address
+    Link,
+    /// `RT_SCOPE_HOST`, e.g. loopback address
+    Host,
+}
+
+impl std::fmt::Display for NipartAddressScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Global => "global",
+                Self::Link => "link",
+                Self::Host => "host",
+            }
+        )
+    }
+}
+
+#[derive(
+    Deserialize,
+    Serialize,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Copy,
+)]
+#[non_exhaustive]
+pub enum NipartRouteMonitorKind {
+    /// Route or route rule added or changed
+    Add,
+    Remove,
+}
+
+impl std::fmt::Display for NipartRouteMonitorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Add => "add",
+                Self::Remove => "remove",
+            }
+        )
+    }
+}
+
+/// Monitor on the route add/remove event. The properties not defined in
+/// `route` are treated as wildcard, for example, setting `destination` to
+/// `0.0.0.0/0` with `Remove` kind will notify when IPv4 default route been
+/// removed. Every matching route change is notified until this rule is
+/// removed by [crate::NipartPluginEvent::RemoveMonitorRule].
+#[derive(
+    Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[non_exhaustive]
+pub struct NipartRouteMonitorRule {
+    pub kind: NipartRouteMonitorKind,
+    /// Who requested this monitor rule
+    pub requester: NipartEventAddress,
+    /// Event ID for tracing the source of this request
+    pub uuid: NipartUuid,
+    /// Route to monitor
+    pub route: RouteEntry,
+}
+
+impl std::fmt::Display for NipartRouteMonitorRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "route_monitor: uuid:{}, kind:{}, requester:{}, route:{}",
+            self.uuid, self.kind, self.requester, self.route
+        )
+    }
+}
+
+impl NipartRouteMonitorRule {
+    pub fn new(
+        kind: NipartRouteMonitorKind,
+        requester: NipartEventAddress,
+        uuid: NipartUuid,
+        route: RouteEntry,
+    ) -> Self {
+        Self {
+            kind,
+            requester,
+            uuid,
+            route,
+        }
+    }
+
+    /// Whether specified route is matching this monitor rule.
+    /// Metric is ignored.
+    pub fn is_match(&self, route: &RouteEntry) -> bool {
+        self.route.is_match(route)
+    }
+}
+
+/// Monitor on the route rule add/remove event. The properties not defined in
+/// `rule` are treated as wildcard, for example, setting `table_id` to `100`
+/// will notify on any change of route rules pointing to route table 100.
+/// This rule will not be removed after notification.
+#[derive(
+    Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[non_exhaustive]
+pub struct NipartRouteRuleMonitorRule {
+    pub kind: NipartRouteMonitorKind,
+    /// Who requested this monitor rule
+    pub requester: NipartEventAddress,
+    /// Event ID for tracing the source of this request
+    pub uuid: NipartUuid,
+    /// Route rule to monitor
+    pub rule: RouteRuleEntry,
+}
+
+impl std::fmt::Display for NipartRouteRuleMonitorRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "route_rule_monitor: uuid:{}, kind:{}, requester:{}, rule:{}",
+            self.uuid, self.kind, self.requester, self.rule
+        )
+    }
+}
+
+impl NipartRouteRuleMonitorRule {
+    pub fn new(
+        kind: NipartRouteMonitorKind,
+        requester: NipartEventAddress,
+        uuid: NipartUuid,
+        rule: RouteRuleEntry,
+    ) -> Self {
+        Self {
+            kind,
+            requester,
+            uuid,
+            rule,
+        }
+    }
+
+    /// Whether specified route rule is matching this monitor rule.
+    pub fn is_match(&self, rule: &RouteRuleEntry) -> bool {
+        self.rule.is_match(rule)
+    }
+}
+
+/// Monitor on changes of OVSDB `Bridge`, `Port`, `Interface` and
+/// `Open_vSwitch` tables, including changes made by `ovs-vsctl` or OVN
+/// controller. A row change is notified as
+/// [NipartMonitorEvent::OvsBridgeChange],
+/// [NipartMonitorEvent::OvsPortChange],
+/// [NipartMonitorEvent::OvsIfaceChange] or
+/// [NipartMonitorEvent::OvsGlobalConfigChange]. Since changes made while
+/// OVSDB is unreachable are unknown, a
+/// [NipartMonitorEvent::OvsGlobalConfigChange] is also notified once OVSDB
+/// is reconnected.
+#[derive(
+    Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[non_exhaustive]
+pub struct NipartOvsdbMonitorRule {
+    /// Who requested this monitor rule
+    pub requester: NipartEventAddress,
+    /// Event ID for tracing the source of this request
+    pub uuid: NipartUuid,
+}
+
+impl std::fmt::Display for NipartOvsdbMonitorRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ovsdb_monitor: uuid:{}, requester:{}",
+            self.uuid, self.requester
+        )
+    }
+}
+
+impl NipartOvsdbMonitorRule {
+    pub fn new(requester: NipartEventAddress, uuid: NipartUuid) -> Self {
+        Self { requester, uuid }
+    }
+}
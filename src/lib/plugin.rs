@@ -6,7 +6,7 @@ use crate::{
     MergedNetworkState, NetworkCommit, NetworkCommitQueryOption, NetworkState,
     NipartApplyOption, NipartDhcpConfig, NipartDhcpLease, NipartLockEntry,
     NipartLockOption, NipartLogLevel, NipartMonitorEvent, NipartMonitorRule,
-    NipartQueryOption, NipartUuid,
+    NipartQueryOption, NipartUuid, NipartWaitOnlineProbe,
 };
 
 /// Data for plugin to do initialize task after daemon fully started
@@ -46,6 +46,10 @@ pub enum NipartRole {
     Commit,
     Locker,
     Logger,
+    WaitOnline,
+    /// Owner of DNS resolver config, will receive DHCP lease holding DNS
+    /// information.
+    Dns,
 }
 
 impl std::fmt::Display for NipartRole {
@@ -63,6 +67,8 @@ impl std::fmt::Display for NipartRole {
                 Self::ApplyDhcpLease => "apply_dhcp_lease",
                 Self::Locker => "locker",
                 Self::Logger => "logger",
+                Self::WaitOnline => "wait_online",
+                Self::Dns => "dns",
             }
         )
     }
@@ -104,6 +110,15 @@ pub enum NipartPluginEvent {
     /// Commander request responsible plugins to apply DHCP lease.
     ApplyDhcpLease(Box<NipartDhcpLease>),
     ApplyDhcpLeaseReply,
+    /// DHCP plugin notify commander on lease expired or renewal failed.
+    LostDhcpLease(Box<NipartDhcpLease>),
+    /// DHCP plugin notify commander on lease released because of DHCP
+    /// disabled or interface removed.
+    ReleasedDhcpLease(Box<NipartDhcpLease>),
+    /// Commander request responsible plugins to remove the address, routes
+    /// and DNS of DHCP lease.
+    RemoveDhcpLease(Box<NipartDhcpLease>),
+    RemoveDhcpLeaseReply,
 
     /// Register a monitor rule to plugin with monitor role.
     /// No reply required.
@@ -150,6 +165,12 @@ pub enum NipartPluginEvent {
     /// Indicate all requested lock entries has been locked as requested.
     LockReply,
     UnlockReply,
+
+    /// Block till specified probe passed or timeout.
+    /// Plugin should reply with [NipartPluginEvent::WaitOnlineReply] after
+    /// probe passed and post sleep finished, or error on timeout.
+    WaitOnline(Box<NipartWaitOnlineProbe>),
+    WaitOnlineReply,
 }
 
 impl std::fmt::Display for NipartPluginEvent {
@@ -197,6 +218,12 @@ impl std::fmt::Display for NipartPluginEvent {
             Self::ApplyDhcpLeaseReply => {
                 write!(f, "apply_dhcp_lease_reply")
             }
+            Self::LostDhcpLease(_) => write!(f, "lost_dhcp_lease"),
+            Self::ReleasedDhcpLease(_) => write!(f, "released_dhcp_lease"),
+            Self::RemoveDhcpLease(_) => write!(f, "remove_dhcp_lease"),
+            Self::RemoveDhcpLeaseReply => {
+                write!(f, "remove_dhcp_lease_reply")
+            }
             Self::RegisterMonitorRule(rule) => {
                 write!(f, "register_monitor_rule:{rule}")
             }
@@ -222,6 +249,8 @@ impl std::fmt::Display for NipartPluginEvent {
             Self::QueryLastCommitStateReply(_) => {
                 write!(f, "query_last_commit_state_reply")
             }
+            Self::WaitOnline(probe) => write!(f, "wait_online:{probe}"),
+            Self::WaitOnlineReply => write!(f, "wait_online_reply"),
         }
     }
 }
@@ -237,6 +266,7 @@ impl NipartPluginEvent {
                 | Self::QueryDhcpConfigReply(_)
                 | Self::ApplyDhcpConfigReply
                 | Self::ApplyDhcpLeaseReply
+                | Self::RemoveDhcpLeaseReply
                 | Self::GotMonitorEvent(_)
                 | Self::QueryCommitsReply(_)
                 | Self::CreateCommitReply
@@ -244,6 +274,7 @@ impl NipartPluginEvent {
                 | Self::UnlockReply
                 | Self::QueryLastCommitStateReply(_)
                 | Self::RemoveCommitsReply(_)
+                | Self::WaitOnlineReply
         )
     }
 }
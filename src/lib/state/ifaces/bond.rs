@@ -586,6 +586,16 @@ impl std::fmt::Display for BondAdSelect {
     }
 }
 
+impl From<BondAdSelect> for u8 {
+    fn from(v: BondAdSelect) -> u8 {
+        match v {
+            BondAdSelect::Stable => 0,
+            BondAdSelect::Bandwidth => 1,
+            BondAdSelect::Count => 2,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 #[serde(rename_all = "kebab-case", remote = "BondLacpRate")]
 #[non_exhaustive]
@@ -639,6 +649,15 @@ impl std::fmt::Display for BondLacpRate {
     }
 }
 
+impl From<BondLacpRate> for u8 {
+    fn from(v: BondLacpRate) -> u8 {
+        match v {
+            BondLacpRate::Slow => 0,
+            BondLacpRate::Fast => 1,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
 #[serde(rename_all = "kebab-case", remote = "BondAllPortsActive")]
 #[non_exhaustive]
@@ -755,6 +774,15 @@ impl std::fmt::Display for BondArpAllTargets {
     }
 }
 
+impl From<BondArpAllTargets> for u8 {
+    fn from(v: BondArpAllTargets) -> u8 {
+        match v {
+            BondArpAllTargets::Any => 0,
+            BondArpAllTargets::All => 1,
+        }
+    }
+}
+
 /// The `arp_validate` kernel bond option.
 ///
 /// Specifies whether or not ARP probes and replies should be validated in any
@@ -843,6 +871,20 @@ impl std::fmt::Display for BondArpValidate {
     }
 }
 
+impl From<BondArpValidate> for u8 {
+    fn from(v: BondArpValidate) -> u8 {
+        match v {
+            BondArpValidate::None => 0,
+            BondArpValidate::Active => 1,
+            BondArpValidate::Backup => 2,
+            BondArpValidate::All => 3,
+            BondArpValidate::Filter => 4,
+            BondArpValidate::FilterActive => 5,
+            BondArpValidate::FilterBackup => 6,
+        }
+    }
+}
+
 /// The `fail_over_mac` kernel bond option.
 ///
 /// Specifies whether active-backup mode should set all ports to the same MAC
@@ -916,6 +958,16 @@ impl std::fmt::Display for BondFailOverMac {
     }
 }
 
+impl From<BondFailOverMac> for u8 {
+    fn from(v: BondFailOverMac) -> u8 {
+        match v {
+            BondFailOverMac::None => 0,
+            BondFailOverMac::Active => 1,
+            BondFailOverMac::Follow => 2,
+        }
+    }
+}
+
 /// The `primary_reselect` kernel bond option.
 ///
 /// Specifies the reselection policy for the primary port. This affects how the
@@ -981,6 +1033,16 @@ impl std::fmt::Display for BondPrimaryReselect {
     }
 }
 
+impl From<BondPrimaryReselect> for u8 {
+    fn from(v: BondPrimaryReselect) -> u8 {
+        match v {
+            BondPrimaryReselect::Always => 0,
+            BondPrimaryReselect::Better => 1,
+            BondPrimaryReselect::Failure => 2,
+        }
+    }
+}
+
 /// The `xmit_hash_policy` kernel bond option.
 ///
 /// Selects the transmit hash policy to use for port selection in balance-xor,
@@ -1054,6 +1116,19 @@ impl std::fmt::Display for BondXmitHashPolicy {
     }
 }
 
+impl From<BondXmitHashPolicy> for u8 {
+    fn from(v: BondXmitHashPolicy) -> u8 {
+        match v {
+            BondXmitHashPolicy::Layer2 => 0,
+            BondXmitHashPolicy::Layer34 => 1,
+            BondXmitHashPolicy::Layer23 => 2,
+            BondXmitHashPolicy::Encap23 => 3,
+            BondXmitHashPolicy::Encap34 => 4,
+            BondXmitHashPolicy::VlanSrcMac => 5,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 #[serde(deny_unknown_fields)]
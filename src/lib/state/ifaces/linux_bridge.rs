@@ -209,7 +209,7 @@ impl LinuxBridgeInterface {
         Ok(())
     }
 
-    pub(crate) fn get_port_conf(
+    pub fn get_port_conf(
         &self,
         port_name: &str,
     ) -> Option<&LinuxBridgePortConfig> {
@@ -223,7 +223,7 @@ impl LinuxBridgeInterface {
             })
     }
 
-    pub(crate) fn vlan_filtering_is_enabled(&self) -> bool {
+    pub fn vlan_filtering_is_enabled(&self) -> bool {
         if let Some(ports) = self.bridge.as_ref().and_then(|b| b.port.as_ref())
         {
             ports.as_slice().iter().any(|port_conf| {
@@ -67,6 +67,13 @@ pub enum LldpOrgOiu {
 pub struct LldpConfig {
     #[serde(deserialize_with = "crate::state::deserializer::bool_or_string")]
     pub enabled: bool,
+    /// Transmit LLDPDU with our hostname and port information.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::state::deserializer::option_bool_or_string"
+    )]
+    pub transmit: Option<bool>,
     #[serde(
         default,
         deserialize_with = "skip",
@@ -23,6 +23,8 @@ mod serializer;
 mod statistic;
 // This one is not copy from nmstate
 mod not_synced;
+#[cfg(test)]
+mod unit_tests;
 
 pub use self::dispatch::DispatchConfig;
 pub use self::dns::{DnsClientState, DnsState, MergedDnsState};
@@ -40,18 +42,19 @@ pub use self::ifaces::{
     BridgePortVlanMode, BridgePortVlanRange, DummyInterface, EthernetConfig,
     EthernetDuplex, EthernetInterface, EthtoolCoalesceConfig, EthtoolConfig,
     EthtoolFeatureConfig, EthtoolPauseConfig, EthtoolRingConfig, HsrConfig,
-    HsrInterface, InfiniBandConfig, InfiniBandInterface, InfiniBandMode,
-    Interfaces, IpsecInterface, LibreswanConfig, LinuxBridgeConfig,
-    LinuxBridgeInterface, LinuxBridgeMulticastRouterType, LinuxBridgeOptions,
-    LinuxBridgePortConfig, LinuxBridgeStpOptions, LoopbackInterface,
-    MacSecConfig, MacSecInterface, MacSecValidate, MacVlanConfig,
-    MacVlanInterface, MacVlanMode, MacVtapConfig, MacVtapInterface,
-    MacVtapMode, MergedInterfaces, OvsBridgeBondConfig, OvsBridgeBondMode,
-    OvsBridgeBondPortConfig, OvsBridgeConfig, OvsBridgeInterface,
-    OvsBridgeOptions, OvsBridgePortConfig, OvsBridgeStpOptions, OvsDpdkConfig,
-    OvsInterface, OvsPatchConfig, SrIovConfig, SrIovVfConfig, VethConfig,
-    VlanConfig, VlanInterface, VlanProtocol, VlanRegistrationProtocol,
-    VrfConfig, VrfInterface, VxlanConfig, VxlanInterface, XfrmInterface,
+    HsrInterface, HsrProtocol, InfiniBandConfig, InfiniBandInterface,
+    InfiniBandMode, Interfaces, IpsecInterface, LibreswanConfig,
+    LinuxBridgeConfig, LinuxBridgeInterface, LinuxBridgeMulticastRouterType,
+    LinuxBridgeOptions, LinuxBridgePortConfig, LinuxBridgeStpOptions,
+    LoopbackInterface, MacSecConfig, MacSecInterface, MacSecOffload,
+    MacSecValidate, MacVlanConfig, MacVlanInterface, MacVlanMode,
+    MacVtapConfig, MacVtapInterface, MacVtapMode, MergedInterfaces,
+    OvsBridgeBondConfig, OvsBridgeBondMode, OvsBridgeBondPortConfig,
+    OvsBridgeConfig, OvsBridgeInterface, OvsBridgeOptions, OvsBridgePortConfig,
+    OvsBridgeStpOptions, OvsDpdkConfig, OvsInterface, OvsPatchConfig,
+    SrIovConfig, SrIovVfConfig, VethConfig, VlanConfig, VlanInterface,
+    VlanProtocol, VlanRegistrationProtocol, VrfConfig, VrfInterface,
+    VxlanConfig, VxlanInterface, XfrmInterface,
 };
 pub use self::ip::{
     AddressFamily, Dhcpv4ClientId, Dhcpv6Duid, InterfaceIpAddr, InterfaceIpv4,
@@ -64,7 +67,9 @@ pub use self::lldp::{
     LldpSystemCapability, LldpSystemDescription, LldpSystemName, LldpVlan,
     LldpVlans,
 };
-pub use self::mptcp::{MptcpAddressFlag, MptcpConfig};
+pub use self::mptcp::{
+    MergedMptcpLimits, MptcpAddressFlag, MptcpConfig, MptcpLimits,
+};
 pub use self::net_state::{MergedNetworkState, NetworkState};
 pub use self::ovn::{
     MergedOvnConfiguration, OvnBridgeMapping, OvnBridgeMappingState,
@@ -52,6 +52,44 @@ pub enum MptcpAddressFlag {
     Fullmesh,
 }
 
+/// Global limits of MPTCP in-kernel path manager, deserialize and serialize
+/// from/to `mptcp-limits`.
+///
+/// Example yaml:
+/// ```yaml
+/// mptcp-limits:
+///   subflows: 2
+///   add-addr-accepted: 2
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct MptcpLimits {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Maximum number of additional subflows allowed for each MPTCP
+    /// connection.
+    pub subflows: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Maximum number of ADD_ADDR sub-options accepted for each MPTCP
+    /// connection.
+    pub add_addr_accepted: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub struct MergedMptcpLimits {
+    pub(crate) desired: Option<MptcpLimits>,
+    pub(crate) current: Option<MptcpLimits>,
+}
+
+impl MergedMptcpLimits {
+    pub(crate) fn new(
+        desired: Option<MptcpLimits>,
+        current: Option<MptcpLimits>,
+    ) -> Self {
+        Self { desired, current }
+    }
+}
+
 impl MergedInterface {
     pub(crate) fn post_inter_ifaces_process_mptcp(
         &self,
@@ -6,9 +6,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     DnsState, ErrorKind, HostNameState, Interface, Interfaces, MergedDnsState,
-    MergedHostNameState, MergedInterfaces, MergedOvnConfiguration,
-    MergedOvsDbGlobalConfig, MergedRouteRules, MergedRoutes, NipartError,
-    OvnConfiguration, OvsDbGlobalConfig, RouteRules, Routes,
+    MergedHostNameState, MergedInterfaces, MergedMptcpLimits,
+    MergedOvnConfiguration, MergedOvsDbGlobalConfig, MergedRouteRules,
+    MergedRoutes, MptcpLimits, NipartError, OvnConfiguration,
+    OvsDbGlobalConfig, RouteRules, Routes,
 };
 
 /// The [NetworkState] represents the whole network state including both
@@ -107,6 +108,10 @@ pub struct NetworkState {
     #[serde(default, skip_serializing_if = "OvnConfiguration::is_none")]
     /// The OVN configuration in the system
     pub ovn: OvnConfiguration,
+    #[serde(rename = "mptcp-limits", skip_serializing_if = "Option::is_none")]
+    /// Global MPTCP limits, deserialize and serialize from/to
+    /// `mptcp-limits`.
+    pub mptcp_limits: Option<MptcpLimits>,
     #[serde(skip)]
     pub(crate) kernel_only: bool,
     #[serde(skip)]
@@ -134,6 +139,7 @@ impl NetworkState {
             && self.routes.is_empty()
             && self.interfaces.is_empty()
             && self.ovn.is_none()
+            && self.mptcp_limits.is_none()
     }
 
     pub(crate) const PASSWORD_HID_BY_NMSTATE: &'static str =
@@ -324,6 +330,7 @@ pub struct MergedNetworkState {
     pub(crate) ovsdb: MergedOvsDbGlobalConfig,
     pub routes: MergedRoutes,
     pub rules: MergedRouteRules,
+    pub(crate) mptcp_limits: MergedMptcpLimits,
     pub(crate) memory_only: bool,
 }
 
@@ -352,7 +359,11 @@ impl MergedNetworkState {
         let hostname =
             MergedHostNameState::new(desired.hostname, current.hostname);
 
+        let mptcp_limits =
+            MergedMptcpLimits::new(desired.mptcp_limits, current.mptcp_limits);
+
         let ovn = MergedOvnConfiguration::new(desired.ovn, current.ovn)?;
+        ovn.validate_bridges(&interfaces)?;
 
         let ovsdb = MergedOvsDbGlobalConfig::new(
             desired.ovsdb,
@@ -371,6 +382,7 @@ impl MergedNetworkState {
             ovn,
             ovsdb,
             hostname,
+            mptcp_limits,
             memory_only,
         };
         ret.validate_ipv6_link_local_address_dns_srv()?;
@@ -1,8 +1,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    HostNameState, MergedInterface, MergedNetworkState, NetworkState,
-    NipartDhcpConfig, NipartDhcpConfigV4, NipartDhcpConfigV6, NipartError,
+    DnsClientState, HostNameState, MergedInterface, MergedNetworkState,
+    MptcpLimits, NetworkState, NipartDhcpConfig, NipartDhcpConfigV4,
+    NipartDhcpConfigV6, NipartError, OvsDbGlobalConfig,
 };
 
 impl NetworkState {
@@ -40,6 +41,13 @@ impl NetworkState {
         if !other.ovn.is_none() {
             self.ovn = other.ovn.clone();
         }
+        if let Some(other_limits) = other.mptcp_limits.as_ref() {
+            if let Some(l) = self.mptcp_limits.as_mut() {
+                l.update(other_limits);
+            } else {
+                self.mptcp_limits = other.mptcp_limits.clone();
+            }
+        }
     }
 
     /// Generate new NetworkState contains only changed properties
@@ -74,29 +82,56 @@ impl NetworkState {
         if merged_state.ovn.is_changed() {
             ret.ovn = self.ovn.clone();
         }
+
+        if merged_state.mptcp_limits.is_changed() {
+            ret.mptcp_limits.clone_from(&self.mptcp_limits);
+        }
         Ok(ret)
     }
 }
 
 impl MergedNetworkState {
+    /// Disabled DHCP config is only included when DHCP is currently enabled,
+    /// so DHCP plugin only get notified for lease to release.
     pub fn get_dhcp_changes(&self) -> Vec<NipartDhcpConfig> {
         let mut ret: Vec<NipartDhcpConfig> = Vec::new();
-        for iface in self
-            .interfaces
-            .kernel_ifaces
-            .values()
-            .filter_map(|i| i.for_apply.as_ref())
-        {
-            if iface.base_iface().can_have_ip() {
+        for merged_iface in self.interfaces.kernel_ifaces.values() {
+            let iface = if let Some(i) = merged_iface.for_apply.as_ref() {
+                i
+            } else {
+                continue;
+            };
+            let cur_v4_enabled = merged_iface
+                .current
+                .as_ref()
+                .and_then(|i| i.base_iface().ipv4.as_ref())
+                .map(|i| i.enabled && i.dhcp == Some(true))
+                .unwrap_or_default();
+            let cur_v6_enabled = merged_iface
+                .current
+                .as_ref()
+                .and_then(|i| i.base_iface().ipv6.as_ref())
+                .map(|i| i.enabled && i.dhcp == Some(true))
+                .unwrap_or_default();
+
+            if !iface.is_absent() && iface.base_iface().can_have_ip() {
                 if let Some(ipv4) = iface.base_iface().ipv4.as_ref() {
-                    let dhcp_conf = NipartDhcpConfigV4::new(
+                    let mut dhcp_conf = NipartDhcpConfigV4::new(
                         iface.name().to_string(),
                         ipv4.enabled && ipv4.dhcp == Some(true),
                     );
+                    dhcp_conf.auto_dns = ipv4.auto_dns != Some(false);
+                    dhcp_conf.auto_routes = ipv4.auto_routes != Some(false);
+                    dhcp_conf.auto_gateway = ipv4.auto_gateway != Some(false);
+                    dhcp_conf.auto_table_id = ipv4.auto_table_id;
+                    dhcp_conf.auto_route_metric = ipv4.auto_route_metric;
+                    dhcp_conf.auto_mtu = iface.base_iface().mtu.is_none();
                     if ipv4.dhcp_client_id.as_ref().is_some() {
                         todo!()
                     }
-                    ret.push(NipartDhcpConfig::V4(dhcp_conf));
+                    if dhcp_conf.enabled || cur_v4_enabled {
+                        ret.push(NipartDhcpConfig::V4(dhcp_conf));
+                    }
                 }
                 if let Some(ipv6) = iface.base_iface().ipv6.as_ref() {
                     let dhcp_conf = NipartDhcpConfigV6::new(
@@ -106,17 +141,25 @@ impl MergedNetworkState {
                     if ipv6.dhcp_duid.as_ref().is_some() {
                         todo!()
                     }
-                    ret.push(NipartDhcpConfig::V6(dhcp_conf));
+                    if dhcp_conf.enabled || cur_v6_enabled {
+                        ret.push(NipartDhcpConfig::V6(dhcp_conf));
+                    }
                 }
             } else {
-                ret.push(NipartDhcpConfig::V4(NipartDhcpConfigV4::new(
-                    iface.name().to_string(),
-                    false,
-                )));
-                ret.push(NipartDhcpConfig::V6(NipartDhcpConfigV6::new(
-                    iface.name().to_string(),
-                    false,
-                )));
+                // Disable DHCP on absent interface, so DHCP lease is released
+                // before interface been removed.
+                if cur_v4_enabled {
+                    ret.push(NipartDhcpConfig::V4(NipartDhcpConfigV4::new(
+                        iface.name().to_string(),
+                        false,
+                    )));
+                }
+                if cur_v6_enabled {
+                    ret.push(NipartDhcpConfig::V6(NipartDhcpConfigV6::new(
+                        iface.name().to_string(),
+                        false,
+                    )));
+                }
             }
         }
         ret
@@ -126,6 +169,40 @@ impl MergedNetworkState {
         self.hostname.desired.as_ref()
     }
 
+    pub fn get_desired_mptcp_limits(&self) -> Option<&MptcpLimits> {
+        if self.mptcp_limits.is_changed() {
+            self.mptcp_limits.desired.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Return the full static DNS config after merge, or None if unchanged.
+    pub fn get_desired_dns(&self) -> Option<DnsClientState> {
+        if self.dns.is_changed() {
+            let mut ret = DnsClientState::new();
+            ret.server = Some(self.dns.servers.clone());
+            ret.search = Some(self.dns.searches.clone());
+            ret.options = Some(self.dns.options.clone());
+            Some(ret)
+        } else {
+            None
+        }
+    }
+
+    /// Return the full OVSDB global config including OVN bridge mappings
+    /// stored in `external_ids`, or None if unchanged.
+    pub fn get_desired_ovsdb_global_conf(&self) -> Option<OvsDbGlobalConfig> {
+        if self.ovsdb.is_changed() {
+            Some(OvsDbGlobalConfig {
+                external_ids: Some(self.ovsdb.external_ids.clone()),
+                other_config: Some(self.ovsdb.other_config.clone()),
+            })
+        } else {
+            None
+        }
+    }
+
     pub fn verify(&self, current: &NetworkState) -> Result<(), NipartError> {
         self.hostname.verify(current.hostname.as_ref())?;
         self.interfaces.verify(&current.interfaces)?;
@@ -148,6 +225,7 @@ impl MergedNetworkState {
         self.ovsdb
             .verify(current.ovsdb.clone().unwrap_or_default())?;
         self.ovn.verify(&current.ovn)?;
+        self.mptcp_limits.verify(current.mptcp_limits.as_ref())?;
         Ok(())
     }
 }
@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::OvnConfiguration;
+use crate::{
+    ErrorKind, InterfaceType, MergedInterfaces, MergedOvnConfiguration,
+    NipartError, OvnConfiguration,
+};
 
 impl OvnConfiguration {
     pub fn is_empty(&self) -> bool {
@@ -11,3 +14,59 @@ impl OvnConfiguration {
         }
     }
 }
+
+impl MergedOvnConfiguration {
+    // The OVN bridge mapping should not point to OVS bridge been removed.
+    // The desired mapping should also point to existing or desired OVS
+    // bridge, while existing mapping to unknown bridge is left untouched so
+    // it does not block unrelated changes.
+    pub(crate) fn validate_bridges(
+        &self,
+        ifaces: &MergedInterfaces,
+    ) -> Result<(), NipartError> {
+        let merged = match self.to_ovsdb_external_id_value() {
+            Some(v) => OvnConfiguration::try_from(v.as_str())?,
+            None => return Ok(()),
+        };
+        for map in merged.bridge_mappings.as_deref().unwrap_or_default() {
+            if let Some(br_name) = map.bridge.as_deref() {
+                match ifaces.get_iface(br_name, InterfaceType::OvsBridge) {
+                    Some(iface) if iface.merged.is_absent() => {
+                        return Err(NipartError::new(
+                            ErrorKind::InvalidArgument,
+                            format!(
+                                "OVN bridge mapping of localnet {} is \
+                                pointing to OVS bridge {br_name} which is \
+                                marked as absent, please remove this \
+                                mapping also",
+                                map.localnet
+                            ),
+                        ));
+                    }
+                    None if self.is_desired_mapping(map.localnet.as_str()) => {
+                        return Err(NipartError::new(
+                            ErrorKind::InvalidArgument,
+                            format!(
+                                "OVN bridge mapping of localnet {} is \
+                                pointing to OVS bridge {br_name} which \
+                                does not exist",
+                                map.localnet
+                            ),
+                        ));
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_desired_mapping(&self, localnet: &str) -> bool {
+        self.desired
+            .bridge_mappings
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .any(|m| m.localnet == localnet && !m.is_absent())
+    }
+}
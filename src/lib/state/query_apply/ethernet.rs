@@ -13,6 +13,72 @@ impl EthernetInterface {
         {
             sriov_conf.sanitize_desired_for_verify();
         }
+        // With auto-negotiation enabled, the speed and duplex are decided by
+        // link partner.
+        if let Some(eth_conf) = self.ethernet.as_mut() {
+            if eth_conf.auto_neg == Some(true) {
+                eth_conf.speed = None;
+                eth_conf.duplex = None;
+            }
+        }
+    }
+
+    // Some drivers(e.g. veth) ignore link mode settings or report nothing
+    // without carrier, hence skip verifying link mode not reported.
+    // Drivers might also round non-standard speed to nearest supported
+    // value, other speed mismatch is left to generic verification.
+    pub(crate) fn process_link_mode_for_verify(
+        &mut self,
+        current: &Self,
+    ) -> Result<(), NipartError> {
+        let des_conf = if let Some(c) = self.ethernet.as_mut() {
+            c
+        } else {
+            return Ok(());
+        };
+        let cur_conf = current.ethernet.clone().unwrap_or_default();
+        if des_conf.auto_neg.is_some() && cur_conf.auto_neg.is_none() {
+            log::warn!(
+                "Driver of interface {} does not report auto-negotiation, \
+                ignoring it in verification",
+                self.base.name
+            );
+            des_conf.auto_neg = None;
+        }
+        if des_conf.duplex.is_some() && cur_conf.duplex.is_none() {
+            log::warn!(
+                "Driver of interface {} does not report duplex, ignoring \
+                it in verification",
+                self.base.name
+            );
+            des_conf.duplex = None;
+        }
+        match (des_conf.speed, cur_conf.speed) {
+            (Some(_), None) => {
+                log::warn!(
+                    "Driver of interface {} does not report speed, \
+                    ignoring it in verification",
+                    self.base.name
+                );
+                des_conf.speed = None;
+            }
+            (Some(des), Some(cur))
+                if des != cur && is_speed_rounded(des, cur) =>
+            {
+                let e = NipartError::new(
+                    ErrorKind::KernelIntegerRoundedError,
+                    format!(
+                        "Driver of interface {} changed ethernet speed \
+                        from {des} to {cur}",
+                        self.base.name
+                    ),
+                );
+                log::error!("{}", e);
+                return Err(e);
+            }
+            _ => (),
+        }
+        Ok(())
     }
 
     pub(crate) fn sriov_is_enabled(&self) -> bool {
@@ -53,6 +119,22 @@ impl EthernetInterface {
     }
 }
 
+// Speeds(Mb/s) defined by ethtool link modes.
+const ETHTOOL_LINK_MODE_SPEEDS: [u32; 16] = [
+    10, 100, 1000, 2500, 5000, 10000, 14000, 20000, 25000, 40000, 50000, 56000,
+    100000, 200000, 400000, 800000,
+];
+
+// Only treat speed mismatch as rounding when desired speed is not a link mode
+// speed and driver picked the nearest link mode speed instead.
+fn is_speed_rounded(desired: u32, current: u32) -> bool {
+    !ETHTOOL_LINK_MODE_SPEEDS.contains(&desired)
+        && ETHTOOL_LINK_MODE_SPEEDS
+            .iter()
+            .min_by_key(|s| s.abs_diff(desired))
+            == Some(&current)
+}
+
 impl EthernetConfig {
     pub(crate) fn update(&mut self, other: Option<&EthernetConfig>) {
         if let Some(other) = other {
@@ -61,6 +143,15 @@ impl EthernetConfig {
             } else {
                 self.sr_iov.clone_from(&other.sr_iov)
             }
+            if other.auto_neg.is_some() {
+                self.auto_neg = other.auto_neg;
+            }
+            if other.speed.is_some() {
+                self.speed = other.speed;
+            }
+            if other.duplex.is_some() {
+                self.duplex = other.duplex;
+            }
         }
     }
 }
@@ -145,7 +236,7 @@ impl NetworkState {
 
     // Return newly create NetworkState containing only ethernet section of
     // interface with SR-IOV PF changes.
-    pub(crate) fn get_sriov_pf_conf_state(&self) -> Option<Self> {
+    pub fn get_sriov_pf_conf_state(&self) -> Option<Self> {
         let mut pf_ifaces: Vec<Interface> = Vec::new();
 
         for iface in self.interfaces.kernel_ifaces.values().filter_map(|i| {
@@ -24,12 +24,19 @@ impl Interface {
             iface.sanitize_desired_for_verify();
         } else if let Interface::Hsr(iface) = self {
             iface.sanitize_desired_for_verify();
+        } else if let Interface::MacSec(iface) = self {
+            iface.sanitize_desired_for_verify();
         }
     }
 
     pub(crate) fn verify(&mut self, current: &Self) -> Result<(), NipartError> {
         let mut current = current.clone();
         self.process_allow_extra_address(&mut current);
+        if let (Interface::Ethernet(des), Interface::Ethernet(cur)) =
+            (&mut *self, &current)
+        {
+            des.process_link_mode_for_verify(cur)?;
+        }
 
         let self_value = serde_json::to_value(self.clone())?;
         let current_value = serde_json::to_value(current.clone())?;
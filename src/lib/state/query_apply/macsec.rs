@@ -10,6 +10,14 @@ impl MacSecInterface {
             self.macsec.clone_from(&other.macsec);
         }
     }
+
+    // The MKA secrets are not stored in kernel, hence cannot be verified.
+    pub(crate) fn sanitize_desired_for_verify(&mut self) {
+        if let Some(conf) = &mut self.macsec {
+            conf.mka_cak = None;
+            conf.mka_ckn = None;
+        }
+    }
 }
 
 impl MacSecConfig {
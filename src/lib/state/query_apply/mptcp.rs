@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::MptcpConfig;
+use crate::{
+    ErrorKind, MergedMptcpLimits, MptcpConfig, MptcpLimits, NipartError,
+};
 
 impl MptcpConfig {
     pub(crate) fn sanitize_desired_for_verify(&mut self) {
@@ -17,3 +19,55 @@ impl MptcpConfig {
         }
     }
 }
+
+impl MptcpLimits {
+    pub(crate) fn update(&mut self, other: &Self) {
+        if other.subflows.is_some() {
+            self.subflows = other.subflows;
+        }
+        if other.add_addr_accepted.is_some() {
+            self.add_addr_accepted = other.add_addr_accepted;
+        }
+    }
+}
+
+impl MergedMptcpLimits {
+    pub fn is_changed(&self) -> bool {
+        if let Some(desired) = self.desired.as_ref() {
+            let current = self.current.clone().unwrap_or_default();
+            (desired.subflows.is_some() && desired.subflows != current.subflows)
+                || (desired.add_addr_accepted.is_some()
+                    && desired.add_addr_accepted != current.add_addr_accepted)
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn verify(
+        &self,
+        current: Option<&MptcpLimits>,
+    ) -> Result<(), NipartError> {
+        let desired = if let Some(d) = &self.desired {
+            d
+        } else {
+            return Ok(());
+        };
+        let current = current.cloned().unwrap_or_default();
+
+        if (desired.subflows.is_some() && desired.subflows != current.subflows)
+            || (desired.add_addr_accepted.is_some()
+                && desired.add_addr_accepted != current.add_addr_accepted)
+        {
+            let e = NipartError::new(
+                ErrorKind::VerificationError,
+                format!(
+                    "Verification fail, desire mptcp-limits: {desired:?}, \
+                    current: {current:?}"
+                ),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        Ok(())
+    }
+}
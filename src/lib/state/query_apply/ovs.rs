@@ -213,7 +213,7 @@ impl MergedInterfaces {
 }
 
 impl NetworkState {
-    pub(crate) fn isolate_ovn(&mut self) -> Result<(), NipartError> {
+    pub fn isolate_ovn(&mut self) -> Result<(), NipartError> {
         if let Some(ovn_maps_str) = self
             .ovsdb
             .as_mut()
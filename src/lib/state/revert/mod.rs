@@ -3,6 +3,7 @@
 mod dns;
 mod hostname;
 mod ifaces;
+mod mptcp;
 mod net_state;
 mod ovn;
 mod ovsdb;
@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{MergedMptcpLimits, MptcpLimits};
+
+impl MergedMptcpLimits {
+    pub(crate) fn generate_revert(&self) -> Option<MptcpLimits> {
+        if self.is_changed() {
+            self.current.clone()
+        } else {
+            None
+        }
+    }
+}
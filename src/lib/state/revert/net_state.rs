@@ -18,6 +18,7 @@ impl NetworkState {
             ovsdb: merged_state.ovsdb.generate_revert(),
             ovn: merged_state.ovn.generate_revert(),
             hostname: merged_state.hostname.generate_revert(),
+            mptcp_limits: merged_state.mptcp_limits.generate_revert(),
             ..Default::default()
         })
     }
@@ -10,9 +10,12 @@ impl MergedRoutes {
 
         let current_rts = self.current.config.as_ref().unwrap_or(&empty_vec);
 
-        // Delete added routes
+        // Delete added routes, routes already exist before apply are kept
         if let Some(config_rts) = self.desired.config.as_ref() {
-            for config_rt in config_rts.iter().filter(|r| !r.is_absent()) {
+            for config_rt in config_rts.iter().filter(|r| {
+                !r.is_absent()
+                    && !current_rts.iter().any(|cur_rt| r.is_match(cur_rt))
+            }) {
                 let mut rt = config_rt.clone();
                 rt.state = Some(RouteState::Absent);
                 revert_rts.push(rt);
@@ -232,7 +232,7 @@ impl RouteEntry {
         Self::default()
     }
 
-    pub(crate) fn is_absent(&self) -> bool {
+    pub fn is_absent(&self) -> bool {
         matches!(self.state, Some(RouteState::Absent))
     }
 
@@ -354,7 +354,7 @@ impl RouteEntry {
         Ok(())
     }
 
-    pub(crate) fn is_ipv6(&self) -> bool {
+    pub fn is_ipv6(&self) -> bool {
         self.destination.as_ref().map(|d| is_ipv6_addr(d.as_str()))
             == Some(true)
     }
@@ -548,29 +548,33 @@ impl MergedRoutes {
 
         if let Some(cur_rts) = current.config.as_ref() {
             for rt in cur_rts {
-                if let Some(via) = rt.next_hop_iface.as_ref() {
-                    // We include current route to merged_routes when it is
-                    // not marked as absent due to absent interface or disabled
-                    // ip stack or route state:absent.
-                    if ifaces_marked_as_absent.contains(&via.as_str())
-                        || (rt.is_ipv6()
-                            && ifaces_with_ipv6_disabled
-                                .contains(&via.as_str()))
-                        || (!rt.is_ipv6()
-                            && ifaces_with_ipv4_disabled
-                                .contains(&via.as_str()))
-                        || desired_routes
-                            .as_slice()
-                            .iter()
-                            .filter(|r| r.is_absent())
-                            .any(|absent_rt| absent_rt.is_match(rt))
-                    {
-                        let mut new_rt = rt.clone();
-                        new_rt.state = Some(RouteState::Absent);
-                        changed_routes.insert(new_rt);
-                    } else {
-                        merged_routes.push(rt.clone());
-                    }
+                // We include current route to merged_routes when it is
+                // not marked as absent due to absent interface or disabled
+                // ip stack or route state:absent.
+                // Route type routes(blackhole and etc) have no next hop
+                // interface and can only be removed by route state:absent.
+                let is_via_absent =
+                    rt.next_hop_iface.as_ref().is_some_and(|via| {
+                        ifaces_marked_as_absent.contains(&via.as_str())
+                            || (rt.is_ipv6()
+                                && ifaces_with_ipv6_disabled
+                                    .contains(&via.as_str()))
+                            || (!rt.is_ipv6()
+                                && ifaces_with_ipv4_disabled
+                                    .contains(&via.as_str()))
+                    });
+                if is_via_absent
+                    || desired_routes
+                        .as_slice()
+                        .iter()
+                        .filter(|r| r.is_absent())
+                        .any(|absent_rt| absent_rt.is_match(rt))
+                {
+                    let mut new_rt = rt.clone();
+                    new_rt.state = Some(RouteState::Absent);
+                    changed_routes.insert(new_rt);
+                } else {
+                    merged_routes.push(rt.clone());
                 }
             }
         }
@@ -650,6 +654,12 @@ impl MergedRoutes {
     pub fn is_changed(&self) -> bool {
         !self.route_changed_ifaces.is_empty()
     }
+
+    /// Routes to add or remove(marked as absent) for backend applying
+    /// incremental route changes.
+    pub fn changed_routes(&self) -> &[RouteEntry] {
+        self.changed_routes.as_slice()
+    }
 }
 
 // Validating if the route destination network is valid,
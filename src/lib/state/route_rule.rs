@@ -120,6 +120,9 @@ pub struct RouteRuleEntry {
     /// Incoming interface.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub iif: Option<String>,
+    /// Outgoing interface.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oif: Option<String>,
     /// Prefix length of suppressor.
     /// Can deserialize from `suppress-prefix-length` or
     /// `suppress_prefixlength`.
@@ -206,11 +209,11 @@ impl RouteRuleEntry {
         Ok(())
     }
 
-    pub(crate) fn is_absent(&self) -> bool {
+    pub fn is_absent(&self) -> bool {
         matches!(self.state, Some(RouteRuleState::Absent))
     }
 
-    pub(crate) fn is_ipv6(&self) -> bool {
+    pub fn is_ipv6(&self) -> bool {
         self.family.as_ref() == Some(&AddressFamily::IPv6)
             || self.ip_from.as_ref().map(|i| is_ipv6_addr(i.as_str()))
                 == Some(true)
@@ -300,6 +303,9 @@ impl RouteRuleEntry {
         if self.iif.is_some() && self.iif != other.iif {
             return false;
         }
+        if self.oif.is_some() && self.oif != other.oif {
+            return false;
+        }
         if self.action.is_some() && self.action != other.action {
             return false;
         }
@@ -312,10 +318,25 @@ impl RouteRuleEntry {
     }
 
     // Return tuple of (no_absent, is_ipv4, table_id, ip_from,
-    // ip_to, priority, fwmark, fwmask, action, suppress_prefix_length)
+    // ip_to, priority, fwmark, fwmask, action, suppress_prefix_length, iif,
+    // oif)
+    #[allow(clippy::type_complexity)]
     fn sort_key(
         &self,
-    ) -> (bool, bool, u32, &str, &str, i64, u32, u32, u8, u32) {
+    ) -> (
+        bool,
+        bool,
+        u32,
+        &str,
+        &str,
+        i64,
+        u32,
+        u32,
+        u8,
+        u32,
+        &str,
+        &str,
+    ) {
         (
             !matches!(self.state, Some(RouteRuleState::Absent)),
             {
@@ -343,6 +364,8 @@ impl RouteRuleEntry {
             self.fwmask.unwrap_or(0),
             self.action.map(u8::from).unwrap_or(0),
             self.suppress_prefix_length.unwrap_or_default(),
+            self.iif.as_deref().unwrap_or(""),
+            self.oif.as_deref().unwrap_or(""),
         )
     }
 
@@ -464,6 +487,9 @@ impl std::fmt::Display for RouteRuleEntry {
         if let Some(v) = self.iif.as_ref() {
             props.push(format!("iif: {v}"));
         }
+        if let Some(v) = self.oif.as_ref() {
+            props.push(format!("oif: {v}"));
+        }
         if let Some(v) = self.action.as_ref() {
             props.push(format!("action: {v}"));
         }
@@ -589,11 +615,10 @@ impl MergedRouteRules {
             .collect();
 
         self.for_apply.retain(|rule| {
-            if let Some(iif) = rule.iif.as_ref() {
-                !ignored_ifaces.contains(&iif.as_str())
-            } else {
-                true
-            }
+            [rule.iif.as_ref(), rule.oif.as_ref()]
+                .iter()
+                .flatten()
+                .all(|i| !ignored_ifaces.contains(&i.as_str()))
         })
     }
 
@@ -602,6 +627,11 @@ impl MergedRouteRules {
             && (self.for_apply
                 != self.current.config.clone().unwrap_or_default())
     }
+
+    /// Desired route rules and current route rules marked as absent.
+    pub fn rules_for_apply(&self) -> &[RouteRuleEntry] {
+        self.for_apply.as_slice()
+    }
 }
 
 fn set_auto_priority(
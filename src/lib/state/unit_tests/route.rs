@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    MergedNetworkState, NetworkState, RouteEntry, RouteState, RouteType,
+};
+
+#[test]
+fn test_remove_blackhole_route() {
+    let cur_state: NetworkState = serde_yaml::from_str(
+        r"---
+        routes:
+          config:
+          - destination: 198.51.100.0/24
+            route-type: blackhole
+            metric: 1024
+            table-id: 254
+          - destination: 203.0.113.0/24
+            route-type: blackhole
+            metric: 1024
+            table-id: 254
+        ",
+    )
+    .unwrap();
+    let des_state: NetworkState = serde_yaml::from_str(
+        r"---
+        routes:
+          config:
+          - destination: 198.51.100.0/24
+            route-type: blackhole
+            state: absent
+        ",
+    )
+    .unwrap();
+
+    let merged_state =
+        MergedNetworkState::new(des_state, cur_state, false, false).unwrap();
+
+    assert!(merged_state.routes.is_changed());
+    let changed_routes = merged_state.routes.changed_routes();
+    assert_eq!(changed_routes.len(), 1);
+    let rt: &RouteEntry = &changed_routes[0];
+    assert_eq!(rt.state, Some(RouteState::Absent));
+    assert_eq!(rt.destination.as_deref(), Some("198.51.100.0/24"));
+    assert_eq!(rt.route_type, Some(RouteType::Blackhole));
+
+    let lo_routes = merged_state.routes.merged.get("lo").unwrap();
+    assert_eq!(lo_routes.len(), 1);
+    assert_eq!(lo_routes[0].destination.as_deref(), Some("203.0.113.0/24"));
+}
@@ -8,24 +8,31 @@ pub struct NipartQueryOption {
     /// Which kind of NetworkState to query
     #[serde(default)]
     pub kind: NipartStateKind,
+    /// Include secrets like MACsec MKA CAK or 802.1X private key password
+    /// in reply. By default, secrets are replaced by placeholder string.
+    #[serde(default)]
+    pub include_secrets: bool,
 }
 
 impl NipartQueryOption {
     pub fn running() -> Self {
         Self {
             kind: NipartStateKind::RunningNetworkState,
+            include_secrets: false,
         }
     }
 
     pub fn saved() -> Self {
         Self {
             kind: NipartStateKind::SavedNetworkState,
+            include_secrets: false,
         }
     }
 
     pub fn post_last_commit() -> Self {
         Self {
             kind: NipartStateKind::PostLastCommitNetworkState,
+            include_secrets: false,
         }
     }
 }
@@ -62,8 +69,10 @@ impl std::fmt::Display for NipartStateKind {
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct NipartApplyOption {
-    // Seconds to rollback desired state after applied.
-    //pub revert_after: u32,
+    /// Seconds to revert the applied state unless confirmed by
+    /// [crate::NipartUserEvent::ConfirmCommit]. 0 means never revert.
+    #[serde(default)]
+    pub revert_after: u32,
     /// Do not store desired state to persistent
     pub memory_only: bool,
     /// Do not verify whether post applied state matches with desired state.
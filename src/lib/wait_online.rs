@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ErrorKind, NipartConnection, NipartError, NipartEvent, NipartEventAddress,
+    NipartPluginEvent, NipartUserEvent,
+};
+
+const DEFAULT_WAIT_ONLINE_TIMEOUT: u32 = 30;
+
+/// Probes to wait before consider network as online. Probes are executed in
+/// the order of the `Vec`.
+///
+/// Example YAML:
+/// ```yaml
+/// wait-onlines:
+///   - name: link
+///     type: link
+///     timeout: 30
+///     post-sleep: 1
+///     links:
+///       - all
+///   - name: nfs
+///     type: ping
+///     addresses:
+///       - 192.168.1.1
+///   - name: dns
+///     type: dns-resolve
+///     dns-resolve:
+///       - www.example.com
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct NipartWaitOnlineOption {
+    #[serde(rename = "wait-onlines", default)]
+    pub probes: Vec<NipartWaitOnlineProbe>,
+}
+
+impl NipartWaitOnlineOption {
+    /// Total timeout in seconds of all probes including the post sleep.
+    pub fn total_timeout(&self) -> u32 {
+        self.probes
+            .iter()
+            .map(|p| p.timeout.saturating_add(p.post_sleep))
+            .fold(0u32, |sum, t| sum.saturating_add(t))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct NipartWaitOnlineProbe {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: NipartWaitOnlineProbeKind,
+    /// Maximum seconds to wait for this probe to pass
+    #[serde(default = "default_wait_online_timeout")]
+    pub timeout: u32,
+    /// Seconds to sleep after probe passed
+    #[serde(default)]
+    pub post_sleep: u32,
+}
+
+fn default_wait_online_timeout() -> u32 {
+    DEFAULT_WAIT_ONLINE_TIMEOUT
+}
+
+impl std::fmt::Display for NipartWaitOnlineProbe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.name, self.kind)
+    }
+}
+
+impl NipartWaitOnlineProbe {
+    pub fn new(name: String, kind: NipartWaitOnlineProbeKind) -> Self {
+        Self {
+            name,
+            kind,
+            timeout: DEFAULT_WAIT_ONLINE_TIMEOUT,
+            post_sleep: 0,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum NipartWaitOnlineProbeKind {
+    /// Wait carrier(`IFLA_OPERSTATE`) up of specified interfaces.
+    /// [NipartWaitOnlineProbeKind::LINK_ALL] means all interfaces
+    /// except loopback and administratively down ones.
+    Link { links: Vec<String> },
+    /// Wait ARP reply from all specified IPv4 addresses.
+    /// Only on-link addresses(within the subnet of any local IPv4 address)
+    /// are supported, as ARP request is never sent to off-link address.
+    /// Probe of off-link address never passes and fails on timeout, please
+    /// use [NipartWaitOnlineProbeKind::Ping] for them instead.
+    Arp { addresses: Vec<Ipv4Addr> },
+    /// Wait ICMP echo reply from all specified IP addresses.
+    Ping { addresses: Vec<IpAddr> },
+    /// Wait all specified host names been resolved.
+    DnsResolve {
+        #[serde(rename = "dns-resolve")]
+        names: Vec<String>,
+    },
+}
+
+impl NipartWaitOnlineProbeKind {
+    pub const LINK_ALL: &'static str = "all";
+}
+
+impl std::fmt::Display for NipartWaitOnlineProbeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Link { .. } => "link",
+                Self::Arp { .. } => "arp",
+                Self::Ping { .. } => "ping",
+                Self::DnsResolve { .. } => "dns_resolve",
+            }
+        )
+    }
+}
+
+impl NipartConnection {
+    /// Block till all probes passed. The connection timeout will be extended
+    /// to cover the total timeout of all probes.
+    pub async fn wait_online(
+        &mut self,
+        option: NipartWaitOnlineOption,
+    ) -> Result<(), NipartError> {
+        let timeout = option
+            .total_timeout()
+            .saturating_mul(1000)
+            .saturating_add(self.timeout);
+        let request = NipartEvent::new(
+            NipartUserEvent::WaitOnline(Box::new(option)),
+            NipartPluginEvent::None,
+            NipartEventAddress::User,
+            NipartEventAddress::Daemon,
+            timeout,
+        );
+        self.send(&request).await?;
+        let event = self.recv_reply(request.uuid, timeout).await?;
+        if let NipartUserEvent::WaitOnlineReply = event.user {
+            Ok(())
+        } else {
+            Err(NipartError::new(
+                ErrorKind::Bug,
+                format!("Invalid reply {event:?} for WaitOnline"),
+            ))
+        }
+    }
+}
@@ -0,0 +1,462 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+
+use futures::stream::{StreamExt, TryStreamExt};
+use netlink_packet_core::{NetlinkMessage, NetlinkPayload};
+use netlink_packet_route::{
+    address::{
+        AddressAttribute, AddressFlag, AddressHeaderFlag, AddressMessage,
+        AddressScope,
+    },
+    link::LinkAttribute,
+    RouteNetlinkMessage,
+};
+use netlink_sys::AsyncSocket;
+use nipart::{
+    ErrorKind, NipartAddressMonitorKind, NipartAddressMonitorRule,
+    NipartAddressScope, NipartError, NipartEvent, NipartEventAddress,
+    NipartMonitorEvent, NipartNativePlugin, NipartPluginEvent, NipartUserEvent,
+};
+use tokio::{
+    sync::mpsc::{Receiver, Sender},
+    task::JoinHandle,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BaizeAddressMonitorCmd {
+    AddAddressRule(NipartAddressMonitorRule),
+    DelAddressRule(NipartAddressMonitorRule),
+}
+
+#[derive(Debug)]
+pub(crate) struct BaizeAddressMonitor {
+    thread_handler: JoinHandle<()>,
+    to_daemon: Sender<NipartEvent>,
+    to_monitor: Sender<BaizeAddressMonitorCmd>,
+}
+
+impl Drop for BaizeAddressMonitor {
+    fn drop(&mut self) {
+        self.thread_handler.abort();
+    }
+}
+
+const MPSC_CHANNLE_SIZE: usize = 1000;
+
+impl BaizeAddressMonitor {
+    pub(crate) fn new(
+        to_daemon: Sender<NipartEvent>,
+    ) -> Result<Self, NipartError> {
+        let (plugin_to_monitor_tx, plugin_to_monitor_rx) =
+            tokio::sync::mpsc::channel::<BaizeAddressMonitorCmd>(
+                MPSC_CHANNLE_SIZE,
+            );
+        let to_daemon_clone = to_daemon.clone();
+        let thread_handler = tokio::task::spawn(async move {
+            AddressMonitorThread::process(to_daemon_clone, plugin_to_monitor_rx)
+                .await
+        });
+
+        Ok(Self {
+            thread_handler,
+            to_monitor: plugin_to_monitor_tx,
+            to_daemon,
+        })
+    }
+
+    pub(crate) async fn add_address_rule(
+        &mut self,
+        rule: NipartAddressMonitorRule,
+    ) -> Result<(), NipartError> {
+        // The rule is added to monitor thread before checking current
+        // addresses, so we will not miss any event in between.
+        self.to_monitor
+            .send(BaizeAddressMonitorCmd::AddAddressRule(rule.clone()))
+            .await
+            .map_err(|e| {
+                NipartError::new(
+                    ErrorKind::Bug,
+                    format!(
+                        "Failed to send command: add rule \
+                        to monitor thread: {e}"
+                    ),
+                )
+            })?;
+
+        // Change rule only cares about changes after registration
+        if rule.kind == NipartAddressMonitorKind::Change {
+            return Ok(());
+        }
+        let cur_ip = get_matched_address(&rule).await?;
+        match rule.kind {
+            NipartAddressMonitorKind::Add => {
+                if let Some(ip) = cur_ip {
+                    self.del_address_rule(rule.clone()).await?;
+                    send_address_notify(&self.to_daemon, &rule, rule.kind, ip)
+                        .await?;
+                }
+            }
+            NipartAddressMonitorKind::Remove => {
+                // Wildcard remove rule has no address to be already removed
+                if let (Some(ip), None) = (rule.ip, cur_ip) {
+                    self.del_address_rule(rule.clone()).await?;
+                    send_address_notify(&self.to_daemon, &rule, rule.kind, ip)
+                        .await?;
+                }
+            }
+            kind => {
+                return Err(NipartError::new(
+                    ErrorKind::Bug,
+                    format!("Bug: Unknown NipartAddressMonitorKind {kind}"),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn del_address_rule(
+        &mut self,
+        rule: NipartAddressMonitorRule,
+    ) -> Result<(), NipartError> {
+        self.to_monitor
+            .send(BaizeAddressMonitorCmd::DelAddressRule(rule))
+            .await
+            .map_err(|e| {
+                NipartError::new(
+                    ErrorKind::Bug,
+                    format!(
+                        "Failed to send command: del rule \
+                        to monitor thread: {e}"
+                    ),
+                )
+            })
+    }
+}
+
+const RTNLGRP_LINK: u32 = 1;
+const RTNLGRP_IPV4_IFADDR: u32 = 5;
+const RTNLGRP_IPV6_IFADDR: u32 = 9;
+
+struct AddressMonitorThread;
+
+impl AddressMonitorThread {
+    async fn process(
+        to_daemon: Sender<NipartEvent>,
+        mut from_plugin: Receiver<BaizeAddressMonitorCmd>,
+    ) {
+        let mut rules: HashSet<NipartAddressMonitorRule> = HashSet::new();
+        // Interface might be already deleted when we got RTM_DELADDR, hence
+        // cache the interface index to name mapping. Kernel sends
+        // RTM_DELLINK after RTM_DELADDR of removed interface, so the entry is
+        // evicted on RTM_DELLINK.
+        let mut iface_names: HashMap<u32, String> = HashMap::new();
+
+        let (mut conn, handle, mut messages) = match rtnetlink::new_connection()
+        {
+            Ok(r) => r,
+            Err(e) => {
+                log::error!("Failed to start rtnetlink connection {e}");
+                return;
+            }
+        };
+
+        let addr = netlink_sys::SocketAddr::new(
+            0,
+            (1 << (RTNLGRP_LINK - 1))
+                | (1 << (RTNLGRP_IPV4_IFADDR - 1))
+                | (1 << (RTNLGRP_IPV6_IFADDR - 1)),
+        );
+
+        if let Err(e) = conn.socket_mut().socket_mut().bind(&addr) {
+            log::error!(
+                "Failed to bind RTNLGRP_LINK, RTNLGRP_IPV4_IFADDR and \
+                RTNLGRP_IPV6_IFADDR: {e}"
+            );
+            return;
+        }
+
+        tokio::spawn(conn);
+
+        loop {
+            tokio::select! {
+                Some((message, _)) = messages.next() => {
+                    Self::process_netlink_message(
+                        message,
+                        &rules,
+                        &handle,
+                        &mut iface_names,
+                        &to_daemon).await;
+                },
+                Some(cmd) = from_plugin.recv() => {
+                    match cmd {
+                        BaizeAddressMonitorCmd::AddAddressRule(rule) => {
+                            rules.insert(rule);
+                        }
+                        BaizeAddressMonitorCmd::DelAddressRule(rule) => {
+                            rules.remove(&rule);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn process_netlink_message(
+        message: NetlinkMessage<RouteNetlinkMessage>,
+        rules: &HashSet<NipartAddressMonitorRule>,
+        handle: &rtnetlink::Handle,
+        iface_names: &mut HashMap<u32, String>,
+        to_daemon: &Sender<NipartEvent>,
+    ) {
+        log::trace!("Got netlink message {message:?}");
+        let (kind, addr_msg) = match &message.payload {
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::NewAddress(
+                addr_msg,
+            )) => (NipartAddressMonitorKind::Add, addr_msg),
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::DelAddress(
+                addr_msg,
+            )) => (NipartAddressMonitorKind::Remove, addr_msg),
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::DelLink(
+                link_msg,
+            )) => {
+                iface_names.remove(&link_msg.header.index);
+                return;
+            }
+            _ => return,
+        };
+        let address = match BaizeAddress::parse(addr_msg) {
+            Some(a) => a,
+            None => return,
+        };
+        // Kernel will send RTM_NEWADDR again once IPv6 duplicate address
+        // detection finished.
+        if kind == NipartAddressMonitorKind::Add && address.tentative {
+            return;
+        }
+
+        let iface = if rules.iter().any(|r| r.iface.is_some()) {
+            if let Some(name) = get_iface_name(handle, address.index).await {
+                iface_names.insert(address.index, name);
+            }
+            iface_names.get(&address.index).map(|n| n.as_str())
+        } else {
+            None
+        };
+
+        for rule in rules.iter().filter(|r| {
+            r.kind == kind || r.kind == NipartAddressMonitorKind::Change
+        }) {
+            if address.is_match(rule, iface) {
+                if let Err(e) =
+                    send_address_notify(to_daemon, rule, kind, address.ip).await
+                {
+                    log::error!(
+                        "BUG: process_netlink_message failed \
+                        to notify {e}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BaizeAddress {
+    index: u32,
+    ip: IpAddr,
+    scope: Option<NipartAddressScope>,
+    tentative: bool,
+}
+
+impl BaizeAddress {
+    fn parse(addr_msg: &AddressMessage) -> Option<Self> {
+        // For IPv4 point-to-point interface, the `IFA_ADDRESS` is the peer
+        // address, hence prefer `IFA_LOCAL`.
+        let ip = addr_msg
+            .attributes
+            .iter()
+            .find_map(|attr| {
+                if let AddressAttribute::Local(ip) = attr {
+                    Some(*ip)
+                } else {
+                    None
+                }
+            })
+            .or_else(|| {
+                addr_msg.attributes.iter().find_map(|attr| {
+                    if let AddressAttribute::Address(ip) = attr {
+                        Some(*ip)
+                    } else {
+                        None
+                    }
+                })
+            })?;
+
+        // The `IFA_FLAGS` holds the full flags while the header only holds
+        // the first 8 bits.
+        let tentative = addr_msg
+            .attributes
+            .iter()
+            .find_map(|attr| {
+                if let AddressAttribute::Flags(flags) = attr {
+                    Some(
+                        flags.contains(&AddressFlag::Tentative)
+                            || flags.contains(&AddressFlag::Dadfailed),
+                    )
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_else(|| {
+                addr_msg
+                    .header
+                    .flags
+                    .contains(&AddressHeaderFlag::Tentative)
+                    || addr_msg
+                        .header
+                        .flags
+                        .contains(&AddressHeaderFlag::Dadfailed)
+            });
+
+        Some(Self {
+            index: addr_msg.header.index,
+            ip,
+            scope: match addr_msg.header.scope {
+                AddressScope::Universe | AddressScope::Site => {
+                    Some(NipartAddressScope::Global)
+                }
+                AddressScope::Link => Some(NipartAddressScope::Link),
+                AddressScope::Host => Some(NipartAddressScope::Host),
+                _ => None,
+            },
+            tentative,
+        })
+    }
+
+    fn is_match(
+        &self,
+        rule: &NipartAddressMonitorRule,
+        iface: Option<&str>,
+    ) -> bool {
+        (rule.ip.is_none() || rule.ip == Some(self.ip))
+            && (rule.scope.is_none() || rule.scope == self.scope)
+            && (rule.iface.is_none() || rule.iface.as_deref() == iface)
+    }
+}
+
+pub(crate) async fn get_iface_name(
+    handle: &rtnetlink::Handle,
+    index: u32,
+) -> Option<String> {
+    let mut links = handle.link().get().match_index(index).execute();
+    match links.try_next().await {
+        Ok(Some(link_msg)) => link_msg.attributes.iter().find_map(|attr| {
+            if let LinkAttribute::IfName(s) = attr {
+                Some(s.to_string())
+            } else {
+                None
+            }
+        }),
+        Ok(None) => None,
+        Err(e) => {
+            log::debug!("Failed to query interface name of index {index}: {e}");
+            None
+        }
+    }
+}
+
+async fn get_iface_index(
+    handle: &rtnetlink::Handle,
+    iface: &str,
+) -> Option<u32> {
+    let mut links = handle.link().get().match_name(iface.to_string()).execute();
+    match links.try_next().await {
+        Ok(Some(link_msg)) => Some(link_msg.header.index),
+        Ok(None) => None,
+        Err(e) => {
+            log::debug!("Failed to query interface index of {iface}: {e}");
+            None
+        }
+    }
+}
+
+// Return the first non-tentative address matching the rule.
+// If the interface does not exist, return None.
+async fn get_matched_address(
+    rule: &NipartAddressMonitorRule,
+) -> Result<Option<IpAddr>, NipartError> {
+    let (conn, handle, _) = rtnetlink::new_connection().map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to create rtnetlink connection: {e}"),
+        )
+    })?;
+    tokio::spawn(conn);
+
+    let mut request = handle.address().get();
+    if let Some(iface) = rule.iface.as_deref() {
+        match get_iface_index(&handle, iface).await {
+            Some(index) => {
+                request = request.set_link_index_filter(index);
+            }
+            None => return Ok(None),
+        }
+    }
+    if let Some(ip) = rule.ip {
+        request = request.set_address_filter(ip);
+    }
+    let mut addr_msgs = request.execute();
+    while let Some(addr_msg) = addr_msgs.try_next().await.map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to query IP addresses: {e}"),
+        )
+    })? {
+        if let Some(address) = BaizeAddress::parse(&addr_msg) {
+            if !address.tentative
+                && address.is_match(rule, rule.iface.as_deref())
+            {
+                return Ok(Some(address.ip));
+            }
+        }
+    }
+    Ok(None)
+}
+
+async fn send_address_notify(
+    to_daemon: &Sender<NipartEvent>,
+    rule: &NipartAddressMonitorRule,
+    kind: NipartAddressMonitorKind,
+    ip: IpAddr,
+) -> Result<(), NipartError> {
+    let monitor_event = match kind {
+        NipartAddressMonitorKind::Add => NipartMonitorEvent::AddressAdd(ip),
+        NipartAddressMonitorKind::Remove => {
+            NipartMonitorEvent::AddressRemove(ip)
+        }
+        kind => {
+            return Err(NipartError::new(
+                ErrorKind::Bug,
+                format!("Unknown NipartAddressMonitorKind {kind}"),
+            ));
+        }
+    };
+    let mut reply = NipartEvent::new(
+        NipartUserEvent::None,
+        NipartPluginEvent::GotMonitorEvent(Box::new(monitor_event)),
+        NipartEventAddress::Unicast(
+            crate::NipartPluginBaize::PLUGIN_NAME.to_string(),
+        ),
+        rule.requester.clone(),
+        nipart::DEFAULT_TIMEOUT,
+    );
+    reply.uuid = rule.uuid;
+    to_daemon.send(reply.clone()).await.map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to send event {reply}: {e}"),
+        )
+    })
+}
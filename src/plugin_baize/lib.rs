@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
+mod address;
 mod link;
 mod plugin;
+mod route;
+mod wait_online;
 
 pub use self::plugin::NipartPluginBaize;
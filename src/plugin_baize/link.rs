@@ -12,7 +12,10 @@ use nipart::{
     NipartNativePlugin, NipartPluginEvent, NipartUserEvent,
 };
 use tokio::{
-    sync::mpsc::{Receiver, Sender},
+    sync::{
+        broadcast,
+        mpsc::{Receiver, Sender},
+    },
     task::JoinHandle,
 };
 
@@ -27,6 +30,7 @@ pub(crate) struct BaizeLinkMonitor {
     thread_handler: JoinHandle<()>,
     to_daemon: Sender<NipartEvent>,
     to_monitor: Sender<BaizeLinkMonitorCmd>,
+    link_state_notifier: broadcast::Sender<(String, NipartLinkMonitorKind)>,
 }
 
 impl Drop for BaizeLinkMonitor {
@@ -36,6 +40,7 @@ impl Drop for BaizeLinkMonitor {
 }
 
 const MPSC_CHANNLE_SIZE: usize = 1000;
+const BROADCAST_CHANNLE_SIZE: usize = 1000;
 
 impl BaizeLinkMonitor {
     pub(crate) fn new(
@@ -45,38 +50,52 @@ impl BaizeLinkMonitor {
             tokio::sync::mpsc::channel::<BaizeLinkMonitorCmd>(
                 MPSC_CHANNLE_SIZE,
             );
+        let (link_state_notifier, _) =
+            broadcast::channel(BROADCAST_CHANNLE_SIZE);
         let to_daemon_clone = to_daemon.clone();
+        let link_state_notifier_clone = link_state_notifier.clone();
         let thread_handler = tokio::task::spawn(async move {
-            LinkMonitorThread::process(to_daemon_clone, plugin_to_monitor_rx)
-                .await
+            LinkMonitorThread::process(
+                to_daemon_clone,
+                plugin_to_monitor_rx,
+                link_state_notifier_clone,
+            )
+            .await
         });
 
         Ok(Self {
             thread_handler,
             to_monitor: plugin_to_monitor_tx,
             to_daemon,
+            link_state_notifier,
         })
     }
 
+    /// Subscribe to all link state changes noticed by monitor thread.
+    pub(crate) fn subscribe(
+        &self,
+    ) -> broadcast::Receiver<(String, NipartLinkMonitorKind)> {
+        self.link_state_notifier.subscribe()
+    }
+
     pub(crate) async fn add_link_rule(
         &mut self,
         rule: NipartLinkMonitorRule,
     ) -> Result<(), NipartError> {
-        let already_link_up = is_link_up(rule.iface.as_str()).await?;
-
         match rule.kind {
             NipartLinkMonitorKind::Up => {
-                if already_link_up {
+                if is_link_up(rule.iface.as_str()).await? {
                     send_link_notify(&self.to_daemon, &rule).await?;
                     return Ok(());
                 }
             }
             NipartLinkMonitorKind::Down => {
-                if !already_link_up {
+                if !is_link_up(rule.iface.as_str()).await? {
                     send_link_notify(&self.to_daemon, &rule).await?;
                     return Ok(());
                 }
             }
+            NipartLinkMonitorKind::Change => (),
             kind => {
                 return Err(NipartError::new(
                     ErrorKind::Bug,
@@ -126,6 +145,7 @@ impl LinkMonitorThread {
     async fn process(
         to_daemon: Sender<NipartEvent>,
         mut from_plugin: Receiver<BaizeLinkMonitorCmd>,
+        link_state_notifier: broadcast::Sender<(String, NipartLinkMonitorKind)>,
     ) {
         let mut link_rules: HashMap<String, HashSet<NipartLinkMonitorRule>> =
             HashMap::new();
@@ -156,7 +176,8 @@ impl LinkMonitorThread {
                     Self::process_netlink_message(
                         message,
                         &mut link_rules,
-                        &to_daemon).await;
+                        &to_daemon,
+                        &link_state_notifier).await;
                 },
                 Some(cmd) = from_plugin.recv() => {
                     match cmd {
@@ -185,11 +206,32 @@ impl LinkMonitorThread {
         message: NetlinkMessage<RouteNetlinkMessage>,
         rules: &mut HashMap<String, HashSet<NipartLinkMonitorRule>>,
         to_daemon: &Sender<NipartEvent>,
+        link_state_notifier: &broadcast::Sender<(
+            String,
+            NipartLinkMonitorKind,
+        )>,
     ) {
         log::trace!("Got netlink message {message:?}");
+        if let Some(iface) = parse_iface_name_from_netlink_message(&message) {
+            if let Some(iface_rules) = rules.get(iface.as_str()) {
+                for rule in iface_rules
+                    .iter()
+                    .filter(|r| r.kind == NipartLinkMonitorKind::Change)
+                {
+                    if let Err(e) = send_link_notify(to_daemon, rule).await {
+                        log::error!(
+                            "BUG: process_netlink_message failed \
+                            to notify {e}"
+                        );
+                    }
+                }
+            }
+        }
         if let Some((iface, kind)) =
             parse_link_state_from_netlink_message(&message)
         {
+            // Error here means no subscriber, which is expected.
+            link_state_notifier.send((iface.clone(), kind)).ok();
             if let Some(iface_rules) = rules.get(iface.as_str()) {
                 for rule in iface_rules {
                     if rule.kind == kind {
@@ -228,6 +270,27 @@ async fn is_link_up(iface: &str) -> Result<bool, NipartError> {
     }
 }
 
+// Both RTM_NEWLINK and RTM_DELLINK are treated as link change
+fn parse_iface_name_from_netlink_message(
+    message: &NetlinkMessage<RouteNetlinkMessage>,
+) -> Option<String> {
+    if let NetlinkPayload::InnerMessage(
+        RouteNetlinkMessage::NewLink(link_msg)
+        | RouteNetlinkMessage::DelLink(link_msg),
+    ) = &message.payload
+    {
+        link_msg.attributes.as_slice().iter().find_map(|attr| {
+            if let LinkAttribute::IfName(s) = attr {
+                Some(s.to_string())
+            } else {
+                None
+            }
+        })
+    } else {
+        None
+    }
+}
+
 fn parse_link_state_from_netlink_message(
     message: &NetlinkMessage<RouteNetlinkMessage>,
 ) -> Option<(String, NipartLinkMonitorKind)> {
@@ -274,6 +337,9 @@ async fn send_link_notify(
         NipartLinkMonitorKind::Down => {
             NipartMonitorEvent::LinkDown(rule.iface.clone())
         }
+        NipartLinkMonitorKind::Change => {
+            NipartMonitorEvent::LinkChange(rule.iface.clone())
+        }
         kind => {
             return Err(NipartError::new(
                 ErrorKind::Bug,
@@ -6,7 +6,10 @@ use nipart::{
 };
 use tokio::sync::mpsc::{Receiver, Sender};
 
-use crate::link::BaizeLinkMonitor;
+use crate::{
+    address::BaizeAddressMonitor, link::BaizeLinkMonitor,
+    route::BaizeRouteMonitor, wait_online::handle_wait_online,
+};
 
 #[derive(Debug)]
 pub struct NipartPluginBaize {
@@ -14,6 +17,8 @@ pub struct NipartPluginBaize {
     to_daemon: Sender<NipartEvent>,
     from_daemon: Receiver<NipartEvent>,
     link_monitor: BaizeLinkMonitor,
+    address_monitor: BaizeAddressMonitor,
+    route_monitor: BaizeRouteMonitor,
 }
 
 impl NipartNativePlugin for NipartPluginBaize {
@@ -36,7 +41,9 @@ impl NipartNativePlugin for NipartPluginBaize {
             log_level,
             to_daemon: to_daemon.clone(),
             from_daemon,
-            link_monitor: BaizeLinkMonitor::new(to_daemon)?,
+            link_monitor: BaizeLinkMonitor::new(to_daemon.clone())?,
+            address_monitor: BaizeAddressMonitor::new(to_daemon.clone())?,
+            route_monitor: BaizeRouteMonitor::new(to_daemon)?,
         })
     }
 
@@ -49,7 +56,7 @@ impl NipartNativePlugin for NipartPluginBaize {
     }
 
     fn roles() -> Vec<NipartRole> {
-        vec![NipartRole::Monitor]
+        vec![NipartRole::Monitor, NipartRole::WaitOnline]
     }
 
     async fn handle_event(
@@ -65,6 +72,22 @@ impl NipartNativePlugin for NipartPluginBaize {
                 log::trace!("Registering monitor rule {rule:?}");
                 self.remove_monitor_rule(*rule).await?;
             }
+            NipartPluginEvent::WaitOnline(probe) => {
+                log::trace!("Waiting online probe {probe:?}");
+                // Probe might take long time, hence spawn new thread instead
+                // of blocking here
+                let link_events = self.link_monitor.subscribe();
+                let to_daemon_clone = self.sender_to_daemon().clone();
+                tokio::spawn(async move {
+                    handle_wait_online(
+                        *probe,
+                        link_events,
+                        to_daemon_clone,
+                        event.uuid,
+                    )
+                    .await
+                });
+            }
             _ => log::warn!("Plugin baize got unknown event {event}"),
         }
         Ok(())
@@ -80,6 +103,15 @@ impl NipartPluginBaize {
             NipartMonitorRule::Link(rule) => {
                 self.link_monitor.add_link_rule(rule).await
             }
+            NipartMonitorRule::Address(rule) => {
+                self.address_monitor.add_address_rule(rule).await
+            }
+            NipartMonitorRule::Route(rule) => {
+                self.route_monitor.add_route(rule).await
+            }
+            NipartMonitorRule::RouteRule(rule) => {
+                self.route_monitor.add_route_rule(rule).await
+            }
             _ => {
                 log::error!("TODO: register_monitor_rule() {rule}");
                 Ok(())
@@ -96,6 +128,18 @@ impl NipartPluginBaize {
                 self.link_monitor.del_link_rule(rule).await?;
                 Ok(())
             }
+            NipartMonitorRule::Address(rule) => {
+                self.address_monitor.del_address_rule(rule).await?;
+                Ok(())
+            }
+            NipartMonitorRule::Route(rule) => {
+                self.route_monitor.del_route(rule).await?;
+                Ok(())
+            }
+            NipartMonitorRule::RouteRule(rule) => {
+                self.route_monitor.del_route_rule(rule).await?;
+                Ok(())
+            }
             _ => {
                 log::error!("TODO: remove_monitor_rule() {rule}");
                 Ok(())
@@ -0,0 +1,540 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+
+use futures::stream::StreamExt;
+use netlink_packet_core::{NetlinkMessage, NetlinkPayload};
+use netlink_packet_route::{
+    route::{
+        RouteAddress, RouteAttribute, RouteHeader, RouteMessage, RouteType,
+        RouteVia,
+    },
+    rule::{RuleAction, RuleAttribute, RuleMessage},
+    AddressFamily, RouteNetlinkMessage,
+};
+use netlink_sys::AsyncSocket;
+use nipart::{
+    ErrorKind, NipartError, NipartEvent, NipartEventAddress,
+    NipartMonitorEvent, NipartNativePlugin, NipartPluginEvent,
+    NipartRouteMonitorKind, NipartRouteMonitorRule, NipartRouteRuleMonitorRule,
+    NipartUserEvent, NipartUuid, RouteEntry, RouteRuleAction, RouteRuleEntry,
+};
+use tokio::{
+    sync::mpsc::{Receiver, Sender},
+    task::JoinHandle,
+};
+
+use crate::address::get_iface_name;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BaizeRouteMonitorCmd {
+    AddRoute(NipartRouteMonitorRule),
+    DelRoute(NipartRouteMonitorRule),
+    AddRouteRule(NipartRouteRuleMonitorRule),
+    DelRouteRule(NipartRouteRuleMonitorRule),
+}
+
+/// Monitor on both route and route rule changes.
+#[derive(Debug)]
+pub(crate) struct BaizeRouteMonitor {
+    thread_handler: JoinHandle<()>,
+    to_monitor: Sender<BaizeRouteMonitorCmd>,
+}
+
+impl Drop for BaizeRouteMonitor {
+    fn drop(&mut self) {
+        self.thread_handler.abort();
+    }
+}
+
+const MPSC_CHANNLE_SIZE: usize = 1000;
+
+impl BaizeRouteMonitor {
+    pub(crate) fn new(
+        to_daemon: Sender<NipartEvent>,
+    ) -> Result<Self, NipartError> {
+        let (plugin_to_monitor_tx, plugin_to_monitor_rx) =
+            tokio::sync::mpsc::channel::<BaizeRouteMonitorCmd>(
+                MPSC_CHANNLE_SIZE,
+            );
+        let thread_handler = tokio::task::spawn(async move {
+            RouteMonitorThread::process(to_daemon, plugin_to_monitor_rx).await
+        });
+
+        Ok(Self {
+            thread_handler,
+            to_monitor: plugin_to_monitor_tx,
+        })
+    }
+
+    pub(crate) async fn add_route(
+        &mut self,
+        rule: NipartRouteMonitorRule,
+    ) -> Result<(), NipartError> {
+        self.send_cmd(BaizeRouteMonitorCmd::AddRoute(rule)).await
+    }
+
+    pub(crate) async fn del_route(
+        &mut self,
+        rule: NipartRouteMonitorRule,
+    ) -> Result<(), NipartError> {
+        self.send_cmd(BaizeRouteMonitorCmd::DelRoute(rule)).await
+    }
+
+    pub(crate) async fn add_route_rule(
+        &mut self,
+        rule: NipartRouteRuleMonitorRule,
+    ) -> Result<(), NipartError> {
+        self.send_cmd(BaizeRouteMonitorCmd::AddRouteRule(rule))
+            .await
+    }
+
+    pub(crate) async fn del_route_rule(
+        &mut self,
+        rule: NipartRouteRuleMonitorRule,
+    ) -> Result<(), NipartError> {
+        self.send_cmd(BaizeRouteMonitorCmd::DelRouteRule(rule))
+            .await
+    }
+
+    async fn send_cmd(
+        &mut self,
+        cmd: BaizeRouteMonitorCmd,
+    ) -> Result<(), NipartError> {
+        self.to_monitor.send(cmd).await.map_err(|e| {
+            NipartError::new(
+                ErrorKind::Bug,
+                format!("Failed to send command to route monitor thread: {e}"),
+            )
+        })
+    }
+}
+
+const RTNLGRP_LINK: u32 = 1;
+const RTNLGRP_IPV4_ROUTE: u32 = 7;
+const RTNLGRP_IPV4_RULE: u32 = 8;
+const RTNLGRP_IPV6_ROUTE: u32 = 11;
+const RTNLGRP_IPV6_RULE: u32 = 19;
+
+const RT_TABLE_LOCAL: u32 = 255;
+
+const IPV4_DEFAULT_GATEWAY: &str = "0.0.0.0/0";
+const IPV6_DEFAULT_GATEWAY: &str = "::/0";
+const IPV4_EMPTY_NEXT_HOP_ADDRESS: &str = "0.0.0.0";
+const IPV6_EMPTY_NEXT_HOP_ADDRESS: &str = "::";
+
+struct RouteMonitorThread;
+
+impl RouteMonitorThread {
+    async fn process(
+        to_daemon: Sender<NipartEvent>,
+        mut from_plugin: Receiver<BaizeRouteMonitorCmd>,
+    ) {
+        let mut route_rules: HashSet<NipartRouteMonitorRule> = HashSet::new();
+        let mut rule_rules: HashSet<NipartRouteRuleMonitorRule> =
+            HashSet::new();
+        // Interface might be already deleted when we got RTM_DELROUTE, hence
+        // cache the interface index to name mapping. Kernel sends
+        // RTM_DELLINK after flushing routes of removed interface, so the
+        // entry is evicted on RTM_DELLINK.
+        let mut iface_names: HashMap<u32, String> = HashMap::new();
+
+        let (mut conn, handle, mut messages) = match rtnetlink::new_connection()
+        {
+            Ok(r) => r,
+            Err(e) => {
+                log::error!("Failed to start rtnetlink connection {e}");
+                return;
+            }
+        };
+
+        let addr = netlink_sys::SocketAddr::new(
+            0,
+            (1 << (RTNLGRP_LINK - 1))
+                | (1 << (RTNLGRP_IPV4_ROUTE - 1))
+                | (1 << (RTNLGRP_IPV4_RULE - 1))
+                | (1 << (RTNLGRP_IPV6_ROUTE - 1))
+                | (1 << (RTNLGRP_IPV6_RULE - 1)),
+        );
+
+        if let Err(e) = conn.socket_mut().socket_mut().bind(&addr) {
+            log::error!(
+                "Failed to bind RTNLGRP link, route and rule groups: {e}"
+            );
+            return;
+        }
+
+        tokio::spawn(conn);
+
+        loop {
+            tokio::select! {
+                Some((message, _)) = messages.next() => {
+                    Self::process_netlink_message(
+                        message,
+                        &route_rules,
+                        &rule_rules,
+                        &handle,
+                        &mut iface_names,
+                        &to_daemon).await;
+                },
+                Some(cmd) = from_plugin.recv() => {
+                    match cmd {
+                        BaizeRouteMonitorCmd::AddRoute(rule) => {
+                            route_rules.insert(rule);
+                        }
+                        BaizeRouteMonitorCmd::DelRoute(rule) => {
+                            route_rules.remove(&rule);
+                        }
+                        BaizeRouteMonitorCmd::AddRouteRule(rule) => {
+                            rule_rules.insert(rule);
+                        }
+                        BaizeRouteMonitorCmd::DelRouteRule(rule) => {
+                            rule_rules.remove(&rule);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn process_netlink_message(
+        message: NetlinkMessage<RouteNetlinkMessage>,
+        route_rules: &HashSet<NipartRouteMonitorRule>,
+        rule_rules: &HashSet<NipartRouteRuleMonitorRule>,
+        handle: &rtnetlink::Handle,
+        iface_names: &mut HashMap<u32, String>,
+        to_daemon: &Sender<NipartEvent>,
+    ) {
+        log::trace!("Got netlink message {message:?}");
+        let mut events: Vec<(
+            NipartMonitorEvent,
+            NipartEventAddress,
+            NipartUuid,
+        )> = Vec::new();
+        match &message.payload {
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::NewRoute(
+                route_msg,
+            )) if !route_rules.is_empty() => {
+                for route in
+                    parse_route_msg(route_msg, handle, iface_names).await
+                {
+                    for rule in route_rules.iter().filter(|r| {
+                        r.kind == NipartRouteMonitorKind::Add
+                            && r.is_match(&route)
+                    }) {
+                        events.push((
+                            NipartMonitorEvent::RouteAdd(Box::new(
+                                route.clone(),
+                            )),
+                            rule.requester.clone(),
+                            rule.uuid,
+                        ));
+                    }
+                }
+            }
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::DelRoute(
+                route_msg,
+            )) if !route_rules.is_empty() => {
+                for route in
+                    parse_route_msg(route_msg, handle, iface_names).await
+                {
+                    for rule in route_rules.iter().filter(|r| {
+                        r.kind == NipartRouteMonitorKind::Remove
+                            && r.is_match(&route)
+                    }) {
+                        events.push((
+                            NipartMonitorEvent::RouteRemove(Box::new(
+                                route.clone(),
+                            )),
+                            rule.requester.clone(),
+                            rule.uuid,
+                        ));
+                    }
+                }
+            }
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::NewRule(
+                rule_msg,
+            )) => {
+                if let Some(route_rule) = parse_rule_msg(rule_msg) {
+                    for rule in rule_rules.iter().filter(|r| {
+                        r.kind == NipartRouteMonitorKind::Add
+                            && r.is_match(&route_rule)
+                    }) {
+                        events.push((
+                            NipartMonitorEvent::RouteRuleAdd(Box::new(
+                                route_rule.clone(),
+                            )),
+                            rule.requester.clone(),
+                            rule.uuid,
+                        ));
+                    }
+                }
+            }
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::DelRule(
+                rule_msg,
+            )) => {
+                if let Some(route_rule) = parse_rule_msg(rule_msg) {
+                    for rule in rule_rules.iter().filter(|r| {
+                        r.kind == NipartRouteMonitorKind::Remove
+                            && r.is_match(&route_rule)
+                    }) {
+                        events.push((
+                            NipartMonitorEvent::RouteRuleRemove(Box::new(
+                                route_rule.clone(),
+                            )),
+                            rule.requester.clone(),
+                            rule.uuid,
+                        ));
+                    }
+                }
+            }
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::DelLink(
+                link_msg,
+            )) => {
+                iface_names.remove(&link_msg.header.index);
+            }
+            _ => (),
+        }
+        for (monitor_event, requester, uuid) in events {
+            if let Err(e) =
+                send_route_notify(to_daemon, monitor_event, requester, uuid)
+                    .await
+            {
+                log::error!(
+                    "BUG: process_netlink_message failed \
+                    to notify {e}"
+                );
+            }
+        }
+    }
+}
+
+// Multipath route will be flatten to multiple RouteEntry.
+// Kernel local routes and routes of unsupported type are ignored.
+async fn parse_route_msg(
+    route_msg: &RouteMessage,
+    handle: &rtnetlink::Handle,
+    iface_names: &mut HashMap<u32, String>,
+) -> Vec<RouteEntry> {
+    let mut ret = Vec::new();
+    let is_ipv6 = match route_msg.header.address_family {
+        AddressFamily::Inet => false,
+        AddressFamily::Inet6 => true,
+        _ => return ret,
+    };
+    let route_type = match route_msg.header.kind {
+        RouteType::Unicast => None,
+        RouteType::BlackHole => Some(nipart::RouteType::Blackhole),
+        RouteType::Unreachable => Some(nipart::RouteType::Unreachable),
+        RouteType::Prohibit => Some(nipart::RouteType::Prohibit),
+        _ => return ret,
+    };
+    let table_id = route_msg
+        .attributes
+        .iter()
+        .find_map(|attr| {
+            if let RouteAttribute::Table(t) = attr {
+                Some(*t)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| u32::from(route_msg.header.table));
+    if table_id == RT_TABLE_LOCAL
+        || table_id == u32::from(RouteHeader::RT_TABLE_UNSPEC)
+    {
+        return ret;
+    }
+
+    let mut route = RouteEntry::new();
+    route.table_id = Some(table_id);
+    route.route_type = route_type;
+    route.destination = Some(
+        route_msg
+            .attributes
+            .iter()
+            .find_map(|attr| {
+                if let RouteAttribute::Destination(dst) = attr {
+                    route_address_to_ip(dst).map(|ip| {
+                        format!(
+                            "{ip}/{}",
+                            route_msg.header.destination_prefix_length
+                        )
+                    })
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_else(|| {
+                if is_ipv6 {
+                    IPV6_DEFAULT_GATEWAY.to_string()
+                } else {
+                    IPV4_DEFAULT_GATEWAY.to_string()
+                }
+            }),
+    );
+    route.metric = route_msg.attributes.iter().find_map(|attr| {
+        if let RouteAttribute::Priority(m) = attr {
+            Some(i64::from(*m))
+        } else {
+            None
+        }
+    });
+    if route_type.is_some() {
+        ret.push(route);
+        return ret;
+    }
+
+    let multipath = route_msg.attributes.iter().find_map(|attr| {
+        if let RouteAttribute::MultiPath(hops) = attr {
+            Some(hops)
+        } else {
+            None
+        }
+    });
+    if let Some(hops) = multipath {
+        for hop in hops {
+            let mut hop_route = route.clone();
+            hop_route.next_hop_iface =
+                resolve_iface_name(handle, iface_names, hop.interface_index)
+                    .await;
+            hop_route.next_hop_addr =
+                Some(get_next_hop_addr(hop.attributes.as_slice(), is_ipv6));
+            hop_route.weight = Some(u16::from(hop.hops) + 1);
+            ret.push(hop_route);
+        }
+    } else {
+        if let Some(oif) = route_msg.attributes.iter().find_map(|attr| {
+            if let RouteAttribute::Oif(i) = attr {
+                Some(*i)
+            } else {
+                None
+            }
+        }) {
+            route.next_hop_iface =
+                resolve_iface_name(handle, iface_names, oif).await;
+        }
+        route.next_hop_addr =
+            Some(get_next_hop_addr(route_msg.attributes.as_slice(), is_ipv6));
+        ret.push(route);
+    }
+    ret
+}
+
+fn get_next_hop_addr(attributes: &[RouteAttribute], is_ipv6: bool) -> String {
+    attributes
+        .iter()
+        .find_map(|attr| match attr {
+            RouteAttribute::Gateway(gw) => route_address_to_ip(gw),
+            RouteAttribute::Via(RouteVia::Inet(ip)) => Some(IpAddr::V4(*ip)),
+            RouteAttribute::Via(RouteVia::Inet6(ip)) => Some(IpAddr::V6(*ip)),
+            _ => None,
+        })
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| {
+            if is_ipv6 {
+                IPV6_EMPTY_NEXT_HOP_ADDRESS.to_string()
+            } else {
+                IPV4_EMPTY_NEXT_HOP_ADDRESS.to_string()
+            }
+        })
+}
+
+fn route_address_to_ip(address: &RouteAddress) -> Option<IpAddr> {
+    match address {
+        RouteAddress::Inet(ip) => Some(IpAddr::V4(*ip)),
+        RouteAddress::Inet6(ip) => Some(IpAddr::V6(*ip)),
+        _ => None,
+    }
+}
+
+async fn resolve_iface_name(
+    handle: &rtnetlink::Handle,
+    iface_names: &mut HashMap<u32, String>,
+    index: u32,
+) -> Option<String> {
+    if let Some(name) = get_iface_name(handle, index).await {
+        iface_names.insert(index, name);
+    }
+    iface_names.get(&index).cloned()
+}
+
+// Route rules with action other than table lookup, blackhole, unreachable
+// and prohibit are ignored.
+fn parse_rule_msg(rule_msg: &RuleMessage) -> Option<RouteRuleEntry> {
+    let mut rule = RouteRuleEntry::new();
+    rule.family = match rule_msg.header.family {
+        AddressFamily::Inet => Some(nipart::AddressFamily::IPv4),
+        AddressFamily::Inet6 => Some(nipart::AddressFamily::IPv6),
+        _ => return None,
+    };
+    rule.action = match rule_msg.header.action {
+        RuleAction::ToTable => None,
+        RuleAction::Blackhole => Some(RouteRuleAction::Blackhole),
+        RuleAction::Unreachable => Some(RouteRuleAction::Unreachable),
+        RuleAction::Prohibit => Some(RouteRuleAction::Prohibit),
+        _ => return None,
+    };
+    for attr in rule_msg.attributes.as_slice() {
+        match attr {
+            RuleAttribute::Source(ip) => {
+                rule.ip_from =
+                    Some(format!("{ip}/{}", rule_msg.header.src_len));
+            }
+            RuleAttribute::Destination(ip) => {
+                rule.ip_to = Some(format!("{ip}/{}", rule_msg.header.dst_len));
+            }
+            RuleAttribute::Priority(p) => {
+                rule.priority = Some(i64::from(*p));
+            }
+            RuleAttribute::Table(t) => {
+                rule.table_id = Some(*t);
+            }
+            RuleAttribute::FwMark(m) => {
+                rule.fwmark = Some(*m);
+            }
+            RuleAttribute::FwMask(m) => {
+                rule.fwmask = Some(*m);
+            }
+            RuleAttribute::Iifname(i) => {
+                rule.iif = Some(i.to_string());
+            }
+            RuleAttribute::Oifname(i) => {
+                rule.oif = Some(i.to_string());
+            }
+            RuleAttribute::SuppressPrefixLen(l) => {
+                rule.suppress_prefix_length = Some(*l);
+            }
+            _ => (),
+        }
+    }
+    if rule.table_id.is_none() && rule.action.is_none() {
+        rule.table_id = Some(u32::from(rule_msg.header.table));
+    }
+    Some(rule)
+}
+
+async fn send_route_notify(
+    to_daemon: &Sender<NipartEvent>,
+    monitor_event: NipartMonitorEvent,
+    requester: NipartEventAddress,
+    uuid: NipartUuid,
+) -> Result<(), NipartError> {
+    let mut reply = NipartEvent::new(
+        NipartUserEvent::None,
+        NipartPluginEvent::GotMonitorEvent(Box::new(monitor_event)),
+        NipartEventAddress::Unicast(
+            crate::NipartPluginBaize::PLUGIN_NAME.to_string(),
+        ),
+        requester,
+        nipart::DEFAULT_TIMEOUT,
+    );
+    reply.uuid = uuid;
+    to_daemon.send(reply.clone()).await.map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to send event {reply}: {e}"),
+        )
+    })
+}
@@ -0,0 +1,364 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use futures::stream::TryStreamExt;
+use netlink_packet_route::neighbour::{
+    NeighbourAddress, NeighbourAttribute, NeighbourState,
+};
+use nipart::{
+    ErrorKind, NipartError, NipartEvent, NipartEventAddress,
+    NipartLinkMonitorKind, NipartNativePlugin, NipartPluginEvent,
+    NipartUserEvent, NipartUuid, NipartWaitOnlineProbe,
+    NipartWaitOnlineProbeKind,
+};
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use tokio::sync::{broadcast, mpsc::Sender};
+
+use crate::NipartPluginBaize;
+
+const PROBE_INTERVAL: Duration = Duration::from_millis(1000);
+// ARP reply to the UDP discard port is enough to populate neighbour cache.
+const ARP_TRIGGER_PORT: u16 = 9;
+
+const ICMP_ECHO_REQUEST: u8 = 8;
+const ICMP_ECHO_REPLY: u8 = 0;
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+const ICMPV6_ECHO_REPLY: u8 = 129;
+const ICMP_ECHO_PAYLOAD: &[u8; 8] = b"nipart\0\0";
+
+pub(crate) async fn handle_wait_online(
+    probe: NipartWaitOnlineProbe,
+    link_events: broadcast::Receiver<(String, NipartLinkMonitorKind)>,
+    to_daemon: Sender<NipartEvent>,
+    uuid: NipartUuid,
+) {
+    let result = match tokio::time::timeout(
+        Duration::from_secs(probe.timeout.into()),
+        run_probe(&probe, link_events),
+    )
+    .await
+    {
+        Ok(Ok(())) => {
+            log::debug!(
+                "Wait online probe {probe} passed, sleeping {} seconds",
+                probe.post_sleep
+            );
+            tokio::time::sleep(Duration::from_secs(probe.post_sleep.into()))
+                .await;
+            Ok(())
+        }
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(NipartError::new(
+            ErrorKind::Timeout,
+            format!(
+                "Timeout on waiting online probe {probe} after {} seconds",
+                probe.timeout
+            ),
+        )),
+    };
+
+    let mut reply = NipartEvent::new(
+        match result {
+            Ok(()) => NipartUserEvent::None,
+            Err(e) => NipartUserEvent::Error(e),
+        },
+        NipartPluginEvent::WaitOnlineReply,
+        NipartEventAddress::Unicast(NipartPluginBaize::PLUGIN_NAME.to_string()),
+        NipartEventAddress::Commander,
+        nipart::DEFAULT_TIMEOUT,
+    );
+    reply.uuid = uuid;
+    log::trace!("Sending reply {reply:?}");
+    if let Err(e) = to_daemon.send(reply).await {
+        log::error!("Failed to reply {e}")
+    }
+}
+
+async fn run_probe(
+    probe: &NipartWaitOnlineProbe,
+    link_events: broadcast::Receiver<(String, NipartLinkMonitorKind)>,
+) -> Result<(), NipartError> {
+    match &probe.kind {
+        NipartWaitOnlineProbeKind::Link { links } => {
+            wait_links_up(links.as_slice(), link_events).await
+        }
+        NipartWaitOnlineProbeKind::Arp { addresses } => {
+            for address in addresses {
+                wait_arp_reply(*address).await?;
+            }
+            Ok(())
+        }
+        NipartWaitOnlineProbeKind::Ping { addresses } => {
+            for address in addresses {
+                wait_ping_reply(*address).await?;
+            }
+            Ok(())
+        }
+        NipartWaitOnlineProbeKind::DnsResolve { names } => {
+            for name in names {
+                wait_dns_resolve(name.as_str()).await;
+            }
+            Ok(())
+        }
+        kind => Err(NipartError::new(
+            ErrorKind::NotSupportedError,
+            format!("Unsupported wait online probe type {kind}"),
+        )),
+    }
+}
+
+async fn wait_links_up(
+    links: &[String],
+    mut link_events: broadcast::Receiver<(String, NipartLinkMonitorKind)>,
+) -> Result<(), NipartError> {
+    // The `link_events` is subscribed before querying current link state,
+    // so we will not miss any link up event.
+    let mut pending = get_pending_links(links).await?;
+    while !pending.is_empty() {
+        log::debug!("Waiting link up of {pending:?}");
+        match link_events.recv().await {
+            Ok((iface, NipartLinkMonitorKind::Up)) => {
+                pending.remove(&iface);
+            }
+            Ok(_) => (),
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                pending = get_pending_links(links).await?;
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                return Err(NipartError::new(
+                    ErrorKind::Bug,
+                    "Link monitor thread closed".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Return interfaces not in link up state.
+async fn get_pending_links(
+    links: &[String],
+) -> Result<HashSet<String>, NipartError> {
+    let mut filter = nispor::NetStateFilter::minimum();
+    filter.iface = Some(nispor::NetStateIfaceFilter::minimum());
+    let np_state = nispor::NetState::retrieve_with_filter_async(&filter)
+        .await
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::Bug,
+                format!("Failed to retrieve nispor net state: {e}"),
+            )
+        })?;
+
+    let mut pending = HashSet::new();
+    for link in links {
+        if link == NipartWaitOnlineProbeKind::LINK_ALL {
+            for np_iface in np_state.ifaces.values() {
+                if np_iface.iface_type != nispor::IfaceType::Loopback
+                    && np_iface.flags.contains(&nispor::IfaceFlag::Up)
+                    && np_iface.state != nispor::IfaceState::Up
+                {
+                    pending.insert(np_iface.name.clone());
+                }
+            }
+        } else if np_state.ifaces.get(link.as_str()).map(|i| &i.state)
+            != Some(&nispor::IfaceState::Up)
+        {
+            pending.insert(link.to_string());
+        }
+    }
+    Ok(pending)
+}
+
+// Sending UDP packet to on-link IPv4 address will trigger kernel to send ARP
+// request, then we check whether neighbour cache is reachable.
+async fn wait_arp_reply(address: Ipv4Addr) -> Result<(), NipartError> {
+    let (conn, handle, _) = rtnetlink::new_connection().map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to create rtnetlink connection: {e}"),
+        )
+    })?;
+    tokio::spawn(conn);
+    let socket =
+        tokio::net::UdpSocket::bind("0.0.0.0:0")
+            .await
+            .map_err(|e| {
+                NipartError::new(
+                    ErrorKind::Bug,
+                    format!("Failed to create UDP socket: {e}"),
+                )
+            })?;
+    loop {
+        if let Err(e) = socket
+            .send_to(&[0u8], SocketAddr::new(address.into(), ARP_TRIGGER_PORT))
+            .await
+        {
+            log::debug!("Failed to send UDP packet to {address}: {e}");
+        }
+        tokio::time::sleep(PROBE_INTERVAL).await;
+        if is_neighbour_reachable(&handle, address).await? {
+            log::debug!("Got ARP reply from {address}");
+            return Ok(());
+        }
+    }
+}
+
+async fn is_neighbour_reachable(
+    handle: &rtnetlink::Handle,
+    address: Ipv4Addr,
+) -> Result<bool, NipartError> {
+    let mut neighbours = handle
+        .neighbours()
+        .get()
+        .set_family(rtnetlink::IpVersion::V4)
+        .execute();
+    while let Some(nl_msg) = neighbours.try_next().await.map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to query neighbour cache: {e}"),
+        )
+    })? {
+        if nl_msg.header.state == NeighbourState::Reachable
+            && nl_msg.attributes.iter().any(|attr| {
+                attr == &NeighbourAttribute::Destination(
+                    NeighbourAddress::Inet(address),
+                )
+            })
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+async fn wait_ping_reply(address: IpAddr) -> Result<(), NipartError> {
+    let mut seq: u16 = 0;
+    loop {
+        seq = seq.wrapping_add(1);
+        let start = Instant::now();
+        let got_reply =
+            tokio::task::spawn_blocking(move || ping_once(address, seq))
+                .await
+                .map_err(|e| {
+                    NipartError::new(
+                        ErrorKind::Bug,
+                        format!("Failed to join ping thread: {e}"),
+                    )
+                })?;
+        match got_reply {
+            Ok(true) => {
+                log::debug!("Got ICMP echo reply from {address}");
+                return Ok(());
+            }
+            Ok(false) => (),
+            Err(e) => {
+                log::debug!("Failed to ping {address}: {e}");
+            }
+        }
+        if let Some(remain) = PROBE_INTERVAL.checked_sub(start.elapsed()) {
+            tokio::time::sleep(remain).await;
+        }
+    }
+}
+
+// Send single ICMP echo request and wait reply for `PROBE_INTERVAL`
+fn ping_once(address: IpAddr, seq: u16) -> std::io::Result<bool> {
+    let (domain, protocol, request_type, reply_type) = if address.is_ipv4() {
+        (
+            Domain::IPV4,
+            Protocol::ICMPV4,
+            ICMP_ECHO_REQUEST,
+            ICMP_ECHO_REPLY,
+        )
+    } else {
+        (
+            Domain::IPV6,
+            Protocol::ICMPV6,
+            ICMPV6_ECHO_REQUEST,
+            ICMPV6_ECHO_REPLY,
+        )
+    };
+    let socket = Socket::new(domain, Type::RAW, Some(protocol))?;
+    socket.set_read_timeout(Some(PROBE_INTERVAL))?;
+    // Connected raw socket only receives packets from the specified address
+    socket.connect(&SockAddr::from(SocketAddr::new(address, 0)))?;
+
+    let id = (std::process::id() & 0xffff) as u16;
+    let mut packet = vec![request_type, 0, 0, 0];
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&seq.to_be_bytes());
+    packet.extend_from_slice(ICMP_ECHO_PAYLOAD);
+    // Kernel will calculate checksum for ICMPv6 raw socket
+    if address.is_ipv4() {
+        let checksum = icmp_checksum(packet.as_slice());
+        packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+    socket.send(packet.as_slice())?;
+
+    let deadline = Instant::now() + PROBE_INTERVAL;
+    let mut buffer = [0u8; 1500];
+    while Instant::now() < deadline {
+        let length = match (&socket).read(&mut buffer) {
+            Ok(l) => l,
+            Err(e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                return Ok(false);
+            }
+            Err(e) => return Err(e),
+        };
+        let data = &buffer[..length];
+        // IPv4 raw socket includes IP header
+        let icmp = if address.is_ipv4() {
+            let header_len = usize::from(data.first().unwrap_or(&0) & 0xf) * 4;
+            data.get(header_len..).unwrap_or_default()
+        } else {
+            data
+        };
+        if icmp.len() >= 8
+            && icmp[0] == reply_type
+            && icmp[4..6] == id.to_be_bytes()
+            && icmp[6..8] == seq.to_be_bytes()
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn icmp_checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = 0;
+    for chunk in data.chunks(2) {
+        let word = if chunk.len() == 2 {
+            u16::from_be_bytes([chunk[0], chunk[1]])
+        } else {
+            u16::from_be_bytes([chunk[0], 0])
+        };
+        sum += u32::from(word);
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+async fn wait_dns_resolve(name: &str) {
+    loop {
+        match tokio::net::lookup_host((name, 0)).await {
+            Ok(mut addrs) => {
+                if let Some(addr) = addrs.next() {
+                    log::debug!("Resolved {name} to {}", addr.ip());
+                    return;
+                }
+            }
+            Err(e) => log::debug!("Failed to resolve {name}: {e}"),
+        }
+        tokio::time::sleep(PROBE_INTERVAL).await;
+    }
+}
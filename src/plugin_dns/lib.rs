@@ -0,0 +1,7 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod plugin;
+mod resolv;
+mod store;
+
+pub use self::plugin::NipartPluginDns;
@@ -0,0 +1,330 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use nipart::{
+    DnsClientState, DnsState, MergedNetworkState, NetworkState,
+    NipartDhcpLease, NipartError, NipartEvent, NipartEventAddress,
+    NipartLogLevel, NipartNativePlugin, NipartPluginEvent, NipartRole,
+    NipartUserEvent, NipartUuid, DEFAULT_TIMEOUT,
+};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::resolv::{
+    gen_resolv_conf, get_resolv_conf_path, is_resolv_conf_managed_by_other,
+    read_resolv_conf, write_resolv_conf, DhcpDns,
+};
+use crate::store::{load_static_conf, store_static_conf};
+
+// Higher than nispor plugin, so DNS information overrides kernel one.
+const STATE_PRIORITY: u32 = 80;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct NipartPluginDns {
+    log_level: NipartLogLevel,
+    to_daemon: Sender<NipartEvent>,
+    from_daemon: Receiver<NipartEvent>,
+    static_conf: DnsClientState,
+    // Indexed by interface name
+    dhcp_dns: HashMap<String, DhcpDns>,
+}
+
+impl NipartNativePlugin for NipartPluginDns {
+    const PLUGIN_NAME: &'static str = "dns";
+
+    fn roles() -> Vec<NipartRole> {
+        vec![NipartRole::QueryAndApply, NipartRole::Dns]
+    }
+
+    fn recver_from_daemon(&mut self) -> &mut Receiver<NipartEvent> {
+        &mut self.from_daemon
+    }
+
+    fn sender_to_daemon(&self) -> &Sender<NipartEvent> {
+        &self.to_daemon
+    }
+
+    fn get_log_level(&self) -> NipartLogLevel {
+        self.log_level
+    }
+
+    fn set_log_level(&mut self, level: NipartLogLevel) {
+        self.log_level = level;
+    }
+
+    async fn init(
+        log_level: NipartLogLevel,
+        to_daemon: Sender<NipartEvent>,
+        from_daemon: Receiver<NipartEvent>,
+    ) -> Result<Self, NipartError> {
+        Ok(Self {
+            log_level,
+            to_daemon,
+            from_daemon,
+            static_conf: load_static_conf(),
+            dhcp_dns: HashMap::new(),
+        })
+    }
+
+    async fn handle_event(
+        &mut self,
+        event: NipartEvent,
+    ) -> Result<(), NipartError> {
+        match event.plugin {
+            NipartPluginEvent::QueryNetState(_) => {
+                self.reply(
+                    event.uuid,
+                    NipartUserEvent::None,
+                    NipartPluginEvent::QueryNetStateReply(
+                        Box::new(self.query()),
+                        STATE_PRIORITY,
+                    ),
+                )
+                .await
+            }
+            NipartPluginEvent::QueryRelatedNetState(_) => {
+                self.reply(
+                    event.uuid,
+                    event.user.clone(),
+                    NipartPluginEvent::QueryNetStateReply(
+                        Box::new(self.query()),
+                        STATE_PRIORITY,
+                    ),
+                )
+                .await
+            }
+            NipartPluginEvent::ApplyNetState(merged_state, _) => {
+                let user = match self.apply(&merged_state) {
+                    Ok(()) => NipartUserEvent::None,
+                    Err(e) => NipartUserEvent::Error(e),
+                };
+                self.reply(
+                    event.uuid,
+                    user,
+                    NipartPluginEvent::ApplyNetStateReply,
+                )
+                .await
+            }
+            NipartPluginEvent::ApplyDhcpLease(lease) => {
+                let user = match self.apply_dhcp_lease(*lease) {
+                    Ok(()) => NipartUserEvent::None,
+                    Err(e) => NipartUserEvent::Error(e),
+                };
+                self.reply(
+                    event.uuid,
+                    user,
+                    NipartPluginEvent::ApplyDhcpLeaseReply,
+                )
+                .await
+            }
+            NipartPluginEvent::RemoveDhcpLease(lease) => {
+                let user = match self.remove_dhcp_lease(*lease) {
+                    Ok(()) => NipartUserEvent::None,
+                    Err(e) => NipartUserEvent::Error(e),
+                };
+                self.reply(
+                    event.uuid,
+                    user,
+                    NipartPluginEvent::RemoveDhcpLeaseReply,
+                )
+                .await
+            }
+            _ => {
+                log::warn!("Plugin dns got unknown event {event:?}");
+                Ok(())
+            }
+        }
+    }
+}
+
+impl NipartPluginDns {
+    async fn reply(
+        &self,
+        uuid: NipartUuid,
+        user: NipartUserEvent,
+        plugin: NipartPluginEvent,
+    ) -> Result<(), NipartError> {
+        let mut reply = NipartEvent::new(
+            user,
+            plugin,
+            NipartEventAddress::Unicast(Self::PLUGIN_NAME.to_string()),
+            NipartEventAddress::Commander,
+            DEFAULT_TIMEOUT,
+        );
+        reply.uuid = uuid;
+        self.sender_to_daemon().send(reply).await?;
+        Ok(())
+    }
+
+    // The `config` is always included even empty, so verification can tell
+    // static DNS config has been purged.
+    fn query(&self) -> NetworkState {
+        let mut dns = DnsState::new();
+        dns.running = read_resolv_conf();
+        dns.config = Some(self.static_conf.clone());
+        let mut state = NetworkState::new();
+        state.dns = Some(dns);
+        state
+    }
+
+    fn apply(
+        &mut self,
+        merged_state: &MergedNetworkState,
+    ) -> Result<(), NipartError> {
+        if let Some(static_conf) = merged_state.get_desired_dns() {
+            self.render_with(&static_conf, &self.dhcp_dns)?;
+            store_static_conf(&static_conf)?;
+            self.static_conf = static_conf;
+        }
+        Ok(())
+    }
+
+    fn apply_dhcp_lease(
+        &mut self,
+        lease: NipartDhcpLease,
+    ) -> Result<(), NipartError> {
+        if let NipartDhcpLease::V4(lease) = lease {
+            let dhcp_dns = DhcpDns {
+                priority: lease.route_metric.unwrap_or_default(),
+                servers: lease.dns_srvs.iter().map(|s| s.to_string()).collect(),
+                searches: lease.search_domains.clone(),
+            };
+            if self.dhcp_dns.get(&lease.iface) == Some(&dhcp_dns) {
+                return Ok(());
+            }
+            let mut new_dhcp_dns = self.dhcp_dns.clone();
+            new_dhcp_dns.insert(lease.iface.to_string(), dhcp_dns);
+            self.render_dhcp_with(&new_dhcp_dns)?;
+            self.dhcp_dns = new_dhcp_dns;
+        }
+        Ok(())
+    }
+
+    fn remove_dhcp_lease(
+        &mut self,
+        lease: NipartDhcpLease,
+    ) -> Result<(), NipartError> {
+        if let NipartDhcpLease::V4(lease) = lease {
+            if !self.dhcp_dns.contains_key(&lease.iface) {
+                return Ok(());
+            }
+            let mut new_dhcp_dns = self.dhcp_dns.clone();
+            new_dhcp_dns.remove(&lease.iface);
+            self.render_dhcp_with(&new_dhcp_dns)?;
+            self.dhcp_dns = new_dhcp_dns;
+        }
+        Ok(())
+    }
+
+    // DHCP lease is not requested by user, hence only cache it when
+    // resolv.conf is owned by other DNS resolver. The explicit `dns:` apply
+    // still fails in that case.
+    fn render_dhcp_with(
+        &self,
+        dhcp_dns: &HashMap<String, DhcpDns>,
+    ) -> Result<(), NipartError> {
+        if is_resolv_conf_managed_by_other() {
+            log::warn!(
+                "Not updating {} with DNS information from DHCP lease as it \
+                is symlink managed by other DNS resolver",
+                get_resolv_conf_path()
+            );
+            return Ok(());
+        }
+        self.render_with(&self.static_conf, dhcp_dns)
+    }
+
+    // Only update plugin cache after file written, so query still reflect
+    // the truth on failure.
+    fn render_with(
+        &self,
+        static_conf: &DnsClientState,
+        dhcp_dns: &HashMap<String, DhcpDns>,
+    ) -> Result<(), NipartError> {
+        log::debug!(
+            "Rendering {} with static config {static_conf:?} and DHCP \
+            {dhcp_dns:?}",
+            get_resolv_conf_path()
+        );
+        write_resolv_conf(&gen_resolv_conf(static_conf, dhcp_dns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nipart::NipartDhcpLeaseV4;
+
+    use super::*;
+    use crate::resolv::TEST_ENV_LOCK;
+
+    const TEST_LEASE: &str = r#"
+iface: eth1
+ip: 192.0.2.100
+prefix_length: 24
+server_ip: 192.0.2.1
+lease_time: 3600
+gateways: []
+dns_srvs:
+  - 192.0.2.53
+search_domains:
+  - example.org
+ntp_srvs: []
+classless_routes: []
+"#;
+
+    fn new_test_plugin() -> NipartPluginDns {
+        let (to_daemon, _) = tokio::sync::mpsc::channel(1);
+        let (_, from_daemon) = tokio::sync::mpsc::channel(1);
+        NipartPluginDns {
+            log_level: NipartLogLevel::Debug,
+            to_daemon,
+            from_daemon,
+            static_conf: DnsClientState::new(),
+            dhcp_dns: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_dhcp_lease_only_cached_when_resolv_conf_is_symlink() {
+        let _lock = TEST_ENV_LOCK.lock().unwrap();
+        let tmp_dir = std::env::temp_dir();
+        let target = tmp_dir
+            .join(format!("nipart-dns-{}-lease-target", std::process::id()));
+        let file_path =
+            tmp_dir.join(format!("nipart-dns-{}-lease", std::process::id()));
+        std::fs::write(&target, "nameserver 127.0.0.53\n").unwrap();
+        std::os::unix::fs::symlink(&target, &file_path).unwrap();
+        std::env::set_var("NIPART_RESOLV_CONF_PATH", &file_path);
+
+        let lease: NipartDhcpLeaseV4 =
+            serde_yaml::from_str(TEST_LEASE).unwrap();
+        let mut plugin = new_test_plugin();
+        let lease_result =
+            plugin.apply_dhcp_lease(NipartDhcpLease::V4(lease.clone()));
+        let cached = plugin.dhcp_dns.get("eth1").cloned();
+        let explicit_result =
+            plugin.render_with(&DnsClientState::new(), &plugin.dhcp_dns);
+        let remove_result =
+            plugin.remove_dhcp_lease(NipartDhcpLease::V4(lease));
+        let target_content = std::fs::read_to_string(&target).unwrap();
+
+        std::env::remove_var("NIPART_RESOLV_CONF_PATH");
+        std::fs::remove_file(&file_path).unwrap();
+        std::fs::remove_file(&target).unwrap();
+
+        lease_result.unwrap();
+        assert_eq!(
+            cached,
+            Some(DhcpDns {
+                priority: 0,
+                servers: vec!["192.0.2.53".to_string()],
+                searches: vec!["example.org".to_string()],
+            })
+        );
+        assert!(explicit_result.is_err());
+        remove_result.unwrap();
+        assert!(plugin.dhcp_dns.is_empty());
+        assert_eq!(target_content, "nameserver 127.0.0.53\n");
+    }
+}
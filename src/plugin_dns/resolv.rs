@@ -0,0 +1,339 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use nipart::{DnsClientState, ErrorKind, NipartError};
+
+const DEFAULT_RESOLV_CONF_PATH: &str = "/etc/resolv.conf";
+// Allowing pointing to other file for testing.
+const RESOLV_CONF_PATH_ENV: &str = "NIPART_RESOLV_CONF_PATH";
+// Set to `true` to replace symlink(e.g. systemd-resolved stub resolver)
+// with file managed by nipart.
+const OVERRIDE_SYMLINK_ENV: &str = "NIPART_DNS_OVERRIDE_SYMLINK";
+
+const RESOLV_CONF_HEADER: &str = "# Generated by nipart";
+
+// Tests changing above environment variables should hold this lock as
+// environment is shared by all test threads.
+#[cfg(test)]
+pub(crate) static TEST_ENV_LOCK: std::sync::Mutex<()> =
+    std::sync::Mutex::new(());
+
+pub(crate) fn get_resolv_conf_path() -> String {
+    std::env::var(RESOLV_CONF_PATH_ENV)
+        .unwrap_or_else(|_| DEFAULT_RESOLV_CONF_PATH.to_string())
+}
+
+fn is_symlink_override_allowed() -> bool {
+    std::env::var(OVERRIDE_SYMLINK_ENV)
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or_default()
+}
+
+// Return symlink target if specified file is a symlink.
+fn get_symlink_target(file_path: &str) -> Option<String> {
+    if std::fs::symlink_metadata(file_path)
+        .ok()?
+        .file_type()
+        .is_symlink()
+    {
+        Some(
+            std::fs::read_link(file_path)
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+        )
+    } else {
+        None
+    }
+}
+
+/// Whether resolv.conf is symlink managed by other DNS resolver which
+/// nipart is not allowed to override.
+pub(crate) fn is_resolv_conf_managed_by_other() -> bool {
+    get_symlink_target(&get_resolv_conf_path()).is_some()
+        && !is_symlink_override_allowed()
+}
+
+/// DNS information provided by DHCP lease of single interface.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct DhcpDns {
+    /// Lower is better
+    pub(crate) priority: u32,
+    pub(crate) servers: Vec<String>,
+    pub(crate) searches: Vec<String>,
+}
+
+/// Static config is placed before DHCP ones, DHCP provided DNS are ordered
+/// by priority of interface, then interface name.
+pub(crate) fn gen_resolv_conf(
+    static_conf: &DnsClientState,
+    dhcp_dns: &HashMap<String, DhcpDns>,
+) -> String {
+    let mut servers: Vec<&str> = Vec::new();
+    let mut searches: Vec<&str> = Vec::new();
+
+    let mut dhcp_dns: Vec<(&String, &DhcpDns)> = dhcp_dns.iter().collect();
+    dhcp_dns.sort_unstable_by(|(a_iface, a), (b_iface, b)| {
+        (a.priority, a_iface).cmp(&(b.priority, b_iface))
+    });
+
+    for srv in static_conf
+        .server
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .chain(dhcp_dns.iter().flat_map(|(_, d)| d.servers.iter()))
+    {
+        if !servers.contains(&srv.as_str()) {
+            servers.push(srv.as_str());
+        }
+    }
+    for search in static_conf
+        .search
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .chain(dhcp_dns.iter().flat_map(|(_, d)| d.searches.iter()))
+    {
+        if !searches.contains(&search.as_str()) {
+            searches.push(search.as_str());
+        }
+    }
+    let options = static_conf.options.as_deref().unwrap_or_default();
+
+    let mut ret = format!("{RESOLV_CONF_HEADER}\n");
+    if !searches.is_empty() {
+        ret += &format!("search {}\n", searches.join(" "));
+    }
+    for srv in servers {
+        ret += &format!("nameserver {srv}\n");
+    }
+    if !options.is_empty() {
+        ret += &format!("options {}\n", options.join(" "));
+    }
+    ret
+}
+
+/// Parse the running resolver config, None if file not exist.
+pub(crate) fn read_resolv_conf() -> Option<DnsClientState> {
+    let file_path = get_resolv_conf_path();
+    let content = match std::fs::read_to_string(&file_path) {
+        Ok(c) => c,
+        Err(e) => {
+            log::debug!("Failed to read {file_path}: {e}");
+            return None;
+        }
+    };
+    let mut servers = Vec::new();
+    let mut searches = Vec::new();
+    let mut options = Vec::new();
+    for line in content.lines() {
+        let mut items = line.split_whitespace();
+        match items.next() {
+            Some("nameserver") => {
+                servers.extend(items.next().map(|s| s.to_string()))
+            }
+            // Like glibc, the last `search` or `domain` wins
+            Some("search") | Some("domain") => {
+                searches = items.map(|s| s.to_string()).collect()
+            }
+            Some("options") => options.extend(items.map(|s| s.to_string())),
+            _ => (),
+        }
+    }
+    let mut ret = DnsClientState::new();
+    ret.server = Some(servers);
+    ret.search = Some(searches);
+    ret.options = Some(options);
+    Some(ret)
+}
+
+/// Write to temporary file then rename to resolv.conf, so resolver of other
+/// process will never read half-written file.
+pub(crate) fn write_resolv_conf(content: &str) -> Result<(), NipartError> {
+    let file_path = get_resolv_conf_path();
+    if let Some(target) = get_symlink_target(&file_path) {
+        if is_symlink_override_allowed() {
+            log::info!(
+                "Replacing symlink {file_path} pointing to {target} with \
+                file managed by nipart"
+            );
+        } else {
+            let e = NipartError::new(
+                ErrorKind::DependencyError,
+                format!(
+                    "{file_path} is symlink to {target} which is managed by \
+                    other DNS resolver, please remove it or set environment \
+                    variable {OVERRIDE_SYMLINK_ENV}=true to allow nipart \
+                    overriding it"
+                ),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+    } else if std::fs::read_to_string(&file_path).ok().as_deref()
+        == Some(content)
+    {
+        log::debug!("{file_path} is already up to date");
+        return Ok(());
+    }
+
+    let tmp_file_path = format!("{file_path}.nipart.tmp");
+    let mut fd = std::fs::OpenOptions::new()
+        .read(false)
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(&tmp_file_path)
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!("Failed to open {tmp_file_path}, error: {e}"),
+            )
+        })?;
+    fd.write_all(content.as_bytes()).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to write {tmp_file_path}, error: {e}"),
+        )
+    })?;
+    std::fs::rename(&tmp_file_path, &file_path).map_err(|e| {
+        std::fs::remove_file(&tmp_file_path).ok();
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!(
+                "Failed to rename {tmp_file_path} to {file_path}, error: {e}"
+            ),
+        )
+    })?;
+    log::info!("Updated {file_path}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dhcp_dns(priority: u32, servers: &[&str], searches: &[&str]) -> DhcpDns {
+        DhcpDns {
+            priority,
+            servers: servers.iter().map(|s| s.to_string()).collect(),
+            searches: searches.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn gen_test_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("nipart-dns-{}-{name}", std::process::id()))
+            .display()
+            .to_string()
+    }
+
+    #[test]
+    fn test_gen_resolv_conf_order_and_dedup() {
+        let mut static_conf = DnsClientState::new();
+        static_conf.server = Some(vec!["192.0.2.1".to_string()]);
+        static_conf.search = Some(vec!["example.org".to_string()]);
+        static_conf.options = Some(vec!["rotate".to_string()]);
+        let dhcp = HashMap::from([
+            (
+                "eth2".to_string(),
+                dhcp_dns(100, &["192.0.2.3", "192.0.2.1"], &["b.example"]),
+            ),
+            (
+                "eth1".to_string(),
+                dhcp_dns(100, &["192.0.2.2"], &["a.example", "example.org"]),
+            ),
+            ("eth0".to_string(), dhcp_dns(200, &["192.0.2.4"], &[])),
+        ]);
+
+        assert_eq!(
+            gen_resolv_conf(&static_conf, &dhcp),
+            "# Generated by nipart\n\
+            search example.org a.example b.example\n\
+            nameserver 192.0.2.1\n\
+            nameserver 192.0.2.2\n\
+            nameserver 192.0.2.3\n\
+            nameserver 192.0.2.4\n\
+            options rotate\n"
+        );
+    }
+
+    #[test]
+    fn test_gen_resolv_conf_empty() {
+        assert_eq!(
+            gen_resolv_conf(&DnsClientState::new(), &HashMap::new()),
+            "# Generated by nipart\n"
+        );
+    }
+
+    #[test]
+    fn test_read_resolv_conf() {
+        let _lock = TEST_ENV_LOCK.lock().unwrap();
+        let file_path = gen_test_path("read");
+        std::fs::write(
+            &file_path,
+            "# comment\n\
+            nameserver 192.0.2.1\n\
+            domain old.example\n\
+            search a.example b.example\n\
+            nameserver 2001:db8::1\n\
+            options rotate\n\
+            options ndots:2\n",
+        )
+        .unwrap();
+        std::env::set_var(RESOLV_CONF_PATH_ENV, &file_path);
+
+        let dns = read_resolv_conf();
+
+        std::fs::remove_file(&file_path).unwrap();
+        assert!(read_resolv_conf().is_none());
+        std::env::remove_var(RESOLV_CONF_PATH_ENV);
+
+        let dns = dns.unwrap();
+        assert_eq!(
+            dns.server,
+            Some(vec!["192.0.2.1".to_string(), "2001:db8::1".to_string()])
+        );
+        assert_eq!(
+            dns.search,
+            Some(vec!["a.example".to_string(), "b.example".to_string()])
+        );
+        assert_eq!(
+            dns.options,
+            Some(vec!["rotate".to_string(), "ndots:2".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_write_resolv_conf_refuse_symlink() {
+        let _lock = TEST_ENV_LOCK.lock().unwrap();
+        let target = gen_test_path("symlink-target");
+        let file_path = gen_test_path("symlink");
+        std::fs::write(&target, "nameserver 127.0.0.53\n").unwrap();
+        std::os::unix::fs::symlink(&target, &file_path).unwrap();
+        std::env::set_var(RESOLV_CONF_PATH_ENV, &file_path);
+
+        let managed_by_other = is_resolv_conf_managed_by_other();
+        let result = write_resolv_conf("nameserver 192.0.2.1\n");
+        let target_content = std::fs::read_to_string(&target).unwrap();
+
+        std::env::set_var(OVERRIDE_SYMLINK_ENV, "true");
+        let managed_by_other_with_override = is_resolv_conf_managed_by_other();
+        let override_result = write_resolv_conf("nameserver 192.0.2.1\n");
+        let is_symlink_after_override =
+            get_symlink_target(&file_path).is_some();
+        std::env::remove_var(OVERRIDE_SYMLINK_ENV);
+        std::env::remove_var(RESOLV_CONF_PATH_ENV);
+        std::fs::remove_file(&file_path).unwrap();
+        std::fs::remove_file(&target).unwrap();
+
+        assert!(managed_by_other);
+        assert_eq!(result.unwrap_err().kind, ErrorKind::DependencyError);
+        assert_eq!(target_content, "nameserver 127.0.0.53\n");
+        assert!(!managed_by_other_with_override);
+        override_result.unwrap();
+        assert!(!is_symlink_after_override);
+    }
+}
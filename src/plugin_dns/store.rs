@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io::Write;
+
+use nipart::{DnsClientState, ErrorKind, NipartError};
+
+const STORE_DIR: &str = "/var/lib/nipart";
+const STATIC_CONF_STORE_PATH: &str = "/var/lib/nipart/dns.yml";
+
+/// Load static DNS config stored by previous run, corrupted file will be
+/// ignored.
+pub(crate) fn load_static_conf() -> DnsClientState {
+    let content = match std::fs::read_to_string(STATIC_CONF_STORE_PATH) {
+        Ok(c) => c,
+        Err(_) => return DnsClientState::new(),
+    };
+    match serde_yaml::from_str(&content) {
+        Ok(c) => c,
+        Err(e) => {
+            log::warn!(
+                "Ignoring corrupted DNS config file \
+                {STATIC_CONF_STORE_PATH}: {e}"
+            );
+            DnsClientState::new()
+        }
+    }
+}
+
+pub(crate) fn store_static_conf(
+    conf: &DnsClientState,
+) -> Result<(), NipartError> {
+    std::fs::create_dir_all(STORE_DIR).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to create folder {STORE_DIR}: {e}"),
+        )
+    })?;
+    let conf_yml = serde_yaml::to_string(conf).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to convert DNS config to string, error: {e}"),
+        )
+    })?;
+
+    let mut fd = std::fs::OpenOptions::new()
+        .read(false)
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(STATIC_CONF_STORE_PATH)
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!(
+                    "Failed to open DNS config file \
+                    {STATIC_CONF_STORE_PATH}, error: {e}"
+                ),
+            )
+        })?;
+
+    fd.write_all(conf_yml.as_bytes()).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!(
+                "Failed to write DNS config file {STATIC_CONF_STORE_PATH}, \
+                error: {e}"
+            ),
+        )
+    })?;
+    Ok(())
+}
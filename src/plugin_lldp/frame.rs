@@ -0,0 +1,592 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use nipart::{
+    LldpAddressFamily, LldpChassisId, LldpChassisIdType, LldpMacPhy,
+    LldpMaxFrameSize, LldpMgmtAddr, LldpMgmtAddrs, LldpNeighborTlv, LldpPortId,
+    LldpPortIdType, LldpPpvids, LldpSystemCapabilities, LldpSystemDescription,
+    LldpSystemName, LldpVlan, LldpVlans,
+};
+
+use crate::socket::{ETH_P_LLDP, LLDP_MULTICAST_MAC};
+
+const ETH_HEADER_LEN: usize = 14;
+const TLV_HEADER_LEN: usize = 2;
+const TLV_MAX_LEN: usize = 0x1ff;
+
+const TLV_TYPE_END: u8 = 0;
+const TLV_TYPE_CHASSIS_ID: u8 = 1;
+const TLV_TYPE_PORT_ID: u8 = 2;
+const TLV_TYPE_TTL: u8 = 3;
+const TLV_TYPE_SYSTEM_NAME: u8 = 5;
+const TLV_TYPE_SYSTEM_DESCRIPTION: u8 = 6;
+const TLV_TYPE_SYSTEM_CAPABILITIES: u8 = 7;
+const TLV_TYPE_MANAGEMENT_ADDRESS: u8 = 8;
+const TLV_TYPE_ORG_SPECIFIC: u8 = 127;
+
+const OUI_IEEE_802_1: [u8; 3] = [0x00, 0x80, 0xc2];
+const OUI_IEEE_802_3: [u8; 3] = [0x00, 0x12, 0x0f];
+
+const IEEE_802_1_SUBTYPE_PPVID: u8 = 2;
+const IEEE_802_1_SUBTYPE_VLAN_NAME: u8 = 3;
+const IEEE_802_3_SUBTYPE_MAC_PHY: u8 = 1;
+const IEEE_802_3_SUBTYPE_MAX_FRAME_SIZE: u8 = 4;
+
+// IANA address family numbers
+const ADDRESS_FAMILY_IP4: u8 = 1;
+const ADDRESS_FAMILY_IP6: u8 = 2;
+
+const MAC_PHY_AUTONEG_ENABLED: u8 = 1 << 1;
+
+const LLDP_SYS_CAP_STATION_ONLY: u16 = 1 << 7;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LldpNeighbor {
+    pub(crate) chassis_id: String,
+    pub(crate) port_id: String,
+    /// Zero means neighbor is shutting down LLDP
+    pub(crate) ttl: u16,
+    pub(crate) tlvs: Vec<LldpNeighborTlv>,
+}
+
+/// Parse ethernet frame holding LLDPDU. Return None if frame is not valid
+/// LLDPDU or missing mandatory Chassis ID, Port ID or TTL TLV.
+pub(crate) fn parse_lldp_frame(frame: &[u8]) -> Option<LldpNeighbor> {
+    if frame.len() < ETH_HEADER_LEN
+        || u16::from_be_bytes([frame[12], frame[13]]) != ETH_P_LLDP
+    {
+        return None;
+    }
+    let mut data = &frame[ETH_HEADER_LEN..];
+
+    let mut chassis_id: Option<LldpChassisId> = None;
+    let mut port_id: Option<LldpPortId> = None;
+    let mut ttl: Option<u16> = None;
+    let mut system_name: Option<LldpSystemName> = None;
+    let mut system_description: Option<LldpSystemDescription> = None;
+    let mut system_caps: Option<LldpSystemCapabilities> = None;
+    let mut vlans: Vec<LldpVlan> = Vec::new();
+    let mut mac_phy: Option<LldpMacPhy> = None;
+    let mut ppvids: Vec<u32> = Vec::new();
+    let mut mgmt_addrs: Vec<LldpMgmtAddr> = Vec::new();
+    let mut max_frame_size: Option<LldpMaxFrameSize> = None;
+
+    while data.len() >= TLV_HEADER_LEN {
+        let header = u16::from_be_bytes([data[0], data[1]]);
+        let tlv_type = (header >> 9) as u8;
+        let tlv_len = (header & TLV_MAX_LEN as u16) as usize;
+        if data.len() < TLV_HEADER_LEN + tlv_len {
+            log::debug!("Ignoring truncated LLDP TLV type {tlv_type}");
+            break;
+        }
+        let value = &data[TLV_HEADER_LEN..TLV_HEADER_LEN + tlv_len];
+        data = &data[TLV_HEADER_LEN + tlv_len..];
+
+        match tlv_type {
+            TLV_TYPE_END => break,
+            TLV_TYPE_CHASSIS_ID => chassis_id = parse_chassis_id(value),
+            TLV_TYPE_PORT_ID => port_id = parse_port_id(value),
+            TLV_TYPE_TTL if value.len() >= 2 => {
+                ttl = Some(u16::from_be_bytes([value[0], value[1]]));
+            }
+            TLV_TYPE_SYSTEM_NAME => {
+                system_name = Some(LldpSystemName::new(parse_string(value)));
+            }
+            TLV_TYPE_SYSTEM_DESCRIPTION => {
+                system_description =
+                    Some(LldpSystemDescription::new(parse_string(value)));
+            }
+            TLV_TYPE_SYSTEM_CAPABILITIES if value.len() >= 4 => {
+                system_caps =
+                    Some(LldpSystemCapabilities::from(u16::from_be_bytes([
+                        value[0], value[1],
+                    ])));
+            }
+            TLV_TYPE_MANAGEMENT_ADDRESS => {
+                if let Some(addr) = parse_mgmt_addr(value) {
+                    mgmt_addrs.push(addr);
+                }
+            }
+            TLV_TYPE_ORG_SPECIFIC if value.len() >= 4 => {
+                let oui = [value[0], value[1], value[2]];
+                let subtype = value[3];
+                let value = &value[4..];
+                match (oui, subtype) {
+                    (OUI_IEEE_802_1, IEEE_802_1_SUBTYPE_PPVID)
+                        if value.len() >= 3 =>
+                    {
+                        ppvids.push(
+                            u16::from_be_bytes([value[1], value[2]]).into(),
+                        );
+                    }
+                    (OUI_IEEE_802_1, IEEE_802_1_SUBTYPE_VLAN_NAME) => {
+                        if let Some(vlan) = parse_vlan_name(value) {
+                            vlans.push(vlan);
+                        }
+                    }
+                    (OUI_IEEE_802_3, IEEE_802_3_SUBTYPE_MAC_PHY)
+                        if value.len() >= 5 =>
+                    {
+                        mac_phy = Some(LldpMacPhy::new(
+                            value[0] & MAC_PHY_AUTONEG_ENABLED > 0,
+                            u16::from_be_bytes([value[3], value[4]]),
+                            u16::from_be_bytes([value[1], value[2]]),
+                        ));
+                    }
+                    (OUI_IEEE_802_3, IEEE_802_3_SUBTYPE_MAX_FRAME_SIZE)
+                        if value.len() >= 2 =>
+                    {
+                        max_frame_size = Some(LldpMaxFrameSize::new(
+                            u16::from_be_bytes([value[0], value[1]]).into(),
+                        ));
+                    }
+                    _ => {
+                        log::trace!(
+                            "Ignoring unsupported LLDP organizationally \
+                            specific TLV {oui:02x?} subtype {subtype}"
+                        );
+                    }
+                }
+            }
+            _ => {
+                log::trace!("Ignoring unsupported LLDP TLV type {tlv_type}");
+            }
+        }
+    }
+
+    let (chassis_id, port_id, ttl) = match (chassis_id, port_id, ttl) {
+        (Some(c), Some(p), Some(t)) => (c, p, t),
+        _ => {
+            log::debug!(
+                "Ignoring LLDPDU without mandatory chassis ID, port ID \
+                or TTL TLV"
+            );
+            return None;
+        }
+    };
+
+    let mut ret = LldpNeighbor {
+        chassis_id: chassis_id.chassis_id.clone(),
+        port_id: port_id.port_id.clone(),
+        ttl,
+        tlvs: Vec::new(),
+    };
+    if let Some(v) = system_name {
+        ret.tlvs.push(LldpNeighborTlv::SystemName(v));
+    }
+    if let Some(v) = system_description {
+        ret.tlvs.push(LldpNeighborTlv::SystemDescription(v));
+    }
+    if let Some(v) = system_caps {
+        ret.tlvs.push(LldpNeighborTlv::SystemCapabilities(v));
+    }
+    ret.tlvs.push(LldpNeighborTlv::ChassisId(chassis_id));
+    ret.tlvs.push(LldpNeighborTlv::PortId(port_id));
+    if !vlans.is_empty() {
+        ret.tlvs
+            .push(LldpNeighborTlv::Ieee8021Vlans(LldpVlans::new(vlans)));
+    }
+    if let Some(v) = mac_phy {
+        ret.tlvs.push(LldpNeighborTlv::Ieee8023MacPhyConf(v));
+    }
+    if !ppvids.is_empty() {
+        ret.tlvs
+            .push(LldpNeighborTlv::Ieee8021Ppvids(LldpPpvids::new(ppvids)));
+    }
+    if !mgmt_addrs.is_empty() {
+        ret.tlvs.push(LldpNeighborTlv::ManagementAddresses(
+            LldpMgmtAddrs::new(mgmt_addrs),
+        ));
+    }
+    if let Some(v) = max_frame_size {
+        ret.tlvs.push(LldpNeighborTlv::Ieee8023MaxFrameSize(v));
+    }
+    Some(ret)
+}
+
+fn parse_string(value: &[u8]) -> String {
+    String::from_utf8_lossy(value)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+fn format_mac(value: &[u8]) -> String {
+    value
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+// The network address is prefixed by IANA address family number
+fn format_network_address(value: &[u8]) -> String {
+    match value.split_first() {
+        Some((&ADDRESS_FAMILY_IP4, addr)) if addr.len() == 4 => {
+            Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]).to_string()
+        }
+        Some((&ADDRESS_FAMILY_IP6, addr)) if addr.len() == 16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(addr);
+            Ipv6Addr::from(octets).to_string()
+        }
+        _ => format_mac(value),
+    }
+}
+
+fn parse_chassis_id(value: &[u8]) -> Option<LldpChassisId> {
+    let (subtype, id) = value.split_first()?;
+    let id_type = LldpChassisIdType::from(*subtype);
+    let id = match id_type {
+        LldpChassisIdType::MacAddress => format_mac(id),
+        LldpChassisIdType::NetworkAddress => format_network_address(id),
+        _ => parse_string(id),
+    };
+    Some(LldpChassisId::new(id, id_type))
+}
+
+fn parse_port_id(value: &[u8]) -> Option<LldpPortId> {
+    let (subtype, id) = value.split_first()?;
+    let id_type = LldpPortIdType::from(*subtype);
+    let id = match id_type {
+        LldpPortIdType::MacAddress => format_mac(id),
+        LldpPortIdType::NetworkAddress => format_network_address(id),
+        _ => parse_string(id),
+    };
+    Some(LldpPortId::new(id, id_type))
+}
+
+// VLAN ID(2 bytes), VLAN name length(1 byte), VLAN name
+fn parse_vlan_name(value: &[u8]) -> Option<LldpVlan> {
+    if value.len() < 3 {
+        return None;
+    }
+    let name_len = value[2] as usize;
+    let name = value.get(3..3 + name_len)?;
+    let mut vlan = LldpVlan::default();
+    vlan.vid = u16::from_be_bytes([value[0], value[1]]).into();
+    vlan.name = parse_string(name);
+    Some(vlan)
+}
+
+// Address string length(1 byte, including subtype), address subtype(1 byte),
+// address, interface numbering subtype(1 byte), interface number(4 bytes),
+// OID length and OID.
+fn parse_mgmt_addr(value: &[u8]) -> Option<LldpMgmtAddr> {
+    let addr_len = *value.first()? as usize;
+    if addr_len < 1 {
+        return None;
+    }
+    let addr_subtype = *value.get(1)?;
+    let addr = value.get(2..1 + addr_len)?;
+    let remains = value.get(1 + addr_len..)?;
+    if remains.len() < 5 {
+        return None;
+    }
+    let mut ret = LldpMgmtAddr::default();
+    ret.address_subtype = LldpAddressFamily::from(addr_subtype as u16);
+    ret.address = match ret.address_subtype {
+        LldpAddressFamily::Ipv4 | LldpAddressFamily::Ipv6 => {
+            format_network_address(&value[1..1 + addr_len])
+        }
+        _ => format_mac(addr),
+    };
+    ret.interface_number_subtype = remains[0].into();
+    ret.interface_number =
+        u32::from_be_bytes([remains[1], remains[2], remains[3], remains[4]]);
+    Some(ret)
+}
+
+/// Generate ethernet frame holding LLDPDU using interface name as port ID
+/// and MAC address of interface as chassis ID.
+pub(crate) fn gen_lldp_frame(
+    iface_name: &str,
+    mac: &[u8; 6],
+    ttl: u16,
+    system_name: &str,
+    system_description: &str,
+) -> Vec<u8> {
+    let mut ret: Vec<u8> = Vec::new();
+    ret.extend_from_slice(&LLDP_MULTICAST_MAC);
+    ret.extend_from_slice(mac);
+    ret.extend_from_slice(&ETH_P_LLDP.to_be_bytes());
+
+    let mut chassis_id = vec![LldpChassisIdType::MacAddress.into()];
+    chassis_id.extend_from_slice(mac);
+    push_tlv(&mut ret, TLV_TYPE_CHASSIS_ID, &chassis_id);
+
+    let mut port_id = vec![LldpPortIdType::InterfaceName.into()];
+    port_id.extend_from_slice(iface_name.as_bytes());
+    push_tlv(&mut ret, TLV_TYPE_PORT_ID, &port_id);
+
+    push_tlv(&mut ret, TLV_TYPE_TTL, &ttl.to_be_bytes());
+    if !system_name.is_empty() {
+        push_tlv(&mut ret, TLV_TYPE_SYSTEM_NAME, system_name.as_bytes());
+    }
+    if !system_description.is_empty() {
+        push_tlv(
+            &mut ret,
+            TLV_TYPE_SYSTEM_DESCRIPTION,
+            system_description.as_bytes(),
+        );
+    }
+    // Supported and enabled capabilities
+    let mut caps = LLDP_SYS_CAP_STATION_ONLY.to_be_bytes().to_vec();
+    caps.extend_from_slice(&LLDP_SYS_CAP_STATION_ONLY.to_be_bytes());
+    push_tlv(&mut ret, TLV_TYPE_SYSTEM_CAPABILITIES, &caps);
+    push_tlv(&mut ret, TLV_TYPE_END, &[]);
+    ret
+}
+
+// Value longer than 511 bytes will be truncated
+fn push_tlv(data: &mut Vec<u8>, tlv_type: u8, value: &[u8]) {
+    let value = &value[..value.len().min(TLV_MAX_LEN)];
+    let header = ((tlv_type as u16) << 9) | value.len() as u16;
+    data.extend_from_slice(&header.to_be_bytes());
+    data.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use nipart::LldpSystemCapability;
+
+    use super::*;
+
+    const MAC: [u8; 6] = [0x00, 0x19, 0x2f, 0xa7, 0xb2, 0x8d];
+
+    // LLDPDU captured from a switch port, TLVs in wire order:
+    // chassis ID(MAC), port ID(interface name), TTL, system name,
+    // system capabilities, management address(IPv4), 802.3 MAC/PHY,
+    // 802.1 PPVID, 802.1 VLAN name, 802.3 maximum frame size, end.
+    const CAPTURED_FRAME: [u8; 108] = [
+        0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e, 0x00, 0x19, 0x2f, 0xa7, 0xb2, 0x8d,
+        0x88, 0xcc, // Ethernet header
+        0x02, 0x07, 0x04, 0x00, 0x19, 0x2f, 0xa7, 0xb2,
+        0x8d, // Chassis ID
+        0x04, 0x06, 0x05, b'G', b'i', b'0', b'/', b'1', // Port ID
+        0x06, 0x02, 0x00, 0x78, // TTL
+        0x0a, 0x06, b's', b'w', b'i', b't', b'c', b'h', // System name
+        0x0e, 0x04, 0x00, 0x14, 0x00, 0x14, // System capabilities
+        0x10, 0x0c, 0x05, 0x01, 0xc0, 0xa8, 0x01, 0x01, 0x02, 0x00, 0x00, 0x00,
+        0x01, 0x00, // Management address
+        0xfe, 0x09, 0x00, 0x12, 0x0f, 0x01, 0x03, 0x6c, 0x03, 0x00,
+        0x1e, // MAC/PHY
+        0xfe, 0x07, 0x00, 0x80, 0xc2, 0x02, 0x06, 0x00, 0x0a, // PPVID
+        0xfe, 0x0d, 0x00, 0x80, 0xc2, 0x03, 0x00, 0x0a, 0x06, b'v', b'l', b'a',
+        b'n', b'1', b'0', // VLAN name
+        0xfe, 0x06, 0x00, 0x12, 0x0f, 0x04, 0x05, 0xee, // Max frame size
+        0x00, 0x00, // End
+    ];
+
+    fn get_tlv<F, T>(neighbor: &LldpNeighbor, f: F) -> Option<&T>
+    where
+        F: Fn(&LldpNeighborTlv) -> Option<&T>,
+    {
+        neighbor.tlvs.iter().find_map(f)
+    }
+
+    #[test]
+    fn test_lldp_frame_round_trip() {
+        let frame = gen_lldp_frame("eth1", &MAC, 120, "host1", "Linux");
+        let neighbor = parse_lldp_frame(&frame).unwrap();
+
+        assert_eq!(neighbor.chassis_id, "00:19:2F:A7:B2:8D");
+        assert_eq!(neighbor.port_id, "eth1");
+        assert_eq!(neighbor.ttl, 120);
+        assert_eq!(
+            neighbor.tlvs,
+            vec![
+                LldpNeighborTlv::SystemName(LldpSystemName::new(
+                    "host1".to_string()
+                )),
+                LldpNeighborTlv::SystemDescription(LldpSystemDescription::new(
+                    "Linux".to_string()
+                )),
+                LldpNeighborTlv::SystemCapabilities(
+                    LldpSystemCapabilities::new(vec![
+                        LldpSystemCapability::StationOnly
+                    ])
+                ),
+                LldpNeighborTlv::ChassisId(LldpChassisId::new(
+                    "00:19:2F:A7:B2:8D".to_string(),
+                    LldpChassisIdType::MacAddress,
+                )),
+                LldpNeighborTlv::PortId(LldpPortId::new(
+                    "eth1".to_string(),
+                    LldpPortIdType::InterfaceName,
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lldp_frame_round_trip_without_system_name() {
+        let frame = gen_lldp_frame("eth1", &MAC, 0, "", "");
+        let neighbor = parse_lldp_frame(&frame).unwrap();
+
+        assert_eq!(neighbor.ttl, 0);
+        assert!(!neighbor.tlvs.iter().any(|t| matches!(
+            t,
+            LldpNeighborTlv::SystemName(_)
+                | LldpNeighborTlv::SystemDescription(_)
+        )));
+    }
+
+    #[test]
+    fn test_parse_captured_frame() {
+        let neighbor = parse_lldp_frame(&CAPTURED_FRAME).unwrap();
+
+        assert_eq!(neighbor.chassis_id, "00:19:2F:A7:B2:8D");
+        assert_eq!(neighbor.port_id, "Gi0/1");
+        assert_eq!(neighbor.ttl, 120);
+        assert_eq!(
+            get_tlv(&neighbor, |t| match t {
+                LldpNeighborTlv::SystemName(v) => Some(v),
+                _ => None,
+            }),
+            Some(&LldpSystemName::new("switch".to_string()))
+        );
+        assert_eq!(
+            get_tlv(&neighbor, |t| match t {
+                LldpNeighborTlv::SystemCapabilities(v) => Some(v),
+                _ => None,
+            }),
+            Some(&LldpSystemCapabilities::new(vec![
+                LldpSystemCapability::MacBridgeComponent,
+                LldpSystemCapability::Router,
+            ]))
+        );
+        assert_eq!(
+            get_tlv(&neighbor, |t| match t {
+                LldpNeighborTlv::Ieee8023MaxFrameSize(v) => Some(v),
+                _ => None,
+            }),
+            Some(&LldpMaxFrameSize::new(1518))
+        );
+    }
+
+    #[test]
+    fn test_parse_captured_mac_phy() {
+        let neighbor = parse_lldp_frame(&CAPTURED_FRAME).unwrap();
+
+        assert_eq!(
+            get_tlv(&neighbor, |t| match t {
+                LldpNeighborTlv::Ieee8023MacPhyConf(v) => Some(v),
+                _ => None,
+            }),
+            Some(&LldpMacPhy::new(true, 30, 0x6c03))
+        );
+    }
+
+    #[test]
+    fn test_parse_captured_mgmt_addr() {
+        let neighbor = parse_lldp_frame(&CAPTURED_FRAME).unwrap();
+
+        let mut expected = LldpMgmtAddr::default();
+        expected.address = "192.168.1.1".to_string();
+        expected.address_subtype = LldpAddressFamily::Ipv4;
+        expected.interface_number_subtype = 2;
+        expected.interface_number = 1;
+        assert_eq!(
+            get_tlv(&neighbor, |t| match t {
+                LldpNeighborTlv::ManagementAddresses(v) => Some(v),
+                _ => None,
+            }),
+            Some(&LldpMgmtAddrs::new(vec![expected]))
+        );
+    }
+
+    #[test]
+    fn test_parse_mgmt_addr_ipv6() {
+        let mut value = vec![17, ADDRESS_FAMILY_IP6];
+        value.extend_from_slice(
+            &"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets(),
+        );
+        value.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x05, 0x00]);
+
+        let addr = parse_mgmt_addr(&value).unwrap();
+
+        assert_eq!(addr.address, "2001:db8::1");
+        assert_eq!(addr.address_subtype, LldpAddressFamily::Ipv6);
+        assert_eq!(addr.interface_number, 5);
+    }
+
+    #[test]
+    fn test_parse_captured_vlan_name() {
+        let neighbor = parse_lldp_frame(&CAPTURED_FRAME).unwrap();
+
+        let mut expected = LldpVlan::default();
+        expected.vid = 10;
+        expected.name = "vlan10".to_string();
+        assert_eq!(
+            get_tlv(&neighbor, |t| match t {
+                LldpNeighborTlv::Ieee8021Vlans(v) => Some(v),
+                _ => None,
+            }),
+            Some(&LldpVlans::new(vec![expected]))
+        );
+    }
+
+    #[test]
+    fn test_parse_captured_ppvid() {
+        let neighbor = parse_lldp_frame(&CAPTURED_FRAME).unwrap();
+
+        assert_eq!(
+            get_tlv(&neighbor, |t| match t {
+                LldpNeighborTlv::Ieee8021Ppvids(v) => Some(v),
+                _ => None,
+            }),
+            Some(&LldpPpvids::new(vec![10]))
+        );
+    }
+
+    #[test]
+    fn test_parse_truncated_tlv() {
+        // Cut in the middle of VLAN name TLV, TLVs before it should be kept
+        let neighbor = parse_lldp_frame(&CAPTURED_FRAME[..85]).unwrap();
+
+        assert_eq!(neighbor.port_id, "Gi0/1");
+        assert!(get_tlv(&neighbor, |t| match t {
+            LldpNeighborTlv::Ieee8021Ppvids(v) => Some(v),
+            _ => None,
+        })
+        .is_some());
+        assert!(!neighbor.tlvs.iter().any(|t| matches!(
+            t,
+            LldpNeighborTlv::Ieee8021Vlans(_)
+                | LldpNeighborTlv::Ieee8023MaxFrameSize(_)
+        )));
+    }
+
+    #[test]
+    fn test_parse_truncated_mandatory_tlv() {
+        // Cut in the middle of TTL TLV
+        assert_eq!(parse_lldp_frame(&CAPTURED_FRAME[..33]), None);
+        // Cut in the middle of ethernet header
+        assert_eq!(parse_lldp_frame(&CAPTURED_FRAME[..10]), None);
+    }
+
+    #[test]
+    fn test_parse_short_tlv_values() {
+        // Length in TLV header is valid but value is shorter than what
+        // the TLV type requires.
+        assert_eq!(parse_vlan_name(&[0x00, 0x0a, 0x06, b'v']), None);
+        assert_eq!(parse_mgmt_addr(&[0x05, 0x01, 0xc0, 0xa8]), None);
+        assert_eq!(
+            parse_mgmt_addr(&[0x05, 0x01, 0xc0, 0xa8, 0x01, 0x01]),
+            None
+        );
+
+        let mut frame = CAPTURED_FRAME[..49].to_vec();
+        // MAC/PHY TLV with 2 bytes of value after OUI and subtype
+        frame.extend_from_slice(&[
+            0xfe, 0x06, 0x00, 0x12, 0x0f, 0x01, 0x03, 0x6c,
+        ]);
+        let neighbor = parse_lldp_frame(&frame).unwrap();
+
+        assert!(!neighbor
+            .tlvs
+            .iter()
+            .any(|t| matches!(t, LldpNeighborTlv::Ieee8023MacPhyConf(_))));
+    }
+}
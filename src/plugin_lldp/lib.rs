@@ -0,0 +1,8 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod frame;
+mod plugin;
+mod socket;
+mod worker;
+
+pub use self::plugin::NipartPluginLldp;
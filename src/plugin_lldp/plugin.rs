@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use nipart::{
+    Interface, MergedNetworkState, NetworkState, NipartError, NipartEvent,
+    NipartEventAddress, NipartLogLevel, NipartNativePlugin, NipartPluginEvent,
+    NipartRole, NipartUserEvent, UnknownInterface, DEFAULT_TIMEOUT,
+};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::worker::LldpWorker;
+
+// Higher than nispor plugin, so LLDP information overrides kernel one.
+const STATE_PRIORITY: u32 = 70;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct NipartPluginLldp {
+    log_level: NipartLogLevel,
+    to_daemon: Sender<NipartEvent>,
+    from_daemon: Receiver<NipartEvent>,
+    workers: HashMap<String, LldpWorker>,
+}
+
+impl NipartNativePlugin for NipartPluginLldp {
+    const PLUGIN_NAME: &'static str = "lldp";
+
+    fn roles() -> Vec<NipartRole> {
+        vec![NipartRole::QueryAndApply, NipartRole::Lldp]
+    }
+
+    fn recver_from_daemon(&mut self) -> &mut Receiver<NipartEvent> {
+        &mut self.from_daemon
+    }
+
+    fn sender_to_daemon(&self) -> &Sender<NipartEvent> {
+        &self.to_daemon
+    }
+
+    fn get_log_level(&self) -> NipartLogLevel {
+        self.log_level
+    }
+
+    fn set_log_level(&mut self, level: NipartLogLevel) {
+        self.log_level = level;
+    }
+
+    async fn init(
+        log_level: NipartLogLevel,
+        to_daemon: Sender<NipartEvent>,
+        from_daemon: Receiver<NipartEvent>,
+    ) -> Result<Self, NipartError> {
+        Ok(Self {
+            log_level,
+            to_daemon,
+            from_daemon,
+            workers: HashMap::new(),
+        })
+    }
+
+    async fn handle_event(
+        &mut self,
+        event: NipartEvent,
+    ) -> Result<(), NipartError> {
+        match event.plugin {
+            NipartPluginEvent::QueryNetState(_) => {
+                let mut reply = NipartEvent::new(
+                    NipartUserEvent::None,
+                    NipartPluginEvent::QueryNetStateReply(
+                        Box::new(self.query()),
+                        STATE_PRIORITY,
+                    ),
+                    NipartEventAddress::Unicast(Self::PLUGIN_NAME.to_string()),
+                    NipartEventAddress::Commander,
+                    DEFAULT_TIMEOUT,
+                );
+                reply.uuid = event.uuid;
+                self.sender_to_daemon().send(reply).await?;
+                Ok(())
+            }
+            NipartPluginEvent::QueryRelatedNetState(_) => {
+                let mut reply = NipartEvent::new(
+                    event.user.clone(),
+                    NipartPluginEvent::QueryNetStateReply(
+                        Box::new(self.query()),
+                        STATE_PRIORITY,
+                    ),
+                    NipartEventAddress::Unicast(Self::PLUGIN_NAME.to_string()),
+                    NipartEventAddress::Commander,
+                    DEFAULT_TIMEOUT,
+                );
+                reply.uuid = event.uuid;
+                self.sender_to_daemon().send(reply).await?;
+                Ok(())
+            }
+            NipartPluginEvent::ApplyNetState(merged_state, _) => {
+                let user = match self.apply(&merged_state).await {
+                    Ok(()) => NipartUserEvent::None,
+                    Err(e) => NipartUserEvent::Error(e),
+                };
+                let mut reply = NipartEvent::new(
+                    user,
+                    NipartPluginEvent::ApplyNetStateReply,
+                    NipartEventAddress::Unicast(Self::PLUGIN_NAME.to_string()),
+                    NipartEventAddress::Commander,
+                    DEFAULT_TIMEOUT,
+                );
+                reply.uuid = event.uuid;
+                self.sender_to_daemon().send(reply).await?;
+                Ok(())
+            }
+            _ => {
+                log::warn!("Plugin lldp got unknown event {event:?}");
+                Ok(())
+            }
+        }
+    }
+}
+
+impl NipartPluginLldp {
+    // Only interfaces with LLDP running are included, nispor plugin reports
+    // LLDP as disabled for the others.
+    fn query(&self) -> NetworkState {
+        let mut state = NetworkState::new();
+        for (iface_name, worker) in self.workers.iter() {
+            if !worker.is_running() {
+                continue;
+            }
+            let mut iface = UnknownInterface::new();
+            iface.base.name = iface_name.to_string();
+            iface.base.lldp = Some(worker.get_config());
+            state.append_interface_data(Interface::Unknown(Box::new(iface)));
+        }
+        state
+    }
+
+    async fn apply(
+        &mut self,
+        merged_state: &MergedNetworkState,
+    ) -> Result<(), NipartError> {
+        for iface in merged_state
+            .interfaces
+            .kernel_ifaces
+            .values()
+            .filter_map(|i| i.for_apply.as_ref())
+        {
+            let iface_name = iface.name();
+            if iface.is_absent() {
+                if self.workers.remove(iface_name).is_some() {
+                    log::info!("LLDP stopped on removed {iface_name}");
+                }
+                continue;
+            }
+            let lldp_conf = match iface.base_iface().lldp.as_ref() {
+                Some(c) => c,
+                None => continue,
+            };
+            if !lldp_conf.enabled {
+                if self.workers.remove(iface_name).is_some() {
+                    log::info!("LLDP stopped on {iface_name}");
+                }
+                continue;
+            }
+            let transmit = lldp_conf.transmit == Some(true);
+            // Keep existing worker to preserve neighbors already learned
+            if let Some(worker) = self.workers.get(iface_name) {
+                if worker.transmit == transmit && worker.is_running() {
+                    continue;
+                }
+            }
+            self.workers.remove(iface_name);
+            let worker = LldpWorker::new(iface_name, transmit).await?;
+            self.workers.insert(iface_name.to_string(), worker);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io::Read;
+use std::os::fd::AsRawFd;
+
+use nipart::{ErrorKind, NipartError};
+use socket2::{Domain, Protocol, SockAddr, SockAddrStorage, Socket, Type};
+use tokio::io::{unix::AsyncFd, Interest};
+
+pub(crate) const ETH_P_LLDP: u16 = 0x88cc;
+// Nearest bridge group address, not forwarded by any IEEE 802.1D bridge.
+pub(crate) const LLDP_MULTICAST_MAC: [u8; 6] =
+    [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e];
+
+/// AF_PACKET socket bound to specified interface, only receiving frames of
+/// LLDP ether type.
+#[derive(Debug)]
+pub(crate) struct LldpSocket {
+    iface_name: String,
+    fd: AsyncFd<Socket>,
+}
+
+impl LldpSocket {
+    pub(crate) fn new(
+        iface_name: &str,
+        iface_index: u32,
+    ) -> Result<Self, NipartError> {
+        let socket = Socket::new(
+            Domain::PACKET,
+            Type::RAW,
+            Some(Protocol::from(ETH_P_LLDP.to_be() as i32)),
+        )
+        .map_err(|e| socket_error(iface_name, "create", e))?;
+
+        let mut storage = SockAddrStorage::zeroed();
+        // SAFETY: SockAddrStorage is big enough to hold sockaddr_ll
+        let addr = unsafe {
+            let sll = storage.view_as::<libc::sockaddr_ll>();
+            sll.sll_family = libc::AF_PACKET as u16;
+            sll.sll_protocol = ETH_P_LLDP.to_be();
+            sll.sll_ifindex = iface_index as i32;
+            SockAddr::new(
+                storage,
+                std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        socket
+            .bind(&addr)
+            .map_err(|e| socket_error(iface_name, "bind", e))?;
+
+        // The NIC might filter out LLDP multicast address without this
+        let mut mreq: libc::packet_mreq = unsafe { std::mem::zeroed() };
+        mreq.mr_ifindex = iface_index as i32;
+        mreq.mr_type = libc::PACKET_MR_MULTICAST as u16;
+        mreq.mr_alen = LLDP_MULTICAST_MAC.len() as u16;
+        mreq.mr_address[..LLDP_MULTICAST_MAC.len()]
+            .copy_from_slice(&LLDP_MULTICAST_MAC);
+        // SAFETY: mreq is valid packet_mreq living through this call
+        let rc = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_PACKET,
+                libc::PACKET_ADD_MEMBERSHIP,
+                &mreq as *const libc::packet_mreq as *const libc::c_void,
+                std::mem::size_of::<libc::packet_mreq>() as libc::socklen_t,
+            )
+        };
+        if rc != 0 {
+            return Err(socket_error(
+                iface_name,
+                "join LLDP multicast group on",
+                std::io::Error::last_os_error(),
+            ));
+        }
+
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| socket_error(iface_name, "set nonblocking", e))?;
+        let fd = AsyncFd::new(socket)
+            .map_err(|e| socket_error(iface_name, "register", e))?;
+        Ok(Self {
+            iface_name: iface_name.to_string(),
+            fd,
+        })
+    }
+
+    /// Receive single ethernet frame including ethernet header.
+    /// The [std::io::Error] is returned as it is, so caller could tell
+    /// interface down(`ENETDOWN`) from interface removal(`ENODEV`).
+    pub(crate) async fn recv(
+        &self,
+        buffer: &mut [u8],
+    ) -> Result<usize, std::io::Error> {
+        loop {
+            // Socket error like ENETDOWN is only signaled as EPOLLERR
+            let mut guard =
+                self.fd.ready(Interest::READABLE | Interest::ERROR).await?;
+            match guard.try_io(|fd| {
+                let mut socket = fd.get_ref();
+                socket.read(buffer)
+            }) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// Send single ethernet frame including ethernet header.
+    pub(crate) async fn send(&self, frame: &[u8]) -> Result<(), NipartError> {
+        loop {
+            let mut guard = self
+                .fd
+                .writable()
+                .await
+                .map_err(|e| socket_error(&self.iface_name, "poll", e))?;
+            match guard.try_io(|fd| fd.get_ref().send(frame)) {
+                Ok(result) => {
+                    return result.map(|_| ()).map_err(|e| {
+                        socket_error(&self.iface_name, "send to", e)
+                    })
+                }
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+fn socket_error(
+    iface_name: &str,
+    action: &str,
+    error: std::io::Error,
+) -> NipartError {
+    NipartError::new(
+        ErrorKind::PluginFailure,
+        format!("Failed to {action} LLDP socket of {iface_name}: {error}"),
+    )
+}
+
+pub(crate) fn get_iface_index(iface_name: &str) -> Option<u32> {
+    let name = std::ffi::CString::new(iface_name).ok()?;
+    // SAFETY: name is valid C string living through this call
+    match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+        0 => None,
+        i => Some(i),
+    }
+}
+
+pub(crate) fn get_iface_mac(iface_name: &str) -> Option<[u8; 6]> {
+    let content =
+        std::fs::read_to_string(format!("/sys/class/net/{iface_name}/address"))
+            .ok()?;
+    let mut mac = [0u8; 6];
+    let items: Vec<&str> = content.trim().split(':').collect();
+    if items.len() != mac.len() {
+        return None;
+    }
+    for (byte, item) in mac.iter_mut().zip(items) {
+        *byte = u8::from_str_radix(item, 16).ok()?;
+    }
+    Some(mac)
+}
@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use nipart::{ErrorKind, LldpConfig, LldpNeighborTlv, NipartError};
+
+use crate::frame::{gen_lldp_frame, parse_lldp_frame};
+use crate::socket::{get_iface_index, get_iface_mac, LldpSocket};
+
+// IEEE 802.1AB msgTxInterval and msgTxHold
+const LLDP_TX_INTERVAL: Duration = Duration::from_secs(30);
+const LLDP_TX_TTL: u16 = 120;
+const LLDP_MAX_FRAME_SIZE: usize = 1522;
+
+const IFACE_WAIT_RETRY_COUNT: u32 = 50;
+const IFACE_WAIT_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+// Indexed by (chassis ID, port ID)
+type LldpNeighbors =
+    Arc<Mutex<HashMap<(String, String), (Vec<LldpNeighborTlv>, Instant)>>>;
+
+/// Receiving LLDPDU and optionally transmitting ours on single interface.
+/// The background task is stopped when dropped.
+#[derive(Debug)]
+pub(crate) struct LldpWorker {
+    pub(crate) transmit: bool,
+    neighbors: LldpNeighbors,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for LldpWorker {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl LldpWorker {
+    pub(crate) async fn new(
+        iface_name: &str,
+        transmit: bool,
+    ) -> Result<Self, NipartError> {
+        let iface_index = wait_iface(iface_name).await?;
+        let socket = match LldpSocket::new(iface_name, iface_index) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("{}", e);
+                return Err(e);
+            }
+        };
+        let neighbors: LldpNeighbors = Arc::new(Mutex::new(HashMap::new()));
+        let handle = tokio::spawn(lldp_thread(
+            iface_name.to_string(),
+            iface_index,
+            socket,
+            transmit,
+            neighbors.clone(),
+        ));
+        log::info!(
+            "LLDP started on {iface_name}{}",
+            if transmit { " with transmit" } else { "" }
+        );
+        Ok(Self {
+            transmit,
+            neighbors,
+            handle,
+        })
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        !self.handle.is_finished()
+    }
+
+    pub(crate) fn get_config(&self) -> LldpConfig {
+        let mut ret = LldpConfig::default();
+        ret.enabled = true;
+        ret.transmit = Some(self.transmit);
+        ret.neighbors = self.get_neighbors();
+        ret
+    }
+
+    // Expired neighbors are removed
+    fn get_neighbors(&self) -> Vec<Vec<LldpNeighborTlv>> {
+        let mut neighbors = match self.neighbors.lock() {
+            Ok(n) => n,
+            Err(e) => {
+                log::error!("BUG: Failed to lock LLDP neighbors: {e}");
+                return Vec::new();
+            }
+        };
+        let now = Instant::now();
+        neighbors.retain(|_, (_, expire)| *expire > now);
+        let mut keys: Vec<&(String, String)> = neighbors.keys().collect();
+        keys.sort_unstable();
+        keys.into_iter()
+            .filter_map(|k| neighbors.get(k).map(|(tlvs, _)| tlvs.clone()))
+            .collect()
+    }
+}
+
+// The interface might be created by other plugin in parallel, wait it to
+// appear in kernel.
+async fn wait_iface(iface_name: &str) -> Result<u32, NipartError> {
+    for _ in 0..IFACE_WAIT_RETRY_COUNT {
+        if let Some(index) = get_iface_index(iface_name) {
+            return Ok(index);
+        }
+        tokio::time::sleep(IFACE_WAIT_RETRY_INTERVAL).await;
+    }
+    let e = NipartError::new(
+        ErrorKind::InvalidArgument,
+        format!("Interface {iface_name} not found for LLDP"),
+    );
+    log::error!("{}", e);
+    Err(e)
+}
+
+async fn lldp_thread(
+    iface_name: String,
+    iface_index: u32,
+    socket: LldpSocket,
+    transmit: bool,
+    neighbors: LldpNeighbors,
+) {
+    let mut buffer = vec![0u8; LLDP_MAX_FRAME_SIZE];
+    let mut tx_interval = tokio::time::interval(LLDP_TX_INTERVAL);
+    loop {
+        tokio::select! {
+            result = socket.recv(&mut buffer) => {
+                match result {
+                    Ok(len) => process_frame(
+                        iface_name.as_str(),
+                        &buffer[..len],
+                        &neighbors,
+                    ),
+                    Err(e) if e.raw_os_error() == Some(libc::ENODEV) => {
+                        log::info!("LLDP stopped on {iface_name}: {e}");
+                        return;
+                    }
+                    // Kernel reports ENETDOWN once on link down, which is
+                    // also the case of deleting an interface in up state.
+                    // Give kernel a moment to finish unregistering before
+                    // checking whether the interface is still there.
+                    Err(e) if e.raw_os_error() == Some(libc::ENETDOWN) => {
+                        tokio::time::sleep(IFACE_WAIT_RETRY_INTERVAL).await;
+                        if is_iface_removed(iface_name.as_str(), iface_index) {
+                            log::info!("LLDP stopped on {iface_name}: {e}");
+                            return;
+                        }
+                        log::debug!("LLDP paused on {iface_name}: {e}");
+                    }
+                    Err(e) => {
+                        if is_iface_removed(iface_name.as_str(), iface_index) {
+                            log::info!("LLDP stopped on {iface_name}: {e}");
+                            return;
+                        }
+                        log::warn!(
+                            "Failed to receive LLDPDU on {iface_name}: {e}"
+                        );
+                        // Avoid busy loop on persistent error
+                        tokio::time::sleep(IFACE_WAIT_RETRY_INTERVAL).await;
+                    }
+                }
+            }
+            _ = tx_interval.tick() => {
+                if is_iface_removed(iface_name.as_str(), iface_index) {
+                    log::info!(
+                        "LLDP stopped on {iface_name}: interface removed"
+                    );
+                    return;
+                }
+                if !transmit {
+                    continue;
+                }
+                if let Err(e) = send_lldp(iface_name.as_str(), &socket).await
+                {
+                    // Interface might be down, try again in next interval
+                    log::debug!("{e}");
+                }
+            }
+        }
+    }
+}
+
+// The socket is bound to interface index, a new interface reusing the same
+// name will not deliver frames to it.
+fn is_iface_removed(iface_name: &str, iface_index: u32) -> bool {
+    get_iface_index(iface_name) != Some(iface_index)
+}
+
+fn process_frame(iface_name: &str, frame: &[u8], neighbors: &LldpNeighbors) {
+    let neighbor = match parse_lldp_frame(frame) {
+        Some(n) => n,
+        None => return,
+    };
+    let mut neighbors = match neighbors.lock() {
+        Ok(n) => n,
+        Err(e) => {
+            log::error!("BUG: Failed to lock LLDP neighbors: {e}");
+            return;
+        }
+    };
+    let key = (neighbor.chassis_id, neighbor.port_id);
+    if neighbor.ttl == 0 {
+        log::debug!("LLDP neighbor {key:?} of {iface_name} shutdown");
+        neighbors.remove(&key);
+    } else {
+        log::trace!("LLDP neighbor {key:?} of {iface_name} updated");
+        neighbors.insert(
+            key,
+            (
+                neighbor.tlvs,
+                Instant::now() + Duration::from_secs(neighbor.ttl.into()),
+            ),
+        );
+    }
+}
+
+async fn send_lldp(
+    iface_name: &str,
+    socket: &LldpSocket,
+) -> Result<(), NipartError> {
+    let mac = get_iface_mac(iface_name).ok_or_else(|| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to get MAC address of {iface_name}"),
+        )
+    })?;
+    let hostname = nix::unistd::gethostname()
+        .ok()
+        .and_then(|h| h.into_string().ok())
+        .unwrap_or_default();
+    let frame = gen_lldp_frame(
+        iface_name,
+        &mac,
+        LLDP_TX_TTL,
+        hostname.as_str(),
+        &format!("nipart {}", env!("CARGO_PKG_VERSION")),
+    );
+    log::trace!("Sending LLDPDU on {iface_name}");
+    socket.send(&frame).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    const TEST_VETH: &str = "nipart-lldp0";
+    const TEST_VETH_PEER: &str = "nipart-lldp1";
+
+    fn ip(args: &[&str]) {
+        assert!(Command::new("ip").args(args).status().unwrap().success());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires CAP_NET_ADMIN for creating veth"]
+    async fn test_lldp_worker_survives_link_down() {
+        ip(&[
+            "link",
+            "add",
+            TEST_VETH,
+            "type",
+            "veth",
+            "peer",
+            "name",
+            TEST_VETH_PEER,
+        ]);
+        ip(&["link", "set", TEST_VETH, "up"]);
+        let worker = LldpWorker::new(TEST_VETH, false).await.unwrap();
+
+        ip(&["link", "set", TEST_VETH, "down"]);
+        tokio::time::sleep(IFACE_WAIT_RETRY_INTERVAL * 3).await;
+        let running_after_down = worker.is_running();
+
+        ip(&["link", "set", TEST_VETH, "up"]);
+        ip(&["link", "del", TEST_VETH]);
+        tokio::time::sleep(IFACE_WAIT_RETRY_INTERVAL * 3).await;
+
+        assert!(running_after_down);
+        assert!(!worker.is_running());
+    }
+}
@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io::Write;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use mozim::{DhcpV4Lease, DhcpV6IaType, DhcpV6Lease};
+use nipart::{ErrorKind, NipartError};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+const LEASE_STORE_PATH: &str = "/var/lib/nipart/dhcp";
+
+/// The DHCPv4 lease stored in file, used for INIT-REBOOT after daemon
+/// restart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct MozimLeaseStoreV4 {
+    pub(crate) siaddr: Ipv4Addr,
+    pub(crate) yiaddr: Ipv4Addr,
+    pub(crate) srv_id: Ipv4Addr,
+    pub(crate) subnet_mask: Ipv4Addr,
+    pub(crate) t1: u32,
+    pub(crate) t2: u32,
+    pub(crate) lease_time: u32,
+    /// Seconds since UNIX epoch when this lease was acquired
+    pub(crate) acquired: u64,
+}
+
+impl MozimLeaseStoreV4 {
+    fn new(lease: &DhcpV4Lease) -> Self {
+        Self {
+            siaddr: lease.siaddr,
+            yiaddr: lease.yiaddr,
+            srv_id: lease.srv_id,
+            subnet_mask: lease.subnet_mask,
+            t1: lease.t1,
+            t2: lease.t2,
+            lease_time: lease.lease_time,
+            acquired: now_secs(),
+        }
+    }
+
+    // Seconds left before lease expire, None if expired.
+    fn remaining(&self) -> Option<u32> {
+        get_remaining(self.acquired, self.lease_time)
+    }
+
+    fn to_mozim(&self, remaining: u32) -> DhcpV4Lease {
+        let elapsed = self.lease_time.saturating_sub(remaining);
+        let mut lease = DhcpV4Lease::default();
+        lease.siaddr = self.siaddr;
+        lease.yiaddr = self.yiaddr;
+        lease.srv_id = self.srv_id;
+        lease.subnet_mask = self.subnet_mask;
+        lease.t1 = self.t1.saturating_sub(elapsed);
+        lease.t2 = self.t2.saturating_sub(elapsed);
+        lease.lease_time = remaining;
+        lease
+    }
+}
+
+/// The DHCPv6 lease stored in file, used for renewing the same address after
+/// daemon or DHCPv6 client restart. Only IA_NA is supported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct MozimLeaseStoreV6 {
+    pub(crate) iaid: u32,
+    pub(crate) addr: Ipv6Addr,
+    pub(crate) prefix_len: u8,
+    pub(crate) srv_ip: Ipv6Addr,
+    pub(crate) cli_duid: Vec<u8>,
+    pub(crate) srv_duid: Vec<u8>,
+    pub(crate) t1: u32,
+    pub(crate) t2: u32,
+    pub(crate) preferred_life: u32,
+    pub(crate) valid_life: u32,
+    /// Seconds since UNIX epoch when this lease was acquired
+    pub(crate) acquired: u64,
+}
+
+impl MozimLeaseStoreV6 {
+    fn new(lease: &DhcpV6Lease) -> Self {
+        Self {
+            iaid: lease.iaid,
+            addr: lease.addr,
+            prefix_len: lease.prefix_len,
+            srv_ip: lease.srv_ip,
+            cli_duid: lease.cli_duid.clone(),
+            srv_duid: lease.srv_duid.clone(),
+            t1: lease.t1,
+            t2: lease.t2,
+            preferred_life: lease.preferred_life,
+            valid_life: lease.valid_life,
+            acquired: now_secs(),
+        }
+    }
+
+    // Seconds left before lease expire, None if expired.
+    fn remaining(&self) -> Option<u32> {
+        get_remaining(self.acquired, self.valid_life)
+    }
+
+    fn to_mozim(&self, remaining: u32) -> DhcpV6Lease {
+        let elapsed = self.valid_life.saturating_sub(remaining);
+        let mut lease = DhcpV6Lease::default();
+        lease.ia_type = DhcpV6IaType::NonTemporaryAddresses;
+        lease.iaid = self.iaid;
+        lease.addr = self.addr;
+        lease.prefix_len = self.prefix_len;
+        lease.srv_ip = self.srv_ip;
+        lease.cli_duid = self.cli_duid.clone();
+        lease.srv_duid = self.srv_duid.clone();
+        lease.t1 = self.t1.saturating_sub(elapsed);
+        lease.t2 = self.t2.saturating_sub(elapsed);
+        lease.preferred_life = self.preferred_life.saturating_sub(elapsed);
+        lease.valid_life = remaining;
+        lease
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn get_remaining(acquired: u64, lifetime: u32) -> Option<u32> {
+    let elapsed = now_secs().saturating_sub(acquired);
+    u64::from(lifetime)
+        .checked_sub(elapsed)
+        .filter(|r| *r > 0)
+        .map(|r| r as u32)
+}
+
+fn lease_file_path_v4(iface: &str) -> String {
+    format!("{LEASE_STORE_PATH}/{iface}.v4.yml")
+}
+
+fn lease_file_path_v6(iface: &str) -> String {
+    format!("{LEASE_STORE_PATH}/{iface}.v6.yml")
+}
+
+/// Load stored lease of specified interface, expired or corrupted lease file
+/// will be removed.
+pub(crate) fn load_lease_v4(iface: &str) -> Option<DhcpV4Lease> {
+    let file_path = lease_file_path_v4(iface);
+    let stored: MozimLeaseStoreV4 = read_lease_file(&file_path)?;
+    match stored.remaining() {
+        Some(remaining) => {
+            log::debug!(
+                "Loaded DHCP lease {} for {iface} with {remaining} seconds \
+                remaining",
+                stored.yiaddr
+            );
+            Some(stored.to_mozim(remaining))
+        }
+        None => {
+            log::debug!(
+                "Discarding expired DHCP lease {} for {iface}",
+                stored.yiaddr
+            );
+            remove_lease_file(&file_path);
+            None
+        }
+    }
+}
+
+/// Load stored DHCPv6 lease of specified interface, expired or corrupted
+/// lease file will be removed.
+pub(crate) fn load_lease_v6(iface: &str) -> Option<DhcpV6Lease> {
+    let file_path = lease_file_path_v6(iface);
+    let stored: MozimLeaseStoreV6 = read_lease_file(&file_path)?;
+    match stored.remaining() {
+        Some(remaining) => {
+            log::debug!(
+                "Loaded DHCPv6 lease {} for {iface} with {remaining} seconds \
+                remaining",
+                stored.addr
+            );
+            Some(stored.to_mozim(remaining))
+        }
+        None => {
+            log::debug!(
+                "Discarding expired DHCPv6 lease {} for {iface}",
+                stored.addr
+            );
+            remove_lease_file(&file_path);
+            None
+        }
+    }
+}
+
+pub(crate) fn store_lease_v4(
+    iface: &str,
+    lease: &DhcpV4Lease,
+) -> Result<(), NipartError> {
+    write_lease_file(&lease_file_path_v4(iface), &MozimLeaseStoreV4::new(lease))
+}
+
+pub(crate) fn store_lease_v6(
+    iface: &str,
+    lease: &DhcpV6Lease,
+) -> Result<(), NipartError> {
+    write_lease_file(&lease_file_path_v6(iface), &MozimLeaseStoreV6::new(lease))
+}
+
+pub(crate) fn remove_lease_v4(iface: &str) {
+    remove_lease_file(&lease_file_path_v4(iface))
+}
+
+pub(crate) fn remove_lease_v6(iface: &str) {
+    remove_lease_file(&lease_file_path_v6(iface))
+}
+
+fn read_lease_file<T: DeserializeOwned>(file_path: &str) -> Option<T> {
+    let content = std::fs::read_to_string(file_path).ok()?;
+    match serde_yaml::from_str(&content) {
+        Ok(l) => Some(l),
+        Err(e) => {
+            log::warn!("Discarding corrupted DHCP lease file {file_path}: {e}");
+            remove_lease_file(file_path);
+            None
+        }
+    }
+}
+
+fn write_lease_file<T: Serialize>(
+    file_path: &str,
+    lease: &T,
+) -> Result<(), NipartError> {
+    std::fs::create_dir_all(LEASE_STORE_PATH).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to create folder {LEASE_STORE_PATH}: {e}"),
+        )
+    })?;
+    let lease_yml = serde_yaml::to_string(lease).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to convert DHCP lease to string, error: {e}"),
+        )
+    })?;
+
+    let mut fd = std::fs::OpenOptions::new()
+        .read(false)
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(file_path)
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!("Failed to open lease file {file_path}, error: {e}"),
+            )
+        })?;
+
+    fd.write_all(lease_yml.as_bytes()).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to write lease file {file_path}, error: {e}"),
+        )
+    })?;
+    Ok(())
+}
+
+fn remove_lease_file(file_path: &str) {
+    if let Err(e) = std::fs::remove_file(file_path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove DHCP lease file {file_path}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lease_store_v6_elapsed() {
+        let mut lease = DhcpV6Lease::default();
+        lease.ia_type = DhcpV6IaType::NonTemporaryAddresses;
+        lease.iaid = 1;
+        lease.addr = "2001:db8::100".parse().unwrap();
+        lease.prefix_len = 64;
+        lease.srv_duid = vec![0, 1, 2, 3];
+        lease.t1 = 1800;
+        lease.t2 = 2880;
+        lease.preferred_life = 3000;
+        lease.valid_life = 3600;
+        let mut stored = MozimLeaseStoreV6::new(&lease);
+        stored.acquired -= 1000;
+
+        let loaded = stored.to_mozim(stored.remaining().unwrap());
+
+        assert_eq!(loaded.addr, lease.addr);
+        assert_eq!(loaded.srv_duid, lease.srv_duid);
+        assert_eq!(loaded.ia_type, DhcpV6IaType::NonTemporaryAddresses);
+        assert_eq!(loaded.t1, 800);
+        assert_eq!(loaded.t2, 1880);
+        assert_eq!(loaded.preferred_life, 2000);
+        assert_eq!(loaded.valid_life, 2600);
+    }
+
+    #[test]
+    fn test_lease_store_v6_expired() {
+        let mut lease = DhcpV6Lease::default();
+        lease.valid_life = 3600;
+        let mut stored = MozimLeaseStoreV6::new(&lease);
+        stored.acquired -= 3600;
+
+        assert_eq!(stored.remaining(), None);
+    }
+}
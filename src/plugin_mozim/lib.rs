@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
+mod lease;
 mod plugin;
 mod worker;
+mod worker_v6;
 
 pub use self::plugin::NipartPluginMozim;
@@ -10,9 +10,9 @@ use nipart::{
 };
 use tokio::sync::mpsc::{Receiver, Sender};
 
+use crate::lease::{remove_lease_v4, remove_lease_v6};
 use crate::worker::MozimWorkerV4;
-
-// TODO: Use /var/lib folder to store DHCP lease files
+use crate::worker_v6::MozimWorkerV6;
 
 #[derive(Debug)]
 pub struct NipartPluginMozim {
@@ -20,6 +20,7 @@ pub struct NipartPluginMozim {
     to_daemon: Sender<NipartEvent>,
     from_daemon: Receiver<NipartEvent>,
     v4_workers: HashMap<String, MozimWorkerV4>,
+    v6_workers: HashMap<String, MozimWorkerV6>,
 }
 
 impl NipartNativePlugin for NipartPluginMozim {
@@ -43,6 +44,7 @@ impl NipartNativePlugin for NipartPluginMozim {
             to_daemon,
             from_daemon,
             v4_workers: HashMap::new(),
+            v6_workers: HashMap::new(),
         })
     }
 
@@ -106,6 +108,11 @@ impl NipartPluginMozim {
         self.v4_workers
             .values()
             .map(|worker| NipartDhcpConfig::V4(worker.get_config()))
+            .chain(
+                self.v6_workers
+                    .values()
+                    .map(|worker| NipartDhcpConfig::V6(worker.get_config())),
+            )
             .collect()
     }
 
@@ -118,7 +125,18 @@ impl NipartPluginMozim {
             NipartDhcpConfig::V4(conf) => {
                 log::debug!("{event_uuid} applying DHCP {conf:?}");
                 // Stop current DHCP process
-                self.v4_workers.remove(conf.iface.as_str());
+                if let Some(mut worker) =
+                    self.v4_workers.remove(conf.iface.as_str())
+                {
+                    if !conf.enabled {
+                        worker.release().await;
+                    }
+                }
+                if !conf.enabled {
+                    // No worker kept for disabled interface
+                    remove_lease_v4(conf.iface.as_str());
+                    return Ok(());
+                }
                 // Create new one
                 let worker = MozimWorkerV4::new(
                     conf,
@@ -126,18 +144,35 @@ impl NipartPluginMozim {
                     self.to_daemon.clone(),
                 )
                 .await?;
-
-                if conf.enabled {
-                    self.register_link_up_event(
-                        conf.iface.as_str(),
-                        event_uuid,
-                    )
+                self.register_link_up_event(conf.iface.as_str(), event_uuid)
                     .await?;
-                }
                 self.v4_workers.insert(conf.iface.clone(), worker);
             }
-            NipartDhcpConfig::V6(_) => {
-                log::error!("Plugin mozim not supporting IPv6 yet");
+            NipartDhcpConfig::V6(conf) => {
+                log::debug!("{event_uuid} applying DHCPv6 {conf:?}");
+                // Stop current DHCPv6 process
+                if let Some(mut worker) =
+                    self.v6_workers.remove(conf.iface.as_str())
+                {
+                    if !conf.enabled {
+                        worker.release().await;
+                    }
+                }
+                if !conf.enabled {
+                    // No worker kept for disabled interface
+                    remove_lease_v6(conf.iface.as_str());
+                    return Ok(());
+                }
+                // Create new one
+                let worker = MozimWorkerV6::new(
+                    conf,
+                    event_uuid,
+                    self.to_daemon.clone(),
+                )
+                .await?;
+                self.register_link_up_event(conf.iface.as_str(), event_uuid)
+                    .await?;
+                self.v6_workers.insert(conf.iface.clone(), worker);
             }
         }
         Ok(())
@@ -191,27 +226,43 @@ impl NipartPluginMozim {
         &mut self,
         iface: &str,
     ) -> Result<(), NipartError> {
+        let mut found = false;
         if let Some(worker) = self.v4_workers.get_mut(iface) {
+            found = true;
             if worker.config.enabled {
-                worker.start()
+                worker.start()?;
             } else {
                 log::debug!(
                     "DHCP is disabled for interface {iface}, \
                     ignore request of start DHCP"
                 );
-                Ok(())
             }
-        } else {
+        }
+        if let Some(worker) = self.v6_workers.get_mut(iface) {
+            found = true;
+            if worker.config.enabled {
+                worker.start()?;
+            } else {
+                log::debug!(
+                    "DHCPv6 is disabled for interface {iface}, \
+                    ignore request of start DHCPv6"
+                );
+            }
+        }
+        if !found {
             log::debug!(
                 "No DHCP worker registered to mozim for interface {iface}"
             );
-            Ok(())
         }
+        Ok(())
     }
 
     async fn stop_dhcp_if_enabled(&mut self, iface: &str) {
         if let Some(worker) = self.v4_workers.get_mut(iface) {
             worker.stop().await;
         }
+        if let Some(worker) = self.v6_workers.get_mut(iface) {
+            worker.stop().await;
+        }
     }
 }
@@ -1,17 +1,28 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::net::Ipv4Addr;
 use std::os::fd::AsRawFd;
 
-use mozim::{DhcpV4Client, DhcpV4Config, DhcpV4Lease};
+use mozim::{DhcpV4Client, DhcpV4Config, DhcpV4Event, DhcpV4Lease};
 use nipart::{
-    ErrorKind, NipartDhcpConfigV4, NipartDhcpLease, NipartDhcpLeaseV4,
-    NipartError, NipartEvent, NipartEventAddress, NipartLinkMonitorKind,
-    NipartLinkMonitorRule, NipartMonitorRule, NipartPluginEvent, NipartRole,
-    NipartUserEvent, NipartUuid, DEFAULT_TIMEOUT,
+    ErrorKind, NipartDhcpClasslessRouteV4, NipartDhcpConfigV4, NipartDhcpLease,
+    NipartDhcpLeaseV4, NipartError, NipartEvent, NipartEventAddress,
+    NipartLinkMonitorKind, NipartLinkMonitorRule, NipartMonitorRule,
+    NipartPluginEvent, NipartRole, NipartUserEvent, NipartUuid,
+    DEFAULT_TIMEOUT,
 };
-use tokio::{io::unix::AsyncFd, sync::mpsc::Sender, task::JoinHandle};
+use tokio::{
+    io::unix::AsyncFd,
+    sync::{mpsc::Sender, oneshot},
+    task::JoinHandle,
+};
+
+use crate::lease::{load_lease_v4, remove_lease_v4, store_lease_v4};
 
 const MOZIM_NO_BLOCKING_TIMEOUT: u32 = 0;
+pub(crate) const MOZIM_STOP_TIMEOUT: std::time::Duration =
+    std::time::Duration::from_secs(5);
+const DHCP_OPT_DOMAIN_SEARCH: u8 = 119;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub(crate) enum MozimWorkerState {
@@ -37,16 +48,29 @@ impl std::fmt::Display for MozimWorkerState {
     }
 }
 
+/// Reason of stopping the DHCP thread.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum MozimStopKind {
+    /// Send DHCPRELEASE and remove the stored lease.
+    Release,
+    /// Link down, notify the lease as lost but keep the stored lease for
+    /// INIT-REBOOT after link up.
+    LinkDown,
+}
+
 #[derive(Debug)]
 pub(crate) struct MozimWorkerV4Thread {}
 
 impl MozimWorkerV4Thread {
     pub(crate) async fn new(
-        iface_name: String,
+        config: NipartDhcpConfigV4,
         mut mozim_client: DhcpV4Client,
         to_daemon: Sender<NipartEvent>,
         event_uuid: NipartUuid,
+        mut stop_recv: oneshot::Receiver<MozimStopKind>,
+        mut last_ip: Option<Ipv4Addr>,
     ) -> Self {
+        let iface_name = config.iface.as_str();
         let fd = match AsyncFd::new(mozim_client.as_raw_fd()) {
             Ok(fd) => fd,
             Err(e) => {
@@ -57,14 +81,53 @@ impl MozimWorkerV4Thread {
                 return Self {};
             }
         };
+        let mut cur_lease: Option<DhcpV4Lease> = None;
         loop {
-            match fd.readable().await {
-                Ok(mut guard) => guard.clear_ready(),
-                Err(e) => {
-                    log::error!(
-                        "Mozim worker for {iface_name} event {event_uuid}: \
-                        AsyncFd::readable() failed with {e}"
-                    );
+            tokio::select! {
+                result = fd.readable() => match result {
+                    Ok(mut guard) => guard.clear_ready(),
+                    Err(e) => {
+                        log::error!(
+                            "Mozim worker for {iface_name} event \
+                            {event_uuid}: AsyncFd::readable() failed with {e}"
+                        );
+                        return Self {};
+                    }
+                },
+                result = &mut stop_recv => {
+                    if let (Ok(kind), Some(lease)) = (result, cur_lease.take())
+                    {
+                        match kind {
+                            MozimStopKind::Release => {
+                                release_lease(
+                                    &mut mozim_client,
+                                    lease,
+                                    &config,
+                                    &to_daemon,
+                                )
+                                .await
+                            }
+                            MozimStopKind::LinkDown => {
+                                log::info!(
+                                    "DHCP lease {} of {iface_name} lost on \
+                                    link down",
+                                    lease.yiaddr
+                                );
+                                send_event(
+                                    &to_daemon,
+                                    gen_dhcp_event(
+                                        NipartPluginEvent::LostDhcpLease(
+                                            Box::new(mozim_lease_to_nipart(
+                                                &lease, &config,
+                                            )),
+                                        ),
+                                    ),
+                                )
+                                .await
+                                .ok();
+                            }
+                        }
+                    }
                     return Self {};
                 }
             }
@@ -81,12 +144,41 @@ impl MozimWorkerV4Thread {
             };
             let mut has_lease = false;
             for event in events {
+                if event == DhcpV4Event::LeaseExpired {
+                    if let Some(lease) = cur_lease.take() {
+                        log::info!(
+                            "DHCP lease {} of {iface_name} expired",
+                            lease.yiaddr
+                        );
+                        remove_lease_v4(iface_name);
+                        reply_events.push(gen_dhcp_event(
+                            NipartPluginEvent::LostDhcpLease(Box::new(
+                                mozim_lease_to_nipart(&lease, &config),
+                            )),
+                        ));
+                    }
+                }
                 match mozim_client.process(event) {
                     Ok(Some(lease)) => {
-                        reply_events.push(gen_dhcp_lease_event(
-                            lease,
-                            iface_name.as_str(),
+                        if let Err(e) = store_lease_v4(iface_name, &lease) {
+                            log::warn!(
+                                "Failed to store DHCP lease of \
+                                {iface_name}: {e}"
+                            );
+                        }
+                        let mut nipart_lease =
+                            mozim_lease_to_nipart(&lease, &config);
+                        if let NipartDhcpLease::V4(l) = &mut nipart_lease {
+                            l.previous_ip =
+                                last_ip.filter(|ip| ip != &lease.yiaddr);
+                        }
+                        reply_events.push(gen_dhcp_event(
+                            NipartPluginEvent::GotDhcpLease(Box::new(
+                                nipart_lease,
+                            )),
                         ));
+                        last_ip = Some(lease.yiaddr);
+                        cur_lease = Some(lease);
                         has_lease = true;
                     }
                     Ok(None) => (),
@@ -95,26 +187,35 @@ impl MozimWorkerV4Thread {
                         "Mozim worker for {iface_name} event {event_uuid}: \
                         mozim_client.process() failed with {e}"
                     );
+                        if let Some(lease) = cur_lease.take() {
+                            remove_lease_v4(iface_name);
+                            send_event(
+                                &to_daemon,
+                                gen_dhcp_event(
+                                    NipartPluginEvent::LostDhcpLease(Box::new(
+                                        mozim_lease_to_nipart(&lease, &config),
+                                    )),
+                                ),
+                            )
+                            .await
+                            .ok();
+                        }
                         return Self {};
                     }
                 }
             }
             for event in reply_events {
-                log::debug!("Sending {event}");
-                log::trace!("Sending {event:?}");
-                if let Err(e) = to_daemon.send(event.clone()).await {
+                if let Err(e) = send_event(&to_daemon, event).await {
                     log::error!(
                         "Mozim worker for {iface_name} event {event_uuid}: \
-                        Failed to send {event}: {e}"
+                        {e}"
                     );
                     return Self {};
                 }
             }
             if has_lease {
                 if let Err(e) = register_monitor_on_link_down(
-                    &to_daemon,
-                    iface_name.as_str(),
-                    event_uuid,
+                    &to_daemon, iface_name, event_uuid,
                 )
                 .await
                 {
@@ -129,11 +230,51 @@ impl MozimWorkerV4Thread {
     }
 }
 
+// Send DHCPRELEASE and notify commander to remove the lease
+async fn release_lease(
+    mozim_client: &mut DhcpV4Client,
+    lease: DhcpV4Lease,
+    config: &NipartDhcpConfigV4,
+    to_daemon: &Sender<NipartEvent>,
+) {
+    let iface_name = config.iface.as_str();
+    log::info!("Releasing DHCP lease {} of {iface_name}", lease.yiaddr);
+    if let Err(e) = mozim_client.release(&lease) {
+        log::warn!(
+            "Failed to send DHCPRELEASE for {} of {iface_name}: {e}",
+            lease.yiaddr
+        );
+    }
+    remove_lease_v4(iface_name);
+    if let Err(e) = send_event(
+        to_daemon,
+        gen_dhcp_event(NipartPluginEvent::ReleasedDhcpLease(Box::new(
+            mozim_lease_to_nipart(&lease, config),
+        ))),
+    )
+    .await
+    {
+        log::error!("Mozim worker for {iface_name}: {e}");
+    }
+}
+
+pub(crate) async fn send_event(
+    to_daemon: &Sender<NipartEvent>,
+    event: NipartEvent,
+) -> Result<(), NipartError> {
+    log::debug!("Sending {event}");
+    log::trace!("Sending {event:?}");
+    to_daemon.send(event.clone()).await.map_err(|e| {
+        NipartError::new(ErrorKind::Bug, format!("Failed to send {event}: {e}"))
+    })
+}
+
 #[derive(Debug)]
 pub(crate) struct MozimWorkerV4 {
     pub(crate) state: MozimWorkerState,
     pub(crate) config: NipartDhcpConfigV4,
     pub(crate) thread_handler: Option<JoinHandle<MozimWorkerV4Thread>>,
+    pub(crate) stop_sender: Option<oneshot::Sender<MozimStopKind>>,
     pub(crate) event_uuid: NipartUuid,
     pub(crate) to_daemon: Sender<NipartEvent>,
 }
@@ -163,6 +304,7 @@ impl MozimWorkerV4 {
                 state: MozimWorkerState::WaitLink,
                 config: conf.clone(),
                 thread_handler: None,
+                stop_sender: None,
                 event_uuid,
                 to_daemon,
             })
@@ -171,6 +313,7 @@ impl MozimWorkerV4 {
                 state: MozimWorkerState::Disabled,
                 config: conf.clone(),
                 thread_handler: None,
+                stop_sender: None,
                 event_uuid,
                 to_daemon,
             })
@@ -188,8 +331,11 @@ impl MozimWorkerV4 {
             handler.abort();
         }
         let mozim_config = gen_mozim_config(&self.config);
-        // TODO: Load existing lease
-        let cli = DhcpV4Client::init(mozim_config, None).map_err(|e| {
+        // Stored lease will be used for INIT-REBOOT
+        let lease = load_lease_v4(self.config.iface.as_str());
+        // The address of stored lease might still be on the interface
+        let last_ip = lease.as_ref().map(|l| l.yiaddr);
+        let cli = DhcpV4Client::init(mozim_config, lease).map_err(|e| {
             NipartError::new(
                 ErrorKind::InvalidArgument,
                 format!("Failed to start DHCP {}", e),
@@ -198,35 +344,38 @@ impl MozimWorkerV4 {
         self.state = MozimWorkerState::Running;
         let to_daemon = self.to_daemon.clone();
         let event_uuid = self.event_uuid;
-        let iface_name = self.config.iface.clone();
+        let config = self.config.clone();
+        let (stop_sender, stop_recv) = oneshot::channel();
+        self.stop_sender = Some(stop_sender);
         self.thread_handler = Some(tokio::task::spawn(async move {
-            MozimWorkerV4Thread::new(iface_name, cli, to_daemon, event_uuid)
-                .await
+            MozimWorkerV4Thread::new(
+                config, cli, to_daemon, event_uuid, stop_recv, last_ip,
+            )
+            .await
         }));
 
         Ok(())
     }
 
+    /// Stop the DHCP thread on link down, the acquired lease is notified
+    /// as lost.
     pub(crate) async fn stop(&mut self) {
-        if let Some(handler) = &self.thread_handler {
-            handler.abort();
+        if self.stop_thread(MozimStopKind::LinkDown).await {
             log::debug!(
                 "DHCP for interface {} has stopped",
                 self.config.iface.as_str()
             );
-            if self.config.enabled {
-                if let Err(e) = register_monitor_on_link_up(
-                    &self.to_daemon,
-                    self.config.iface.as_str(),
-                    self.event_uuid,
-                )
-                .await
-                {
-                    log::error!(
-                        "BUG: MozimWorkerV4::stop(): \
+            if let Err(e) = register_monitor_on_link_up(
+                &self.to_daemon,
+                self.config.iface.as_str(),
+                self.event_uuid,
+            )
+            .await
+            {
+                log::error!(
+                    "BUG: MozimWorkerV4::stop(): \
                     register_monitor_on_link_up got failure {e}"
-                    );
-                }
+                );
             }
         } else {
             log::debug!(
@@ -234,8 +383,35 @@ impl MozimWorkerV4 {
                 self.config.iface.as_str()
             );
         }
+        self.state = MozimWorkerState::WaitLink;
+    }
+
+    /// Send DHCPRELEASE if lease acquired and stop the DHCP thread.
+    pub(crate) async fn release(&mut self) {
+        self.stop_thread(MozimStopKind::Release).await;
         self.state = MozimWorkerState::Disabled;
-        self.thread_handler = None;
+    }
+
+    // Return false if no DHCP thread running
+    async fn stop_thread(&mut self, kind: MozimStopKind) -> bool {
+        if let (Some(sender), Some(mut handler)) =
+            (self.stop_sender.take(), self.thread_handler.take())
+        {
+            if sender.send(kind).is_ok()
+                && tokio::time::timeout(MOZIM_STOP_TIMEOUT, &mut handler)
+                    .await
+                    .is_err()
+            {
+                log::warn!(
+                    "Timeout on stopping DHCP thread of {}",
+                    self.config.iface.as_str()
+                );
+            }
+            handler.abort();
+            true
+        } else {
+            false
+        }
     }
 }
 
@@ -243,31 +419,117 @@ fn get_prefix_len(ip: &std::net::Ipv4Addr) -> u8 {
     u32::from_be_bytes(ip.octets()).count_ones() as u8
 }
 
-fn gen_dhcp_lease_event(
-    mozim_lease: DhcpV4Lease,
-    iface_name: &str,
-) -> NipartEvent {
-    let lease = mozim_lease_to_nipart(mozim_lease, iface_name);
+pub(crate) fn gen_dhcp_event(plugin_event: NipartPluginEvent) -> NipartEvent {
     NipartEvent::new(
         NipartUserEvent::None,
-        NipartPluginEvent::GotDhcpLease(Box::new(lease)),
+        plugin_event,
         NipartEventAddress::Dhcp,
         NipartEventAddress::Commander,
         DEFAULT_TIMEOUT,
     )
 }
 
+// The DHCP options not wanted by `auto_dns`, `auto_routes` and `auto_gateway`
+// will be excluded.
 fn mozim_lease_to_nipart(
-    mozim_lease: DhcpV4Lease,
-    iface_name: &str,
+    mozim_lease: &DhcpV4Lease,
+    config: &NipartDhcpConfigV4,
 ) -> NipartDhcpLease {
-    NipartDhcpLease::V4(NipartDhcpLeaseV4::new(
-        iface_name.to_string(),
+    let mut lease = NipartDhcpLeaseV4::new(
+        config.iface.to_string(),
         mozim_lease.yiaddr,
         get_prefix_len(&mozim_lease.subnet_mask),
         mozim_lease.siaddr,
         mozim_lease.lease_time,
-    ))
+    );
+    if config.auto_dns {
+        lease.dns_srvs = mozim_lease.dns_srvs.clone().unwrap_or_default();
+        lease.search_domains = mozim_lease
+            .get_option_raw(DHCP_OPT_DOMAIN_SEARCH)
+            .map(parse_domain_search)
+            .unwrap_or_default();
+        if lease.search_domains.is_empty() {
+            if let Some(domain) = mozim_lease.domain_name.as_ref() {
+                lease.search_domains.push(domain.to_string());
+            }
+        }
+    }
+    if config.auto_routes {
+        lease.classless_routes = mozim_lease
+            .classless_routes
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .filter(|r| config.auto_gateway || r.prefix_length != 0)
+            .map(|r| {
+                NipartDhcpClasslessRouteV4::new(
+                    r.destination,
+                    r.prefix_length,
+                    r.router,
+                )
+            })
+            .collect();
+        if config.auto_gateway {
+            lease.gateways = mozim_lease.gateways.clone().unwrap_or_default();
+        }
+        lease.route_table_id = config.auto_table_id;
+        lease.route_metric = config.auto_route_metric;
+    }
+    lease.ntp_srvs = mozim_lease.ntp_srvs.clone().unwrap_or_default();
+    if config.auto_mtu {
+        lease.mtu = mozim_lease.mtu;
+    }
+    NipartDhcpLease::V4(lease)
+}
+
+// Decode RFC 3397 Domain Search option which is RFC 1035 encoded domain names
+// with compression pointer.
+fn parse_domain_search(raw: &[u8]) -> Vec<String> {
+    let mut ret = Vec::new();
+    let mut pos = 0usize;
+    while pos < raw.len() {
+        let (name, next_pos) = match parse_domain_name(raw, pos) {
+            Some(n) => n,
+            None => {
+                log::warn!("Invalid DHCP domain search option {raw:?}");
+                break;
+            }
+        };
+        if !name.is_empty() {
+            ret.push(name);
+        }
+        pos = next_pos;
+    }
+    ret
+}
+
+// Return domain name and the position after it
+fn parse_domain_name(raw: &[u8], start: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut pos = start;
+    let mut next_pos = None;
+    // Limit the pointer jumps to prevent infinite loop
+    let mut jumps = 0;
+    loop {
+        let len = *raw.get(pos)? as usize;
+        if len == 0 {
+            return Some((labels.join("."), next_pos.unwrap_or(pos + 1)));
+        } else if len & 0xc0 == 0xc0 {
+            jumps += 1;
+            if jumps > raw.len() {
+                return None;
+            }
+            let offset = ((len & 0x3f) << 8) | *raw.get(pos + 1)? as usize;
+            if next_pos.is_none() {
+                next_pos = Some(pos + 2);
+            }
+            pos = offset;
+        } else {
+            let label = raw.get(pos + 1..pos + 1 + len)?;
+            labels.push(String::from_utf8_lossy(label).to_string());
+            pos += 1 + len;
+        }
+    }
 }
 
 fn gen_mozim_config(conf: &NipartDhcpConfigV4) -> DhcpV4Config {
@@ -280,7 +542,7 @@ fn gen_mozim_config(conf: &NipartDhcpConfigV4) -> DhcpV4Config {
     mozim_config
 }
 
-async fn register_monitor_on_link_down(
+pub(crate) async fn register_monitor_on_link_down(
     to_daemon: &Sender<NipartEvent>,
     iface: &str,
     event_uuid: NipartUuid,
@@ -304,7 +566,7 @@ async fn register_monitor_on_link_down(
     Ok(())
 }
 
-async fn register_monitor_on_link_up(
+pub(crate) async fn register_monitor_on_link_up(
     to_daemon: &Sender<NipartEvent>,
     iface: &str,
     event_uuid: NipartUuid,
@@ -0,0 +1,434 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::os::fd::AsRawFd;
+use std::time::{Duration, Instant};
+
+use mozim::{
+    DhcpV6Client, DhcpV6Config, DhcpV6Event, DhcpV6IaType, DhcpV6Lease,
+};
+use nipart::{
+    NipartDhcpConfigV6, NipartDhcpLease, NipartDhcpLeaseV6, NipartError,
+    NipartEvent, NipartPluginEvent, NipartUuid,
+};
+use tokio::{
+    io::unix::AsyncFd,
+    sync::{mpsc::Sender, oneshot},
+    task::JoinHandle,
+};
+
+use crate::lease::{load_lease_v6, remove_lease_v6, store_lease_v6};
+use crate::worker::{
+    gen_dhcp_event, register_monitor_on_link_down, register_monitor_on_link_up,
+    send_event, MozimStopKind, MozimWorkerState, MOZIM_STOP_TIMEOUT,
+};
+
+const MOZIM_NO_BLOCKING_TIMEOUT: u32 = 0;
+// DHCPv6 client require IPv6 link-local address which might still in DAD
+// progress after link up, hence retry with this interval.
+const MOZIM_V6_INIT_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub(crate) struct MozimWorkerV6Thread {}
+
+impl MozimWorkerV6Thread {
+    pub(crate) async fn new(
+        config: NipartDhcpConfigV6,
+        to_daemon: Sender<NipartEvent>,
+        event_uuid: NipartUuid,
+        mut stop_recv: oneshot::Receiver<MozimStopKind>,
+        lease: Option<DhcpV6Lease>,
+    ) -> Self {
+        let iface_name = config.iface.as_str();
+        // The address of stored lease might still be on the interface
+        let mut last_ip = lease.as_ref().map(|l| l.addr);
+        let mut mozim_client = match init_mozim_client(&config, lease).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!(
+                    "Mozim worker for {iface_name} event {event_uuid}: \
+                    Failed to start DHCPv6 client: {e}"
+                );
+                return Self {};
+            }
+        };
+        let fd = match AsyncFd::new(mozim_client.as_raw_fd()) {
+            Ok(fd) => fd,
+            Err(e) => {
+                log::error!(
+                    "Mozim worker for {iface_name} event {event_uuid}: \
+                    AsyncFd::new() failed with {e}"
+                );
+                return Self {};
+            }
+        };
+        let mut cur_lease: Option<DhcpV6Lease> = None;
+        loop {
+            tokio::select! {
+                result = fd.readable() => match result {
+                    Ok(mut guard) => guard.clear_ready(),
+                    Err(e) => {
+                        log::error!(
+                            "Mozim worker for {iface_name} event \
+                            {event_uuid}: AsyncFd::readable() failed with {e}"
+                        );
+                        return Self {};
+                    }
+                },
+                result = &mut stop_recv => {
+                    if let (Ok(kind), Some(lease)) = (result, cur_lease.take())
+                    {
+                        match kind {
+                            MozimStopKind::Release => {
+                                release_lease(
+                                    &mut mozim_client,
+                                    lease,
+                                    iface_name,
+                                    &to_daemon,
+                                )
+                                .await
+                            }
+                            MozimStopKind::LinkDown => {
+                                log::info!(
+                                    "DHCPv6 lease {} of {iface_name} lost on \
+                                    link down",
+                                    lease.addr
+                                );
+                                send_event(
+                                    &to_daemon,
+                                    gen_dhcp_event(
+                                        NipartPluginEvent::LostDhcpLease(
+                                            Box::new(mozim_lease_to_nipart(
+                                                &lease, iface_name,
+                                            )),
+                                        ),
+                                    ),
+                                )
+                                .await
+                                .ok();
+                            }
+                        }
+                    }
+                    return Self {};
+                }
+            }
+            let mut reply_events = Vec::new();
+            let events = match mozim_client.poll(MOZIM_NO_BLOCKING_TIMEOUT) {
+                Ok(e) => e,
+                Err(e) => {
+                    log::error!(
+                        "Mozim worker for {iface_name} event {event_uuid}: \
+                        mozim_client.poll() failed with {e}"
+                    );
+                    return Self {};
+                }
+            };
+            let mut has_lease = false;
+            for event in events {
+                if event == DhcpV6Event::LeaseExpired {
+                    if let Some(lease) = cur_lease.take() {
+                        log::info!(
+                            "DHCPv6 lease {} of {iface_name} expired",
+                            lease.addr
+                        );
+                        remove_lease_v6(iface_name);
+                        reply_events.push(gen_dhcp_event(
+                            NipartPluginEvent::LostDhcpLease(Box::new(
+                                mozim_lease_to_nipart(&lease, iface_name),
+                            )),
+                        ));
+                    }
+                }
+                match mozim_client.process(event) {
+                    Ok(Some(lease)) => {
+                        if let Err(e) = store_lease_v6(iface_name, &lease) {
+                            log::warn!(
+                                "Failed to store DHCPv6 lease of \
+                                {iface_name}: {e}"
+                            );
+                        }
+                        let mut nipart_lease =
+                            mozim_lease_to_nipart(&lease, iface_name);
+                        if let NipartDhcpLease::V6(l) = &mut nipart_lease {
+                            l.previous_ip =
+                                last_ip.filter(|ip| ip != &lease.addr);
+                        }
+                        reply_events.push(gen_dhcp_event(
+                            NipartPluginEvent::GotDhcpLease(Box::new(
+                                nipart_lease,
+                            )),
+                        ));
+                        last_ip = Some(lease.addr);
+                        cur_lease = Some(lease);
+                        has_lease = true;
+                    }
+                    Ok(None) => (),
+                    Err(e) => {
+                        log::error!(
+                            "Mozim worker for {iface_name} event \
+                            {event_uuid}: mozim_client.process() failed \
+                            with {e}"
+                        );
+                        if let Some(lease) = cur_lease.take() {
+                            remove_lease_v6(iface_name);
+                            send_event(
+                                &to_daemon,
+                                gen_dhcp_event(
+                                    NipartPluginEvent::LostDhcpLease(Box::new(
+                                        mozim_lease_to_nipart(
+                                            &lease, iface_name,
+                                        ),
+                                    )),
+                                ),
+                            )
+                            .await
+                            .ok();
+                        }
+                        return Self {};
+                    }
+                }
+            }
+            for event in reply_events {
+                if let Err(e) = send_event(&to_daemon, event).await {
+                    log::error!(
+                        "Mozim worker for {iface_name} event {event_uuid}: \
+                        {e}"
+                    );
+                    return Self {};
+                }
+            }
+            if has_lease {
+                if let Err(e) = register_monitor_on_link_down(
+                    &to_daemon, iface_name, event_uuid,
+                )
+                .await
+                {
+                    log::error!(
+                        "Failed to register link down monitor rule for \
+                        interface {iface_name}: {e}"
+                    );
+                    return Self {};
+                }
+            }
+        }
+    }
+}
+
+// Send DHCPv6 RELEASE and notify commander to remove the lease
+async fn release_lease(
+    mozim_client: &mut DhcpV6Client,
+    lease: DhcpV6Lease,
+    iface_name: &str,
+    to_daemon: &Sender<NipartEvent>,
+) {
+    log::info!("Releasing DHCPv6 lease {} of {iface_name}", lease.addr);
+    if let Err(e) = mozim_client.release(&lease) {
+        log::warn!(
+            "Failed to send DHCPv6 RELEASE for {} of {iface_name}: {e}",
+            lease.addr
+        );
+    }
+    remove_lease_v6(iface_name);
+    if let Err(e) = send_event(
+        to_daemon,
+        gen_dhcp_event(NipartPluginEvent::ReleasedDhcpLease(Box::new(
+            mozim_lease_to_nipart(&lease, iface_name),
+        ))),
+    )
+    .await
+    {
+        log::error!("Mozim worker for {iface_name}: {e}");
+    }
+}
+
+// Only IA_NA is requested as network state cannot express delegated prefix.
+// With stored lease, the DHCPv6 client starts with RENEW of the same address.
+async fn init_mozim_client(
+    config: &NipartDhcpConfigV6,
+    mut lease: Option<DhcpV6Lease>,
+) -> Result<DhcpV6Client, NipartError> {
+    let deadline = Instant::now() + Duration::from_secs(config.timeout.into());
+    loop {
+        let mut mozim_config = DhcpV6Config::new(
+            config.iface.as_str(),
+            DhcpV6IaType::NonTemporaryAddresses,
+        );
+        mozim_config.set_timeout(config.timeout);
+        match DhcpV6Client::init(mozim_config, lease.clone()) {
+            Ok(c) => return Ok(c),
+            Err(e) => {
+                if Instant::now() >= deadline {
+                    return Err(NipartError::new(
+                        nipart::ErrorKind::Timeout,
+                        format!("Failed to start DHCPv6 {e}"),
+                    ));
+                }
+                log::debug!(
+                    "Failed to start DHCPv6 on {}: {e}, will retry",
+                    config.iface
+                );
+                // Stored lease might be refused by mozim, start from
+                // SOLICIT in next retry.
+                if let Some(l) = lease.take() {
+                    log::debug!(
+                        "Discarding stored DHCPv6 lease {} of {}",
+                        l.addr,
+                        config.iface
+                    );
+                }
+                tokio::time::sleep(MOZIM_V6_INIT_RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct MozimWorkerV6 {
+    pub(crate) state: MozimWorkerState,
+    pub(crate) config: NipartDhcpConfigV6,
+    pub(crate) thread_handler: Option<JoinHandle<MozimWorkerV6Thread>>,
+    pub(crate) stop_sender: Option<oneshot::Sender<MozimStopKind>>,
+    pub(crate) event_uuid: NipartUuid,
+    pub(crate) to_daemon: Sender<NipartEvent>,
+}
+
+impl Drop for MozimWorkerV6 {
+    fn drop(&mut self) {
+        if let Some(handler) = &self.thread_handler {
+            handler.abort();
+        }
+    }
+}
+
+impl MozimWorkerV6 {
+    pub(crate) async fn new(
+        conf: &NipartDhcpConfigV6,
+        event_uuid: NipartUuid,
+        to_daemon: Sender<NipartEvent>,
+    ) -> Result<Self, NipartError> {
+        if conf.enabled {
+            register_monitor_on_link_up(
+                &to_daemon,
+                conf.iface.as_str(),
+                event_uuid,
+            )
+            .await?;
+            Ok(Self {
+                state: MozimWorkerState::WaitLink,
+                config: conf.clone(),
+                thread_handler: None,
+                stop_sender: None,
+                event_uuid,
+                to_daemon,
+            })
+        } else {
+            Ok(Self {
+                state: MozimWorkerState::Disabled,
+                config: conf.clone(),
+                thread_handler: None,
+                stop_sender: None,
+                event_uuid,
+                to_daemon,
+            })
+        }
+    }
+
+    pub(crate) fn get_config(&self) -> NipartDhcpConfigV6 {
+        self.config.clone()
+    }
+
+    /// Please only invoke this function after link up.
+    pub(crate) fn start(&mut self) -> Result<(), NipartError> {
+        if let Some(handler) = &self.thread_handler {
+            log::debug!("Stopping existing DHCPv6 thread before invoke new");
+            handler.abort();
+        }
+        self.state = MozimWorkerState::Running;
+        let to_daemon = self.to_daemon.clone();
+        let event_uuid = self.event_uuid;
+        let config = self.config.clone();
+        // Stored lease will be renewed and its address removed once got a
+        // different one.
+        let lease = load_lease_v6(self.config.iface.as_str());
+        let (stop_sender, stop_recv) = oneshot::channel();
+        self.stop_sender = Some(stop_sender);
+        self.thread_handler = Some(tokio::task::spawn(async move {
+            MozimWorkerV6Thread::new(
+                config, to_daemon, event_uuid, stop_recv, lease,
+            )
+            .await
+        }));
+
+        Ok(())
+    }
+
+    /// Stop the DHCPv6 thread on link down, the acquired lease is notified
+    /// as lost.
+    pub(crate) async fn stop(&mut self) {
+        if self.stop_thread(MozimStopKind::LinkDown).await {
+            log::debug!(
+                "DHCPv6 for interface {} has stopped",
+                self.config.iface.as_str()
+            );
+            if let Err(e) = register_monitor_on_link_up(
+                &self.to_daemon,
+                self.config.iface.as_str(),
+                self.event_uuid,
+            )
+            .await
+            {
+                log::error!(
+                    "BUG: MozimWorkerV6::stop(): \
+                    register_monitor_on_link_up got failure {e}"
+                );
+            }
+        } else {
+            log::debug!(
+                "No DHCPv6 thread for interface {} require stop",
+                self.config.iface.as_str()
+            );
+        }
+        self.state = MozimWorkerState::WaitLink;
+    }
+
+    /// Send DHCPv6 RELEASE if lease acquired and stop the DHCPv6 thread.
+    pub(crate) async fn release(&mut self) {
+        self.stop_thread(MozimStopKind::Release).await;
+        self.state = MozimWorkerState::Disabled;
+    }
+
+    // Return false if no DHCPv6 thread running
+    async fn stop_thread(&mut self, kind: MozimStopKind) -> bool {
+        if let (Some(sender), Some(mut handler)) =
+            (self.stop_sender.take(), self.thread_handler.take())
+        {
+            if sender.send(kind).is_ok()
+                && tokio::time::timeout(MOZIM_STOP_TIMEOUT, &mut handler)
+                    .await
+                    .is_err()
+            {
+                log::warn!(
+                    "Timeout on stopping DHCPv6 thread of {}",
+                    self.config.iface.as_str()
+                );
+            }
+            handler.abort();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn mozim_lease_to_nipart(
+    mozim_lease: &DhcpV6Lease,
+    iface_name: &str,
+) -> NipartDhcpLease {
+    NipartDhcpLease::V6(NipartDhcpLeaseV6::new(
+        iface_name.to_string(),
+        mozim_lease.addr,
+        mozim_lease.prefix_len,
+        mozim_lease.srv_ip,
+        mozim_lease.preferred_life,
+        mozim_lease.valid_life,
+    ))
+}
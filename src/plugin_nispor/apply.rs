@@ -1,20 +1,49 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use netlink_packet_route::{link::InfoKind, route as rtnl_route};
 use nipart::{
     ErrorKind, Interface, InterfaceType, MergedInterface, MergedInterfaces,
-    MergedNetworkState, NipartApplyOption, NipartDhcpLease, NipartError,
+    MergedNetworkState, MptcpAddressFlag, NipartApplyOption, NipartDhcpLease,
+    NipartDhcpLeaseV4, NipartError, RouteEntry, RouteState,
 };
 
 use crate::{
+    bond::{apply_bond_conf, nipart_bond_conf_to_np},
+    ethernet::apply_ethernet_link_mode,
+    ethtool::apply_ethtool_conf,
     hostname::set_running_hostname,
+    hsr::{is_hsr_conf_changed, nipart_hsr_to_rtnl},
+    infiniband::{is_ib_conf_changed, nipart_ib_to_rtnl},
     ip::{nipart_ipv4_to_np, nipart_ipv6_to_np},
+    linux_bridge::apply_bridge_conf,
+    mac_vlan::{nipart_mac_vlan_to_rtnl, nipart_mac_vtap_to_rtnl},
+    macsec::{
+        apply_macsec_mka, is_macsec_conf_changed, nipart_macsec_to_rtnl,
+        purge_macsec_mka,
+    },
+    mptcp::{apply_mptcp_conf, sync_iface_mptcp_endpoints},
+    netlink::{
+        create_link, delete_link, get_iface_index, get_iface_index_or_err,
+        new_rtnl_handle, set_link_controller,
+    },
+    ovs::{is_ovs_port, is_ovs_userspace_iface, wait_ovs_internal_ifaces},
+    route::{apply_route_entries, apply_routes, get_iface_routes_v4},
+    route_rule::apply_route_rules,
+    sriov::apply_sriov_conf,
     veth::nms_veth_conf_to_np,
     vlan::nms_vlan_conf_to_np,
+    vrf::{is_vrf_conf_changed, nipart_vrf_to_rtnl},
+    vxlan::nipart_vxlan_to_rtnl,
 };
 
 pub(crate) async fn nispor_apply(
     merged_state: MergedNetworkState,
     _opt: NipartApplyOption,
+    timeout: u32,
+    mptcp_flags: HashMap<String, Vec<MptcpAddressFlag>>,
 ) -> Result<(), NipartError> {
     if let Some(hostname) = merged_state
         .get_desired_hostname()
@@ -28,7 +57,7 @@ pub(crate) async fn nispor_apply(
     let mut ifaces: Vec<&MergedInterface> = merged_state
         .interfaces
         .iter()
-        .filter(|i| i.is_changed())
+        .filter(|i| i.is_changed() && !is_ovs_userspace_iface(&i.merged))
         .collect();
 
     ifaces.sort_unstable_by_key(|iface| iface.merged.name());
@@ -42,15 +71,72 @@ pub(crate) async fn nispor_apply(
         }
     });
 
+    let handle = new_rtnl_handle()?;
+    wait_ovs_internal_ifaces(&handle, ifaces.as_slice()).await?;
+    // The nispor cannot create some interface types yet, they are created
+    // here in the order of up priority, so their controllers and base
+    // interfaces created by nispor exist and their ports can be attached
+    // by nispor afterwards.
     let mut np_ifaces: Vec<nispor::IfaceConf> = Vec::new();
     for merged_iface in ifaces.iter().filter(|i| {
         i.merged.iface_type() != InterfaceType::Unknown && !i.merged.is_absent()
     }) {
-        np_ifaces.push(nipart_iface_to_np(merged_iface)?);
+        let mut recreated = false;
+        if is_created_by_rtnl(&merged_iface.merged.iface_type()) {
+            apply_np_ifaces(std::mem::take(&mut np_ifaces)).await?;
+            recreated = apply_rtnl_iface(&handle, merged_iface).await?;
+            if let Interface::MacSec(macsec_iface) = &merged_iface.merged {
+                // Remove stale MKA config of interface deleted by others
+                if merged_iface.current.is_none() {
+                    purge_macsec_mka(macsec_iface.base.name.as_str());
+                }
+                if let Some(conf) = macsec_iface.macsec.as_ref() {
+                    apply_macsec_mka(
+                        macsec_iface.base.name.as_str(),
+                        conf,
+                        recreated || merged_iface.current.is_none(),
+                    )?;
+                }
+            }
+        }
+        np_ifaces.push(nipart_iface_to_np(merged_iface, recreated)?);
     }
 
     // TODO: Purge DHCP/autoconf IP/routes if DHCP/autoconf disabled
 
+    apply_np_ifaces(np_ifaces).await?;
+
+    for merged_iface in ifaces.iter().filter(|i| !i.merged.is_absent()) {
+        match merged_iface.merged.iface_type() {
+            InterfaceType::Bond => {
+                apply_bond_conf(&handle, merged_iface).await?
+            }
+            InterfaceType::LinuxBridge => {
+                apply_bridge_conf(&handle, merged_iface).await?
+            }
+            InterfaceType::Ethernet => {
+                apply_sriov_conf(&handle, merged_iface, timeout).await?;
+                apply_ethernet_link_mode(merged_iface).await?;
+            }
+            _ => (),
+        }
+        apply_ethtool_conf(merged_iface).await?;
+    }
+
+    // MPTCP endpoints are created after IP addresses been assigned.
+    apply_mptcp_conf(&merged_state, &mptcp_flags).await?;
+
+    // Routes are applied after interfaces, so next hop interfaces exist.
+    apply_routes(&merged_state.routes).await?;
+    apply_route_rules(&merged_state.rules).await
+}
+
+async fn apply_np_ifaces(
+    np_ifaces: Vec<nispor::IfaceConf>,
+) -> Result<(), NipartError> {
+    if np_ifaces.is_empty() {
+        return Ok(());
+    }
     let mut net_conf = nispor::NetConf::default();
     net_conf.ifaces = Some(np_ifaces);
 
@@ -64,6 +150,203 @@ pub(crate) async fn nispor_apply(
     }
 }
 
+fn is_created_by_rtnl(iface_type: &InterfaceType) -> bool {
+    matches!(
+        iface_type,
+        InterfaceType::Dummy
+            | InterfaceType::Vxlan
+            | InterfaceType::Vrf
+            | InterfaceType::MacVlan
+            | InterfaceType::MacVtap
+            | InterfaceType::MacSec
+            | InterfaceType::Hsr
+            | InterfaceType::InfiniBand
+    )
+}
+
+// Kernel does not support changing most properties of these interface types,
+// hence interface is recreated on config change.
+// Return true if existing interface been recreated.
+async fn apply_rtnl_iface(
+    handle: &rtnetlink::Handle,
+    merged_iface: &MergedInterface,
+) -> Result<bool, NipartError> {
+    let iface = &merged_iface.merged;
+    let iface_name = iface.name();
+    let mut recreated = false;
+    if let Some(cur_iface) = merged_iface.current.as_ref() {
+        if !is_rtnl_iface_conf_changed(iface, cur_iface) {
+            return Ok(false);
+        }
+        log::info!("Recreating interface {iface_name} for config change");
+        if let Some(index) = get_iface_index(handle, iface_name).await {
+            delete_link(handle, iface_name, index).await?;
+            recreated = true;
+        }
+    }
+    log::debug!("Creating interface {iface_name}");
+    match iface {
+        Interface::Dummy(_) => {
+            create_link(handle, iface_name, None, InfoKind::Dummy, None).await?
+        }
+        Interface::Vxlan(vxlan_iface) => {
+            let conf = get_iface_conf(iface_name, vxlan_iface.vxlan.as_ref())?;
+            let data = nipart_vxlan_to_rtnl(handle, conf).await?;
+            create_link(handle, iface_name, None, InfoKind::Vxlan, Some(data))
+                .await?
+        }
+        Interface::Vrf(vrf_iface) => {
+            let conf = get_iface_conf(iface_name, vrf_iface.vrf.as_ref())?;
+            let data = nipart_vrf_to_rtnl(iface_name, conf)?;
+            create_link(handle, iface_name, None, InfoKind::Vrf, Some(data))
+                .await?;
+            // Ports been detached when deleting the old VRF interface
+            if merged_iface.current.is_some() {
+                let index = get_iface_index_or_err(handle, iface_name).await?;
+                for port in conf.port.as_deref().unwrap_or_default() {
+                    let port_index =
+                        get_iface_index_or_err(handle, port).await?;
+                    set_link_controller(handle, port, port_index, Some(index))
+                        .await?;
+                }
+            }
+        }
+        Interface::MacVlan(mac_vlan_iface) => {
+            let conf =
+                get_iface_conf(iface_name, mac_vlan_iface.mac_vlan.as_ref())?;
+            let (parent_index, data) =
+                nipart_mac_vlan_to_rtnl(handle, iface_name, conf).await?;
+            create_link(
+                handle,
+                iface_name,
+                Some(parent_index),
+                InfoKind::MacVlan,
+                Some(data),
+            )
+            .await?
+        }
+        Interface::MacVtap(mac_vtap_iface) => {
+            let conf =
+                get_iface_conf(iface_name, mac_vtap_iface.mac_vtap.as_ref())?;
+            let (parent_index, data) =
+                nipart_mac_vtap_to_rtnl(handle, iface_name, conf).await?;
+            create_link(
+                handle,
+                iface_name,
+                Some(parent_index),
+                InfoKind::MacVtap,
+                Some(data),
+            )
+            .await?
+        }
+        Interface::MacSec(macsec_iface) => {
+            let conf =
+                get_iface_conf(iface_name, macsec_iface.macsec.as_ref())?;
+            let (parent_index, data) =
+                nipart_macsec_to_rtnl(handle, iface_name, conf).await?;
+            create_link(
+                handle,
+                iface_name,
+                Some(parent_index),
+                InfoKind::MacSec,
+                Some(data),
+            )
+            .await?
+        }
+        Interface::Hsr(hsr_iface) => {
+            let conf = get_iface_conf(iface_name, hsr_iface.hsr.as_ref())?;
+            let data = nipart_hsr_to_rtnl(handle, conf).await?;
+            create_link(handle, iface_name, None, InfoKind::Hsr, Some(data))
+                .await?
+        }
+        Interface::InfiniBand(ib_iface) => {
+            // Only PKEY child interface can be created
+            if let Some((parent_index, data)) = match ib_iface.ib.as_ref() {
+                Some(conf) => nipart_ib_to_rtnl(handle, conf).await?,
+                None => None,
+            } {
+                create_link(
+                    handle,
+                    iface_name,
+                    Some(parent_index),
+                    InfoKind::Ipoib,
+                    Some(data),
+                )
+                .await?
+            } else if merged_iface.current.is_none() {
+                return Err(NipartError::new(
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "Cannot create InfiniBand interface {iface_name} \
+                        without base interface and PKEY defined"
+                    ),
+                ));
+            }
+        }
+        _ => (),
+    }
+    Ok(recreated)
+}
+
+fn is_rtnl_iface_conf_changed(
+    iface: &Interface,
+    cur_iface: &Interface,
+) -> bool {
+    match (iface, cur_iface) {
+        (Interface::Dummy(_), Interface::Dummy(_)) => false,
+        (Interface::Vxlan(des), Interface::Vxlan(cur)) => {
+            des.vxlan != cur.vxlan
+        }
+        (Interface::Vrf(des), Interface::Vrf(cur)) => {
+            match (des.vrf.as_ref(), cur.vrf.as_ref()) {
+                (Some(des), Some(cur)) => is_vrf_conf_changed(des, cur),
+                _ => false,
+            }
+        }
+        (Interface::MacVlan(des), Interface::MacVlan(cur)) => {
+            des.mac_vlan != cur.mac_vlan
+        }
+        (Interface::MacVtap(des), Interface::MacVtap(cur)) => {
+            des.mac_vtap != cur.mac_vtap
+        }
+        (Interface::MacSec(des), Interface::MacSec(cur)) => {
+            match (des.macsec.as_ref(), cur.macsec.as_ref()) {
+                (Some(des), Some(cur)) => is_macsec_conf_changed(des, cur),
+                _ => false,
+            }
+        }
+        (Interface::Hsr(des), Interface::Hsr(cur)) => {
+            match (des.hsr.as_ref(), cur.hsr.as_ref()) {
+                (Some(des), Some(cur)) => is_hsr_conf_changed(des, cur),
+                _ => false,
+            }
+        }
+        (Interface::InfiniBand(des), Interface::InfiniBand(cur)) => {
+            match (des.ib.as_ref(), cur.ib.as_ref()) {
+                (Some(des), Some(cur)) => is_ib_conf_changed(des, cur),
+                _ => false,
+            }
+        }
+        // Interface type changed
+        _ => true,
+    }
+}
+
+fn get_iface_conf<'a, T>(
+    iface_name: &str,
+    conf: Option<&'a T>,
+) -> Result<&'a T, NipartError> {
+    conf.ok_or_else(|| {
+        NipartError::new(
+            ErrorKind::InvalidArgument,
+            format!(
+                "Interface specific configure is mandatory for creating \
+                interface {iface_name}"
+            ),
+        )
+    })
+}
+
 fn nipart_iface_type_to_np(
     nms_iface_type: &InterfaceType,
 ) -> nispor::IfaceType {
@@ -73,16 +356,29 @@ fn nipart_iface_type_to_np(
         InterfaceType::Ethernet => nispor::IfaceType::Ethernet,
         InterfaceType::Veth => nispor::IfaceType::Veth,
         InterfaceType::Vlan => nispor::IfaceType::Vlan,
+        InterfaceType::Dummy => nispor::IfaceType::Dummy,
+        InterfaceType::Vxlan => nispor::IfaceType::Vxlan,
+        InterfaceType::Vrf => nispor::IfaceType::Vrf,
+        InterfaceType::MacVlan => nispor::IfaceType::MacVlan,
+        InterfaceType::MacVtap => nispor::IfaceType::MacVtap,
+        InterfaceType::Loopback => nispor::IfaceType::Loopback,
+        InterfaceType::MacSec => nispor::IfaceType::MacSec,
+        InterfaceType::Hsr => nispor::IfaceType::Hsr,
+        InterfaceType::InfiniBand => nispor::IfaceType::Ipoib,
         _ => nispor::IfaceType::Unknown,
     }
 }
 
+// The recreated interface lost its controller, IP and MTU, hence the full
+// merged config is used instead of changed properties only.
 fn nipart_iface_to_np(
     merged_iface: &MergedInterface,
+    recreated: bool,
 ) -> Result<nispor::IfaceConf, NipartError> {
     let mut np_iface = nispor::IfaceConf::default();
 
     let for_apply = match merged_iface.for_apply.as_ref() {
+        Some(_) if recreated => &merged_iface.merged,
         Some(i) => i,
         None => {
             return Err(NipartError::new(
@@ -113,8 +409,11 @@ fn nipart_iface_to_np(
     np_iface.state = nispor::IfaceState::Up;
 
     let base_iface = &for_apply.base_iface();
+    // OVS bridge ports are attached by OVS plugin via OVSDB
     if let Some(ctrl_name) = &base_iface.controller {
-        np_iface.controller = Some(ctrl_name.to_string())
+        if !is_ovs_port(for_apply) {
+            np_iface.controller = Some(ctrl_name.to_string())
+        }
     }
     if base_iface.can_have_ip() {
         np_iface.ipv4 = Some(nipart_ipv4_to_np(base_iface.ipv4.as_ref()));
@@ -122,11 +421,14 @@ fn nipart_iface_to_np(
     }
 
     np_iface.mac_address = base_iface.mac_address.clone();
+    np_iface.mtu = base_iface.mtu.and_then(|m| u32::try_from(m).ok());
 
     if let Interface::Ethernet(eth_iface) = for_apply {
         np_iface.veth = nms_veth_conf_to_np(eth_iface.veth.as_ref());
     } else if let Interface::Vlan(vlan_iface) = &merged_iface.merged {
         np_iface.vlan = nms_vlan_conf_to_np(vlan_iface.vlan.as_ref());
+    } else if let Interface::Bond(bond_iface) = &merged_iface.merged {
+        np_iface.bond = nipart_bond_conf_to_np(bond_iface.bond.as_ref())?;
     }
 
     Ok(np_iface)
@@ -142,6 +444,10 @@ async fn delete_ifaces(
         .values()
         .filter(|i| i.merged.is_absent())
     {
+        // OVS interfaces are removed by OVS plugin via OVSDB
+        if iface.merged.iface_type() == InterfaceType::OvsInterface {
+            continue;
+        }
         // Deleting one end of veth peer is enough
         if deleted_veths.contains(&iface.merged.name()) {
             continue;
@@ -157,59 +463,527 @@ async fn delete_ifaces(
                 deleted_veths.push(peer_name);
             }
         }
+        if let Some(Interface::MacSec(_)) = &iface.current {
+            purge_macsec_mka(iface.merged.name());
+        }
         log::debug!("Deleting interface {}", iface.merged.name());
-        np_ifaces.push(nipart_iface_to_np(iface)?);
+        np_ifaces.push(nipart_iface_to_np(iface, false)?);
     }
 
-    let mut net_conf = nispor::NetConf::default();
-    net_conf.ifaces = Some(np_ifaces);
-
-    if let Err(e) = net_conf.apply_async().await {
-        Err(NipartError::new(
-            ErrorKind::PluginFailure,
-            format!("Unknown error from nipsor plugin: {}, {}", e.kind, e.msg),
-        ))
-    } else {
-        Ok(())
-    }
+    apply_np_ifaces(np_ifaces).await
 }
 
+// Static addresses are preserved as nispor only add the lease address.
+// Address of previous lease is removed after the new one been added.
+// Routes of previous lease not provided by this lease are removed before
+// adding new routes.
+// MPTCP endpoints are synced afterwards with the stored MPTCP flags of the
+// interface.
 pub(crate) async fn nispor_apply_dhcp_lease(
     lease: NipartDhcpLease,
+    mptcp_flags: Option<Vec<MptcpAddressFlag>>,
 ) -> Result<(), NipartError> {
+    let mut np_iface = nispor::IfaceConf::default();
+    let mut ip_conf = nispor::IpConf::default();
+    let mut ip_addr = nispor::IpAddrConf::default();
+    let mut routes = Vec::new();
+    let cur_iface = get_cur_iface(lease.iface()).await?;
     match lease {
         NipartDhcpLease::V4(lease) => {
-            let mut net_conf = nispor::NetConf::default();
-            let mut np_iface = nispor::IfaceConf::default();
+            let new_routes = dhcp_lease_v4_to_routes(&lease, false);
+            routes = get_stale_dhcp_routes_v4(
+                lease.iface.as_str(),
+                new_routes.as_slice(),
+            )
+            .await?;
+            routes.extend(new_routes);
+            np_iface.mtu = lease.mtu.map(u32::from);
             np_iface.name = lease.iface.to_string();
-            let mut ip_conf = nispor::IpConf::default();
-            let mut ip_addr = nispor::IpAddrConf::default();
             ip_addr.address = lease.ip.to_string();
             ip_addr.prefix_len = lease.prefix_length;
             ip_addr.valid_lft = format!("{}sec", lease.lease_time);
             ip_addr.preferred_lft = format!("{}sec", lease.lease_time);
-            // BUG: We should preserve existing IP address
             ip_conf.addresses.push(ip_addr);
+            if let Some(prev_ip) = lease.previous_ip {
+                ip_conf.addresses.extend(get_previous_lease_addr_v4(
+                    &cur_iface,
+                    prev_ip.to_string().as_str(),
+                ));
+            }
             np_iface.ipv4 = Some(ip_conf);
-            np_iface.state = nispor::IfaceState::Up;
-            net_conf.ifaces = Some(vec![np_iface]);
+        }
+        NipartDhcpLease::V6(lease) => {
+            np_iface.name = lease.iface.to_string();
+            ip_addr.address = lease.ip.to_string();
+            ip_addr.prefix_len = lease.prefix_length;
+            ip_addr.valid_lft = format!("{}sec", lease.valid_time);
+            ip_addr.preferred_lft = format!("{}sec", lease.preferred_time);
+            ip_conf.addresses.push(ip_addr);
+            if let Some(prev_ip) = lease.previous_ip {
+                ip_conf.addresses.extend(get_previous_lease_addr_v6(
+                    &cur_iface,
+                    prev_ip.to_string().as_str(),
+                ));
+            }
+            np_iface.ipv6 = Some(ip_conf);
+        }
+    }
+    np_iface.state = nispor::IfaceState::Up;
+    let mut net_conf = nispor::NetConf::default();
+    net_conf.ifaces = Some(vec![np_iface]);
 
-            log::debug!("Plugin nispor apply {net_conf:?}");
+    log::debug!("Plugin nispor apply {net_conf:?}");
 
-            if let Err(e) = net_conf.apply_async().await {
-                Err(NipartError::new(
-                    ErrorKind::PluginFailure,
-                    format!(
-                        "Unknown error nispor apply_async: {}, {}",
-                        e.kind, e.msg
-                    ),
-                ))
-            } else {
-                Ok(())
+    if let Err(e) = net_conf.apply_async().await {
+        return Err(NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Unknown error nispor apply_async: {}, {}", e.kind, e.msg),
+        ));
+    }
+    // Routes are added after lease address, so gateway is reachable
+    apply_route_entries(routes.as_slice(), rtnl_route::RouteProtocol::Dhcp)
+        .await?;
+    if let Some(flags) = mptcp_flags {
+        sync_iface_mptcp_endpoints(cur_iface.name.as_str(), &flags).await?;
+    }
+    Ok(())
+}
+
+/// Remove the address and routes of lost or released DHCP lease.
+pub(crate) async fn nispor_remove_dhcp_lease(
+    lease: NipartDhcpLease,
+    mptcp_flags: Option<Vec<MptcpAddressFlag>>,
+) -> Result<(), NipartError> {
+    let cur_iface = match get_cur_iface(lease.iface()).await {
+        Ok(i) => i,
+        Err(e) if e.kind == ErrorKind::InvalidArgument => {
+            log::debug!(
+                "Interface {} removed, no need to remove DHCP lease",
+                lease.iface()
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let mut np_iface = nispor::IfaceConf::default();
+    np_iface.name = cur_iface.name.to_string();
+    np_iface.state = cur_iface.state.clone();
+    let mut ip_conf = nispor::IpConf::default();
+    let mut routes = Vec::new();
+    match lease {
+        NipartDhcpLease::V4(lease) => {
+            routes = dhcp_lease_v4_to_routes(&lease, true);
+            ip_conf.addresses.push(gen_remove_addr_conf(
+                lease.ip.to_string().as_str(),
+                lease.prefix_length,
+            ));
+            np_iface.ipv4 = Some(ip_conf);
+        }
+        NipartDhcpLease::V6(lease) => {
+            ip_conf.addresses.push(gen_remove_addr_conf(
+                lease.ip.to_string().as_str(),
+                lease.prefix_length,
+            ));
+            np_iface.ipv6 = Some(ip_conf);
+        }
+    }
+    // Routes are removed before lease address, as kernel might have
+    // removed them along with the address
+    apply_route_entries(routes.as_slice(), rtnl_route::RouteProtocol::Dhcp)
+        .await?;
+
+    let mut net_conf = nispor::NetConf::default();
+    net_conf.ifaces = Some(vec![np_iface]);
+
+    log::debug!("Plugin nispor apply {net_conf:?}");
+
+    if let Err(e) = net_conf.apply_async().await {
+        return Err(NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Unknown error nispor apply_async: {}, {}", e.kind, e.msg),
+        ));
+    }
+    if let Some(flags) = mptcp_flags {
+        sync_iface_mptcp_endpoints(cur_iface.name.as_str(), &flags).await?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn get_cur_iface(
+    iface_name: &str,
+) -> Result<nispor::Iface, NipartError> {
+    let mut iface_filter = nispor::NetStateIfaceFilter::minimum();
+    iface_filter.iface_name = Some(iface_name.to_string());
+    iface_filter.include_ip_address = true;
+    iface_filter.include_mptcp = true;
+    let mut filter = nispor::NetStateFilter::minimum();
+    filter.iface = Some(iface_filter);
+    let mut np_state = nispor::NetState::retrieve_with_filter_async(&filter)
+        .await
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!(
+                    "Failed to retrieve interface {iface_name}: {}, {}",
+                    e.kind, e.msg
+                ),
+            )
+        })?;
+    np_state.ifaces.remove(iface_name).ok_or_else(|| {
+        NipartError::new(
+            ErrorKind::InvalidArgument,
+            format!("Interface {iface_name} does not exist"),
+        )
+    })
+}
+
+fn gen_remove_addr_conf(address: &str, prefix_len: u8) -> nispor::IpAddrConf {
+    let mut ip_addr = nispor::IpAddrConf::default();
+    ip_addr.remove = true;
+    ip_addr.address = address.to_string();
+    ip_addr.prefix_len = prefix_len;
+    ip_addr
+}
+
+// The previous lease address is only removed when still holding lifetime,
+// in case user has configured the same address as static.
+fn get_previous_lease_addr_v4(
+    cur_iface: &nispor::Iface,
+    prev_addr: &str,
+) -> Option<nispor::IpAddrConf> {
+    cur_iface
+        .ipv4
+        .as_ref()
+        .map(|i| i.addresses.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .find(|a| a.valid_lft != "forever" && a.address == prev_addr)
+        .map(|a| {
+            log::debug!(
+                "Removing previous DHCP address {}/{} from {}",
+                a.address,
+                a.prefix_len,
+                cur_iface.name
+            );
+            gen_remove_addr_conf(a.address.as_str(), a.prefix_len)
+        })
+}
+
+fn get_previous_lease_addr_v6(
+    cur_iface: &nispor::Iface,
+    prev_addr: &str,
+) -> Option<nispor::IpAddrConf> {
+    cur_iface
+        .ipv6
+        .as_ref()
+        .map(|i| i.addresses.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .find(|a| a.valid_lft != "forever" && a.address == prev_addr)
+        .map(|a| {
+            log::debug!(
+                "Removing previous DHCPv6 address {}/{} from {}",
+                a.address,
+                a.prefix_len,
+                cur_iface.name
+            );
+            gen_remove_addr_conf(a.address.as_str(), a.prefix_len)
+        })
+}
+
+// The classless static routes takes precedence over router option as
+// RFC 3442 required.
+// Current DHCP routes of specified interface not found in new routes, marked
+// as absent.
+async fn get_stale_dhcp_routes_v4(
+    iface_name: &str,
+    new_routes: &[RouteEntry],
+) -> Result<Vec<RouteEntry>, NipartError> {
+    let new_keys: Vec<_> =
+        new_routes.iter().map(gen_dhcp_route_key_v4).collect();
+    let mut ret = Vec::new();
+    for mut rt in
+        get_iface_routes_v4(iface_name, nispor::RouteProtocol::Dhcp).await?
+    {
+        if !new_keys.contains(&gen_dhcp_route_key_v4(&rt)) {
+            log::debug!("Removing stale DHCP route {rt}");
+            rt.state = Some(RouteState::Absent);
+            ret.push(rt);
+        }
+    }
+    Ok(ret)
+}
+
+// Destination, next hop address, metric and route table with kernel default
+// values filled.
+fn gen_dhcp_route_key_v4(rt: &RouteEntry) -> (String, String, i64, u32) {
+    let metric = match rt.metric {
+        None | Some(RouteEntry::USE_DEFAULT_METRIC) => 0,
+        Some(m) => m,
+    };
+    let table_id = match rt.table_id {
+        None | Some(RouteEntry::USE_DEFAULT_ROUTE_TABLE) => {
+            u32::from(rtnl_route::RouteHeader::RT_TABLE_MAIN)
+        }
+        Some(t) => t,
+    };
+    (
+        rt.destination.clone().unwrap_or_default(),
+        rt.next_hop_addr
+            .clone()
+            .unwrap_or_else(|| Ipv4Addr::UNSPECIFIED.to_string()),
+        metric,
+        table_id,
+    )
+}
+
+fn dhcp_lease_v4_to_routes(
+    lease: &NipartDhcpLeaseV4,
+    absent: bool,
+) -> Vec<RouteEntry> {
+    let new_route = |dst: String, via: Option<String>| {
+        let mut route = RouteEntry::new();
+        if absent {
+            route.state = Some(RouteState::Absent);
+        }
+        route.destination = Some(dst);
+        route.next_hop_iface = Some(lease.iface.to_string());
+        route.next_hop_addr = via;
+        route.metric = lease.route_metric.map(i64::from);
+        route.table_id = lease.route_table_id;
+        route
+    };
+
+    let mut routes = Vec::new();
+    if lease.classless_routes.is_empty() {
+        if let Some(gateway) = lease.gateways.first() {
+            routes.push(new_route(
+                "0.0.0.0/0".to_string(),
+                Some(gateway.to_string()),
+            ));
+        }
+    } else {
+        for route in lease.classless_routes.as_slice() {
+            routes.push(new_route(
+                format!("{}/{}", route.destination, route.prefix_length),
+                if route.router.is_unspecified() {
+                    None
+                } else {
+                    Some(route.router.to_string())
+                },
+            ));
+        }
+    }
+    routes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::show::nispor_retrieve;
+    use nipart::{MacVlanMode, NetworkState};
+
+    const TEST_VETH: &str = "nipart-dhcp0";
+    const TEST_VETH_PEER: &str = "nipart-dhcp1";
+    const TEST_BRIDGE: &str = "nipart-rc-br";
+    const TEST_MAC_VLAN: &str = "nipart-rc-mv0";
+    const TEST_MAC_VLAN2: &str = "nipart-rc-mv1";
+
+    fn gen_test_lease(gateway: &str, metric: Option<u32>) -> NipartDhcpLease {
+        let mut lease = NipartDhcpLeaseV4::new(
+            TEST_VETH.to_string(),
+            "192.0.2.100".parse().unwrap(),
+            24,
+            "192.0.2.1".parse().unwrap(),
+            3600,
+        );
+        lease.gateways = vec![gateway.parse().unwrap()];
+        lease.route_metric = metric;
+        NipartDhcpLease::V4(lease)
+    }
+
+    #[test]
+    fn test_dhcp_route_key_default_values() {
+        let mut lease_rt = RouteEntry::new();
+        lease_rt.destination = Some("0.0.0.0/0".to_string());
+        lease_rt.next_hop_addr = Some("192.0.2.1".to_string());
+        let mut cur_rt = lease_rt.clone();
+        cur_rt.table_id = Some(254);
+        cur_rt.metric = Some(0);
+
+        assert_eq!(
+            gen_dhcp_route_key_v4(&lease_rt),
+            gen_dhcp_route_key_v4(&cur_rt)
+        );
+
+        cur_rt.next_hop_addr = Some("192.0.2.2".to_string());
+        assert_ne!(
+            gen_dhcp_route_key_v4(&lease_rt),
+            gen_dhcp_route_key_v4(&cur_rt)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires CAP_NET_ADMIN for creating veth"]
+    async fn test_renew_dhcp_lease_remove_stale_routes() {
+        let (conn, handle, _) = rtnetlink::new_connection().unwrap();
+        tokio::spawn(conn);
+        handle
+            .link()
+            .add()
+            .veth(TEST_VETH.to_string(), TEST_VETH_PEER.to_string())
+            .execute()
+            .await
+            .unwrap();
+
+        let result = renew_lease_and_get_gateways().await;
+
+        let index = get_iface_index_or_err(&handle, TEST_VETH).await.unwrap();
+        handle.link().del(index).execute().await.unwrap();
+        assert_eq!(result.unwrap(), vec!["192.0.2.2".to_string()]);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires CAP_NET_ADMIN for creating veth"]
+    async fn test_recreate_mac_vlan_keep_controller_ip_mtu() {
+        let (conn, handle, _) = rtnetlink::new_connection().unwrap();
+        tokio::spawn(conn);
+        handle
+            .link()
+            .add()
+            .veth(TEST_VETH.to_string(), TEST_VETH_PEER.to_string())
+            .execute()
+            .await
+            .unwrap();
+
+        let result = change_mac_vlan_mode().await;
+
+        for iface_name in
+            [TEST_BRIDGE, TEST_MAC_VLAN, TEST_MAC_VLAN2, TEST_VETH]
+        {
+            if let Some(index) = get_iface_index(&handle, iface_name).await {
+                handle.link().del(index).execute().await.ok();
             }
         }
-        NipartDhcpLease::V6(_) => {
-            todo!()
+        let (port_iface, ip_iface) = result.unwrap();
+        assert_eq!(get_mac_vlan_mode(&port_iface), Some(MacVlanMode::Bridge));
+        assert_eq!(
+            port_iface.base_iface().controller.as_deref(),
+            Some(TEST_BRIDGE)
+        );
+        assert_eq!(port_iface.base_iface().mtu, Some(1400));
+
+        assert_eq!(get_mac_vlan_mode(&ip_iface), Some(MacVlanMode::Bridge));
+        assert_eq!(
+            ip_iface
+                .base_iface()
+                .ipv4
+                .as_ref()
+                .and_then(|i| i.addresses.as_ref())
+                .map(|a| a.iter().map(|a| a.ip.to_string()).collect()),
+            Some(vec!["192.0.2.10".to_string()])
+        );
+    }
+
+    fn get_mac_vlan_mode(iface: &Interface) -> Option<MacVlanMode> {
+        if let Interface::MacVlan(mac_vlan_iface) = iface {
+            mac_vlan_iface.mac_vlan.as_ref().map(|c| c.mode)
+        } else {
+            None
+        }
+    }
+
+    async fn apply_state(yaml: &str) -> Result<(), NipartError> {
+        let des_state = NetworkState::new_from_yaml(yaml)?;
+        let cur_state = nispor_retrieve(false).await?;
+        nispor_apply(
+            MergedNetworkState::new(des_state, cur_state, false, false)?,
+            NipartApplyOption::default(),
+            nipart::DEFAULT_TIMEOUT,
+            HashMap::new(),
+        )
+        .await
+    }
+
+    async fn change_mac_vlan_mode(
+    ) -> Result<(Interface, Interface), NipartError> {
+        apply_state(
+            r"---
+            interfaces:
+            - name: nipart-rc-mv0
+              type: mac-vlan
+              mtu: 1400
+              mac-vlan:
+                base-iface: nipart-dhcp0
+                mode: vepa
+            - name: nipart-rc-mv1
+              type: mac-vlan
+              mac-vlan:
+                base-iface: nipart-dhcp0
+                mode: vepa
+              ipv4:
+                enabled: true
+                address:
+                - ip: 192.0.2.10
+                  prefix-length: 24
+            ",
+        )
+        .await?;
+        // Attach to bridge directly, so bridge options are not changed
+        let handle = new_rtnl_handle()?;
+        create_link(&handle, TEST_BRIDGE, None, InfoKind::Bridge, None).await?;
+        let br_index = get_iface_index_or_err(&handle, TEST_BRIDGE).await?;
+        let index = get_iface_index_or_err(&handle, TEST_MAC_VLAN).await?;
+        set_link_controller(&handle, TEST_MAC_VLAN, index, Some(br_index))
+            .await?;
+
+        apply_state(
+            r"---
+            interfaces:
+            - name: nipart-rc-mv0
+              type: mac-vlan
+              mac-vlan:
+                base-iface: nipart-dhcp0
+                mode: bridge
+            - name: nipart-rc-mv1
+              type: mac-vlan
+              mac-vlan:
+                base-iface: nipart-dhcp0
+                mode: bridge
+            ",
+        )
+        .await?;
+        let state = nispor_retrieve(false).await?;
+        let mut ifaces = Vec::new();
+        for iface_name in [TEST_MAC_VLAN, TEST_MAC_VLAN2] {
+            ifaces.push(
+                state
+                    .interfaces
+                    .get_iface(iface_name, InterfaceType::MacVlan)
+                    .cloned()
+                    .ok_or_else(|| {
+                        NipartError::new(
+                            ErrorKind::Bug,
+                            format!("Interface {iface_name} not found"),
+                        )
+                    })?,
+            );
         }
+        let ip_iface = ifaces.pop().unwrap();
+        let port_iface = ifaces.pop().unwrap();
+        Ok((port_iface, ip_iface))
+    }
+
+    async fn renew_lease_and_get_gateways() -> Result<Vec<String>, NipartError>
+    {
+        nispor_apply_dhcp_lease(gen_test_lease("192.0.2.1", Some(500)), None)
+            .await?;
+        nispor_apply_dhcp_lease(gen_test_lease("192.0.2.2", Some(500)), None)
+            .await?;
+        Ok(get_iface_routes_v4(TEST_VETH, nispor::RouteProtocol::Dhcp)
+            .await?
+            .into_iter()
+            .filter_map(|rt| rt.next_hop_addr)
+            .collect())
     }
 }
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use nipart::{BaseInterface, InterfaceState, InterfaceType};
+use nipart::{BaseInterface, InterfaceState, InterfaceType, LldpConfig};
 
 use crate::{
     ethtool::np_ethtool_to_nipart,
@@ -14,6 +14,7 @@ fn np_iface_type_to_nipart(np_iface_type: &nispor::IfaceType) -> InterfaceType {
         nispor::IfaceType::Bridge => InterfaceType::LinuxBridge,
         nispor::IfaceType::Dummy => InterfaceType::Dummy,
         nispor::IfaceType::Ethernet => InterfaceType::Ethernet,
+        nispor::IfaceType::Hsr => InterfaceType::Hsr,
         nispor::IfaceType::Loopback => InterfaceType::Loopback,
         nispor::IfaceType::MacSec => InterfaceType::MacSec,
         nispor::IfaceType::MacVlan => InterfaceType::MacVlan,
@@ -111,6 +112,11 @@ pub(crate) fn np_iface_to_base_iface(
 
     base_iface.mptcp = get_iface_mptcp_conf(&base_iface);
 
+    // LLDP plugin will override this when LLDP is running on this interface
+    if base_iface.iface_type != InterfaceType::Loopback {
+        base_iface.lldp = Some(LldpConfig::default());
+    }
+
     base_iface
 }
 
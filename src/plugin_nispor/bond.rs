@@ -1,11 +1,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
 use log::warn;
+use netlink_packet_route::link::{InfoBond, InfoData, InfoKind};
 use nipart::{
     BaseInterface, BondAdSelect, BondAllPortsActive, BondArpAllTargets,
     BondArpValidate, BondConfig, BondFailOverMac, BondInterface, BondLacpRate,
     BondMode, BondOptions, BondPortConfig, BondPrimaryReselect,
-    BondXmitHashPolicy,
+    BondXmitHashPolicy, ErrorKind, Interface, MergedInterface, NipartError,
+};
+
+use crate::changed::desired_if_changed;
+use crate::netlink::{
+    change_link_info, get_iface_index, get_iface_index_or_err, parse_mac,
+    set_link_controller, set_link_up,
 };
 
 pub(crate) fn np_bond_to_nipart(
@@ -221,3 +231,362 @@ fn np_bond_options_to_nipart(np_iface: &nispor::Iface) -> BondOptions {
     }
     options
 }
+
+pub(crate) fn nipart_bond_conf_to_np(
+    bond_conf: Option<&BondConfig>,
+) -> Result<Option<nispor::BondConf>, NipartError> {
+    if let Some(mode) = bond_conf.and_then(|b| b.mode) {
+        let mut np_bond_conf = nispor::BondConf::default();
+        np_bond_conf.mode = Some(match mode {
+            BondMode::RoundRobin => nispor::BondMode::BalanceRoundRobin,
+            BondMode::ActiveBackup => nispor::BondMode::ActiveBackup,
+            BondMode::XOR => nispor::BondMode::BalanceXor,
+            BondMode::Broadcast => nispor::BondMode::Broadcast,
+            BondMode::LACP => nispor::BondMode::Ieee8021AD,
+            BondMode::TLB => nispor::BondMode::BalanceTlb,
+            BondMode::ALB => nispor::BondMode::BalanceAlb,
+            _ => {
+                return Err(NipartError::new(
+                    ErrorKind::InvalidArgument,
+                    format!("Unsupported bond mode {mode}"),
+                ));
+            }
+        });
+        Ok(Some(np_bond_conf))
+    } else {
+        Ok(None)
+    }
+}
+
+fn bond_mode_to_u8(mode: BondMode) -> Result<u8, NipartError> {
+    match mode {
+        BondMode::RoundRobin => Ok(0),
+        BondMode::ActiveBackup => Ok(1),
+        BondMode::XOR => Ok(2),
+        BondMode::Broadcast => Ok(3),
+        BondMode::LACP => Ok(4),
+        BondMode::TLB => Ok(5),
+        BondMode::ALB => Ok(6),
+        _ => Err(NipartError::new(
+            ErrorKind::InvalidArgument,
+            format!("Unsupported bond mode {mode}"),
+        )),
+    }
+}
+
+// The nispor only set bond mode on creation, the bond options, mode change
+// and bond port configurations are done here after nispor created the bond
+// and attached its ports.
+pub(crate) async fn apply_bond_conf(
+    handle: &rtnetlink::Handle,
+    merged_iface: &MergedInterface,
+) -> Result<(), NipartError> {
+    let (apply_iface, merged_iface, cur_iface) = match (
+        merged_iface.for_apply.as_ref(),
+        &merged_iface.merged,
+        merged_iface.current.as_ref(),
+    ) {
+        (Some(Interface::Bond(a)), Interface::Bond(m), c) => (
+            a,
+            m,
+            if let Some(Interface::Bond(c)) = c {
+                Some(c)
+            } else {
+                None
+            },
+        ),
+        _ => return Ok(()),
+    };
+    let iface_name = apply_iface.base.name.as_str();
+    let apply_conf = match apply_iface.bond.as_ref() {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+    let cur_conf = cur_iface.and_then(|c| c.bond.as_ref());
+    let index = get_iface_index_or_err(handle, iface_name).await?;
+
+    let mut changes = Vec::new();
+    // Newly created bond already holds the desired mode
+    if let (Some(mode), Some(cur_mode)) =
+        (apply_conf.mode, cur_conf.and_then(|c| c.mode))
+    {
+        if mode != cur_mode {
+            changes.push(InfoBond::Mode(bond_mode_to_u8(mode)?));
+        }
+    }
+    let cur_opts = cur_conf.and_then(|c| c.options.clone()).unwrap_or_default();
+    let mut primary = None;
+    if let Some(opts) = apply_conf.options.as_ref() {
+        changes.extend(gen_changed_bond_opts(iface_name, opts, &cur_opts)?);
+        primary = desired_if_changed(&opts.primary, &cur_opts.primary);
+    }
+
+    if !changes.is_empty() {
+        log::debug!("Changing bond {iface_name} options {changes:?}");
+        let need_detach = changes
+            .iter()
+            .any(|c| matches!(c, InfoBond::Mode(_) | InfoBond::FailOverMac(_)));
+        let need_down = changes.iter().any(|c| {
+            matches!(
+                c,
+                InfoBond::Mode(_)
+                    | InfoBond::AdLacpRate(_)
+                    | InfoBond::AdSelect(_)
+                    | InfoBond::AdActorSysPrio(_)
+                    | InfoBond::AdActorSystem(_)
+                    | InfoBond::AdUserPortKey(_)
+                    | InfoBond::TlbDynamicLb(_)
+            )
+        });
+        let ports: Vec<&str> = merged_iface.ports().unwrap_or_default();
+        if need_detach {
+            // The nispor has attached the desired ports, also detach the
+            // current ports in case they are not removed yet.
+            let mut ports_to_detach = ports.clone();
+            for port in cur_iface.and_then(|c| c.ports()).unwrap_or_default() {
+                if !ports_to_detach.contains(&port) {
+                    ports_to_detach.push(port);
+                }
+            }
+            for port in ports_to_detach {
+                if let Some(port_index) = get_iface_index(handle, port).await {
+                    set_link_controller(handle, port, port_index, None).await?;
+                }
+            }
+        }
+        if need_down {
+            set_link_up(handle, iface_name, index, false).await?;
+        }
+        change_link_info(
+            handle,
+            iface_name,
+            index,
+            InfoKind::Bond,
+            InfoData::Bond(changes),
+        )
+        .await?;
+        if need_down {
+            set_link_up(handle, iface_name, index, true).await?;
+        }
+        if need_detach {
+            for port in ports {
+                let port_index = get_iface_index_or_err(handle, port).await?;
+                set_link_up(handle, port, port_index, false).await?;
+                set_link_controller(handle, port, port_index, Some(index))
+                    .await?;
+                set_link_up(handle, port, port_index, true).await?;
+            }
+        }
+    }
+
+    // Primary port should be set after ports attached
+    if let Some(primary) = primary {
+        let primary_index = if primary.is_empty() {
+            0
+        } else {
+            get_iface_index_or_err(handle, primary.as_str()).await?
+        };
+        log::debug!("Changing bond {iface_name} primary to '{primary}'");
+        change_link_info(
+            handle,
+            iface_name,
+            index,
+            InfoKind::Bond,
+            InfoData::Bond(vec![InfoBond::Primary(primary_index)]),
+        )
+        .await?;
+    }
+
+    apply_bond_ports_config(
+        handle,
+        apply_conf.ports_config.as_deref().unwrap_or_default(),
+        cur_conf
+            .and_then(|c| c.ports_config.as_deref())
+            .unwrap_or_default(),
+    )
+    .await
+}
+
+async fn apply_bond_ports_config(
+    handle: &rtnetlink::Handle,
+    ports_config: &[BondPortConfig],
+    cur_ports_config: &[BondPortConfig],
+) -> Result<(), NipartError> {
+    for port_conf in ports_config {
+        let cur_port_conf =
+            cur_ports_config.iter().find(|c| c.name == port_conf.name);
+        let priority = desired_if_changed(
+            &port_conf.priority,
+            &cur_port_conf.and_then(|c| c.priority),
+        );
+        let queue_id = desired_if_changed(
+            &port_conf.queue_id,
+            &cur_port_conf.and_then(|c| c.queue_id),
+        );
+        if priority.is_none() && queue_id.is_none() {
+            continue;
+        }
+        let port_index =
+            get_iface_index_or_err(handle, port_conf.name.as_str()).await?;
+        let mut req = handle.link().set_bond_port(port_index);
+        if let Some(priority) = priority {
+            req = req.prio(priority);
+        }
+        if let Some(queue_id) = queue_id {
+            req = req.queue_id(queue_id);
+        }
+        log::debug!("Changing bond port config {port_conf}");
+        req.execute().await.map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!(
+                    "Failed to change bond port config of {}: {e}",
+                    port_conf.name
+                ),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+// The `primary` option is not included as it requires interface index.
+fn gen_changed_bond_opts(
+    iface_name: &str,
+    opts: &BondOptions,
+    cur_opts: &BondOptions,
+) -> Result<Vec<InfoBond>, NipartError> {
+    let mut ret = Vec::new();
+    if let Some(v) =
+        desired_if_changed(&opts.ad_actor_sys_prio, &cur_opts.ad_actor_sys_prio)
+    {
+        ret.push(InfoBond::AdActorSysPrio(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.ad_actor_system, &cur_opts.ad_actor_system)
+    {
+        ret.push(InfoBond::AdActorSystem(parse_mac(v.as_str())?));
+    }
+    if let Some(v) = desired_if_changed(&opts.ad_select, &cur_opts.ad_select) {
+        ret.push(InfoBond::AdSelect(v.into()));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.ad_user_port_key, &cur_opts.ad_user_port_key)
+    {
+        ret.push(InfoBond::AdUserPortKey(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.all_slaves_active, &cur_opts.all_slaves_active)
+    {
+        ret.push(InfoBond::AllPortsActive(v.into()));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.arp_all_targets, &cur_opts.arp_all_targets)
+    {
+        ret.push(InfoBond::ArpAllTargets(u8::from(v).into()));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.arp_interval, &cur_opts.arp_interval)
+    {
+        ret.push(InfoBond::ArpInterval(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.arp_ip_target, &cur_opts.arp_ip_target)
+    {
+        ret.push(InfoBond::ArpIpTarget(parse_arp_ip_target(v.as_str())?));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.arp_validate, &cur_opts.arp_validate)
+    {
+        ret.push(InfoBond::ArpValidate(u8::from(v).into()));
+    }
+    if let Some(v) = desired_if_changed(&opts.downdelay, &cur_opts.downdelay) {
+        ret.push(InfoBond::DownDelay(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.fail_over_mac, &cur_opts.fail_over_mac)
+    {
+        ret.push(InfoBond::FailOverMac(v.into()));
+    }
+    if let Some(v) = desired_if_changed(&opts.lacp_rate, &cur_opts.lacp_rate) {
+        ret.push(InfoBond::AdLacpRate(v.into()));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.lp_interval, &cur_opts.lp_interval)
+    {
+        ret.push(InfoBond::LpInterval(v));
+    }
+    if let Some(v) = desired_if_changed(&opts.miimon, &cur_opts.miimon) {
+        ret.push(InfoBond::MiiMon(v));
+    }
+    if let Some(v) = desired_if_changed(&opts.min_links, &cur_opts.min_links) {
+        ret.push(InfoBond::MinLinks(v));
+    }
+    // Kernel is using the same option for gratuitous ARP and unsolicited
+    // IPv6 NA.
+    if let Some(v) =
+        desired_if_changed(&opts.num_grat_arp, &cur_opts.num_grat_arp).or_else(
+            || desired_if_changed(&opts.num_unsol_na, &cur_opts.num_unsol_na),
+        )
+    {
+        ret.push(InfoBond::NumPeerNotif(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.packets_per_slave, &cur_opts.packets_per_slave)
+    {
+        ret.push(InfoBond::PacketsPerPort(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.primary_reselect, &cur_opts.primary_reselect)
+    {
+        ret.push(InfoBond::PrimaryReselect(v.into()));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.resend_igmp, &cur_opts.resend_igmp)
+    {
+        ret.push(InfoBond::ResendIgmp(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.tlb_dynamic_lb, &cur_opts.tlb_dynamic_lb)
+    {
+        ret.push(InfoBond::TlbDynamicLb(v.into()));
+    }
+    if let Some(v) = desired_if_changed(&opts.updelay, &cur_opts.updelay) {
+        ret.push(InfoBond::UpDelay(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.use_carrier, &cur_opts.use_carrier)
+    {
+        ret.push(InfoBond::UseCarrier(v.into()));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.xmit_hash_policy, &cur_opts.xmit_hash_policy)
+    {
+        ret.push(InfoBond::XmitHashPolicy(v.into()));
+    }
+    if let Some(v) =
+        desired_if_changed(&opts.arp_missed_max, &cur_opts.arp_missed_max)
+    {
+        ret.push(InfoBond::MissedMax(v));
+    }
+    if opts.balance_slb.is_some() {
+        warn!(
+            "Plugin nispor does not support balance-slb bond option, \
+            ignoring it for bond {iface_name}"
+        );
+    }
+    Ok(ret)
+}
+
+fn parse_arp_ip_target(value: &str) -> Result<Vec<Ipv4Addr>, NipartError> {
+    let mut ret = Vec::new();
+    for addr_str in value.split(',').map(|a| a.trim()).filter(|a| !a.is_empty())
+    {
+        ret.push(Ipv4Addr::from_str(addr_str).map_err(|e| {
+            NipartError::new(
+                ErrorKind::InvalidArgument,
+                format!("Invalid bond arp_ip_target {addr_str}: {e}"),
+            )
+        })?);
+    }
+    Ok(ret)
+}
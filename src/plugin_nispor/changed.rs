@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Return the desired value when it is defined and differs from the current
+/// value, otherwise `None` meaning nothing to change.
+pub(crate) fn desired_if_changed<T: PartialEq + Clone>(
+    desired: &Option<T>,
+    current: &Option<T>,
+) -> Option<T> {
+    desired
+        .as_ref()
+        .filter(|d| Some(*d) != current.as_ref())
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desired_if_changed() {
+        assert_eq!(desired_if_changed(&Some(1u32), &Some(2)), Some(1));
+        assert_eq!(desired_if_changed(&Some(1u32), &None), Some(1));
+        assert_eq!(desired_if_changed(&Some(1u32), &Some(1)), None);
+        assert_eq!(desired_if_changed::<u32>(&None, &Some(1)), None);
+        assert_eq!(desired_if_changed::<u32>(&None, &None), None);
+    }
+}
@@ -1,8 +1,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use ethtool::{EthtoolLinkModeAttr, EthtoolLinkModeDuplex};
 use nipart::{
     BaseInterface, EthernetConfig, EthernetDuplex, EthernetInterface,
-    SrIovConfig, SrIovVfConfig,
+    Interface, MergedInterface, NipartError, SrIovConfig, SrIovVfConfig,
+};
+
+use crate::ethtool_netlink::{
+    new_ethtool_handle, send_ethtool_set, EthtoolSetMessage,
 };
 
 pub(crate) fn np_ethernet_to_nipart(
@@ -58,7 +63,64 @@ fn gen_sriov_conf(sriov_info: &nispor::SriovInfo) -> SrIovConfig {
         vf.qos = Some(vf_info.qos);
         vfs.push(vf);
     }
-    ret.total_vfs = Some(vfs.len() as u32);
+    ret.total_vfs = Some(sriov_info.num_vfs.unwrap_or(vfs.len() as u32));
+    ret.drivers_autoprobe = sriov_info.drivers_autoprobe;
     ret.vfs = Some(vfs);
     ret
 }
+
+// Kernel will only advertise link modes matching desired speed and duplex
+// when auto-negotiation is enabled.
+pub(crate) async fn apply_ethernet_link_mode(
+    merged_iface: &MergedInterface,
+) -> Result<(), NipartError> {
+    let des_conf = if let Some(Interface::Ethernet(eth_iface)) =
+        merged_iface.for_apply.as_ref()
+    {
+        if let Some(c) = eth_iface.ethernet.as_ref() {
+            c
+        } else {
+            return Ok(());
+        }
+    } else {
+        return Ok(());
+    };
+    let cur_conf = if let Some(Interface::Ethernet(eth_iface)) =
+        merged_iface.current.as_ref()
+    {
+        eth_iface.ethernet.clone().unwrap_or_default()
+    } else {
+        EthernetConfig::new()
+    };
+
+    let mut attrs = Vec::new();
+    if let Some(auto_neg) = des_conf.auto_neg {
+        if cur_conf.auto_neg != Some(auto_neg) {
+            attrs.push(EthtoolLinkModeAttr::Autoneg(auto_neg));
+        }
+    }
+    if let Some(speed) = des_conf.speed {
+        if cur_conf.speed != Some(speed) {
+            attrs.push(EthtoolLinkModeAttr::Speed(speed));
+        }
+    }
+    if let Some(duplex) = des_conf.duplex.as_ref() {
+        if cur_conf.duplex.as_ref() != Some(duplex) {
+            attrs.push(EthtoolLinkModeAttr::Duplex(match duplex {
+                EthernetDuplex::Half => EthtoolLinkModeDuplex::Half,
+                _ => EthtoolLinkModeDuplex::Full,
+            }));
+        }
+    }
+    if attrs.is_empty() {
+        return Ok(());
+    }
+    let iface_name = merged_iface.merged.name();
+    let mut handle = new_ethtool_handle()?;
+    send_ethtool_set(
+        &mut handle,
+        iface_name,
+        EthtoolSetMessage::new_link_mode_set(iface_name, attrs),
+    )
+    .await
+}
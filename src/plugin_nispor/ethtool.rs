@@ -1,7 +1,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use ethtool::{EthtoolCoalesceAttr, EthtoolPauseAttr, EthtoolRingAttr};
 use nipart::{
-    EthtoolCoalesceConfig, EthtoolConfig, EthtoolPauseConfig, EthtoolRingConfig,
+    ErrorKind, EthtoolCoalesceConfig, EthtoolConfig, EthtoolFeatureConfig,
+    EthtoolPauseConfig, EthtoolRingConfig, MergedInterface, NipartError,
+};
+
+use crate::changed::desired_if_changed;
+use crate::ethtool_netlink::{
+    new_ethtool_handle, send_ethtool_set, EthtoolSetMessage,
 };
 
 pub(crate) fn np_ethtool_to_nipart(
@@ -29,6 +36,7 @@ fn gen_ethtool_config(ethtool_info: &nispor::EthtoolInfo) -> EthtoolConfig {
         coalesce_config.sample_interval = coalesce.rate_sample_interval;
         coalesce_config.rx_frames = coalesce.rx_max_frames;
         coalesce_config.rx_frames_high = coalesce.rx_max_frames_high;
+        coalesce_config.rx_frames_irq = coalesce.rx_max_frames_irq;
         coalesce_config.rx_frames_low = coalesce.rx_max_frames_low;
         coalesce_config.rx_usecs = coalesce.rx_usecs;
         coalesce_config.rx_usecs_high = coalesce.rx_usecs_high;
@@ -63,3 +71,520 @@ fn gen_ethtool_config(ethtool_info: &nispor::EthtoolInfo) -> EthtoolConfig {
     }
     ret
 }
+
+pub(crate) async fn apply_ethtool_conf(
+    merged_iface: &MergedInterface,
+) -> Result<(), NipartError> {
+    let des_conf = if let Some(c) = merged_iface
+        .for_apply
+        .as_ref()
+        .and_then(|i| i.base_iface().ethtool.as_ref())
+    {
+        c
+    } else {
+        return Ok(());
+    };
+    let cur_conf = merged_iface
+        .current
+        .as_ref()
+        .and_then(|i| i.base_iface().ethtool.as_ref());
+    let iface_name = merged_iface.merged.name();
+    let mut handle = new_ethtool_handle()?;
+
+    if let Some(des_features) = des_conf.feature.as_ref() {
+        let features = gen_changed_features(iface_name, des_features).await?;
+        if !features.is_empty() {
+            send_ethtool_set(
+                &mut handle,
+                iface_name,
+                EthtoolSetMessage::new_feature_set(iface_name, features),
+            )
+            .await?;
+        }
+    }
+    if let Some(des_ring) = des_conf.ring.as_ref() {
+        let attrs = gen_changed_ring_attrs(
+            des_ring,
+            cur_conf.and_then(|c| c.ring.as_ref()),
+        );
+        if !attrs.is_empty() {
+            send_ethtool_set(
+                &mut handle,
+                iface_name,
+                EthtoolSetMessage::new_ring_set(iface_name, attrs),
+            )
+            .await?;
+        }
+    }
+    if let Some(des_pause) = des_conf.pause.as_ref() {
+        let attrs = gen_changed_pause_attrs(
+            des_pause,
+            cur_conf.and_then(|c| c.pause.as_ref()),
+        );
+        if !attrs.is_empty() {
+            send_ethtool_set(
+                &mut handle,
+                iface_name,
+                EthtoolSetMessage::new_pause_set(iface_name, attrs),
+            )
+            .await?;
+        }
+    }
+    if let Some(des_coalesce) = des_conf.coalesce.as_ref() {
+        let attrs = gen_changed_coalesce_attrs(
+            des_coalesce,
+            cur_conf.and_then(|c| c.coalesce.as_ref()),
+        );
+        if !attrs.is_empty() {
+            send_ethtool_set(
+                &mut handle,
+                iface_name,
+                EthtoolSetMessage::new_coalesce_set(iface_name, attrs),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+// The queried ethtool features only contains changeable ones, hence query
+// nispor again for fixed features to provide clear error.
+async fn gen_changed_features(
+    iface_name: &str,
+    des_features: &EthtoolFeatureConfig,
+) -> Result<Vec<(String, bool)>, NipartError> {
+    let np_features = get_np_ethtool_features(iface_name).await?;
+    let mut ret = Vec::new();
+    for (name, value) in des_features {
+        if let Some(cur_value) = np_features.changeable.get(name) {
+            if cur_value != value {
+                ret.push((name.to_string(), *value));
+            }
+        } else if let Some(cur_value) = np_features.fixed.get(name) {
+            if cur_value != value {
+                return Err(NipartError::new(
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "Ethtool feature {name} of interface {iface_name} \
+                        is read-only, cannot change it from {cur_value} to \
+                        {value}"
+                    ),
+                ));
+            }
+        } else {
+            return Err(NipartError::new(
+                ErrorKind::NotSupportedError,
+                format!(
+                    "Ethtool feature {name} is not supported by interface \
+                    {iface_name}"
+                ),
+            ));
+        }
+    }
+    ret.sort_unstable();
+    Ok(ret)
+}
+
+async fn get_np_ethtool_features(
+    iface_name: &str,
+) -> Result<nispor::EthtoolFeatureInfo, NipartError> {
+    let mut iface_filter = nispor::NetStateIfaceFilter::minimum();
+    iface_filter.iface_name = Some(iface_name.to_string());
+    iface_filter.include_ethtool = true;
+    let mut filter = nispor::NetStateFilter::minimum();
+    filter.iface = Some(iface_filter);
+    let np_state = nispor::NetState::retrieve_with_filter_async(&filter)
+        .await
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!(
+                    "Failed to retrieve ethtool information of interface \
+                    {iface_name}: {}, {}",
+                    e.kind, e.msg
+                ),
+            )
+        })?;
+    np_state
+        .ifaces
+        .get(iface_name)
+        .and_then(|i| i.ethtool.as_ref())
+        .and_then(|e| e.features.clone())
+        .ok_or_else(|| {
+            NipartError::new(
+                ErrorKind::NotSupportedError,
+                format!(
+                    "Interface {iface_name} does not support ethtool features"
+                ),
+            )
+        })
+}
+
+// The `*_max` properties are read-only, hence ignored.
+fn gen_changed_ring_attrs(
+    des: &EthtoolRingConfig,
+    cur: Option<&EthtoolRingConfig>,
+) -> Vec<EthtoolRingAttr> {
+    let mut ret = Vec::new();
+    if let Some(v) = desired_if_changed(&des.rx, &cur.and_then(|c| c.rx)) {
+        ret.push(EthtoolRingAttr::Rx(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&des.rx_jumbo, &cur.and_then(|c| c.rx_jumbo))
+    {
+        ret.push(EthtoolRingAttr::RxJumbo(v));
+    }
+    if let Some(v) =
+        desired_if_changed(&des.rx_mini, &cur.and_then(|c| c.rx_mini))
+    {
+        ret.push(EthtoolRingAttr::RxMini(v));
+    }
+    if let Some(v) = desired_if_changed(&des.tx, &cur.and_then(|c| c.tx)) {
+        ret.push(EthtoolRingAttr::Tx(v));
+    }
+    ret
+}
+
+fn gen_changed_pause_attrs(
+    des: &EthtoolPauseConfig,
+    cur: Option<&EthtoolPauseConfig>,
+) -> Vec<EthtoolPauseAttr> {
+    let mut ret = Vec::new();
+    if let Some(v) =
+        desired_if_changed(&des.autoneg, &cur.and_then(|c| c.autoneg))
+    {
+        ret.push(EthtoolPauseAttr::AutoNeg(v));
+    }
+    if let Some(v) = desired_if_changed(&des.rx, &cur.and_then(|c| c.rx)) {
+        ret.push(EthtoolPauseAttr::Rx(v));
+    }
+    if let Some(v) = desired_if_changed(&des.tx, &cur.and_then(|c| c.tx)) {
+        ret.push(EthtoolPauseAttr::Tx(v));
+    }
+    ret
+}
+
+fn gen_changed_coalesce_attrs(
+    des: &EthtoolCoalesceConfig,
+    cur: Option<&EthtoolCoalesceConfig>,
+) -> Vec<EthtoolCoalesceAttr> {
+    let mut ret = Vec::new();
+    let cur = cur.cloned().unwrap_or_default();
+
+    if let Some(v) = desired_if_changed(&des.adaptive_rx, &cur.adaptive_rx) {
+        ret.push(EthtoolCoalesceAttr::UseAdaptiveRx(v));
+    }
+    if let Some(v) = desired_if_changed(&des.adaptive_tx, &cur.adaptive_tx) {
+        ret.push(EthtoolCoalesceAttr::UseAdaptiveTx(v));
+    }
+    for (des_v, cur_v, attr_fn) in [
+        (
+            des.pkt_rate_high,
+            cur.pkt_rate_high,
+            EthtoolCoalesceAttr::PktRateHigh as fn(u32) -> EthtoolCoalesceAttr,
+        ),
+        (
+            des.pkt_rate_low,
+            cur.pkt_rate_low,
+            EthtoolCoalesceAttr::PktRateLow,
+        ),
+        (
+            des.sample_interval,
+            cur.sample_interval,
+            EthtoolCoalesceAttr::RateSampleInterval,
+        ),
+        (
+            des.rx_frames,
+            cur.rx_frames,
+            EthtoolCoalesceAttr::RxMaxFrames,
+        ),
+        (
+            des.rx_frames_high,
+            cur.rx_frames_high,
+            EthtoolCoalesceAttr::RxMaxFramesHigh,
+        ),
+        (
+            des.rx_frames_irq,
+            cur.rx_frames_irq,
+            EthtoolCoalesceAttr::RxMaxFramesIrq,
+        ),
+        (
+            des.rx_frames_low,
+            cur.rx_frames_low,
+            EthtoolCoalesceAttr::RxMaxFramesLow,
+        ),
+        (des.rx_usecs, cur.rx_usecs, EthtoolCoalesceAttr::RxUsecs),
+        (
+            des.rx_usecs_high,
+            cur.rx_usecs_high,
+            EthtoolCoalesceAttr::RxUsecsHigh,
+        ),
+        (
+            des.rx_usecs_irq,
+            cur.rx_usecs_irq,
+            EthtoolCoalesceAttr::RxUsecsIrq,
+        ),
+        (
+            des.rx_usecs_low,
+            cur.rx_usecs_low,
+            EthtoolCoalesceAttr::RxUsecsLow,
+        ),
+        (
+            des.stats_block_usecs,
+            cur.stats_block_usecs,
+            EthtoolCoalesceAttr::StatsBlockUsecs,
+        ),
+        (
+            des.tx_frames,
+            cur.tx_frames,
+            EthtoolCoalesceAttr::TxMaxFrames,
+        ),
+        (
+            des.tx_frames_high,
+            cur.tx_frames_high,
+            EthtoolCoalesceAttr::TxMaxFramesHigh,
+        ),
+        (
+            des.tx_frames_irq,
+            cur.tx_frames_irq,
+            EthtoolCoalesceAttr::TxMaxFramesIrq,
+        ),
+        (
+            des.tx_frames_low,
+            cur.tx_frames_low,
+            EthtoolCoalesceAttr::TxMaxFramesLow,
+        ),
+        (des.tx_usecs, cur.tx_usecs, EthtoolCoalesceAttr::TxUsecs),
+        (
+            des.tx_usecs_high,
+            cur.tx_usecs_high,
+            EthtoolCoalesceAttr::TxUsecsHigh,
+        ),
+        (
+            des.tx_usecs_irq,
+            cur.tx_usecs_irq,
+            EthtoolCoalesceAttr::TxUsecsIrq,
+        ),
+        (
+            des.tx_usecs_low,
+            cur.tx_usecs_low,
+            EthtoolCoalesceAttr::TxUsecsLow,
+        ),
+    ] {
+        if let Some(v) = desired_if_changed(&des_v, &cur_v) {
+            ret.push(attr_fn(v));
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_VETH: &str = "nipart-eth0";
+    const TEST_VETH_PEER: &str = "nipart-eth0.ep";
+    const TEST_FEATURE: &str = "tx-checksum-ip-generic";
+    const TEST_NETDEVSIM_ID: u32 = 1709;
+
+    #[test]
+    fn test_gen_changed_pause_attrs_skip_unchanged() {
+        let mut des = EthtoolPauseConfig::new();
+        des.rx = Some(true);
+        des.tx = Some(false);
+        let mut cur = EthtoolPauseConfig::new();
+        cur.autoneg = Some(true);
+        cur.rx = Some(false);
+        cur.tx = Some(false);
+
+        assert_eq!(
+            gen_changed_pause_attrs(&des, Some(&cur)),
+            vec![EthtoolPauseAttr::Rx(true)]
+        );
+        assert_eq!(
+            gen_changed_pause_attrs(&des, None),
+            vec![EthtoolPauseAttr::Rx(true), EthtoolPauseAttr::Tx(false)]
+        );
+    }
+
+    #[test]
+    fn test_gen_changed_coalesce_attrs_skip_unchanged() {
+        let mut des = EthtoolCoalesceConfig::new();
+        des.rx_usecs = Some(10);
+        des.tx_usecs = Some(20);
+        let mut cur = EthtoolCoalesceConfig::new();
+        cur.rx_usecs = Some(10);
+        cur.tx_usecs = Some(30);
+
+        assert_eq!(
+            gen_changed_coalesce_attrs(&des, Some(&cur)),
+            vec![EthtoolCoalesceAttr::TxUsecs(20)]
+        );
+    }
+
+    #[test]
+    fn test_gen_ethtool_config_coalesce_frames_irq() {
+        let mut coalesce = nispor::EthtoolCoalesceInfo::default();
+        coalesce.rx_max_frames_irq = Some(8);
+        coalesce.tx_max_frames_irq = Some(16);
+        let mut ethtool_info = nispor::EthtoolInfo::default();
+        ethtool_info.coalesce = Some(coalesce);
+
+        let cur = gen_ethtool_config(&ethtool_info).coalesce.unwrap();
+        assert_eq!(cur.rx_frames_irq, Some(8));
+        assert_eq!(cur.tx_frames_irq, Some(16));
+
+        let mut des = EthtoolCoalesceConfig::new();
+        des.rx_frames_irq = Some(8);
+        des.tx_frames_irq = Some(16);
+        assert!(gen_changed_coalesce_attrs(&des, Some(&cur)).is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires netdevsim kernel module and CAP_NET_ADMIN"]
+    async fn test_apply_ethtool_coalesce_on_netdevsim() {
+        std::fs::write(
+            "/sys/bus/netdevsim/new_device",
+            format!("{TEST_NETDEVSIM_ID} 1"),
+        )
+        .unwrap();
+
+        let result = set_coalesce_and_verify().await;
+
+        std::fs::write(
+            "/sys/bus/netdevsim/del_device",
+            TEST_NETDEVSIM_ID.to_string(),
+        )
+        .unwrap();
+        result.unwrap();
+    }
+
+    async fn set_coalesce_and_verify() -> Result<(), NipartError> {
+        let iface_name = get_netdevsim_iface_name(TEST_NETDEVSIM_ID).await?;
+        let mut des = EthtoolCoalesceConfig::new();
+        des.rx_frames_irq = Some(8);
+        des.tx_frames_irq = Some(16);
+
+        let cur = get_coalesce_config(&iface_name).await?;
+        let attrs = gen_changed_coalesce_attrs(&des, Some(&cur));
+        assert!(!attrs.is_empty());
+        let mut handle = new_ethtool_handle()?;
+        send_ethtool_set(
+            &mut handle,
+            &iface_name,
+            EthtoolSetMessage::new_coalesce_set(&iface_name, attrs),
+        )
+        .await?;
+
+        let cur = get_coalesce_config(&iface_name).await?;
+        assert_eq!(cur.rx_frames_irq, Some(8));
+        assert_eq!(cur.tx_frames_irq, Some(16));
+        assert!(gen_changed_coalesce_attrs(&des, Some(&cur)).is_empty());
+        Ok(())
+    }
+
+    // The netdevsim port interface shows up asynchronously after device
+    // created.
+    async fn get_netdevsim_iface_name(id: u32) -> Result<String, NipartError> {
+        let dir = format!("/sys/bus/netdevsim/devices/netdevsim{id}/net");
+        for _ in 0..50 {
+            if let Some(name) = std::fs::read_dir(&dir)
+                .ok()
+                .and_then(|mut d| d.next())
+                .and_then(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+            {
+                return Ok(name);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        Err(NipartError::new(
+            ErrorKind::Timeout,
+            format!("Timeout on waiting netdevsim{id} interface"),
+        ))
+    }
+
+    async fn get_coalesce_config(
+        iface_name: &str,
+    ) -> Result<EthtoolCoalesceConfig, NipartError> {
+        let mut iface_filter = nispor::NetStateIfaceFilter::minimum();
+        iface_filter.iface_name = Some(iface_name.to_string());
+        iface_filter.include_ethtool = true;
+        let mut filter = nispor::NetStateFilter::minimum();
+        filter.iface = Some(iface_filter);
+        let np_state = nispor::NetState::retrieve_with_filter_async(&filter)
+            .await
+            .map_err(|e| {
+                NipartError::new(ErrorKind::PluginFailure, e.msg.to_string())
+            })?;
+        np_state
+            .ifaces
+            .get(iface_name)
+            .and_then(np_ethtool_to_nipart)
+            .and_then(|e| e.coalesce)
+            .ok_or_else(|| {
+                NipartError::new(
+                    ErrorKind::NotSupportedError,
+                    format!("Interface {iface_name} has no coalesce info"),
+                )
+            })
+    }
+
+    #[tokio::test]
+    #[ignore = "requires CAP_NET_ADMIN for creating veth"]
+    async fn test_apply_ethtool_feature_on_veth() {
+        let (conn, handle, _) = rtnetlink::new_connection().unwrap();
+        tokio::spawn(conn);
+        handle
+            .link()
+            .add()
+            .veth(TEST_VETH.to_string(), TEST_VETH_PEER.to_string())
+            .execute()
+            .await
+            .unwrap();
+
+        let result = toggle_feature_and_verify(TEST_VETH, TEST_FEATURE).await;
+
+        let index = crate::netlink::get_iface_index_or_err(&handle, TEST_VETH)
+            .await
+            .unwrap();
+        handle.link().del(index).execute().await.unwrap();
+        result.unwrap();
+    }
+
+    async fn toggle_feature_and_verify(
+        iface_name: &str,
+        feature: &str,
+    ) -> Result<(), NipartError> {
+        let old_value = *get_np_ethtool_features(iface_name)
+            .await?
+            .changeable
+            .get(feature)
+            .unwrap();
+        let mut des_features = EthtoolFeatureConfig::default();
+        des_features.insert(feature.to_string(), !old_value);
+
+        let features = gen_changed_features(iface_name, &des_features).await?;
+        assert_eq!(features, vec![(feature.to_string(), !old_value)]);
+
+        let mut handle = new_ethtool_handle()?;
+        send_ethtool_set(
+            &mut handle,
+            iface_name,
+            EthtoolSetMessage::new_feature_set(iface_name, features),
+        )
+        .await?;
+
+        assert_eq!(
+            get_np_ethtool_features(iface_name)
+                .await?
+                .changeable
+                .get(feature),
+            Some(&!old_value)
+        );
+        assert!(gen_changed_features(iface_name, &des_features)
+            .await?
+            .is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,368 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// The ethtool crate only support querying, this file holds the ethtool
+// netlink messages for changing ethtool settings.
+
+use ethtool::{
+    EthtoolAttr, EthtoolCoalesceAttr, EthtoolFeatureAttr, EthtoolHeader,
+    EthtoolLinkModeAttr, EthtoolLinkModeDuplex, EthtoolPauseAttr,
+    EthtoolRingAttr,
+};
+use futures::StreamExt;
+use genetlink_packet_core::{
+    DecodeError, DefaultNla, Emitable, NetlinkMessage, NetlinkPayload, Nla,
+    ParseableParametrized, NLA_F_NESTED, NLM_F_ACK, NLM_F_REQUEST,
+};
+use netlink_packet_generic::{GenlFamily, GenlHeader, GenlMessage};
+use nipart::{ErrorKind, NipartError};
+use nix::errno::Errno;
+
+const ETHTOOL_MSG_LINKMODES_SET: u8 = 5;
+const ETHTOOL_MSG_FEATURES_SET: u8 = 12;
+const ETHTOOL_MSG_RINGS_SET: u8 = 16;
+const ETHTOOL_MSG_COALESCE_SET: u8 = 20;
+const ETHTOOL_MSG_PAUSE_SET: u8 = 22;
+
+const ETHTOOL_FLAG_OMIT_REPLY: u32 = 1 << 1;
+
+const ETHTOOL_A_FEATURES_WANTED: u16 = 3;
+
+const ETHTOOL_A_BITSET_BITS: u16 = 3;
+const ETHTOOL_A_BITSET_BITS_BIT: u16 = 1;
+const ETHTOOL_A_BITSET_BIT_NAME: u16 = 2;
+const ETHTOOL_A_BITSET_BIT_VALUE: u16 = 3;
+
+const DUPLEX_HALF: u8 = 0x00;
+const DUPLEX_FULL: u8 = 0x01;
+const DUPLEX_UNKNOWN: u8 = 0xff;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum EthtoolSetCmd {
+    LinkMode,
+    Feature,
+    Ring,
+    Coalesce,
+    Pause,
+}
+
+impl std::fmt::Display for EthtoolSetCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::LinkMode => "link mode",
+                Self::Feature => "feature",
+                Self::Ring => "ring",
+                Self::Coalesce => "coalesce",
+                Self::Pause => "pause",
+            }
+        )
+    }
+}
+
+impl From<EthtoolSetCmd> for u8 {
+    fn from(cmd: EthtoolSetCmd) -> Self {
+        match cmd {
+            EthtoolSetCmd::LinkMode => ETHTOOL_MSG_LINKMODES_SET,
+            EthtoolSetCmd::Feature => ETHTOOL_MSG_FEATURES_SET,
+            EthtoolSetCmd::Ring => ETHTOOL_MSG_RINGS_SET,
+            EthtoolSetCmd::Coalesce => ETHTOOL_MSG_COALESCE_SET,
+            EthtoolSetCmd::Pause => ETHTOOL_MSG_PAUSE_SET,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum EthtoolSetAttr {
+    Ethtool(EthtoolAttr),
+    // The ethtool crate cannot emit link mode settings
+    LinkMode(EthtoolLinkModeAttr),
+    // The ethtool crate cannot emit feature bitset
+    FeatureWanted(Vec<(String, bool)>),
+}
+
+impl Nla for EthtoolSetAttr {
+    fn value_len(&self) -> usize {
+        match self {
+            Self::Ethtool(attr) => attr.value_len(),
+            Self::LinkMode(attr) => match attr {
+                EthtoolLinkModeAttr::Autoneg(_)
+                | EthtoolLinkModeAttr::Duplex(_) => 1,
+                EthtoolLinkModeAttr::Speed(_) => 4,
+                _ => attr.value_len(),
+            },
+            Self::FeatureWanted(features) => {
+                gen_feature_bitset(features).as_slice().buffer_len()
+            }
+        }
+    }
+
+    fn kind(&self) -> u16 {
+        match self {
+            Self::Ethtool(attr) => attr.kind(),
+            Self::LinkMode(attr) => attr.kind(),
+            Self::FeatureWanted(_) => ETHTOOL_A_FEATURES_WANTED | NLA_F_NESTED,
+        }
+    }
+
+    fn emit_value(&self, buffer: &mut [u8]) {
+        match self {
+            Self::Ethtool(attr) => attr.emit_value(buffer),
+            Self::LinkMode(attr) => match attr {
+                EthtoolLinkModeAttr::Autoneg(v) => buffer[0] = *v as u8,
+                EthtoolLinkModeAttr::Speed(v) => {
+                    buffer[..4].copy_from_slice(&v.to_ne_bytes())
+                }
+                EthtoolLinkModeAttr::Duplex(v) => {
+                    buffer[0] = match v {
+                        EthtoolLinkModeDuplex::Half => DUPLEX_HALF,
+                        EthtoolLinkModeDuplex::Full => DUPLEX_FULL,
+                        EthtoolLinkModeDuplex::Unknown => DUPLEX_UNKNOWN,
+                        EthtoolLinkModeDuplex::Other(d) => *d,
+                    }
+                }
+                _ => attr.emit_value(buffer),
+            },
+            Self::FeatureWanted(features) => {
+                gen_feature_bitset(features).as_slice().emit(buffer)
+            }
+        }
+    }
+}
+
+// Bitset without ETHTOOL_A_BITSET_NOMASK, hence only listed bits are changed.
+fn gen_feature_bitset(features: &[(String, bool)]) -> Vec<DefaultNla> {
+    let bits: Vec<DefaultNla> = features
+        .iter()
+        .map(|(name, value)| {
+            let mut name_bytes = name.as_bytes().to_vec();
+            name_bytes.push(0);
+            let mut bit_nlas =
+                vec![DefaultNla::new(ETHTOOL_A_BITSET_BIT_NAME, name_bytes)];
+            if *value {
+                bit_nlas.push(DefaultNla::new(
+                    ETHTOOL_A_BITSET_BIT_VALUE,
+                    Vec::new(),
+                ));
+            }
+            DefaultNla::new(
+                ETHTOOL_A_BITSET_BITS_BIT | NLA_F_NESTED,
+                emit_nlas(bit_nlas.as_slice()),
+            )
+        })
+        .collect();
+    vec![DefaultNla::new(
+        ETHTOOL_A_BITSET_BITS | NLA_F_NESTED,
+        emit_nlas(bits.as_slice()),
+    )]
+}
+
+fn emit_nlas(nlas: &[DefaultNla]) -> Vec<u8> {
+    let mut buffer = vec![0u8; nlas.buffer_len()];
+    nlas.emit(&mut buffer);
+    buffer
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct EthtoolSetMessage {
+    cmd: EthtoolSetCmd,
+    nlas: Vec<EthtoolSetAttr>,
+}
+
+impl EthtoolSetMessage {
+    // Only `Autoneg`, `Speed` and `Duplex` are supported.
+    pub(crate) fn new_link_mode_set(
+        iface_name: &str,
+        attrs: Vec<EthtoolLinkModeAttr>,
+    ) -> Self {
+        let mut nlas =
+            vec![EthtoolSetAttr::LinkMode(EthtoolLinkModeAttr::Header(vec![
+                EthtoolHeader::DevName(iface_name.to_string()),
+            ]))];
+        for attr in attrs {
+            nlas.push(EthtoolSetAttr::LinkMode(attr));
+        }
+        Self {
+            cmd: EthtoolSetCmd::LinkMode,
+            nlas,
+        }
+    }
+
+    pub(crate) fn new_feature_set(
+        iface_name: &str,
+        features: Vec<(String, bool)>,
+    ) -> Self {
+        Self {
+            cmd: EthtoolSetCmd::Feature,
+            nlas: vec![
+                EthtoolSetAttr::Ethtool(EthtoolAttr::Feature(
+                    EthtoolFeatureAttr::Header(vec![
+                        EthtoolHeader::DevName(iface_name.to_string()),
+                        EthtoolHeader::Flags(ETHTOOL_FLAG_OMIT_REPLY),
+                    ]),
+                )),
+                EthtoolSetAttr::FeatureWanted(features),
+            ],
+        }
+    }
+
+    pub(crate) fn new_ring_set(
+        iface_name: &str,
+        attrs: Vec<EthtoolRingAttr>,
+    ) -> Self {
+        let mut nlas = vec![EthtoolSetAttr::Ethtool(EthtoolAttr::Ring(
+            EthtoolRingAttr::Header(vec![EthtoolHeader::DevName(
+                iface_name.to_string(),
+            )]),
+        ))];
+        for attr in attrs {
+            nlas.push(EthtoolSetAttr::Ethtool(EthtoolAttr::Ring(attr)));
+        }
+        Self {
+            cmd: EthtoolSetCmd::Ring,
+            nlas,
+        }
+    }
+
+    pub(crate) fn new_coalesce_set(
+        iface_name: &str,
+        attrs: Vec<EthtoolCoalesceAttr>,
+    ) -> Self {
+        let mut nlas = vec![EthtoolSetAttr::Ethtool(EthtoolAttr::Coalesce(
+            EthtoolCoalesceAttr::Header(vec![EthtoolHeader::DevName(
+                iface_name.to_string(),
+            )]),
+        ))];
+        for attr in attrs {
+            nlas.push(EthtoolSetAttr::Ethtool(EthtoolAttr::Coalesce(attr)));
+        }
+        Self {
+            cmd: EthtoolSetCmd::Coalesce,
+            nlas,
+        }
+    }
+
+    pub(crate) fn new_pause_set(
+        iface_name: &str,
+        attrs: Vec<EthtoolPauseAttr>,
+    ) -> Self {
+        let mut nlas = vec![EthtoolSetAttr::Ethtool(EthtoolAttr::Pause(
+            EthtoolPauseAttr::Header(vec![EthtoolHeader::DevName(
+                iface_name.to_string(),
+            )]),
+        ))];
+        for attr in attrs {
+            nlas.push(EthtoolSetAttr::Ethtool(EthtoolAttr::Pause(attr)));
+        }
+        Self {
+            cmd: EthtoolSetCmd::Pause,
+            nlas,
+        }
+    }
+}
+
+impl GenlFamily for EthtoolSetMessage {
+    fn family_name() -> &'static str {
+        "ethtool"
+    }
+
+    fn version(&self) -> u8 {
+        1
+    }
+
+    fn command(&self) -> u8 {
+        self.cmd.into()
+    }
+}
+
+impl Emitable for EthtoolSetMessage {
+    fn buffer_len(&self) -> usize {
+        self.nlas.as_slice().buffer_len()
+    }
+
+    fn emit(&self, buffer: &mut [u8]) {
+        self.nlas.as_slice().emit(buffer)
+    }
+}
+
+// Reply of set command is omitted or ignored, hence no parsing.
+impl ParseableParametrized<[u8], GenlHeader> for EthtoolSetMessage {
+    fn parse_with_param(
+        _buffer: &[u8],
+        header: GenlHeader,
+    ) -> Result<Self, DecodeError> {
+        Ok(Self {
+            cmd: match header.cmd {
+                ETHTOOL_MSG_LINKMODES_SET => EthtoolSetCmd::LinkMode,
+                ETHTOOL_MSG_RINGS_SET => EthtoolSetCmd::Ring,
+                ETHTOOL_MSG_COALESCE_SET => EthtoolSetCmd::Coalesce,
+                ETHTOOL_MSG_PAUSE_SET => EthtoolSetCmd::Pause,
+                _ => EthtoolSetCmd::Feature,
+            },
+            nlas: Vec::new(),
+        })
+    }
+}
+
+pub(crate) fn new_ethtool_handle() -> Result<ethtool::EthtoolHandle, NipartError>
+{
+    let (conn, handle, _) = ethtool::new_connection().map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to create ethtool netlink connection: {e}"),
+        )
+    })?;
+    tokio::spawn(conn);
+    Ok(handle)
+}
+
+pub(crate) async fn send_ethtool_set(
+    handle: &mut ethtool::EthtoolHandle,
+    iface_name: &str,
+    message: EthtoolSetMessage,
+) -> Result<(), NipartError> {
+    let cmd = message.cmd;
+    log::debug!("Changing ethtool {cmd} of interface {iface_name}");
+    let mut nl_msg = NetlinkMessage::from(GenlMessage::from_payload(message));
+    nl_msg.header.flags = NLM_F_REQUEST | NLM_F_ACK;
+
+    let mut response = handle.handle.request(nl_msg).await.map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!(
+                "Failed to change ethtool {cmd} of interface {iface_name}: \
+                {e}"
+            ),
+        )
+    })?;
+    while let Some(reply) = response.next().await {
+        match reply.map(|r| r.payload) {
+            Ok(NetlinkPayload::Error(e)) if e.code.is_some() => {
+                let e = e.to_io();
+                return Err(NipartError::new(
+                    if e.raw_os_error() == Some(Errno::EOPNOTSUPP as i32) {
+                        ErrorKind::NotSupportedError
+                    } else {
+                        ErrorKind::PluginFailure
+                    },
+                    format!(
+                        "Failed to change ethtool {cmd} of interface \
+                        {iface_name}: {e}"
+                    ),
+                ));
+            }
+            Ok(_) => (),
+            Err(e) => {
+                return Err(NipartError::new(
+                    ErrorKind::Bug,
+                    format!(
+                        "Failed to parse reply of changing ethtool {cmd} \
+                        of interface {iface_name}: {e}"
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
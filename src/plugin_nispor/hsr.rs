@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use netlink_packet_route::link::{InfoData, InfoHsr};
+use nipart::{
+    BaseInterface, HsrConfig, HsrInterface, HsrProtocol, NipartError,
+};
+
+use crate::netlink::get_iface_index_or_err;
+
+pub(crate) fn np_hsr_to_nipart(
+    np_iface: &nispor::Iface,
+    base_iface: BaseInterface,
+) -> HsrInterface {
+    let hsr_conf = np_iface.hsr.as_ref().map(|np_hsr_info| {
+        let mut conf = HsrConfig::new();
+        conf.port1 = np_hsr_info.port1.clone().unwrap_or_default();
+        conf.port2 = np_hsr_info.port2.clone().unwrap_or_default();
+        conf.supervision_address =
+            Some(np_hsr_info.supervision_addr.to_uppercase());
+        conf.multicast_spec = np_hsr_info.multicast_spec;
+        conf.protocol = match np_hsr_info.protocol {
+            nispor::HsrProtocol::Hsr => HsrProtocol::Hsr,
+            nispor::HsrProtocol::Prp => HsrProtocol::Prp,
+            _ => {
+                log::warn!("Unknown HSR protocol {:?}", np_hsr_info.protocol);
+                HsrProtocol::default()
+            }
+        };
+        conf
+    });
+
+    let mut ret = HsrInterface::new();
+    ret.base = base_iface;
+    ret.hsr = hsr_conf;
+    ret
+}
+
+// The supervision address is read-only, kernel generates it from multicast
+// spec.
+pub(crate) async fn nipart_hsr_to_rtnl(
+    handle: &rtnetlink::Handle,
+    conf: &HsrConfig,
+) -> Result<InfoData, NipartError> {
+    Ok(InfoData::Hsr(vec![
+        InfoHsr::Port1(
+            get_iface_index_or_err(handle, conf.port1.as_str()).await?,
+        ),
+        InfoHsr::Port2(
+            get_iface_index_or_err(handle, conf.port2.as_str()).await?,
+        ),
+        InfoHsr::MulticastSpec(conf.multicast_spec),
+        InfoHsr::Protocol(match conf.protocol {
+            HsrProtocol::Prp => netlink_packet_route::link::HsrProtocol::Prp,
+            _ => netlink_packet_route::link::HsrProtocol::Hsr,
+        }),
+    ]))
+}
+
+pub(crate) fn is_hsr_conf_changed(des: &HsrConfig, cur: &HsrConfig) -> bool {
+    des.port1 != cur.port1
+        || des.port2 != cur.port2
+        || des.multicast_spec != cur.multicast_spec
+        || des.protocol != cur.protocol
+}
@@ -1,9 +1,16 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use netlink_packet_route::link::{InfoData, InfoIpoib};
 use nipart::{
     BaseInterface, InfiniBandConfig, InfiniBandInterface, InfiniBandMode,
+    NipartError,
 };
 
+use crate::netlink::get_iface_index_or_err;
+
+const IPOIB_MODE_DATAGRAM: u16 = 0;
+const IPOIB_MODE_CONNECTED: u16 = 1;
+
 fn np_ipoib_mode_to_nipart(m: nispor::IpoibMode) -> InfiniBandMode {
     match m {
         nispor::IpoibMode::Datagram => InfiniBandMode::Datagram,
@@ -32,3 +39,34 @@ pub(crate) fn np_ib_to_nipart(
     ret.ib = ib_conf;
     ret
 }
+
+// Return None if not a PKEY child interface
+pub(crate) async fn nipart_ib_to_rtnl(
+    handle: &rtnetlink::Handle,
+    conf: &InfiniBandConfig,
+) -> Result<Option<(u32, InfoData)>, NipartError> {
+    let (base_iface, pkey) = match (conf.base_iface.as_ref(), conf.pkey) {
+        (Some(b), Some(p)) if !b.is_empty() => (b, p),
+        _ => return Ok(None),
+    };
+    Ok(Some((
+        get_iface_index_or_err(handle, base_iface.as_str()).await?,
+        InfoData::Ipoib(vec![
+            InfoIpoib::Pkey(pkey),
+            InfoIpoib::Mode(match conf.mode {
+                InfiniBandMode::Connected => IPOIB_MODE_CONNECTED,
+                _ => IPOIB_MODE_DATAGRAM,
+            }),
+        ]),
+    )))
+}
+
+pub(crate) fn is_ib_conf_changed(
+    des: &InfiniBandConfig,
+    cur: &InfiniBandConfig,
+) -> bool {
+    des.base_iface.is_some()
+        && (des.base_iface != cur.base_iface
+            || des.pkey != cur.pkey
+            || des.mode != cur.mode)
+}
@@ -3,10 +3,13 @@
 mod apply;
 mod base_iface;
 mod bond;
+mod changed;
 mod error;
 mod ethernet;
 mod ethtool;
+mod ethtool_netlink;
 mod hostname;
+mod hsr;
 mod infiniband;
 mod ip;
 mod linux_bridge;
@@ -14,10 +17,14 @@ mod linux_bridge_port_vlan;
 mod mac_vlan;
 mod macsec;
 mod mptcp;
+mod mptcp_netlink;
+mod netlink;
+mod ovs;
 mod plugin;
 mod route;
 mod route_rule;
 mod show;
+mod sriov;
 mod veth;
 mod vlan;
 mod vrf;
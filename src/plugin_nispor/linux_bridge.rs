@@ -1,13 +1,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use log::warn;
+use netlink_packet_route::link::{
+    InfoBridge, InfoBridgePort, InfoData, InfoKind, InfoPortData, InfoPortKind,
+};
 use nipart::{
-    BaseInterface, ErrorKind, LinuxBridgeConfig, LinuxBridgeInterface,
-    LinuxBridgeMulticastRouterType, LinuxBridgeOptions, LinuxBridgePortConfig,
-    LinuxBridgeStpOptions, NipartError, VlanProtocol,
+    BaseInterface, ErrorKind, Interface, LinuxBridgeConfig,
+    LinuxBridgeInterface, LinuxBridgeMulticastRouterType, LinuxBridgeOptions,
+    LinuxBridgePortConfig, LinuxBridgeStpOptions, MergedInterface, NipartError,
+    VlanProtocol,
 };
 
-use crate::linux_bridge_port_vlan::parse_port_vlan_conf;
+use crate::linux_bridge_port_vlan::{
+    apply_port_vlan_conf, parse_port_vlan_conf,
+};
+use crate::netlink::{
+    change_link_info, change_port_link_info, get_iface_index_or_err, parse_mac,
+};
 
 pub(crate) fn np_bridge_to_nipart(
     np_iface: &nispor::Iface,
@@ -151,8 +160,16 @@ fn np_bridge_options_to_nipart(
 //   * hello_time
 //   * max_age
 fn devide_by_user_hz(v: u32) -> Result<u32, NipartError> {
-    let user_hz = match nix::unistd::sysconf(nix::unistd::SysconfVar::CLK_TCK) {
-        Ok(value) => value.unwrap_or_default() as u32,
+    Ok(v / get_user_hz()?)
+}
+
+fn multiply_by_user_hz(v: u32) -> Result<u32, NipartError> {
+    Ok(v.saturating_mul(get_user_hz()?))
+}
+
+fn get_user_hz() -> Result<u32, NipartError> {
+    match nix::unistd::sysconf(nix::unistd::SysconfVar::CLK_TCK) {
+        Ok(value) => Ok(value.unwrap_or_default() as u32),
         Err(_) => {
             let e = NipartError::new(
                 ErrorKind::KernelIntegerRoundedError,
@@ -160,10 +177,9 @@ fn devide_by_user_hz(v: u32) -> Result<u32, NipartError> {
                     .to_string(),
             );
             log::error!("{}", e);
-            return Err(e);
+            Err(e)
         }
-    };
-    Ok(v / user_hz)
+    }
 }
 
 fn get_stp_options(
@@ -201,3 +217,192 @@ fn get_stp_options(
     stp_opt.priority = np_bridge.priority;
     Ok(stp_opt)
 }
+
+// The nispor only create the linux bridge and attach ports to it, the bridge
+// options, STP and port configurations are done here.
+pub(crate) async fn apply_bridge_conf(
+    handle: &rtnetlink::Handle,
+    merged_iface: &MergedInterface,
+) -> Result<(), NipartError> {
+    let (apply_iface, merged_iface, cur_iface) = match (
+        merged_iface.for_apply.as_ref(),
+        &merged_iface.merged,
+        merged_iface.current.as_ref(),
+    ) {
+        (Some(Interface::LinuxBridge(a)), Interface::LinuxBridge(m), c) => (
+            a,
+            m,
+            if let Some(Interface::LinuxBridge(c)) = c {
+                Some(c)
+            } else {
+                None
+            },
+        ),
+        _ => return Ok(()),
+    };
+    let iface_name = apply_iface.base.name.as_str();
+    let index = get_iface_index_or_err(handle, iface_name).await?;
+
+    let vlan_filtering = merged_iface.vlan_filtering_is_enabled();
+    let mut br_opts = vec![InfoBridge::VlanFiltering(vlan_filtering.into())];
+    if let Some(opts) =
+        apply_iface.bridge.as_ref().and_then(|b| b.options.as_ref())
+    {
+        br_opts.extend(nipart_bridge_opts_to_rtnl(opts)?);
+    }
+    log::debug!("Changing linux bridge {iface_name} options {br_opts:?}");
+    change_link_info(
+        handle,
+        iface_name,
+        index,
+        InfoKind::Bridge,
+        InfoData::Bridge(br_opts),
+    )
+    .await?;
+
+    let default_pvid = merged_iface
+        .bridge
+        .as_ref()
+        .and_then(|b| b.options.as_ref())
+        .and_then(|o| o.vlan_default_pvid)
+        .unwrap_or(1);
+    for port_conf in apply_iface
+        .bridge
+        .as_ref()
+        .and_then(|b| b.port.as_deref())
+        .unwrap_or_default()
+    {
+        let port_name = port_conf.name.as_str();
+        let port_index = get_iface_index_or_err(handle, port_name).await?;
+        let port_opts = nipart_bridge_port_opts_to_rtnl(port_conf);
+        if !port_opts.is_empty() {
+            log::debug!(
+                "Changing linux bridge {iface_name} port {port_name} \
+                options {port_opts:?}"
+            );
+            change_port_link_info(
+                handle,
+                port_name,
+                port_index,
+                InfoPortKind::Bridge,
+                InfoPortData::BridgePort(port_opts),
+            )
+            .await?;
+        }
+        if vlan_filtering {
+            if let Some(vlan_conf) = port_conf.vlan.as_ref() {
+                apply_port_vlan_conf(
+                    handle,
+                    port_name,
+                    port_index,
+                    vlan_conf,
+                    cur_iface
+                        .and_then(|c| c.get_port_conf(port_name))
+                        .and_then(|p| p.vlan.as_ref()),
+                    default_pvid,
+                )
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// The `gc_timer` and `hello_timer` are runtime only timers, ignored.
+fn nipart_bridge_opts_to_rtnl(
+    opts: &LinuxBridgeOptions,
+) -> Result<Vec<InfoBridge>, NipartError> {
+    let mut ret = Vec::new();
+    if let Some(v) = opts.group_addr.as_ref() {
+        ret.push(InfoBridge::GroupAddr(parse_mac(v.as_str())?));
+    }
+    if let Some(v) = opts.group_fwd_mask.or(opts.group_forward_mask) {
+        ret.push(InfoBridge::GroupFwdMask(v));
+    }
+    if let Some(v) = opts.hash_max {
+        ret.push(InfoBridge::MulticastHashMax(v));
+    }
+    if let Some(v) = opts.mac_ageing_time {
+        ret.push(InfoBridge::AgeingTime(multiply_by_user_hz(v)?));
+    }
+    if let Some(v) = opts.multicast_last_member_count {
+        ret.push(InfoBridge::MulticastLastMemberCount(v));
+    }
+    if let Some(v) = opts.multicast_last_member_interval {
+        ret.push(InfoBridge::MulticastLastMemberInterval(v));
+    }
+    if let Some(v) = opts.multicast_membership_interval {
+        ret.push(InfoBridge::MulticastMembershipInterval(v));
+    }
+    if let Some(v) = opts.multicast_querier {
+        ret.push(InfoBridge::MulticastQuerier(v.into()));
+    }
+    if let Some(v) = opts.multicast_querier_interval {
+        ret.push(InfoBridge::MulticastQuerierInterval(v));
+    }
+    if let Some(v) = opts.multicast_query_interval {
+        ret.push(InfoBridge::MulticastQueryInterval(v));
+    }
+    if let Some(v) = opts.multicast_query_response_interval {
+        ret.push(InfoBridge::MulticastQueryResponseInterval(v));
+    }
+    if let Some(v) = opts.multicast_query_use_ifaddr {
+        ret.push(InfoBridge::MulticastQueryUseIfaddr(v.into()));
+    }
+    if let Some(v) = opts.multicast_router.as_ref() {
+        // The enum discriminant is identical to kernel value
+        ret.push(InfoBridge::MulticastRouter(v.clone() as u8));
+    }
+    if let Some(v) = opts.multicast_snooping {
+        ret.push(InfoBridge::MulticastSnooping(v.into()));
+    }
+    if let Some(v) = opts.multicast_startup_query_count {
+        ret.push(InfoBridge::MulticastStartupQueryCount(v));
+    }
+    if let Some(v) = opts.multicast_startup_query_interval {
+        ret.push(InfoBridge::MulticastStartupQueryInterval(v));
+    }
+    if let Some(v) = opts.vlan_protocol {
+        ret.push(InfoBridge::VlanProtocol(match v {
+            VlanProtocol::Ieee8021Q => 0x8100,
+            VlanProtocol::Ieee8021Ad => 0x88a8,
+        }));
+    }
+    if let Some(v) = opts.vlan_default_pvid {
+        ret.push(InfoBridge::VlanDefaultPvid(v));
+    }
+    if let Some(stp_opts) = opts.stp.as_ref() {
+        if let Some(v) = stp_opts.enabled {
+            ret.push(InfoBridge::StpState(v.into()));
+        }
+        if let Some(v) = stp_opts.forward_delay {
+            ret.push(InfoBridge::ForwardDelay(multiply_by_user_hz(v.into())?));
+        }
+        if let Some(v) = stp_opts.hello_time {
+            ret.push(InfoBridge::HelloTime(multiply_by_user_hz(v.into())?));
+        }
+        if let Some(v) = stp_opts.max_age {
+            ret.push(InfoBridge::MaxAge(multiply_by_user_hz(v.into())?));
+        }
+        if let Some(v) = stp_opts.priority {
+            ret.push(InfoBridge::Priority(v));
+        }
+    }
+    Ok(ret)
+}
+
+fn nipart_bridge_port_opts_to_rtnl(
+    port_conf: &LinuxBridgePortConfig,
+) -> Vec<InfoBridgePort> {
+    let mut ret = Vec::new();
+    if let Some(v) = port_conf.stp_hairpin_mode {
+        ret.push(InfoBridgePort::HairpinMode(v));
+    }
+    if let Some(v) = port_conf.stp_path_cost {
+        ret.push(InfoBridgePort::Cost(v));
+    }
+    if let Some(v) = port_conf.stp_priority {
+        ret.push(InfoBridgePort::Priority(v));
+    }
+    ret
+}
@@ -1,9 +1,23 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeMap;
+
+use netlink_packet_route::{
+    link::{AfSpecBridge, BridgeVlanInfo, LinkAttribute},
+    AddressFamily,
+};
 use nipart::{
     BridgePortTrunkTag, BridgePortVlanConfig, BridgePortVlanMode,
-    BridgePortVlanRange,
+    BridgePortVlanRange, NipartError,
 };
+use nix::errno::Errno;
+
+use crate::netlink::{is_rtnl_errno, rtnl_error_to_nipart};
+
+const BRIDGE_VLAN_INFO_PVID: u16 = 1 << 1;
+const BRIDGE_VLAN_INFO_UNTAGGED: u16 = 1 << 2;
+const BRIDGE_VLAN_INFO_RANGE_BEGIN: u16 = 1 << 3;
+const BRIDGE_VLAN_INFO_RANGE_END: u16 = 1 << 4;
 
 pub(crate) fn parse_port_vlan_conf(
     np_vlan_entries: &[nispor::BridgeVlanEntry],
@@ -69,3 +83,152 @@ fn get_vlan_tag_range(np_vlan_entry: &nispor::BridgeVlanEntry) -> (u16, u16) {
         np_vlan_entry.vid.unwrap_or(1),
     ))
 }
+
+// Equivalent to `bridge vlan add/del dev <port> vid <vid> [pvid untagged]`.
+// VLANs not desired are removed first, then desired VLANs are added.
+// The kernel will update the flags of existing VLAN on adding.
+pub(crate) async fn apply_port_vlan_conf(
+    handle: &rtnetlink::Handle,
+    port_name: &str,
+    port_index: u32,
+    vlan_conf: &BridgePortVlanConfig,
+    cur_vlan_conf: Option<&BridgePortVlanConfig>,
+    default_pvid: u16,
+) -> Result<(), NipartError> {
+    let des_vlans = get_port_vlans(vlan_conf);
+    let cur_vlans = cur_vlan_conf.map(get_port_vlans).unwrap_or_default();
+
+    let mut vlans_to_del: BTreeMap<u16, bool> = cur_vlans
+        .iter()
+        .filter(|(vid, _)| !des_vlans.contains_key(vid))
+        .map(|(vid, is_native)| (*vid, *is_native))
+        .collect();
+    // The kernel add default PVID to new port which is not included in
+    // current VLAN config
+    if !des_vlans.contains_key(&default_pvid) {
+        vlans_to_del.insert(default_pvid, true);
+    }
+    let vlans_to_add: BTreeMap<u16, bool> = des_vlans
+        .iter()
+        .filter(|(vid, is_native)| cur_vlans.get(vid) != Some(is_native))
+        .map(|(vid, is_native)| (*vid, *is_native))
+        .collect();
+
+    for (vid, is_native) in vlans_to_del {
+        log::debug!("Removing VLAN {vid} from bridge port {port_name}");
+        let mut req = handle.link().del(port_index);
+        set_vlan_request(
+            req.message_mut(),
+            gen_vlan_infos(&[(vid, is_native)]),
+        );
+        if let Err(e) = req.execute().await {
+            if !is_rtnl_errno(&e, Errno::ENOENT) {
+                return Err(rtnl_error_to_nipart(
+                    e,
+                    format!("remove VLAN {vid} from bridge port {port_name}")
+                        .as_str(),
+                ));
+            }
+        }
+    }
+
+    if !vlans_to_add.is_empty() {
+        log::debug!("Adding VLANs {vlans_to_add:?} to bridge port {port_name}");
+        let vlans: Vec<(u16, bool)> = vlans_to_add.into_iter().collect();
+        let mut req = handle.link().set(port_index);
+        set_vlan_request(req.message_mut(), gen_vlan_infos(vlans.as_slice()));
+        req.execute().await.map_err(|e| {
+            rtnl_error_to_nipart(
+                e,
+                format!("add VLANs to bridge port {port_name}").as_str(),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+// Return VLAN IDs with whether it is the native(PVID and egress untagged)
+// VLAN.
+fn get_port_vlans(vlan_conf: &BridgePortVlanConfig) -> BTreeMap<u16, bool> {
+    let mut ret = BTreeMap::new();
+    match vlan_conf.mode.unwrap_or_default() {
+        BridgePortVlanMode::Trunk => {
+            for trunk_tag in vlan_conf.trunk_tags.as_deref().unwrap_or_default()
+            {
+                let (vlan_min, vlan_max) = trunk_tag.get_vlan_tag_range();
+                for vid in vlan_min..=vlan_max {
+                    ret.insert(vid, false);
+                }
+            }
+            if vlan_conf.enable_native == Some(true) {
+                if let Some(tag) = vlan_conf.tag {
+                    ret.insert(tag, true);
+                }
+            }
+        }
+        _ => {
+            if let Some(tag) = vlan_conf.tag {
+                ret.insert(tag, true);
+            }
+        }
+    }
+    ret
+}
+
+fn set_vlan_request(
+    link_msg: &mut netlink_packet_route::link::LinkMessage,
+    vlan_infos: Vec<AfSpecBridge>,
+) {
+    link_msg.header.interface_family = AddressFamily::Bridge;
+    link_msg
+        .attributes
+        .push(LinkAttribute::AfSpecBridge(vlan_infos));
+}
+
+// Consecutive non-native VLANs are merged into range.
+fn gen_vlan_infos(vlans: &[(u16, bool)]) -> Vec<AfSpecBridge> {
+    let new_vlan_info = |vid: u16, flags: u16| {
+        let mut info = BridgeVlanInfo::default();
+        info.vid = vid;
+        info.flags = flags;
+        AfSpecBridge::VlanInfo(info)
+    };
+    let mut ret = Vec::new();
+    let mut range: Option<(u16, u16)> = None;
+    for (vid, is_native) in vlans.iter().copied() {
+        if is_native {
+            ret.push(new_vlan_info(
+                vid,
+                BRIDGE_VLAN_INFO_PVID | BRIDGE_VLAN_INFO_UNTAGGED,
+            ));
+            continue;
+        }
+        range = match range {
+            Some((min, max)) if max + 1 == vid => Some((min, vid)),
+            Some((min, max)) => {
+                ret.extend(gen_vlan_range_infos(min, max, new_vlan_info));
+                Some((vid, vid))
+            }
+            None => Some((vid, vid)),
+        };
+    }
+    if let Some((min, max)) = range {
+        ret.extend(gen_vlan_range_infos(min, max, new_vlan_info));
+    }
+    ret
+}
+
+fn gen_vlan_range_infos(
+    min: u16,
+    max: u16,
+    new_vlan_info: impl Fn(u16, u16) -> AfSpecBridge,
+) -> Vec<AfSpecBridge> {
+    if min == max {
+        vec![new_vlan_info(min, 0)]
+    } else {
+        vec![
+            new_vlan_info(min, BRIDGE_VLAN_INFO_RANGE_BEGIN),
+            new_vlan_info(max, BRIDGE_VLAN_INFO_RANGE_END),
+        ]
+    }
+}
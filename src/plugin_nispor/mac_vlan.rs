@@ -1,13 +1,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use netlink_packet_route::link::{InfoData, InfoMacVlan, InfoMacVtap};
 use nipart::{
-    BaseInterface, MacVlanConfig, MacVlanInterface, MacVlanMode, MacVtapConfig,
-    MacVtapInterface, MacVtapMode,
+    BaseInterface, ErrorKind, MacVlanConfig, MacVlanInterface, MacVlanMode,
+    MacVtapConfig, MacVtapInterface, MacVtapMode, NipartError,
 };
 
+use crate::netlink::get_iface_index_or_err;
+
 const MACVLAN_FLAG_NOPROMISC: u16 = 1;
 const MACVTAP_FLAG_NOPROMISC: u16 = 1;
 
+const MACVLAN_MODE_PRIVATE: u32 = 1;
+const MACVLAN_MODE_VEPA: u32 = 2;
+const MACVLAN_MODE_BRIDGE: u32 = 4;
+const MACVLAN_MODE_PASSTHRU: u32 = 8;
+const MACVLAN_MODE_SOURCE: u32 = 16;
+
 pub(crate) fn np_mac_vlan_to_nipart(
     np_iface: &nispor::Iface,
     base_iface: BaseInterface,
@@ -72,3 +81,86 @@ pub(crate) fn np_mac_vtap_to_nipart(
     ret.mac_vtap = vtap_conf;
     ret
 }
+
+// Return the index of base interface and link info data
+pub(crate) async fn nipart_mac_vlan_to_rtnl(
+    handle: &rtnetlink::Handle,
+    iface_name: &str,
+    conf: &MacVlanConfig,
+) -> Result<(u32, InfoData), NipartError> {
+    let (base_index, mode, flags) = gen_mac_vlan_rtnl_conf(
+        handle,
+        iface_name,
+        conf.base_iface.as_str(),
+        conf.mode,
+        conf.accept_all_mac,
+    )
+    .await?;
+    let mut info = vec![InfoMacVlan::Mode(mode)];
+    if let Some(flags) = flags {
+        info.push(InfoMacVlan::Flags(flags));
+    }
+    Ok((base_index, InfoData::MacVlan(info)))
+}
+
+pub(crate) async fn nipart_mac_vtap_to_rtnl(
+    handle: &rtnetlink::Handle,
+    iface_name: &str,
+    conf: &MacVtapConfig,
+) -> Result<(u32, InfoData), NipartError> {
+    let mode = match conf.mode {
+        MacVtapMode::Private => MacVlanMode::Private,
+        MacVtapMode::Vepa => MacVlanMode::Vepa,
+        MacVtapMode::Bridge => MacVlanMode::Bridge,
+        MacVtapMode::Passthru => MacVlanMode::Passthru,
+        MacVtapMode::Source => MacVlanMode::Source,
+        _ => MacVlanMode::Unknown,
+    };
+    let (base_index, mode, flags) = gen_mac_vlan_rtnl_conf(
+        handle,
+        iface_name,
+        conf.base_iface.as_str(),
+        mode,
+        conf.accept_all_mac,
+    )
+    .await?;
+    let mut info = vec![InfoMacVtap::Mode(mode)];
+    if let Some(flags) = flags {
+        info.push(InfoMacVtap::Flags(flags));
+    }
+    Ok((base_index, InfoData::MacVtap(info)))
+}
+
+// Kernel MAC VTAP is sharing the same modes and flags with MAC VLAN.
+// Return the index of base interface, kernel mode and flags.
+async fn gen_mac_vlan_rtnl_conf(
+    handle: &rtnetlink::Handle,
+    iface_name: &str,
+    base_iface: &str,
+    mode: MacVlanMode,
+    accept_all_mac: Option<bool>,
+) -> Result<(u32, u32, Option<u16>), NipartError> {
+    let mode = match mode {
+        MacVlanMode::Private => MACVLAN_MODE_PRIVATE,
+        MacVlanMode::Vepa => MACVLAN_MODE_VEPA,
+        MacVlanMode::Bridge => MACVLAN_MODE_BRIDGE,
+        MacVlanMode::Passthru => MACVLAN_MODE_PASSTHRU,
+        MacVlanMode::Source => MACVLAN_MODE_SOURCE,
+        _ => {
+            return Err(NipartError::new(
+                ErrorKind::InvalidArgument,
+                format!("Unsupported mode {mode:?} for interface {iface_name}"),
+            ));
+        }
+    };
+    let flags = if accept_all_mac == Some(false) {
+        Some(MACVLAN_FLAG_NOPROMISC)
+    } else {
+        None
+    };
+    Ok((
+        get_iface_index_or_err(handle, base_iface).await?,
+        mode,
+        flags,
+    ))
+}
@@ -1,6 +1,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use nipart::{BaseInterface, MacSecConfig, MacSecInterface, MacSecValidate};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+
+use netlink_packet_route::link::{InfoData, InfoMacSec};
+use nipart::{
+    BaseInterface, ErrorKind, MacSecConfig, MacSecInterface, MacSecOffload,
+    MacSecValidate, NipartError,
+};
+
+use crate::netlink::get_iface_index_or_err;
+
+const MKA_CONF_DIR: &str = "/run/nipart/macsec";
+const WPA_SUPPLICANT_BIN: &str = "wpa_supplicant";
 
 fn np_mac_sec_validate_to_nipart(v: nispor::MacSecValidate) -> MacSecValidate {
     match v {
@@ -14,6 +26,20 @@ fn np_mac_sec_validate_to_nipart(v: nispor::MacSecValidate) -> MacSecValidate {
     }
 }
 
+fn np_mac_sec_offload_to_nipart(
+    v: nispor::MacSecOffload,
+) -> Option<MacSecOffload> {
+    match v {
+        nispor::MacSecOffload::Off => Some(MacSecOffload::Off),
+        nispor::MacSecOffload::Phy => Some(MacSecOffload::Phy),
+        nispor::MacSecOffload::Mac => Some(MacSecOffload::Mac),
+        _ => {
+            log::warn!("Unknown MACsec offload mode {:?}", v);
+            None
+        }
+    }
+}
+
 pub(crate) fn np_macsec_to_nipart(
     np_iface: &nispor::Iface,
     base_iface: BaseInterface,
@@ -25,6 +51,7 @@ pub(crate) fn np_macsec_to_nipart(
         conf.validation =
             np_mac_sec_validate_to_nipart(np_macsec_info.validate);
         conf.send_sci = np_macsec_info.send_sci;
+        conf.offload = np_mac_sec_offload_to_nipart(np_macsec_info.offload);
         conf.base_iface = np_macsec_info.base_iface.clone().unwrap_or_default();
         conf.mka_cak = None;
         conf.mka_ckn = None;
@@ -36,3 +63,278 @@ pub(crate) fn np_macsec_to_nipart(
     ret.macsec = macsec_conf;
     ret
 }
+
+// Return the index of base interface and link info data.
+pub(crate) async fn nipart_macsec_to_rtnl(
+    handle: &rtnetlink::Handle,
+    iface_name: &str,
+    conf: &MacSecConfig,
+) -> Result<(u32, InfoData), NipartError> {
+    let port = u16::try_from(conf.port).map_err(|_| {
+        NipartError::new(
+            ErrorKind::InvalidArgument,
+            format!(
+                "Invalid MACsec port {} for interface {iface_name}",
+                conf.port
+            ),
+        )
+    })?;
+    let mut info = vec![
+        InfoMacSec::Port(port),
+        InfoMacSec::Encrypt(conf.encrypt.into()),
+        InfoMacSec::IncSci(conf.send_sci.into()),
+        InfoMacSec::Validation(match conf.validation {
+            MacSecValidate::Disabled => {
+                netlink_packet_route::link::MacSecValidate::Disabled
+            }
+            MacSecValidate::Check => {
+                netlink_packet_route::link::MacSecValidate::Check
+            }
+            _ => netlink_packet_route::link::MacSecValidate::Strict,
+        }),
+    ];
+    if let Some(offload) = conf.offload.as_ref() {
+        info.push(InfoMacSec::Offload(match offload {
+            MacSecOffload::Phy => {
+                netlink_packet_route::link::MacSecOffload::Phy
+            }
+            MacSecOffload::Mac => {
+                netlink_packet_route::link::MacSecOffload::Mac
+            }
+            _ => netlink_packet_route::link::MacSecOffload::Off,
+        }));
+    }
+    Ok((
+        get_iface_index_or_err(handle, conf.base_iface.as_str()).await?,
+        InfoData::MacSec(info),
+    ))
+}
+
+// The MACsec Key Agreement is not done by kernel, the `wpa_supplicant` with
+// `macsec_linux` driver is started for each MACsec interface with `mka_cak`
+// and `mka_ckn`. The `wpa_supplicant` reuses the existing MACsec interface
+// holding the same SCI(MAC address of base interface and port) instead of
+// creating new one.
+// The MKA secrets are not stored in kernel, the `wpa_supplicant` config of
+// previous apply is used for restarting the MKA of recreated MACsec
+// interface.
+pub(crate) fn apply_macsec_mka(
+    iface_name: &str,
+    conf: &MacSecConfig,
+    link_created: bool,
+) -> Result<(), NipartError> {
+    let secrets = match (conf.mka_cak.as_ref(), conf.mka_ckn.as_ref()) {
+        (Some(cak), Some(ckn)) => Some((cak.to_string(), ckn.to_string())),
+        _ if link_created => load_mka_secrets(iface_name),
+        _ => return Ok(()),
+    };
+    let mka_conf =
+        secrets.map(|(cak, ckn)| gen_wpa_supplicant_conf(conf, &cak, &ckn));
+    if !link_created
+        && mka_conf.is_some()
+        && std::fs::read_to_string(mka_conf_path(iface_name)).ok() == mka_conf
+        && std::path::Path::new(&mka_pid_path(iface_name)).exists()
+    {
+        log::debug!("MACsec MKA of interface {iface_name} is unchanged");
+        return Ok(());
+    }
+    stop_macsec_mka(iface_name);
+    if let Some(mka_conf) = mka_conf {
+        start_macsec_mka(iface_name, conf.base_iface.as_str(), &mka_conf)
+    } else {
+        Ok(())
+    }
+}
+
+fn mka_conf_path(iface_name: &str) -> String {
+    format!("{MKA_CONF_DIR}/{iface_name}.conf")
+}
+
+fn mka_pid_path(iface_name: &str) -> String {
+    format!("{MKA_CONF_DIR}/{iface_name}.pid")
+}
+
+fn gen_wpa_supplicant_conf(
+    conf: &MacSecConfig,
+    cak: &str,
+    ckn: &str,
+) -> String {
+    format!(
+        "eapol_version=3\n\
+        ap_scan=0\n\
+        network={{\n\
+        \tkey_mgmt=NONE\n\
+        \teapol_flags=0\n\
+        \tmacsec_policy=1\n\
+        \tmacsec_integ_only={}\n\
+        \tmacsec_port={}\n\
+        \tmka_cak={cak}\n\
+        \tmka_ckn={ckn}\n\
+        }}\n",
+        u8::from(!conf.encrypt),
+        conf.port,
+    )
+}
+
+// Return the (mka_cak, mka_ckn) of existing `wpa_supplicant` config.
+fn load_mka_secrets(iface_name: &str) -> Option<(String, String)> {
+    let content = std::fs::read_to_string(mka_conf_path(iface_name)).ok()?;
+    let get_value = |key: &str| {
+        content.lines().find_map(|l| {
+            l.trim()
+                .strip_prefix(key)
+                .and_then(|l| l.strip_prefix('='))
+                .map(|v| v.to_string())
+        })
+    };
+    Some((get_value("mka_cak")?, get_value("mka_ckn")?))
+}
+
+fn start_macsec_mka(
+    iface_name: &str,
+    base_iface: &str,
+    mka_conf: &str,
+) -> Result<(), NipartError> {
+    std::fs::create_dir_all(MKA_CONF_DIR).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to create folder {MKA_CONF_DIR}: {e}"),
+        )
+    })?;
+    let conf_path = mka_conf_path(iface_name);
+    // The config file holds MKA secrets, hence only readable by owner.
+    let mut fd = std::fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .mode(0o600)
+        .open(&conf_path)
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!(
+                    "Failed to open MACsec MKA config file {conf_path}: {e}"
+                ),
+            )
+        })?;
+    fd.write_all(mka_conf.as_bytes()).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to write MACsec MKA config file {conf_path}: {e}"),
+        )
+    })?;
+
+    log::debug!(
+        "Starting {WPA_SUPPLICANT_BIN} for MACsec MKA of interface \
+        {iface_name}"
+    );
+    match std::process::Command::new(WPA_SUPPLICANT_BIN)
+        .arg("-B")
+        .arg("-D")
+        .arg("macsec_linux")
+        .arg("-i")
+        .arg(base_iface)
+        .arg("-c")
+        .arg(conf_path.as_str())
+        .arg("-P")
+        .arg(mka_pid_path(iface_name))
+        .status()
+    {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(NipartError::new(
+            ErrorKind::PluginFailure,
+            format!(
+                "Failed to start {WPA_SUPPLICANT_BIN} for MACsec MKA of \
+                interface {iface_name}: {status}"
+            ),
+        )),
+        Err(e) => Err(NipartError::new(
+            ErrorKind::DependencyError,
+            format!(
+                "Failed to start {WPA_SUPPLICANT_BIN} for MACsec MKA of \
+                interface {iface_name}: {e}"
+            ),
+        )),
+    }
+}
+
+// Stop the `wpa_supplicant` of specified MACsec interface if any, the config
+// file is kept for restarting MKA of recreated MACsec interface.
+fn stop_macsec_mka(iface_name: &str) {
+    let pid_path = mka_pid_path(iface_name);
+    let pid = match std::fs::read_to_string(&pid_path)
+        .ok()
+        .and_then(|p| p.trim().parse::<i32>().ok())
+    {
+        Some(p) => p,
+        None => return,
+    };
+    log::debug!(
+        "Stopping {WPA_SUPPLICANT_BIN}(pid {pid}) for MACsec MKA of \
+        interface {iface_name}"
+    );
+    if let Err(e) = nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(pid),
+        nix::sys::signal::Signal::SIGTERM,
+    ) {
+        log::debug!("Failed to stop {WPA_SUPPLICANT_BIN}(pid {pid}): {e}");
+    }
+    if let Err(e) = std::fs::remove_file(&pid_path) {
+        log::debug!("Failed to remove {pid_path}: {e}");
+    }
+}
+
+// Stop the MKA and remove its config for deleted MACsec interface.
+pub(crate) fn purge_macsec_mka(iface_name: &str) {
+    stop_macsec_mka(iface_name);
+    let conf_path = mka_conf_path(iface_name);
+    if std::path::Path::new(&conf_path).exists() {
+        if let Err(e) = std::fs::remove_file(&conf_path) {
+            log::warn!("Failed to remove {conf_path}: {e}");
+        }
+    }
+}
+
+// The MKA secrets are not stored in kernel, hence not compared.
+pub(crate) fn is_macsec_conf_changed(
+    des: &MacSecConfig,
+    cur: &MacSecConfig,
+) -> bool {
+    des.encrypt != cur.encrypt
+        || des.base_iface != cur.base_iface
+        || des.port != cur.port
+        || des.validation != cur.validation
+        || des.send_sci != cur.send_sci
+        || (des.offload.is_some() && des.offload != cur.offload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gen_wpa_supplicant_conf() {
+        let mut conf = MacSecConfig::new();
+        conf.encrypt = false;
+        conf.port = 10;
+
+        assert_eq!(
+            gen_wpa_supplicant_conf(
+                &conf,
+                "50b71a8ef0bd5751ea76de6d6c98c03a",
+                "f2b4297d39da7330910a74abc0449feb",
+            ),
+            "eapol_version=3\n\
+            ap_scan=0\n\
+            network={\n\
+            \tkey_mgmt=NONE\n\
+            \teapol_flags=0\n\
+            \tmacsec_policy=1\n\
+            \tmacsec_integ_only=1\n\
+            \tmacsec_port=10\n\
+            \tmka_cak=50b71a8ef0bd5751ea76de6d6c98c03a\n\
+            \tmka_ckn=f2b4297d39da7330910a74abc0449feb\n\
+            }\n"
+        );
+    }
+}
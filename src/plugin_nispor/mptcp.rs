@@ -1,11 +1,157 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::net::Ipv6Addr;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::{Mutex, MutexGuard};
 
 use nipart::{
-    BaseInterface, ErrorKind, MptcpAddressFlag, MptcpConfig, NipartError,
+    BaseInterface, ErrorKind, InterfaceType, MergedNetworkState,
+    MptcpAddressFlag, MptcpConfig, MptcpLimits, NipartAddressMonitorKind,
+    NipartAddressMonitorRule, NipartError, NipartEvent, NipartEventAddress,
+    NipartMonitorRule, NipartNativePlugin, NipartPluginEvent, NipartRole,
+    NipartUserEvent, NipartUuid, DEFAULT_TIMEOUT,
 };
 
+use crate::{
+    apply::get_cur_iface,
+    mptcp_netlink::{new_mptcp_handle, send_mptcp_set, MptcpSetMessage},
+    NipartPluginNispor,
+};
+
+/// MPTCP address flags of interfaces stored from applied network states, so
+/// addresses added or removed afterwards by DHCP or IPv6 autoconf can be
+/// synced with the same flags. Each interface is tracked by an address
+/// monitor rule of the baize plugin identified by the UUID.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MptcpIfaceFlags {
+    ifaces: HashMap<String, (NipartUuid, Vec<MptcpAddressFlag>)>,
+}
+
+impl MptcpIfaceFlags {
+    /// Store desired MPTCP address flags of applying network state and
+    /// remove absent interfaces. Return events for registering or removing
+    /// address monitor rules.
+    pub(crate) fn update(
+        &mut self,
+        merged_state: &MergedNetworkState,
+    ) -> Vec<NipartEvent> {
+        let mut ret = Vec::new();
+        for merged_iface in merged_state
+            .interfaces
+            .kernel_ifaces
+            .values()
+            .filter(|i| i.merged.iface_type() != InterfaceType::Unknown)
+        {
+            let iface_name = merged_iface.merged.name();
+            if merged_iface.merged.is_absent() {
+                if let Some((uuid, _)) = self.ifaces.remove(iface_name) {
+                    ret.push(gen_monitor_rule_event(
+                        NipartPluginEvent::RemoveMonitorRule(Box::new(
+                            gen_address_monitor_rule(iface_name, uuid),
+                        )),
+                        uuid,
+                    ));
+                }
+                continue;
+            }
+            let flags = if let Some(f) =
+                merged_iface.for_apply.as_ref().and_then(|i| {
+                    i.base_iface()
+                        .mptcp
+                        .as_ref()
+                        .and_then(|m| m.address_flags.as_ref())
+                }) {
+                let mut f = f.clone();
+                f.sort_unstable();
+                f.dedup();
+                f
+            } else {
+                continue;
+            };
+            if let Some((_, cur_flags)) = self.ifaces.get_mut(iface_name) {
+                *cur_flags = flags;
+            } else {
+                let uuid = NipartUuid::new();
+                ret.push(gen_monitor_rule_event(
+                    NipartPluginEvent::RegisterMonitorRule(Box::new(
+                        gen_address_monitor_rule(iface_name, uuid),
+                    )),
+                    uuid,
+                ));
+                self.ifaces.insert(iface_name.to_string(), (uuid, flags));
+            }
+        }
+        ret
+    }
+
+    pub(crate) fn get(&self, iface_name: &str) -> Option<&[MptcpAddressFlag]> {
+        self.ifaces.get(iface_name).map(|(_, f)| f.as_slice())
+    }
+
+    /// Get interface name and MPTCP flags of specified monitor rule UUID
+    pub(crate) fn get_by_uuid(
+        &self,
+        uuid: NipartUuid,
+    ) -> Option<(&str, &[MptcpAddressFlag])> {
+        self.ifaces.iter().find_map(|(iface_name, (u, f))| {
+            if *u == uuid {
+                Some((iface_name.as_str(), f.as_slice()))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub(crate) fn to_map(&self) -> HashMap<String, Vec<MptcpAddressFlag>> {
+        self.ifaces
+            .iter()
+            .map(|(iface_name, (_, f))| (iface_name.to_string(), f.clone()))
+            .collect()
+    }
+}
+
+pub(crate) fn lock_mptcp_flags(
+    mptcp_flags: &Mutex<MptcpIfaceFlags>,
+) -> Result<MutexGuard<'_, MptcpIfaceFlags>, NipartError> {
+    mptcp_flags.lock().map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to lock MPTCP interface flags: {e}"),
+        )
+    })
+}
+
+fn gen_monitor_rule_event(
+    plugin_event: NipartPluginEvent,
+    uuid: NipartUuid,
+) -> NipartEvent {
+    NipartEvent::new_with_uuid(
+        uuid,
+        NipartUserEvent::None,
+        plugin_event,
+        NipartEventAddress::Unicast(
+            NipartPluginNispor::PLUGIN_NAME.to_string(),
+        ),
+        NipartEventAddress::Group(NipartRole::Monitor),
+        DEFAULT_TIMEOUT,
+    )
+}
+
+fn gen_address_monitor_rule(
+    iface_name: &str,
+    uuid: NipartUuid,
+) -> NipartMonitorRule {
+    let mut rule = NipartAddressMonitorRule::new(
+        NipartAddressMonitorKind::Change,
+        NipartEventAddress::Unicast(
+            NipartPluginNispor::PLUGIN_NAME.to_string(),
+        ),
+        uuid,
+    );
+    rule.iface = Some(iface_name.to_string());
+    NipartMonitorRule::Address(rule)
+}
+
 pub(crate) fn get_mptcp_flags(
     np_iface: &nispor::Iface,
     ip_addr: &str,
@@ -31,6 +177,16 @@ pub(crate) fn get_mptcp_flags(
     flags
 }
 
+pub(crate) fn get_mptcp_limits(
+    np_state: &nispor::NetState,
+) -> Option<MptcpLimits> {
+    let np_mptcp = np_state.mptcp.as_ref().filter(|m| m.enabled)?;
+    let mut limits = MptcpLimits::default();
+    limits.subflows = np_mptcp.subflows_limit;
+    limits.add_addr_accepted = np_mptcp.add_addr_accepted_limit;
+    Some(limits)
+}
+
 pub(crate) fn get_iface_mptcp_conf(
     iface: &BaseInterface,
 ) -> Option<MptcpConfig> {
@@ -40,13 +196,8 @@ pub(crate) fn get_iface_mptcp_conf(
     if let Some(addrs) = iface.ipv4.as_ref().and_then(|i| i.addresses.as_ref())
     {
         for addr in addrs {
-            if let std::net::IpAddr::V4(ip_addr) = &addr.ip {
-                if ip_addr.is_loopback()
-                    || ip_addr.is_link_local()
-                    || ip_addr.is_multicast()
-                {
-                    continue;
-                }
+            if !is_mptcp_valid_ip(&addr.ip) {
+                continue;
             }
             has_mptcp_valid_ip_addr = true;
             if let Some(mptcp_flags) = addr.mptcp_flags.as_ref() {
@@ -61,15 +212,8 @@ pub(crate) fn get_iface_mptcp_conf(
     if let Some(addrs) = iface.ipv6.as_ref().and_then(|i| i.addresses.as_ref())
     {
         for addr in addrs {
-            if let std::net::IpAddr::V6(ip_addr) = &addr.ip {
-                // TODO: Skip IPv6 privacy extensions address also.
-                if ip_addr.is_loopback()
-                    || ip_addr.is_multicast()
-                    || is_ipv6_unicast_local(ip_addr)
-                    || is_ipv6_unicast_link_local(ip_addr)
-                {
-                    continue;
-                }
+            if !is_mptcp_valid_ip(&addr.ip) {
+                continue;
             }
             has_mptcp_valid_ip_addr = true;
             if let Some(mptcp_flags) = addr.mptcp_flags.as_ref() {
@@ -91,6 +235,152 @@ pub(crate) fn get_iface_mptcp_conf(
     }
 }
 
+fn is_mptcp_valid_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip_addr) => {
+            !(ip_addr.is_loopback()
+                || ip_addr.is_link_local()
+                || ip_addr.is_multicast())
+        }
+        // TODO: Skip IPv6 privacy extensions address also.
+        IpAddr::V6(ip_addr) => {
+            !(ip_addr.is_loopback()
+                || ip_addr.is_multicast()
+                || is_ipv6_unicast_local(ip_addr)
+                || is_ipv6_unicast_link_local(ip_addr))
+        }
+    }
+}
+
+/// The `mptcp_flags` holds the desired or previously stored MPTCP address
+/// flags of interfaces, interfaces not included are untouched.
+pub(crate) async fn apply_mptcp_conf(
+    merged_state: &MergedNetworkState,
+    mptcp_flags: &HashMap<String, Vec<MptcpAddressFlag>>,
+) -> Result<(), NipartError> {
+    if let Some(limits) = merged_state.get_desired_mptcp_limits() {
+        apply_mptcp_limits(limits).await?;
+    }
+
+    for merged_iface in
+        merged_state.interfaces.kernel_ifaces.values().filter(|i| {
+            i.is_changed()
+                && !i.merged.is_absent()
+                && i.merged.iface_type() != InterfaceType::Unknown
+                && i.merged.base_iface().can_have_ip()
+        })
+    {
+        let iface_name = merged_iface.merged.name();
+        if let Some(flags) = mptcp_flags.get(iface_name) {
+            sync_iface_mptcp_endpoints(iface_name, flags).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn apply_mptcp_limits(limits: &MptcpLimits) -> Result<(), NipartError> {
+    log::debug!("Setting MPTCP limits {limits:?}");
+    let mut handle = new_mptcp_handle()?;
+    send_mptcp_set(
+        &mut handle,
+        MptcpSetMessage::new_limits_set(
+            limits.subflows,
+            limits.add_addr_accepted,
+        ),
+    )
+    .await
+}
+
+/// Make sure every valid IP address of specified interface has MPTCP
+/// endpoint with specified flags and remove endpoints of vanished addresses.
+/// Empty `flags` means removing all MPTCP endpoints of this interface.
+pub(crate) async fn sync_iface_mptcp_endpoints(
+    iface_name: &str,
+    flags: &[MptcpAddressFlag],
+) -> Result<(), NipartError> {
+    let np_iface = get_cur_iface(iface_name).await?;
+    let endpoints = np_iface.mptcp.as_deref().unwrap_or_default();
+    let mut flags = flags.to_vec();
+    flags.sort_unstable();
+    flags.dedup();
+
+    let ips: Vec<IpAddr> = np_iface
+        .ipv4
+        .as_ref()
+        .map(|i| i.addresses.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|a| a.address.as_str())
+        .chain(
+            np_iface
+                .ipv6
+                .as_ref()
+                .map(|i| i.addresses.as_slice())
+                .unwrap_or_default()
+                .iter()
+                .map(|a| a.address.as_str()),
+        )
+        .filter_map(|a| a.parse::<IpAddr>().ok())
+        .filter(is_mptcp_valid_ip)
+        .collect();
+
+    let mut messages = Vec::new();
+    let mut synced_ips: Vec<&IpAddr> = Vec::new();
+    for endpoint in endpoints {
+        if !flags.is_empty()
+            && ips.contains(&endpoint.address)
+            && get_np_endpoint_flags(endpoint) == flags
+        {
+            synced_ips.push(&endpoint.address);
+        } else {
+            log::debug!(
+                "Removing MPTCP endpoint {} from interface {iface_name}",
+                endpoint.address
+            );
+            messages.push(MptcpSetMessage::new_addr_del(
+                &endpoint.address,
+                endpoint.id.unwrap_or_default(),
+            ));
+        }
+    }
+    if !flags.is_empty() {
+        for ip in ips.iter().filter(|ip| !synced_ips.contains(ip)) {
+            log::debug!(
+                "Adding MPTCP endpoint {ip} with flags {flags:?} to \
+                interface {iface_name}"
+            );
+            messages.push(MptcpSetMessage::new_addr_add(
+                ip,
+                np_iface.index,
+                flags.as_slice(),
+            ));
+        }
+    }
+
+    if messages.is_empty() {
+        return Ok(());
+    }
+    let mut handle = new_mptcp_handle()?;
+    for message in messages {
+        send_mptcp_set(&mut handle, message).await?;
+    }
+    Ok(())
+}
+
+fn get_np_endpoint_flags(
+    endpoint: &nispor::MptcpAddress,
+) -> Vec<MptcpAddressFlag> {
+    let mut flags: Vec<MptcpAddressFlag> = endpoint
+        .flags
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|f| np_mptcp_addr_flag_to_nipart(*f).ok())
+        .collect();
+    flags.sort_unstable();
+    flags
+}
+
 fn np_mptcp_addr_flag_to_nipart(
     value: nispor::MptcpAddressFlag,
 ) -> Result<MptcpAddressFlag, NipartError> {
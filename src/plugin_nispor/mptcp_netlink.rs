@@ -0,0 +1,276 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// The mptcp-pm crate only support querying, this file holds the MPTCP path
+// manager netlink messages for changing endpoints and limits.
+
+use std::net::IpAddr;
+
+use futures::StreamExt;
+use genetlink_packet_core::{
+    DecodeError, Emitable, NetlinkMessage, NetlinkPayload, Nla,
+    ParseableParametrized, NLA_F_NESTED, NLM_F_ACK, NLM_F_REQUEST,
+};
+use mptcp_pm::{
+    MptcpPathManagerAddressAttr, MptcpPathManagerAddressAttrFlag,
+    MptcpPathManagerHandle, MptcpPathManagerLimitsAttr,
+};
+use netlink_packet_generic::{GenlFamily, GenlHeader, GenlMessage};
+use nipart::{ErrorKind, MptcpAddressFlag, NipartError};
+use nix::errno::Errno;
+
+const MPTCP_PM_CMD_ADD_ADDR: u8 = 1;
+const MPTCP_PM_CMD_DEL_ADDR: u8 = 2;
+const MPTCP_PM_CMD_SET_LIMITS: u8 = 5;
+
+const MPTCP_PM_ATTR_ADDR: u16 = 1;
+
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum MptcpSetCmd {
+    AddAddr,
+    DelAddr,
+    SetLimits,
+}
+
+impl std::fmt::Display for MptcpSetCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::AddAddr => "add endpoint",
+                Self::DelAddr => "delete endpoint",
+                Self::SetLimits => "set limits",
+            }
+        )
+    }
+}
+
+impl From<MptcpSetCmd> for u8 {
+    fn from(cmd: MptcpSetCmd) -> Self {
+        match cmd {
+            MptcpSetCmd::AddAddr => MPTCP_PM_CMD_ADD_ADDR,
+            MptcpSetCmd::DelAddr => MPTCP_PM_CMD_DEL_ADDR,
+            MptcpSetCmd::SetLimits => MPTCP_PM_CMD_SET_LIMITS,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum MptcpSetAttr {
+    // The mptcp-pm crate cannot emit nested MPTCP_PM_ATTR_ADDR
+    Address(Vec<MptcpPathManagerAddressAttr>),
+    Limits(MptcpPathManagerLimitsAttr),
+}
+
+impl Nla for MptcpSetAttr {
+    fn value_len(&self) -> usize {
+        match self {
+            Self::Address(attrs) => attrs.as_slice().buffer_len(),
+            Self::Limits(attr) => attr.value_len(),
+        }
+    }
+
+    fn kind(&self) -> u16 {
+        match self {
+            Self::Address(_) => MPTCP_PM_ATTR_ADDR | NLA_F_NESTED,
+            Self::Limits(attr) => attr.kind(),
+        }
+    }
+
+    fn emit_value(&self, buffer: &mut [u8]) {
+        match self {
+            Self::Address(attrs) => attrs.as_slice().emit(buffer),
+            Self::Limits(attr) => attr.emit_value(buffer),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct MptcpSetMessage {
+    cmd: MptcpSetCmd,
+    nlas: Vec<MptcpSetAttr>,
+}
+
+impl MptcpSetMessage {
+    pub(crate) fn new_addr_add(
+        ip: &IpAddr,
+        iface_index: u32,
+        flags: &[MptcpAddressFlag],
+    ) -> Self {
+        let mut attrs = gen_addr_attrs(ip);
+        attrs.push(MptcpPathManagerAddressAttr::Flags(
+            flags.iter().filter_map(nipart_mptcp_flag_to_pm).collect(),
+        ));
+        attrs.push(MptcpPathManagerAddressAttr::IfIndex(iface_index as i32));
+        Self {
+            cmd: MptcpSetCmd::AddAddr,
+            nlas: vec![MptcpSetAttr::Address(attrs)],
+        }
+    }
+
+    // Kernel search endpoint by ID, or by address when ID is 0.
+    pub(crate) fn new_addr_del(ip: &IpAddr, id: u8) -> Self {
+        let mut attrs = gen_addr_attrs(ip);
+        attrs.push(MptcpPathManagerAddressAttr::Id(id));
+        Self {
+            cmd: MptcpSetCmd::DelAddr,
+            nlas: vec![MptcpSetAttr::Address(attrs)],
+        }
+    }
+
+    // Kernel keeps the limit unchanged if not defined.
+    pub(crate) fn new_limits_set(
+        subflows: Option<u32>,
+        add_addr_accepted: Option<u32>,
+    ) -> Self {
+        let mut nlas = Vec::new();
+        if let Some(v) = add_addr_accepted {
+            nlas.push(MptcpSetAttr::Limits(
+                MptcpPathManagerLimitsAttr::RcvAddAddrs(v),
+            ));
+        }
+        if let Some(v) = subflows {
+            nlas.push(MptcpSetAttr::Limits(
+                MptcpPathManagerLimitsAttr::Subflows(v),
+            ));
+        }
+        Self {
+            cmd: MptcpSetCmd::SetLimits,
+            nlas,
+        }
+    }
+}
+
+fn gen_addr_attrs(ip: &IpAddr) -> Vec<MptcpPathManagerAddressAttr> {
+    match ip {
+        IpAddr::V4(i) => vec![
+            MptcpPathManagerAddressAttr::Family(AF_INET),
+            MptcpPathManagerAddressAttr::Addr4(*i),
+        ],
+        IpAddr::V6(i) => vec![
+            MptcpPathManagerAddressAttr::Family(AF_INET6),
+            MptcpPathManagerAddressAttr::Addr6(*i),
+        ],
+    }
+}
+
+fn nipart_mptcp_flag_to_pm(
+    flag: &MptcpAddressFlag,
+) -> Option<MptcpPathManagerAddressAttrFlag> {
+    match flag {
+        MptcpAddressFlag::Signal => {
+            Some(MptcpPathManagerAddressAttrFlag::Signal)
+        }
+        MptcpAddressFlag::Subflow => {
+            Some(MptcpPathManagerAddressAttrFlag::Subflow)
+        }
+        MptcpAddressFlag::Backup => {
+            Some(MptcpPathManagerAddressAttrFlag::Backup)
+        }
+        MptcpAddressFlag::Fullmesh => {
+            Some(MptcpPathManagerAddressAttrFlag::Fullmesh)
+        }
+        _ => {
+            log::warn!("Ignoring unsupported MPTCP address flag {flag:?}");
+            None
+        }
+    }
+}
+
+impl GenlFamily for MptcpSetMessage {
+    fn family_name() -> &'static str {
+        "mptcp_pm"
+    }
+
+    fn version(&self) -> u8 {
+        1
+    }
+
+    fn command(&self) -> u8 {
+        self.cmd.into()
+    }
+}
+
+impl Emitable for MptcpSetMessage {
+    fn buffer_len(&self) -> usize {
+        self.nlas.as_slice().buffer_len()
+    }
+
+    fn emit(&self, buffer: &mut [u8]) {
+        self.nlas.as_slice().emit(buffer)
+    }
+}
+
+// Reply of set command is ignored, hence no parsing.
+impl ParseableParametrized<[u8], GenlHeader> for MptcpSetMessage {
+    fn parse_with_param(
+        _buffer: &[u8],
+        header: GenlHeader,
+    ) -> Result<Self, DecodeError> {
+        Ok(Self {
+            cmd: match header.cmd {
+                MPTCP_PM_CMD_ADD_ADDR => MptcpSetCmd::AddAddr,
+                MPTCP_PM_CMD_DEL_ADDR => MptcpSetCmd::DelAddr,
+                _ => MptcpSetCmd::SetLimits,
+            },
+            nlas: Vec::new(),
+        })
+    }
+}
+
+pub(crate) fn new_mptcp_handle() -> Result<MptcpPathManagerHandle, NipartError>
+{
+    let (conn, handle, _) = mptcp_pm::new_connection().map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to create MPTCP netlink connection: {e}"),
+        )
+    })?;
+    tokio::spawn(conn);
+    Ok(handle)
+}
+
+pub(crate) async fn send_mptcp_set(
+    handle: &mut MptcpPathManagerHandle,
+    message: MptcpSetMessage,
+) -> Result<(), NipartError> {
+    let cmd = message.cmd;
+    log::debug!("Sending MPTCP {cmd} request {message:?}");
+    let mut nl_msg = NetlinkMessage::from(GenlMessage::from_payload(message));
+    nl_msg.header.flags = NLM_F_REQUEST | NLM_F_ACK;
+
+    let mut response = handle.handle.request(nl_msg).await.map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to MPTCP {cmd}: {e}"),
+        )
+    })?;
+    while let Some(reply) = response.next().await {
+        match reply.map(|r| r.payload) {
+            Ok(NetlinkPayload::Error(e)) if e.code.is_some() => {
+                let e = e.to_io();
+                return Err(NipartError::new(
+                    if e.raw_os_error() == Some(Errno::EOPNOTSUPP as i32) {
+                        ErrorKind::NotSupportedError
+                    } else if e.raw_os_error() == Some(Errno::EINVAL as i32) {
+                        ErrorKind::InvalidArgument
+                    } else {
+                        ErrorKind::PluginFailure
+                    },
+                    format!("Failed to MPTCP {cmd}: {e}"),
+                ));
+            }
+            Ok(_) => (),
+            Err(e) => {
+                return Err(NipartError::new(
+                    ErrorKind::Bug,
+                    format!("Failed to parse reply of MPTCP {cmd}: {e}"),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
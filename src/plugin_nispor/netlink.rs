@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// Settings not supported by nispor yet are applied through rtnetlink directly.
+
+use futures::stream::{StreamExt, TryStreamExt};
+use netlink_packet_core::{
+    NetlinkMessage, NetlinkPayload, NLM_F_ACK, NLM_F_REQUEST,
+};
+use netlink_packet_route::{
+    link::{
+        InfoData, InfoKind, InfoPortData, InfoPortKind, LinkAttribute,
+        LinkInfo, LinkMessage,
+    },
+    RouteNetlinkMessage,
+};
+use nipart::{ErrorKind, NipartError};
+use nix::errno::Errno;
+
+pub(crate) fn new_rtnl_handle() -> Result<rtnetlink::Handle, NipartError> {
+    let (connection, handle, _) = rtnetlink::new_connection().map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to create rtnetlink connection: {e}"),
+        )
+    })?;
+    tokio::spawn(connection);
+    Ok(handle)
+}
+
+pub(crate) async fn get_iface_index(
+    handle: &rtnetlink::Handle,
+    iface: &str,
+) -> Option<u32> {
+    let mut links = handle.link().get().match_name(iface.to_string()).execute();
+    match links.try_next().await {
+        Ok(Some(link_msg)) => Some(link_msg.header.index),
+        Ok(None) => None,
+        Err(e) => {
+            log::debug!("Failed to query interface index of {iface}: {e}");
+            None
+        }
+    }
+}
+
+pub(crate) async fn get_iface_index_or_err(
+    handle: &rtnetlink::Handle,
+    iface: &str,
+) -> Result<u32, NipartError> {
+    get_iface_index(handle, iface).await.ok_or_else(|| {
+        NipartError::new(
+            ErrorKind::InvalidArgument,
+            format!("Interface {iface} does not exist"),
+        )
+    })
+}
+
+pub(crate) fn is_rtnl_errno(e: &rtnetlink::Error, errno: Errno) -> bool {
+    if let rtnetlink::Error::NetlinkError(e) = e {
+        e.raw_code() == -(errno as i32)
+    } else {
+        false
+    }
+}
+
+pub(crate) fn rtnl_error_to_nipart(
+    e: rtnetlink::Error,
+    action: &str,
+) -> NipartError {
+    NipartError::new(
+        ErrorKind::PluginFailure,
+        format!("Failed to {action}: {e}"),
+    )
+}
+
+/// Change interface type specific settings, equivalent to
+/// `ip link set <iface> type <kind> <options>`.
+pub(crate) async fn change_link_info(
+    handle: &rtnetlink::Handle,
+    iface: &str,
+    index: u32,
+    kind: InfoKind,
+    data: InfoData,
+) -> Result<(), NipartError> {
+    send_new_link(
+        handle,
+        index,
+        vec![LinkInfo::Kind(kind), LinkInfo::Data(data)],
+        format!("change link info of interface {iface}").as_str(),
+    )
+    .await
+}
+
+/// Change port settings, equivalent to
+/// `ip link set <iface> type <kind>_slave <options>`.
+pub(crate) async fn change_port_link_info(
+    handle: &rtnetlink::Handle,
+    iface: &str,
+    index: u32,
+    kind: InfoPortKind,
+    data: InfoPortData,
+) -> Result<(), NipartError> {
+    send_new_link(
+        handle,
+        index,
+        vec![LinkInfo::PortKind(kind), LinkInfo::PortData(data)],
+        format!("change port link info of interface {iface}").as_str(),
+    )
+    .await
+}
+
+// RTM_SETLINK does not support changing link info, RTM_NEWLINK without
+// NLM_F_CREATE is required.
+async fn send_new_link(
+    handle: &rtnetlink::Handle,
+    index: u32,
+    link_info: Vec<LinkInfo>,
+    action: &str,
+) -> Result<(), NipartError> {
+    let mut link_msg = LinkMessage::default();
+    link_msg.header.index = index;
+    link_msg.attributes.push(LinkAttribute::LinkInfo(link_info));
+    let mut req = NetlinkMessage::from(RouteNetlinkMessage::NewLink(link_msg));
+    req.header.flags = NLM_F_REQUEST | NLM_F_ACK;
+    let mut response = handle
+        .clone()
+        .request(req)
+        .map_err(|e| rtnl_error_to_nipart(e, action))?;
+    while let Some(msg) = response.next().await {
+        if let NetlinkPayload::Error(e) = msg.payload {
+            if e.code.is_some() {
+                return Err(rtnl_error_to_nipart(
+                    rtnetlink::Error::NetlinkError(e),
+                    action,
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Create interface, equivalent to
+/// `ip link add <iface> [link <parent>] type <kind> <options>`.
+pub(crate) async fn create_link(
+    handle: &rtnetlink::Handle,
+    iface: &str,
+    parent_index: Option<u32>,
+    kind: InfoKind,
+    data: Option<InfoData>,
+) -> Result<(), NipartError> {
+    let mut req = handle.link().add();
+    let link_msg = req.message_mut();
+    link_msg
+        .attributes
+        .push(LinkAttribute::IfName(iface.to_string()));
+    if let Some(parent_index) = parent_index {
+        link_msg.attributes.push(LinkAttribute::Link(parent_index));
+    }
+    let mut link_info = vec![LinkInfo::Kind(kind)];
+    if let Some(data) = data {
+        link_info.push(LinkInfo::Data(data));
+    }
+    link_msg.attributes.push(LinkAttribute::LinkInfo(link_info));
+    req.execute().await.map_err(|e| {
+        rtnl_error_to_nipart(e, format!("create interface {iface}").as_str())
+    })
+}
+
+pub(crate) async fn delete_link(
+    handle: &rtnetlink::Handle,
+    iface: &str,
+    index: u32,
+) -> Result<(), NipartError> {
+    handle.link().del(index).execute().await.map_err(|e| {
+        rtnl_error_to_nipart(e, format!("delete interface {iface}").as_str())
+    })
+}
+
+pub(crate) async fn set_link_up(
+    handle: &rtnetlink::Handle,
+    iface: &str,
+    index: u32,
+    up: bool,
+) -> Result<(), NipartError> {
+    let req = handle.link().set(index);
+    let req = if up { req.up() } else { req.down() };
+    req.execute().await.map_err(|e| {
+        rtnl_error_to_nipart(
+            e,
+            format!("set interface {iface} {}", if up { "up" } else { "down" })
+                .as_str(),
+        )
+    })
+}
+
+/// Attach interface to specified controller, or detach it from its current
+/// controller when `ctrl_index` is None.
+pub(crate) async fn set_link_controller(
+    handle: &rtnetlink::Handle,
+    iface: &str,
+    index: u32,
+    ctrl_index: Option<u32>,
+) -> Result<(), NipartError> {
+    let req = handle.link().set(index);
+    let req = match ctrl_index {
+        Some(i) => req.controller(i),
+        None => req.nocontroller(),
+    };
+    req.execute().await.map_err(|e| {
+        rtnl_error_to_nipart(
+            e,
+            format!("change controller of interface {iface}").as_str(),
+        )
+    })
+}
+
+pub(crate) fn parse_mac(mac: &str) -> Result<[u8; 6], NipartError> {
+    let e = || {
+        NipartError::new(
+            ErrorKind::InvalidArgument,
+            format!("Invalid MAC address {mac}"),
+        )
+    };
+    let mut ret = [0u8; 6];
+    let mut octets = mac.split(':');
+    for octet in ret.iter_mut() {
+        *octet = octets
+            .next()
+            .and_then(|o| u8::from_str_radix(o, 16).ok())
+            .ok_or_else(e)?;
+    }
+    if octets.next().is_some() {
+        return Err(e());
+    }
+    Ok(ret)
+}
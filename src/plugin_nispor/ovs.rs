@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use nipart::{
+    ErrorKind, Interface, InterfaceType, MergedInterface, NipartError,
+};
+
+use crate::netlink::get_iface_index;
+
+const OVS_IFACE_WAIT_RETRY_COUNT: u32 = 50;
+const OVS_IFACE_WAIT_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+// The OVS bridge, patch and DPDK interfaces only exist in OVSDB, they are
+// managed by OVS plugin.
+pub(crate) fn is_ovs_userspace_iface(iface: &Interface) -> bool {
+    match iface {
+        Interface::OvsBridge(_) => true,
+        Interface::OvsInterface(ovs_iface) => {
+            ovs_iface.patch.is_some() || ovs_iface.dpdk.is_some()
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn is_ovs_port(iface: &Interface) -> bool {
+    iface.base_iface().controller_type == Some(InterfaceType::OvsBridge)
+}
+
+// The OVS internal interfaces are created by OVS plugin in parallel, wait
+// them to appear in kernel before configuring them.
+pub(crate) async fn wait_ovs_internal_ifaces(
+    handle: &rtnetlink::Handle,
+    ifaces: &[&MergedInterface],
+) -> Result<(), NipartError> {
+    let iface_names: Vec<&str> = ifaces
+        .iter()
+        .filter(|i| {
+            i.current.is_none()
+                && !i.merged.is_absent()
+                && i.merged.iface_type() == InterfaceType::OvsInterface
+                && !is_ovs_userspace_iface(&i.merged)
+        })
+        .map(|i| i.merged.name())
+        .collect();
+
+    for iface_name in iface_names {
+        let mut found = false;
+        for _ in 0..OVS_IFACE_WAIT_RETRY_COUNT {
+            if get_iface_index(handle, iface_name).await.is_some() {
+                found = true;
+                break;
+            }
+            tokio::time::sleep(OVS_IFACE_WAIT_RETRY_INTERVAL).await;
+        }
+        if !found {
+            return Err(NipartError::new(
+                ErrorKind::PluginFailure,
+                format!(
+                    "Timeout on waiting OVS internal interface {iface_name} \
+                    to appear in kernel"
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
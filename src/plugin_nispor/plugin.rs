@@ -1,14 +1,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::sync::{Arc, Mutex};
+
 use nipart::{
-    MergedNetworkState, NipartApplyOption, NipartDhcpLease, NipartError,
-    NipartEvent, NipartEventAddress, NipartLogLevel, NipartNativePlugin,
-    NipartPluginEvent, NipartRole, NipartUserEvent, NipartUuid,
-    DEFAULT_TIMEOUT,
+    MergedNetworkState, MptcpAddressFlag, NipartApplyOption, NipartDhcpLease,
+    NipartError, NipartEvent, NipartEventAddress, NipartLogLevel,
+    NipartNativePlugin, NipartPluginEvent, NipartRole, NipartUserEvent,
+    NipartUuid, DEFAULT_TIMEOUT,
 };
 use tokio::sync::mpsc::{Receiver, Sender};
 
-use crate::apply::{nispor_apply, nispor_apply_dhcp_lease};
+use crate::apply::{
+    nispor_apply, nispor_apply_dhcp_lease, nispor_remove_dhcp_lease,
+};
+use crate::mptcp::{
+    lock_mptcp_flags, sync_iface_mptcp_endpoints, MptcpIfaceFlags,
+};
 use crate::show::nispor_retrieve;
 
 const STATE_PRIORITY: u32 = 50;
@@ -19,6 +26,7 @@ pub struct NipartPluginNispor {
     log_level: NipartLogLevel,
     to_daemon: Sender<NipartEvent>,
     from_daemon: Receiver<NipartEvent>,
+    mptcp_flags: Arc<Mutex<MptcpIfaceFlags>>,
 }
 
 impl NipartNativePlugin for NipartPluginNispor {
@@ -53,6 +61,7 @@ impl NipartNativePlugin for NipartPluginNispor {
             log_level,
             to_daemon,
             from_daemon,
+            mptcp_flags: Arc::new(Mutex::new(MptcpIfaceFlags::default())),
         })
     }
 
@@ -96,6 +105,7 @@ impl NipartNativePlugin for NipartPluginNispor {
                 Ok(())
             }
             NipartPluginEvent::ApplyNetState(merged_state, opt) => {
+                let mptcp_flags = self.mptcp_flags.clone();
                 // We spawn new thread for apply instead of blocking
                 // here
                 let to_daemon_clone = self.sender_to_daemon().clone();
@@ -105,6 +115,8 @@ impl NipartNativePlugin for NipartPluginNispor {
                         opt,
                         to_daemon_clone,
                         event.uuid,
+                        event.timeout,
+                        mptcp_flags,
                     )
                     .await
                 });
@@ -113,13 +125,59 @@ impl NipartNativePlugin for NipartPluginNispor {
             NipartPluginEvent::ApplyDhcpLease(lease) => {
                 // We spawn new thread for apply instead of blocking
                 // here
+                let mptcp_flags = lock_mptcp_flags(&self.mptcp_flags)?
+                    .get(lease.iface())
+                    .map(|f| f.to_vec());
                 let to_daemon_clone = self.sender_to_daemon().clone();
                 tokio::spawn(async move {
-                    handle_apply_dhcp_lease(*lease, to_daemon_clone, event.uuid)
-                        .await
+                    handle_apply_dhcp_lease(
+                        *lease,
+                        mptcp_flags,
+                        to_daemon_clone,
+                        event.uuid,
+                    )
+                    .await
                 });
                 Ok(())
             }
+            NipartPluginEvent::RemoveDhcpLease(lease) => {
+                let mptcp_flags = lock_mptcp_flags(&self.mptcp_flags)?
+                    .get(lease.iface())
+                    .map(|f| f.to_vec());
+                let to_daemon_clone = self.sender_to_daemon().clone();
+                tokio::spawn(async move {
+                    handle_remove_dhcp_lease(
+                        *lease,
+                        mptcp_flags,
+                        to_daemon_clone,
+                        event.uuid,
+                    )
+                    .await
+                });
+                Ok(())
+            }
+            // Addresses changed by DHCP or IPv6 autoconf on interface with
+            // stored MPTCP flags
+            NipartPluginEvent::GotMonitorEvent(monitor_event) => {
+                let iface_flags = lock_mptcp_flags(&self.mptcp_flags)?
+                    .get_by_uuid(event.uuid)
+                    .map(|(n, f)| (n.to_string(), f.to_vec()));
+                if let Some((iface_name, flags)) = iface_flags {
+                    log::debug!(
+                        "Syncing MPTCP endpoints of {iface_name} on \
+                        {monitor_event}"
+                    );
+                    if let Err(e) =
+                        sync_iface_mptcp_endpoints(&iface_name, &flags).await
+                    {
+                        log::warn!(
+                            "Failed to sync MPTCP endpoints of \
+                            {iface_name}: {e}"
+                        );
+                    }
+                }
+                Ok(())
+            }
             _ => {
                 log::warn!("Plugin nispor got unknown event {event:?}");
                 Ok(())
@@ -133,11 +191,72 @@ async fn handle_apply(
     opt: NipartApplyOption,
     to_daemon: Sender<NipartEvent>,
     uuid: NipartUuid,
+    timeout: u32,
+    mptcp_flags: Arc<Mutex<MptcpIfaceFlags>>,
 ) {
-    let mut reply = match nispor_apply(merged_state, opt).await {
+    let result =
+        apply_and_store_mptcp_flags(merged_state, opt, timeout, &mptcp_flags)
+            .await;
+    let mut reply = match result {
+        Ok(monitor_events) => {
+            for monitor_event in monitor_events {
+                log::debug!("Sending {monitor_event}");
+                if let Err(e) = to_daemon.send(monitor_event).await {
+                    log::error!("Failed to send monitor rule event {e}")
+                }
+            }
+            NipartEvent::new(
+                NipartUserEvent::None,
+                NipartPluginEvent::ApplyNetStateReply,
+                NipartEventAddress::Unicast(
+                    NipartPluginNispor::PLUGIN_NAME.to_string(),
+                ),
+                NipartEventAddress::Commander,
+                DEFAULT_TIMEOUT,
+            )
+        }
+        Err(e) => NipartEvent::new(
+            NipartUserEvent::Error(e),
+            NipartPluginEvent::ApplyNetStateReply,
+            NipartEventAddress::Unicast(
+                NipartPluginNispor::PLUGIN_NAME.to_string(),
+            ),
+            NipartEventAddress::Commander,
+            DEFAULT_TIMEOUT,
+        ),
+    };
+    reply.uuid = uuid;
+    log::trace!("Sending reply {reply:?}");
+    if let Err(e) = to_daemon.send(reply).await {
+        log::error!("Failed to reply {e}")
+    }
+}
+
+// The MPTCP flags of desired state are only stored after applied, so failed
+// apply will not impact later DHCP lease.
+async fn apply_and_store_mptcp_flags(
+    merged_state: MergedNetworkState,
+    opt: NipartApplyOption,
+    timeout: u32,
+    mptcp_flags: &Mutex<MptcpIfaceFlags>,
+) -> Result<Vec<NipartEvent>, NipartError> {
+    let mut new_mptcp_flags = lock_mptcp_flags(mptcp_flags)?.clone();
+    let monitor_events = new_mptcp_flags.update(&merged_state);
+    nispor_apply(merged_state, opt, timeout, new_mptcp_flags.to_map()).await?;
+    *lock_mptcp_flags(mptcp_flags)? = new_mptcp_flags;
+    Ok(monitor_events)
+}
+
+async fn handle_apply_dhcp_lease(
+    lease: NipartDhcpLease,
+    mptcp_flags: Option<Vec<MptcpAddressFlag>>,
+    to_daemon: Sender<NipartEvent>,
+    uuid: NipartUuid,
+) {
+    let mut reply = match nispor_apply_dhcp_lease(lease, mptcp_flags).await {
         Ok(()) => NipartEvent::new(
             NipartUserEvent::None,
-            NipartPluginEvent::ApplyNetStateReply,
+            NipartPluginEvent::ApplyDhcpLeaseReply,
             NipartEventAddress::Unicast(
                 NipartPluginNispor::PLUGIN_NAME.to_string(),
             ),
@@ -146,7 +265,7 @@ async fn handle_apply(
         ),
         Err(e) => NipartEvent::new(
             NipartUserEvent::Error(e),
-            NipartPluginEvent::ApplyNetStateReply,
+            NipartPluginEvent::ApplyDhcpLeaseReply,
             NipartEventAddress::Unicast(
                 NipartPluginNispor::PLUGIN_NAME.to_string(),
             ),
@@ -161,15 +280,16 @@ async fn handle_apply(
     }
 }
 
-async fn handle_apply_dhcp_lease(
+async fn handle_remove_dhcp_lease(
     lease: NipartDhcpLease,
+    mptcp_flags: Option<Vec<MptcpAddressFlag>>,
     to_daemon: Sender<NipartEvent>,
     uuid: NipartUuid,
 ) {
-    let mut reply = match nispor_apply_dhcp_lease(lease).await {
+    let mut reply = match nispor_remove_dhcp_lease(lease, mptcp_flags).await {
         Ok(()) => NipartEvent::new(
             NipartUserEvent::None,
-            NipartPluginEvent::ApplyDhcpLeaseReply,
+            NipartPluginEvent::RemoveDhcpLeaseReply,
             NipartEventAddress::Unicast(
                 NipartPluginNispor::PLUGIN_NAME.to_string(),
             ),
@@ -178,7 +298,7 @@ async fn handle_apply_dhcp_lease(
         ),
         Err(e) => NipartEvent::new(
             NipartUserEvent::Error(e),
-            NipartPluginEvent::ApplyDhcpLeaseReply,
+            NipartPluginEvent::RemoveDhcpLeaseReply,
             NipartEventAddress::Unicast(
                 NipartPluginNispor::PLUGIN_NAME.to_string(),
             ),
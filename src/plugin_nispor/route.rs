@@ -1,7 +1,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::HashMap;
+use std::net::IpAddr;
+
 use log::warn;
-use nipart::{RouteEntry, RouteType, Routes};
+use netlink_packet_route::{
+    route::{
+        self as rtnl_route, RouteAddress, RouteAttribute, RouteHeader,
+        RouteMessage, RouteMetric, RouteScope,
+    },
+    AddressFamily,
+};
+use nipart::{
+    ErrorKind, MergedRoutes, NipartError, RouteEntry, RouteType, Routes,
+};
+use nix::errno::Errno;
+
+use crate::netlink::{get_iface_index, is_rtnl_errno, new_rtnl_handle};
 
 const SUPPORTED_ROUTE_SCOPE: [nispor::RouteScope; 2] =
     [nispor::RouteScope::Universe, nispor::RouteScope::Link];
@@ -19,6 +34,8 @@ const SUPPORTED_ROUTE_PROTOCOL: [nispor::RouteProtocol; 7] = [
 const SUPPORTED_STATIC_ROUTE_PROTOCOL: [nispor::RouteProtocol; 2] =
     [nispor::RouteProtocol::Boot, nispor::RouteProtocol::Static];
 
+const RTAX_CWND: u32 = 7;
+
 const IPV4_DEFAULT_GATEWAY: &str = "0.0.0.0/0";
 const IPV6_DEFAULT_GATEWAY: &str = "::/0";
 const IPV4_EMPTY_NEXT_HOP_ADDRESS: &str = "0.0.0.0";
@@ -217,3 +234,273 @@ fn flat_multipath_route(np_route: &nispor::Route) -> Vec<RouteEntry> {
     }
     ret
 }
+
+/// Query IPv4 routes of specified protocol using specified interface as next
+/// hop.
+pub(crate) async fn get_iface_routes_v4(
+    iface_name: &str,
+    protocol: nispor::RouteProtocol,
+) -> Result<Vec<RouteEntry>, NipartError> {
+    let mut rt_filter = nispor::NetStateRouteFilter::default();
+    rt_filter.protocol = Some(protocol);
+    rt_filter.oif = Some(iface_name.to_string());
+    let mut filter = nispor::NetStateFilter::minimum();
+    filter.route = Some(rt_filter);
+    let np_state = nispor::NetState::retrieve_with_filter_async(&filter)
+        .await
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!(
+                    "Failed to retrieve {protocol:?} routes of \
+                    {iface_name}: {}, {}",
+                    e.kind, e.msg
+                ),
+            )
+        })?;
+    Ok(np_state
+        .routes
+        .iter()
+        .filter(|np_route| {
+            np_route.address_family == nispor::AddressFamily::IPv4
+                && SUPPORTED_ROUTE_SCOPE.contains(&np_route.scope)
+                && !is_multipath(np_route)
+        })
+        .map(np_route_to_nipart)
+        .collect())
+}
+
+/// Apply changed routes, absent routes are removed before adding new routes.
+pub(crate) async fn apply_routes(
+    merged_routes: &MergedRoutes,
+) -> Result<(), NipartError> {
+    if !merged_routes.is_changed() {
+        return Ok(());
+    }
+    let mut changed_routes = merged_routes.changed_routes().to_vec();
+    // Absent routes are sorted before others
+    changed_routes.sort_unstable();
+
+    apply_route_entries(
+        changed_routes.as_slice(),
+        rtnl_route::RouteProtocol::Static,
+    )
+    .await
+}
+
+/// Add routes or remove routes marked as absent using specified route
+/// protocol, routes are applied in the order of `routes`.
+pub(crate) async fn apply_route_entries(
+    routes: &[RouteEntry],
+    protocol: rtnl_route::RouteProtocol,
+) -> Result<(), NipartError> {
+    let handle = new_rtnl_handle()?;
+    let mut iface_indexes: HashMap<String, Option<u32>> = HashMap::new();
+
+    for rt in routes {
+        let oif_index = match rt.next_hop_iface.as_deref() {
+            Some(iface) if rt.route_type.is_none() || rt.is_ipv6() => {
+                let index = match iface_indexes.get(iface) {
+                    Some(i) => *i,
+                    None => {
+                        let i = get_iface_index(&handle, iface).await;
+                        iface_indexes.insert(iface.to_string(), i);
+                        i
+                    }
+                };
+                if index.is_none() {
+                    if rt.is_absent() {
+                        log::debug!(
+                            "Next hop interface of absent route {rt} \
+                            does not exist, ignoring"
+                        );
+                        continue;
+                    }
+                    return Err(NipartError::new(
+                        ErrorKind::InvalidArgument,
+                        format!(
+                            "Next hop interface {iface} of route {rt} \
+                            does not exist"
+                        ),
+                    ));
+                }
+                index
+            }
+            _ => None,
+        };
+        let rt_msg = nipart_route_to_rtnl(rt, oif_index, protocol)?;
+        if rt.is_absent() {
+            log::debug!("Removing route {rt}");
+            if let Err(e) = handle.route().del(rt_msg).execute().await {
+                if is_rtnl_errno(&e, Errno::ESRCH) {
+                    log::debug!("Route {rt} already removed");
+                } else {
+                    return Err(NipartError::new(
+                        ErrorKind::PluginFailure,
+                        format!("Failed to remove route {rt}: {e}"),
+                    ));
+                }
+            }
+        } else {
+            log::debug!("Adding route {rt}");
+            let mut req = handle.route().add();
+            *req.message_mut() = rt_msg;
+            if let Err(e) = req.execute().await {
+                if is_rtnl_errno(&e, Errno::EEXIST) {
+                    log::debug!("Route {rt} already exists");
+                } else {
+                    return Err(NipartError::new(
+                        ErrorKind::PluginFailure,
+                        format!("Failed to add route {rt}: {e}"),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn nipart_route_to_rtnl(
+    rt: &RouteEntry,
+    oif_index: Option<u32>,
+    protocol: rtnl_route::RouteProtocol,
+) -> Result<RouteMessage, NipartError> {
+    let mut rt_msg = RouteMessage::default();
+    let (dst_addr, dst_prefix) = parse_route_dst(rt)?;
+    rt_msg.header.destination_prefix_length = dst_prefix;
+    rt_msg.header.address_family = match dst_addr {
+        IpAddr::V4(_) => AddressFamily::Inet,
+        IpAddr::V6(_) => AddressFamily::Inet6,
+    };
+    rt_msg
+        .attributes
+        .push(RouteAttribute::Destination(ip_to_rtnl(dst_addr)));
+
+    let table_id = match rt.table_id {
+        None | Some(RouteEntry::USE_DEFAULT_ROUTE_TABLE) => {
+            u32::from(RouteHeader::RT_TABLE_MAIN)
+        }
+        Some(t) => t,
+    };
+    if let Ok(t) = u8::try_from(table_id) {
+        rt_msg.header.table = t;
+    } else {
+        rt_msg.header.table = RouteHeader::RT_TABLE_UNSPEC;
+    }
+    rt_msg.attributes.push(RouteAttribute::Table(table_id));
+
+    let next_hop_addr = match rt.next_hop_addr.as_deref() {
+        None | Some("") => None,
+        Some(via) => {
+            let via = via.parse::<IpAddr>().map_err(|e| {
+                NipartError::new(
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "Invalid next hop address {via} of route {rt}: {e}"
+                    ),
+                )
+            })?;
+            if via.is_unspecified() {
+                None
+            } else {
+                Some(via)
+            }
+        }
+    };
+
+    if rt.is_absent() {
+        // Wildcard for removal
+        rt_msg.header.protocol = rtnl_route::RouteProtocol::Unspec;
+        rt_msg.header.scope = RouteScope::NoWhere;
+        rt_msg.header.kind = rtnl_route::RouteType::Unspec;
+    } else {
+        rt_msg.header.protocol = protocol;
+        rt_msg.header.scope = if next_hop_addr.is_none()
+            && rt.route_type.is_none()
+            && !rt.is_ipv6()
+        {
+            RouteScope::Link
+        } else {
+            RouteScope::Universe
+        };
+        rt_msg.header.kind = rtnl_route::RouteType::Unicast;
+    }
+    if let Some(route_type) = rt.route_type {
+        rt_msg.header.kind = match route_type {
+            RouteType::Blackhole => rtnl_route::RouteType::BlackHole,
+            RouteType::Unreachable => rtnl_route::RouteType::Unreachable,
+            RouteType::Prohibit => rtnl_route::RouteType::Prohibit,
+            _ => {
+                return Err(NipartError::new(
+                    ErrorKind::NotSupportedError,
+                    format!("Unsupported route type {route_type} of {rt}"),
+                ));
+            }
+        };
+    }
+
+    if let Some(via) = next_hop_addr {
+        rt_msg
+            .attributes
+            .push(RouteAttribute::Gateway(ip_to_rtnl(via)));
+    }
+    if let Some(index) = oif_index {
+        rt_msg.attributes.push(RouteAttribute::Oif(index));
+    }
+    if let Some(metric) = rt.metric {
+        if metric != RouteEntry::USE_DEFAULT_METRIC {
+            rt_msg.attributes.push(RouteAttribute::Priority(
+                u32::try_from(metric).map_err(|_| {
+                    NipartError::new(
+                        ErrorKind::InvalidArgument,
+                        format!("Invalid metric {metric} of route {rt}"),
+                    )
+                })?,
+            ));
+        }
+    }
+    // Weight is not included for removal, kernel remove route with any
+    // matching next hop.
+    if rt.weight.is_some() && !rt.is_absent() {
+        return Err(NipartError::new(
+            ErrorKind::NotSupportedError,
+            format!(
+                "Plugin nispor does not support applying ECMP route with \
+                weight yet: {rt}"
+            ),
+        ));
+    }
+    if let Some(cwnd) = rt.cwnd {
+        rt_msg.attributes.push(RouteAttribute::Metrics(vec![
+            RouteMetric::Lock(1 << RTAX_CWND),
+            RouteMetric::Cwnd(cwnd),
+        ]));
+    }
+    Ok(rt_msg)
+}
+
+fn ip_to_rtnl(ip: IpAddr) -> RouteAddress {
+    match ip {
+        IpAddr::V4(i) => RouteAddress::Inet(i),
+        IpAddr::V6(i) => RouteAddress::Inet6(i),
+    }
+}
+
+fn parse_route_dst(rt: &RouteEntry) -> Result<(IpAddr, u8), NipartError> {
+    let dst = rt.destination.as_deref().unwrap_or_default();
+    let e = NipartError::new(
+        ErrorKind::InvalidArgument,
+        format!("Invalid destination of route {rt}"),
+    );
+    let (addr, prefix) = match dst.split_once('/') {
+        Some((addr, prefix)) => (
+            addr.parse::<IpAddr>().map_err(|_| e.clone())?,
+            prefix.parse::<u8>().map_err(|_| e.clone())?,
+        ),
+        None => {
+            let addr = dst.parse::<IpAddr>().map_err(|_| e.clone())?;
+            (addr, if addr.is_ipv6() { 128 } else { 32 })
+        }
+    };
+    Ok((addr, prefix))
+}
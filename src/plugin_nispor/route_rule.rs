@@ -1,8 +1,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::net::IpAddr;
+
 use log::warn;
+use netlink_packet_route::{
+    route::{RouteHeader, RouteProtocol},
+    rule::{RuleAction, RuleAttribute, RuleMessage},
+};
+use nipart::{
+    AddressFamily, ErrorKind, MergedRouteRules, NipartError, RouteRuleAction,
+    RouteRuleEntry, RouteRules,
+};
+use nix::errno::Errno;
 
-use nipart::{AddressFamily, RouteRuleAction, RouteRuleEntry, RouteRules};
+use crate::netlink::{is_rtnl_errno, new_rtnl_handle};
 
 // Due to a bug in NetworkManager all route rules added using NetworkManager are
 // using RTM_PROTOCOL Unspec. Therefore, we need to support it until it is
@@ -63,6 +74,7 @@ pub(crate) fn get_route_rules(
             }
         }
         rule.iif = np_rule.iif.clone();
+        rule.oif = np_rule.oif.clone();
         rule.ip_to = np_rule.dst.clone();
         rule.ip_from = np_rule.src.clone();
         rule.table_id = np_rule.table;
@@ -87,3 +99,156 @@ pub(crate) fn get_route_rules(
 
     ret
 }
+
+/// Apply route rules, absent route rules are removed before adding new ones.
+pub(crate) async fn apply_route_rules(
+    merged_rules: &MergedRouteRules,
+) -> Result<(), NipartError> {
+    if !merged_rules.is_changed() {
+        return Ok(());
+    }
+    let mut rules = merged_rules.rules_for_apply().to_vec();
+    // Absent route rules are sorted before others
+    rules.sort_unstable();
+
+    let handle = new_rtnl_handle()?;
+    for rule in rules.as_slice() {
+        let rule_msg = nipart_rule_to_rtnl(rule)?;
+        if rule.is_absent() {
+            log::debug!("Removing route rule {rule}");
+            if let Err(e) = handle.rule().del(rule_msg).execute().await {
+                if is_rtnl_errno(&e, Errno::ENOENT) {
+                    log::debug!("Route rule {rule} already removed");
+                } else {
+                    return Err(NipartError::new(
+                        ErrorKind::PluginFailure,
+                        format!("Failed to remove route rule {rule}: {e}"),
+                    ));
+                }
+            }
+        } else {
+            log::debug!("Adding route rule {rule}");
+            let mut req = handle.rule().add();
+            *req.message_mut() = rule_msg;
+            if let Err(e) = req.execute().await {
+                if is_rtnl_errno(&e, Errno::EEXIST) {
+                    log::debug!("Route rule {rule} already exists");
+                } else {
+                    return Err(NipartError::new(
+                        ErrorKind::PluginFailure,
+                        format!("Failed to add route rule {rule}: {e}"),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn nipart_rule_to_rtnl(
+    rule: &RouteRuleEntry,
+) -> Result<RuleMessage, NipartError> {
+    let mut rule_msg = RuleMessage::default();
+    // Route rule without IP and family is treated as IPv4 route rule
+    rule_msg.header.family = if rule.is_ipv6() {
+        netlink_packet_route::AddressFamily::Inet6
+    } else {
+        netlink_packet_route::AddressFamily::Inet
+    };
+
+    if let Some((ip, prefix)) = parse_rule_ip(rule, rule.ip_from.as_deref())? {
+        rule_msg.header.src_len = prefix;
+        rule_msg.attributes.push(RuleAttribute::Source(ip));
+    }
+    if let Some((ip, prefix)) = parse_rule_ip(rule, rule.ip_to.as_deref())? {
+        rule_msg.header.dst_len = prefix;
+        rule_msg.attributes.push(RuleAttribute::Destination(ip));
+    }
+
+    rule_msg.header.action = match rule.action {
+        Some(RouteRuleAction::Blackhole) => RuleAction::Blackhole,
+        Some(RouteRuleAction::Unreachable) => RuleAction::Unreachable,
+        Some(RouteRuleAction::Prohibit) => RuleAction::Prohibit,
+        Some(action) => {
+            return Err(NipartError::new(
+                ErrorKind::NotSupportedError,
+                format!("Unsupported route rule action {action} of {rule}"),
+            ));
+        }
+        None => RuleAction::ToTable,
+    };
+    if let Some(table_id) = rule.table_id {
+        let table_id = if table_id == RouteRuleEntry::USE_DEFAULT_ROUTE_TABLE {
+            RouteRuleEntry::DEFAULR_ROUTE_TABLE_ID
+        } else {
+            table_id
+        };
+        rule_msg.header.table =
+            u8::try_from(table_id).unwrap_or(RouteHeader::RT_TABLE_UNSPEC);
+        rule_msg.attributes.push(RuleAttribute::Table(table_id));
+    }
+    if let Some(priority) = rule.priority {
+        if priority != RouteRuleEntry::USE_DEFAULT_PRIORITY {
+            rule_msg.attributes.push(RuleAttribute::Priority(
+                u32::try_from(priority).map_err(|_| {
+                    NipartError::new(
+                        ErrorKind::InvalidArgument,
+                        format!("Invalid priority {priority} of {rule}"),
+                    )
+                })?,
+            ));
+        }
+    }
+    if let Some(fwmark) = rule.fwmark {
+        rule_msg.attributes.push(RuleAttribute::FwMark(fwmark));
+    }
+    if let Some(fwmask) = rule.fwmask {
+        rule_msg.attributes.push(RuleAttribute::FwMask(fwmask));
+    }
+    if let Some(iif) = rule.iif.as_ref() {
+        rule_msg
+            .attributes
+            .push(RuleAttribute::Iifname(iif.to_string()));
+    }
+    if let Some(oif) = rule.oif.as_ref() {
+        rule_msg
+            .attributes
+            .push(RuleAttribute::Oifname(oif.to_string()));
+    }
+    if let Some(len) = rule.suppress_prefix_length {
+        rule_msg
+            .attributes
+            .push(RuleAttribute::SuppressPrefixLen(len));
+    }
+    if !rule.is_absent() {
+        rule_msg
+            .attributes
+            .push(RuleAttribute::Protocol(RouteProtocol::Static));
+    }
+    Ok(rule_msg)
+}
+
+fn parse_rule_ip(
+    rule: &RouteRuleEntry,
+    ip: Option<&str>,
+) -> Result<Option<(IpAddr, u8)>, NipartError> {
+    let ip = match ip {
+        Some(i) if !i.is_empty() => i,
+        _ => return Ok(None),
+    };
+    let e = NipartError::new(
+        ErrorKind::InvalidArgument,
+        format!("Invalid IP {ip} in route rule {rule}"),
+    );
+    let (addr, prefix) = match ip.split_once('/') {
+        Some((addr, prefix)) => (
+            addr.parse::<IpAddr>().map_err(|_| e.clone())?,
+            prefix.parse::<u8>().map_err(|_| e.clone())?,
+        ),
+        None => {
+            let addr = ip.parse::<IpAddr>().map_err(|_| e.clone())?;
+            (addr, if addr.is_ipv6() { 128 } else { 32 })
+        }
+    };
+    Ok(Some((addr, prefix)))
+}
@@ -13,10 +13,12 @@ use crate::{
     error::np_error_to_nipart,
     ethernet::np_ethernet_to_nipart,
     hostname::get_hostname_state,
+    hsr::np_hsr_to_nipart,
     infiniband::np_ib_to_nipart,
     linux_bridge::{append_bridge_port_config, np_bridge_to_nipart},
     mac_vlan::{np_mac_vlan_to_nipart, np_mac_vtap_to_nipart},
     macsec::np_macsec_to_nipart,
+    mptcp::get_mptcp_limits,
     route::get_routes,
     route_rule::get_route_rules,
     veth::np_veth_to_nipart,
@@ -133,6 +135,9 @@ pub(crate) async fn nispor_retrieve(
             InterfaceType::MacSec => Interface::MacSec(Box::new(
                 np_macsec_to_nipart(np_iface, base_iface),
             )),
+            InterfaceType::Hsr => {
+                Interface::Hsr(Box::new(np_hsr_to_nipart(np_iface, base_iface)))
+            }
             InterfaceType::Xfrm => {
                 let mut iface = XfrmInterface::new();
                 iface.base = base_iface;
@@ -156,6 +161,7 @@ pub(crate) async fn nispor_retrieve(
     set_controller_type(&mut net_state.interfaces);
     net_state.routes = get_routes(running_config_only).await;
     net_state.rules = get_route_rules(&np_state.rules, running_config_only);
+    net_state.mptcp_limits = get_mptcp_limits(&np_state);
 
     Ok(net_state)
 }
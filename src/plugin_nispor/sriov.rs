@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use netlink_packet_route::link::{
+    LinkAttribute, LinkVfInfo, VfInfo, VfInfoMac, VfInfoRate, VfInfoSpoofCheck,
+    VfInfoTrust, VfInfoVlan, VfVlan, VfVlanInfo,
+};
+use nipart::{
+    ErrorKind, Interface, MergedInterface, NipartError, SrIovConfig,
+    SrIovVfConfig, VlanProtocol,
+};
+
+use crate::netlink::{get_iface_index_or_err, parse_mac, rtnl_error_to_nipart};
+
+const VF_WAIT_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+// Only spend a third of the apply timeout on waiting VF interfaces, the rest
+// is left for remaining apply actions and verification of daemon.
+const VF_WAIT_TIMEOUT_DIVISOR: u32 = 3;
+
+/// The `timeout` is the timeout in milliseconds of the apply event, used to
+/// bound the time of waiting newly created VF interfaces.
+pub(crate) async fn apply_sriov_conf(
+    handle: &rtnetlink::Handle,
+    merged_iface: &MergedInterface,
+    timeout: u32,
+) -> Result<(), NipartError> {
+    let des_conf =
+        if let Some(c) = get_sriov_conf(merged_iface.for_apply.as_ref()) {
+            c
+        } else {
+            return Ok(());
+        };
+    let cur_conf = get_sriov_conf(merged_iface.current.as_ref())
+        .cloned()
+        .unwrap_or_default();
+    let pf_name = merged_iface.merged.name();
+
+    if let Some(autoprobe) = des_conf.drivers_autoprobe {
+        if cur_conf.drivers_autoprobe != Some(autoprobe) {
+            write_sriov_sysfs(
+                pf_name,
+                "sriov_drivers_autoprobe",
+                if autoprobe { "1" } else { "0" },
+            )?;
+        }
+    }
+
+    let mut vfs_created = false;
+    if let Some(total_vfs) = des_conf.total_vfs {
+        let cur_total_vfs = cur_conf.total_vfs.unwrap_or_default();
+        if cur_total_vfs != total_vfs {
+            // Kernel refuses changing VF count from non-zero to another
+            // non-zero value.
+            if cur_total_vfs != 0 && total_vfs != 0 {
+                write_sriov_sysfs(pf_name, "sriov_numvfs", "0")?;
+            }
+            log::info!("Changing SR-IOV VF count of {pf_name} to {total_vfs}");
+            write_sriov_sysfs(
+                pf_name,
+                "sriov_numvfs",
+                total_vfs.to_string().as_str(),
+            )?;
+            vfs_created = total_vfs > 0;
+        }
+    }
+
+    // VF driver is not loaded without autoprobe, hence no VF interface.
+    if vfs_created
+        && des_conf.drivers_autoprobe.or(cur_conf.drivers_autoprobe)
+            != Some(false)
+    {
+        wait_vf_ifaces(
+            pf_name,
+            des_conf.total_vfs.unwrap_or_default(),
+            get_vf_wait_retry_count(timeout),
+        )
+        .await?;
+    }
+
+    let des_vfs = match des_conf.vfs.as_deref() {
+        Some(vfs) if !vfs.is_empty() => vfs,
+        _ => return Ok(()),
+    };
+    // Newly created VFs are holding default settings, hence apply all
+    // desired VF config.
+    let cur_vfs = if vfs_created {
+        Vec::new()
+    } else {
+        cur_conf.vfs.clone().unwrap_or_default()
+    };
+    let mut vf_infos = Vec::new();
+    for des_vf in des_vfs {
+        let cur_vf = cur_vfs.iter().find(|v| v.id == des_vf.id);
+        let vf_info = gen_vf_info(pf_name, des_vf, cur_vf)?;
+        if !vf_info.is_empty() {
+            vf_infos.push(LinkVfInfo(vf_info));
+        }
+    }
+    if vf_infos.is_empty() {
+        return Ok(());
+    }
+    let index = get_iface_index_or_err(handle, pf_name).await?;
+    let mut req = handle.link().set(index);
+    req.message_mut()
+        .attributes
+        .push(LinkAttribute::VfInfoList(vf_infos));
+    req.execute().await.map_err(|e| {
+        rtnl_error_to_nipart(
+            e,
+            format!("change SR-IOV VF config of {pf_name}").as_str(),
+        )
+    })
+}
+
+fn get_sriov_conf(iface: Option<&Interface>) -> Option<&SrIovConfig> {
+    if let Some(Interface::Ethernet(eth_iface)) = iface {
+        eth_iface.ethernet.as_ref().and_then(|e| e.sr_iov.as_ref())
+    } else {
+        None
+    }
+}
+
+fn write_sriov_sysfs(
+    pf_name: &str,
+    file_name: &str,
+    content: &str,
+) -> Result<(), NipartError> {
+    let path = format!("/sys/class/net/{pf_name}/device/{file_name}");
+    std::fs::write(&path, content).map_err(|e| {
+        NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to write {content} to {path}: {e}"),
+        )
+    })
+}
+
+fn get_vf_wait_retry_count(timeout: u32) -> u32 {
+    (timeout
+        / VF_WAIT_TIMEOUT_DIVISOR
+        / VF_WAIT_RETRY_INTERVAL.as_millis() as u32)
+        .max(1)
+}
+
+// Kernel and udev need time to create and rename VF interfaces.
+async fn wait_vf_ifaces(
+    pf_name: &str,
+    total_vfs: u32,
+    retry_count: u32,
+) -> Result<(), NipartError> {
+    for _ in 0..retry_count {
+        let vf_names = get_vf_iface_names(pf_name).await?;
+        if vf_names.len() >= total_vfs as usize {
+            log::debug!(
+                "SR-IOV VF interfaces of {pf_name} are ready: {vf_names:?}"
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(VF_WAIT_RETRY_INTERVAL).await;
+    }
+    Err(NipartError::new(
+        ErrorKind::SrIovVfNotFound,
+        format!(
+            "Timeout on waiting {total_vfs} SR-IOV VF interfaces of \
+            {pf_name} to appear"
+        ),
+    ))
+}
+
+async fn get_vf_iface_names(pf_name: &str) -> Result<Vec<String>, NipartError> {
+    let mut iface_filter = nispor::NetStateIfaceFilter::minimum();
+    iface_filter.iface_name = Some(pf_name.to_string());
+    iface_filter.include_sriov_vf_info = true;
+    let mut filter = nispor::NetStateFilter::minimum();
+    filter.iface = Some(iface_filter);
+    let np_state = nispor::NetState::retrieve_with_filter_async(&filter)
+        .await
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!(
+                    "Failed to retrieve SR-IOV information of {pf_name}: \
+                    {}, {}",
+                    e.kind, e.msg
+                ),
+            )
+        })?;
+    Ok(np_state
+        .ifaces
+        .get(pf_name)
+        .and_then(|i| i.sriov.as_ref())
+        .map(|s| {
+            s.vfs
+                .iter()
+                .filter_map(|v| v.iface_name.clone())
+                .filter(|n| !n.is_empty())
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn gen_vf_info(
+    pf_name: &str,
+    des: &SrIovVfConfig,
+    cur: Option<&SrIovVfConfig>,
+) -> Result<Vec<VfInfo>, NipartError> {
+    let mut ret = Vec::new();
+    let id = des.id;
+    if let Some(mac) = des.mac_address.as_deref() {
+        if cur.and_then(|c| c.mac_address.as_deref()) != Some(mac) {
+            let mac = parse_mac(mac).map_err(|e| {
+                NipartError::new(
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "Invalid MAC address for VF {id} of {pf_name}: {e}"
+                    ),
+                )
+            })?;
+            ret.push(VfInfo::Mac(VfInfoMac::new(id, &mac)));
+        }
+    }
+    if let Some(v) = des.spoof_check {
+        if cur.and_then(|c| c.spoof_check) != Some(v) {
+            ret.push(VfInfo::SpoofCheck(VfInfoSpoofCheck::new(id, v)));
+        }
+    }
+    if let Some(v) = des.trust {
+        if cur.and_then(|c| c.trust) != Some(v) {
+            ret.push(VfInfo::Trust(VfInfoTrust::new(id, v)));
+        }
+    }
+    if (des.min_tx_rate.is_some()
+        && des.min_tx_rate != cur.and_then(|c| c.min_tx_rate))
+        || (des.max_tx_rate.is_some()
+            && des.max_tx_rate != cur.and_then(|c| c.max_tx_rate))
+    {
+        ret.push(VfInfo::Rate(VfInfoRate::new(
+            id,
+            des.min_tx_rate
+                .or(cur.and_then(|c| c.min_tx_rate))
+                .unwrap_or_default(),
+            des.max_tx_rate
+                .or(cur.and_then(|c| c.max_tx_rate))
+                .unwrap_or_default(),
+        )));
+    }
+    if (des.vlan_id.is_some() && des.vlan_id != cur.and_then(|c| c.vlan_id))
+        || (des.qos.is_some() && des.qos != cur.and_then(|c| c.qos))
+        || (des.vlan_proto.is_some()
+            && des.vlan_proto != cur.and_then(|c| c.vlan_proto))
+    {
+        let vlan_id = des
+            .vlan_id
+            .or(cur.and_then(|c| c.vlan_id))
+            .unwrap_or_default();
+        let qos = des.qos.or(cur.and_then(|c| c.qos)).unwrap_or_default();
+        match des.vlan_proto {
+            Some(VlanProtocol::Ieee8021Ad) => {
+                ret.push(VfInfo::VlanList(vec![VfVlan::Info(
+                    VfVlanInfo::new(
+                        id,
+                        vlan_id,
+                        qos,
+                        netlink_packet_route::link::VlanProtocol::Ieee8021Ad,
+                    ),
+                )]));
+            }
+            _ => {
+                ret.push(VfInfo::Vlan(VfInfoVlan::new(id, vlan_id, qos)));
+            }
+        }
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vf_wait_bounded_below_apply_timeout() {
+        let retry_count = get_vf_wait_retry_count(nipart::DEFAULT_TIMEOUT);
+        assert_eq!(retry_count, 20);
+        assert!(
+            VF_WAIT_RETRY_INTERVAL * retry_count
+                < Duration::from_millis(nipart::DEFAULT_TIMEOUT.into())
+        );
+        assert_eq!(get_vf_wait_retry_count(120000), 80);
+        assert_eq!(get_vf_wait_retry_count(0), 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_vf_ifaces_timeout() {
+        // Loopback interface never has VF, retry once for 500 milliseconds.
+        let e = wait_vf_ifaces("lo", 1, get_vf_wait_retry_count(1500))
+            .await
+            .unwrap_err();
+        assert_eq!(e.kind, ErrorKind::SrIovVfNotFound);
+    }
+
+    #[test]
+    fn test_gen_vf_info_skip_unchanged() {
+        let mut des = SrIovVfConfig::new();
+        des.id = 1;
+        des.trust = Some(true);
+        des.spoof_check = Some(false);
+        des.max_tx_rate = Some(1000);
+        let mut cur = SrIovVfConfig::new();
+        cur.id = 1;
+        cur.trust = Some(true);
+        cur.spoof_check = Some(true);
+        cur.min_tx_rate = Some(100);
+        cur.max_tx_rate = Some(500);
+
+        assert_eq!(
+            gen_vf_info("eth1", &des, Some(&cur)).unwrap(),
+            vec![
+                VfInfo::SpoofCheck(VfInfoSpoofCheck::new(1, false)),
+                VfInfo::Rate(VfInfoRate::new(1, 100, 1000)),
+            ]
+        );
+    }
+}
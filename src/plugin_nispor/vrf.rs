@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use nipart::{BaseInterface, VrfConfig, VrfInterface};
+use netlink_packet_route::link::{InfoData, InfoVrf};
+use nipart::{BaseInterface, ErrorKind, NipartError, VrfConfig, VrfInterface};
 
 pub(crate) fn np_vrf_to_nipart(
     np_iface: &nispor::Iface,
@@ -21,3 +22,24 @@ pub(crate) fn np_vrf_to_nipart(
     ret.vrf = vrf_conf;
     ret
 }
+
+pub(crate) fn nipart_vrf_to_rtnl(
+    iface_name: &str,
+    vrf_conf: &VrfConfig,
+) -> Result<InfoData, NipartError> {
+    if let Some(table_id) = vrf_conf.table_id {
+        Ok(InfoData::Vrf(vec![InfoVrf::TableId(table_id)]))
+    } else {
+        Err(NipartError::new(
+            ErrorKind::InvalidArgument,
+            format!(
+                "Route table ID is mandatory for VRF interface {iface_name}"
+            ),
+        ))
+    }
+}
+
+// Ports are excluded as they could be changed without recreating VRF.
+pub(crate) fn is_vrf_conf_changed(des: &VrfConfig, cur: &VrfConfig) -> bool {
+    des.table_id != cur.table_id
+}
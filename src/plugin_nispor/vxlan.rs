@@ -1,8 +1,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::net::IpAddr;
 use std::str::FromStr;
 
-use nipart::{BaseInterface, VxlanConfig, VxlanInterface};
+use netlink_packet_route::link::{InfoData, InfoVxlan};
+use nipart::{BaseInterface, NipartError, VxlanConfig, VxlanInterface};
+
+use crate::netlink::get_iface_index_or_err;
 
 pub(crate) fn np_vxlan_to_nipart(
     np_iface: &nispor::Iface,
@@ -27,3 +31,39 @@ pub(crate) fn np_vxlan_to_nipart(
     ret.vxlan = vxlan_conf;
     ret
 }
+
+// The remote address is sent as group address, kernel will treat unicast
+// group address as remote.
+pub(crate) async fn nipart_vxlan_to_rtnl(
+    handle: &rtnetlink::Handle,
+    vxlan_conf: &VxlanConfig,
+) -> Result<InfoData, NipartError> {
+    let mut info = vec![InfoVxlan::Id(vxlan_conf.id)];
+    if !vxlan_conf.base_iface.is_empty() {
+        info.push(InfoVxlan::Link(
+            get_iface_index_or_err(handle, vxlan_conf.base_iface.as_str())
+                .await?,
+        ));
+    }
+    if let Some(v) = vxlan_conf.learning {
+        info.push(InfoVxlan::Learning(v));
+    }
+    match vxlan_conf.local {
+        Some(IpAddr::V4(v)) => info.push(InfoVxlan::Local(v.octets().to_vec())),
+        Some(IpAddr::V6(v)) => {
+            info.push(InfoVxlan::Local6(v.octets().to_vec()))
+        }
+        None => (),
+    }
+    match vxlan_conf.remote {
+        Some(IpAddr::V4(v)) => info.push(InfoVxlan::Group(v.octets().to_vec())),
+        Some(IpAddr::V6(v)) => {
+            info.push(InfoVxlan::Group6(v.octets().to_vec()))
+        }
+        None => (),
+    }
+    if let Some(v) = vxlan_conf.dst_port {
+        info.push(InfoVxlan::Port(v));
+    }
+    Ok(InfoData::Vxlan(info))
+}
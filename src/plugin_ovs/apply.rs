@@ -0,0 +1,740 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use nipart::{
+    BridgePortTrunkTag, BridgePortVlanConfig, BridgePortVlanMode, ErrorKind,
+    Interface, InterfaceType, MergedInterface, MergedInterfaces,
+    MergedNetworkState, NipartApplyOption, NipartError, OvsBridgeBondConfig,
+    OvsBridgeBondMode, OvsBridgeInterface, OvsBridgeOptions,
+    OvsBridgePortConfig, OvsDbGlobalConfig, OvsDbIfaceConfig, OvsInterface,
+};
+use serde_json::Value;
+
+use crate::db::{
+    gen_named_uuid_value, gen_set_value, gen_str_map_value, gen_uuid_value,
+    OvsDbCondition, OvsDbConnection, OvsDbOperation, GLOBAL_CONFIG_TABLE,
+};
+
+pub(crate) async fn ovs_apply(
+    merged_state: MergedNetworkState,
+    _opt: NipartApplyOption,
+) -> Result<(), NipartError> {
+    tokio::task::spawn_blocking(move || ovsdb_apply(&merged_state))
+        .await
+        .map_err(|e| {
+            NipartError::new(
+                ErrorKind::Bug,
+                format!("Failed to wait OVSDB apply thread: {e}"),
+            )
+        })?
+}
+
+fn ovsdb_apply(merged_state: &MergedNetworkState) -> Result<(), NipartError> {
+    let changed_ifaces: Vec<&MergedInterface> = merged_state
+        .interfaces
+        .iter()
+        .filter(|i| {
+            i.is_changed()
+                && matches!(
+                    i.merged.iface_type(),
+                    InterfaceType::OvsBridge | InterfaceType::OvsInterface
+                )
+        })
+        .collect();
+    let global_conf = merged_state.get_desired_ovsdb_global_conf();
+
+    if changed_ifaces.is_empty() && global_conf.is_none() {
+        log::debug!("No OVS interface or OVSDB global config changes");
+        return Ok(());
+    }
+
+    let mut cli = OvsDbConnection::new()?;
+    let mut txn = OvsDbTransaction::new(&mut cli)?;
+
+    let mut changed_brs: Vec<&str> = Vec::new();
+    for merged_iface in changed_ifaces
+        .iter()
+        .filter(|i| i.merged.iface_type() == InterfaceType::OvsBridge)
+    {
+        if let Interface::OvsBridge(br_iface) = &merged_iface.merged {
+            if merged_iface.merged.is_absent() {
+                txn.del_bridge(br_iface.base.name.as_str());
+            } else {
+                txn.add_or_update_bridge(br_iface, &merged_state.interfaces);
+            }
+            changed_brs.push(br_iface.base.name.as_str());
+        }
+    }
+
+    // OVS interfaces attached to changed bridges are handled by above
+    // bridge change, here we only update the standalone interface change.
+    // The absent interface is always detached from OVSDB as its controller
+    // bridge might be unchanged.
+    for merged_iface in changed_ifaces
+        .iter()
+        .filter(|i| i.merged.iface_type() == InterfaceType::OvsInterface)
+    {
+        if let Interface::OvsInterface(ovs_iface) = &merged_iface.merged {
+            if merged_iface.merged.is_absent() {
+                txn.del_iface(ovs_iface.base.name.as_str());
+                continue;
+            }
+            if ovs_iface
+                .base
+                .controller
+                .as_deref()
+                .map(|c| changed_brs.contains(&c))
+                .unwrap_or_default()
+            {
+                continue;
+            }
+            txn.update_iface(&merged_iface.merged);
+        }
+    }
+
+    // The OVN bridge mappings are stored in global `external_ids`, update
+    // them in the same transaction of the OVS bridges they are pointing to.
+    if let Some(global_conf) = global_conf.as_ref() {
+        txn.update_global_conf(global_conf);
+    }
+
+    log::debug!("Sending OVSDB transaction {:?}", txn.operations);
+    cli.transact(&txn.operations)
+}
+
+// Holding the OVSDB operations of single transaction along with the UUIDs of
+// existing Bridge, Port and Interface rows indexed by name.
+#[derive(Debug, Default)]
+struct OvsDbTransaction {
+    bridges: HashMap<String, String>,
+    ports: HashMap<String, String>,
+    ifaces: HashMap<String, String>,
+    // Port UUIDs indexed by Bridge UUID
+    bridge_ports: HashMap<String, Vec<String>>,
+    // Interface UUIDs indexed by Port UUID
+    port_ifaces: HashMap<String, Vec<String>>,
+    global_conf: OvsDbGlobalConfig,
+    operations: Vec<OvsDbOperation>,
+    named_uuid_count: u32,
+}
+
+impl OvsDbTransaction {
+    fn new(cli: &mut OvsDbConnection) -> Result<Self, NipartError> {
+        let mut ret = Self::default();
+        for (uuid, entry) in cli.get_ovs_bridges()? {
+            ret.bridge_ports.insert(uuid.clone(), entry.ports);
+            ret.bridges.insert(entry.name, uuid);
+        }
+        for (uuid, entry) in cli.get_ovs_ports()? {
+            // The `ports` of Port entry holds its Interface UUIDs
+            ret.port_ifaces.insert(uuid.clone(), entry.ports);
+            ret.ports.insert(entry.name, uuid);
+        }
+        for (uuid, entry) in cli.get_ovs_ifaces()? {
+            ret.ifaces.insert(entry.name, uuid);
+        }
+        ret.global_conf = cli.get_ovsdb_global_conf()?;
+        Ok(ret)
+    }
+
+    fn gen_named_uuid(&mut self, prefix: &str) -> String {
+        self.named_uuid_count += 1;
+        format!("nipart_{prefix}_{}", self.named_uuid_count)
+    }
+
+    // Update the row if found by name, or else insert new one.
+    // Return the UUID value for referring this row.
+    fn add_or_update_row(
+        &mut self,
+        table: &str,
+        name: &str,
+        mut row: HashMap<String, Value>,
+    ) -> Value {
+        let existing_uuid = match table {
+            "Bridge" => self.bridges.get(name),
+            "Port" => self.ports.get(name),
+            _ => self.ifaces.get(name),
+        }
+        .cloned();
+        if let Some(uuid) = existing_uuid {
+            self.operations.push(OvsDbOperation::Update {
+                table: table.to_string(),
+                conditions: vec![OvsDbCondition::uuid_eq(uuid.as_str())],
+                row,
+            });
+            gen_uuid_value(uuid.as_str())
+        } else {
+            row.insert("name".to_string(), Value::String(name.to_string()));
+            let uuid_name = self.gen_named_uuid(&table.to_lowercase());
+            self.operations.push(OvsDbOperation::Insert {
+                table: table.to_string(),
+                uuid_name: uuid_name.clone(),
+                row,
+            });
+            gen_named_uuid_value(uuid_name.as_str())
+        }
+    }
+
+    // The Port and Interface rows are garbage collected by OVSDB once
+    // no longer referred by any bridge.
+    fn del_bridge(&mut self, br_name: &str) {
+        if let Some(uuid) = self.bridges.get(br_name) {
+            log::debug!("Deleting OVS bridge {br_name}");
+            self.operations.push(OvsDbOperation::Mutate {
+                table: GLOBAL_CONFIG_TABLE.to_string(),
+                conditions: Vec::new(),
+                mutations: vec![(
+                    "bridges".to_string(),
+                    "delete".to_string(),
+                    gen_set_value(vec![gen_uuid_value(uuid)]),
+                )],
+            });
+        } else {
+            log::debug!("OVS bridge {br_name} does not exist in OVSDB");
+        }
+    }
+
+    // Only touch the changed keys of `external_ids` and `other_config`, so
+    // keys modified by others (e.g. ovn-controller) since we queried OVSDB
+    // are not overridden.
+    fn update_global_conf(&mut self, global_conf: &OvsDbGlobalConfig) {
+        let mut mutations = Vec::new();
+        if let Some(eids) = global_conf.external_ids.as_ref() {
+            mutations.extend(gen_str_map_mutations(
+                "external_ids",
+                self.global_conf.external_ids.as_ref(),
+                eids,
+            ));
+        }
+        if let Some(cfgs) = global_conf.other_config.as_ref() {
+            mutations.extend(gen_str_map_mutations(
+                "other_config",
+                self.global_conf.other_config.as_ref(),
+                cfgs,
+            ));
+        }
+        if mutations.is_empty() {
+            log::debug!("No OVSDB global config changes");
+            return;
+        }
+        log::debug!("Updating OVSDB global config {global_conf:?}");
+        self.operations.push(OvsDbOperation::Mutate {
+            table: GLOBAL_CONFIG_TABLE.to_string(),
+            conditions: Vec::new(),
+            mutations,
+        });
+    }
+
+    // Remove the Port from its bridge if this interface is the only one of
+    // the Port, otherwise remove this interface from the bond Port.
+    fn del_iface(&mut self, iface_name: &str) {
+        let iface_uuid = if let Some(u) = self.ifaces.get(iface_name) {
+            u.as_str()
+        } else {
+            log::debug!("OVS interface {iface_name} does not exist in OVSDB");
+            return;
+        };
+        let (port_uuid, port_ifaces) = if let Some((u, i)) = self
+            .port_ifaces
+            .iter()
+            .find(|(_, ifaces)| ifaces.iter().any(|i| i == iface_uuid))
+        {
+            (u.as_str(), i)
+        } else {
+            log::debug!(
+                "OVS interface {iface_name} is not attached to any OVS port"
+            );
+            return;
+        };
+        if port_ifaces.len() > 1 {
+            log::debug!("Detaching OVS interface {iface_name} from bond");
+            self.operations.push(OvsDbOperation::Mutate {
+                table: "Port".to_string(),
+                conditions: vec![OvsDbCondition::uuid_eq(port_uuid)],
+                mutations: vec![(
+                    "interfaces".to_string(),
+                    "delete".to_string(),
+                    gen_set_value(vec![gen_uuid_value(iface_uuid)]),
+                )],
+            });
+        } else if let Some(br_uuid) = self
+            .bridge_ports
+            .iter()
+            .find(|(_, ports)| ports.iter().any(|p| p == port_uuid))
+            .map(|(u, _)| u.as_str())
+        {
+            log::debug!("Deleting OVS interface {iface_name}");
+            self.operations.push(OvsDbOperation::Mutate {
+                table: "Bridge".to_string(),
+                conditions: vec![OvsDbCondition::uuid_eq(br_uuid)],
+                mutations: vec![(
+                    "ports".to_string(),
+                    "delete".to_string(),
+                    gen_set_value(vec![gen_uuid_value(port_uuid)]),
+                )],
+            });
+        }
+    }
+
+    fn add_or_update_bridge(
+        &mut self,
+        br_iface: &OvsBridgeInterface,
+        merged_ifaces: &MergedInterfaces,
+    ) {
+        let br_name = br_iface.base.name.as_str();
+        let is_new = !self.bridges.contains_key(br_name);
+        log::debug!(
+            "{} OVS bridge {br_name}",
+            if is_new { "Creating" } else { "Updating" }
+        );
+
+        let mut port_uuids = Vec::new();
+        if let Some(port_confs) =
+            br_iface.bridge.as_ref().and_then(|b| b.ports.as_ref())
+        {
+            for port_conf in port_confs {
+                port_uuids
+                    .push(self.add_or_update_port(port_conf, merged_ifaces));
+            }
+        }
+
+        let mut row = HashMap::new();
+        row.insert("ports".to_string(), gen_set_value(port_uuids));
+        if let Some(opts) =
+            br_iface.bridge.as_ref().and_then(|b| b.options.as_ref())
+        {
+            insert_bridge_options(&mut row, opts);
+        }
+        insert_ovsdb_iface_conf(&mut row, br_iface.base.ovsdb.as_ref());
+
+        let br_uuid = self.add_or_update_row("Bridge", br_name, row);
+        if is_new {
+            self.operations.push(OvsDbOperation::Mutate {
+                table: GLOBAL_CONFIG_TABLE.to_string(),
+                conditions: Vec::new(),
+                mutations: vec![(
+                    "bridges".to_string(),
+                    "insert".to_string(),
+                    gen_set_value(vec![br_uuid]),
+                )],
+            });
+        }
+    }
+
+    fn add_or_update_port(
+        &mut self,
+        port_conf: &OvsBridgePortConfig,
+        merged_ifaces: &MergedInterfaces,
+    ) -> Value {
+        let iface_names: Vec<&str> = match port_conf.bond.as_ref() {
+            Some(bond_conf) => bond_conf.ports(),
+            None => vec![port_conf.name.as_str()],
+        };
+        let mut iface_uuids = Vec::new();
+        for iface_name in iface_names {
+            let row = match merged_ifaces
+                .kernel_ifaces
+                .get(iface_name)
+                .map(|i| &i.merged)
+            {
+                Some(iface) => gen_iface_row(iface),
+                // Port without interface defined is treated as system
+                // interface.
+                None => {
+                    let mut row = HashMap::new();
+                    row.insert(
+                        "type".to_string(),
+                        Value::String(String::new()),
+                    );
+                    row
+                }
+            };
+            iface_uuids.push(self.add_or_update_row(
+                "Interface",
+                iface_name,
+                row,
+            ));
+        }
+
+        let mut row = HashMap::new();
+        row.insert("interfaces".to_string(), gen_set_value(iface_uuids));
+        insert_port_vlan_conf(&mut row, port_conf.vlan.as_ref());
+        insert_port_bond_conf(&mut row, port_conf.bond.as_ref());
+        self.add_or_update_row("Port", port_conf.name.as_str(), row)
+    }
+
+    fn update_iface(&mut self, iface: &Interface) {
+        if self.ifaces.contains_key(iface.name()) {
+            log::debug!("Updating OVS interface {}", iface.name());
+            self.add_or_update_row(
+                "Interface",
+                iface.name(),
+                gen_iface_row(iface),
+            );
+        } else {
+            log::warn!(
+                "Ignoring OVS interface {} which is not attached to any \
+                OVS bridge",
+                iface.name()
+            );
+        }
+    }
+}
+
+fn gen_iface_row(iface: &Interface) -> HashMap<String, Value> {
+    let mut row = HashMap::new();
+    if let Interface::OvsInterface(ovs_iface) = iface {
+        insert_ovs_iface_type_conf(&mut row, ovs_iface);
+    } else {
+        row.insert("type".to_string(), Value::String(String::new()));
+    }
+    insert_ovsdb_iface_conf(&mut row, iface.base_iface().ovsdb.as_ref());
+    row
+}
+
+fn insert_ovs_iface_type_conf(
+    row: &mut HashMap<String, Value>,
+    ovs_iface: &OvsInterface,
+) {
+    let mut options: HashMap<String, Option<String>> = HashMap::new();
+    let iface_type = if let Some(patch_conf) = ovs_iface.patch.as_ref() {
+        options.insert("peer".to_string(), Some(patch_conf.peer.clone()));
+        "patch"
+    } else if let Some(dpdk_conf) = ovs_iface.dpdk.as_ref() {
+        options.insert(
+            "dpdk-devargs".to_string(),
+            Some(dpdk_conf.devargs.clone()),
+        );
+        // Setting to 0 means remove this setting from OVS database.
+        for (key, value) in [
+            ("n_rxq", dpdk_conf.rx_queue),
+            ("n_rxq_desc", dpdk_conf.n_rxq_desc),
+            ("n_txq_desc", dpdk_conf.n_txq_desc),
+        ] {
+            if let Some(v) = value.filter(|v| *v != 0) {
+                options.insert(key.to_string(), Some(v.to_string()));
+            }
+        }
+        // DPDK interface does not have kernel representative, the MTU is
+        // set in ovsdb.
+        if let Some(mtu) = ovs_iface.base.mtu {
+            row.insert("mtu_request".to_string(), Value::from(mtu));
+        }
+        "dpdk"
+    } else {
+        "internal"
+    };
+    row.insert("type".to_string(), Value::String(iface_type.to_string()));
+    row.insert("options".to_string(), gen_str_map_value(&options));
+}
+
+// When `external_ids` or `other_config` not defined, we preserve current
+// value in OVSDB.
+fn insert_ovsdb_iface_conf(
+    row: &mut HashMap<String, Value>,
+    conf: Option<&OvsDbIfaceConfig>,
+) {
+    if let Some(conf) = conf {
+        if let Some(external_ids) = conf.external_ids.as_ref() {
+            row.insert(
+                "external_ids".to_string(),
+                gen_str_map_value(external_ids),
+            );
+        }
+        if let Some(other_config) = conf.other_config.as_ref() {
+            row.insert(
+                "other_config".to_string(),
+                gen_str_map_value(other_config),
+            );
+        }
+    }
+}
+
+fn insert_bridge_options(
+    row: &mut HashMap<String, Value>,
+    opts: &OvsBridgeOptions,
+) {
+    if let Some(enabled) = opts.stp.as_ref().and_then(|s| s.enabled) {
+        row.insert("stp_enable".to_string(), Value::Bool(enabled));
+    }
+    if let Some(rstp) = opts.rstp {
+        row.insert("rstp_enable".to_string(), Value::Bool(rstp));
+    }
+    if let Some(mcast_snooping_enable) = opts.mcast_snooping_enable {
+        row.insert(
+            "mcast_snooping_enable".to_string(),
+            Value::Bool(mcast_snooping_enable),
+        );
+    }
+    if let Some(fail_mode) = opts.fail_mode.as_deref() {
+        row.insert(
+            "fail_mode".to_string(),
+            if fail_mode.is_empty() {
+                gen_set_value(Vec::new())
+            } else {
+                Value::String(fail_mode.to_string())
+            },
+        );
+    }
+    if let Some(datapath) = opts.datapath.as_deref() {
+        row.insert(
+            "datapath_type".to_string(),
+            Value::String(datapath.to_string()),
+        );
+    }
+}
+
+fn insert_port_vlan_conf(
+    row: &mut HashMap<String, Value>,
+    vlan_conf: Option<&BridgePortVlanConfig>,
+) {
+    let mut vlan_mode = gen_set_value(Vec::new());
+    let mut tag = gen_set_value(Vec::new());
+    let mut trunks = Vec::new();
+    if let Some(vlan_conf) = vlan_conf {
+        match vlan_conf.mode {
+            Some(BridgePortVlanMode::Access) => {
+                vlan_mode = Value::String("access".to_string());
+                if let Some(t) = vlan_conf.tag.filter(|t| *t != 0) {
+                    tag = Value::from(t);
+                }
+            }
+            Some(BridgePortVlanMode::Trunk) => {
+                vlan_mode = Value::String("trunk".to_string());
+                if vlan_conf.enable_native == Some(true) {
+                    if let Some(t) = vlan_conf.tag.filter(|t| *t != 0) {
+                        tag = Value::from(t);
+                    }
+                }
+                for trunk_tag in
+                    vlan_conf.trunk_tags.as_deref().unwrap_or_default()
+                {
+                    match trunk_tag {
+                        BridgePortTrunkTag::Id(id) => {
+                            trunks.push(Value::from(*id))
+                        }
+                        BridgePortTrunkTag::IdRange(range) => trunks
+                            .extend((range.min..=range.max).map(Value::from)),
+                        _ => {
+                            log::warn!(
+                                "Ignoring unsupported OVS trunk tag \
+                                {trunk_tag:?}"
+                            );
+                        }
+                    }
+                }
+            }
+            Some(mode) => {
+                log::warn!("Ignoring unsupported OVS VLAN mode {mode:?}");
+            }
+            None => (),
+        }
+    }
+    row.insert("vlan_mode".to_string(), vlan_mode);
+    row.insert("tag".to_string(), tag);
+    row.insert("trunks".to_string(), gen_set_value(trunks));
+}
+
+fn insert_port_bond_conf(
+    row: &mut HashMap<String, Value>,
+    bond_conf: Option<&OvsBridgeBondConfig>,
+) {
+    let mut bond_mode = gen_set_value(Vec::new());
+    let mut lacp = gen_set_value(Vec::new());
+    if let Some(bond_conf) = bond_conf {
+        match bond_conf.mode.as_ref() {
+            Some(OvsBridgeBondMode::Lacp) => {
+                lacp = Value::String("active".to_string());
+            }
+            Some(mode) => {
+                bond_mode = Value::String(mode.to_string());
+            }
+            None => (),
+        }
+        row.insert(
+            "bond_updelay".to_string(),
+            Value::from(bond_conf.bond_updelay.unwrap_or_default()),
+        );
+        row.insert(
+            "bond_downdelay".to_string(),
+            Value::from(bond_conf.bond_downdelay.unwrap_or_default()),
+        );
+        insert_ovsdb_iface_conf(row, bond_conf.ovsdb.as_ref());
+    }
+    row.insert("bond_mode".to_string(), bond_mode);
+    row.insert("lacp".to_string(), lacp);
+}
+
+// OVSDB `insert` mutator does not override existing key, hence delete the
+// removed or changed keys first and then insert the new values.
+fn gen_str_map_mutations(
+    column: &str,
+    current: Option<&HashMap<String, Option<String>>>,
+    merged: &HashMap<String, Option<String>>,
+) -> Vec<(String, String, Value)> {
+    let empty_map = HashMap::new();
+    let current = current.unwrap_or(&empty_map);
+    let mut ret = Vec::new();
+
+    let mut del_keys: Vec<&str> = current
+        .iter()
+        .filter(|(k, v)| v.is_some() && merged.get(*k) != Some(*v))
+        .map(|(k, _)| k.as_str())
+        .collect();
+    del_keys.sort_unstable();
+    if !del_keys.is_empty() {
+        ret.push((
+            column.to_string(),
+            "delete".to_string(),
+            gen_set_value(
+                del_keys
+                    .into_iter()
+                    .map(|k| Value::String(k.to_string()))
+                    .collect(),
+            ),
+        ));
+    }
+
+    let new_pairs: HashMap<String, Option<String>> = merged
+        .iter()
+        .filter(|(k, v)| v.is_some() && current.get(*k) != Some(*v))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    if !new_pairs.is_empty() {
+        ret.push((
+            column.to_string(),
+            "insert".to_string(),
+            gen_str_map_value(&new_pairs),
+        ));
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use nipart::NetworkState;
+
+    use super::*;
+    use crate::show::ovsdb_retrieve;
+
+    const TEST_BRIDGE: &str = "nipart-br0";
+
+    fn apply_json(desired: &str) {
+        let desired = NetworkState::new_from_json(desired).unwrap();
+        let current = ovsdb_retrieve().unwrap();
+        let merged_state =
+            MergedNetworkState::new(desired, current, false, false).unwrap();
+        ovsdb_apply(&merged_state).unwrap();
+    }
+
+    fn get_bridge_ports() -> Option<Vec<String>> {
+        let state = ovsdb_retrieve().unwrap();
+        if let Some(Interface::OvsBridge(br_iface)) = state
+            .interfaces
+            .get_iface(TEST_BRIDGE, InterfaceType::OvsBridge)
+        {
+            let mut ports: Vec<String> = br_iface
+                .ports()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|p| p.to_string())
+                .collect();
+            ports.sort_unstable();
+            Some(ports)
+        } else {
+            None
+        }
+    }
+
+    fn gen_map(pairs: &[(&str, &str)]) -> HashMap<String, Option<String>> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), Some(v.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn test_gen_str_map_mutations_only_changed_keys() {
+        let current = gen_map(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let merged = gen_map(&[("a", "1"), ("b", "4"), ("d", "5")]);
+
+        let mutations =
+            gen_str_map_mutations("external_ids", Some(&current), &merged);
+
+        assert_eq!(
+            mutations,
+            vec![
+                (
+                    "external_ids".to_string(),
+                    "delete".to_string(),
+                    serde_json::json!(["set", ["b", "c"]]),
+                ),
+                (
+                    "external_ids".to_string(),
+                    "insert".to_string(),
+                    serde_json::json!(["map", [["b", "4"], ["d", "5"]]]),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gen_str_map_mutations_no_change() {
+        let current = gen_map(&[("a", "1")]);
+
+        assert!(gen_str_map_mutations(
+            "other_config",
+            Some(&current),
+            &current
+        )
+        .is_empty());
+    }
+
+    #[test]
+    #[ignore = "requires ovsdb-server pointed by OVS_DB_UNIX_SOCKET_PATH"]
+    fn test_ovsdb_apply_absent_ovs_iface_of_unchanged_bridge() {
+        apply_json(
+            r#"{
+              "interfaces": [
+                {
+                  "name": "nipart-br0",
+                  "type": "ovs-bridge",
+                  "state": "up",
+                  "bridge": {
+                    "ports": [{"name": "nipart-ovs0"}, {"name": "nipart-ovs1"}]
+                  }
+                },
+                {"name": "nipart-ovs0", "type": "ovs-interface", "state": "up"},
+                {"name": "nipart-ovs1", "type": "ovs-interface", "state": "up"}
+              ]
+            }"#,
+        );
+        assert_eq!(
+            get_bridge_ports(),
+            Some(vec!["nipart-ovs0".to_string(), "nipart-ovs1".to_string()])
+        );
+
+        apply_json(
+            r#"{
+              "interfaces": [
+                {
+                  "name": "nipart-ovs1",
+                  "type": "ovs-interface",
+                  "state": "absent"
+                }
+              ]
+            }"#,
+        );
+        assert_eq!(get_bridge_ports(), Some(vec!["nipart-ovs0".to_string()]));
+
+        apply_json(
+            r#"{
+              "interfaces": [
+                {"name": "nipart-br0", "type": "ovs-bridge", "state": "absent"}
+              ]
+            }"#,
+        );
+        assert_eq!(get_bridge_ports(), None);
+    }
+}
@@ -0,0 +1,563 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use nipart::{ErrorKind, NipartError, OvsDbGlobalConfig};
+use serde_json::{Map, Value};
+
+use super::json_rpc::OvsDbJsonRpc;
+
+pub(crate) const OVS_DB_NAME: &str = "Open_vSwitch";
+pub(crate) const GLOBAL_CONFIG_TABLE: &str = "Open_vSwitch";
+const NM_RESERVED_EXTERNAL_ID: &str = "NM.connection.uuid";
+
+const DEFAULT_OVS_DB_SOCKET_PATH: &str = "/run/openvswitch/db.sock";
+// Allowing pointing to ovsdb-server started for testing.
+const OVS_DB_SOCKET_PATH_ENV: &str = "OVS_DB_UNIX_SOCKET_PATH";
+
+pub(crate) fn get_ovsdb_socket_path() -> String {
+    std::env::var(OVS_DB_SOCKET_PATH_ENV)
+        .unwrap_or_else(|_| DEFAULT_OVS_DB_SOCKET_PATH.to_string())
+}
+
+#[derive(Debug)]
+pub(crate) struct OvsDbConnection {
+    rpc: OvsDbJsonRpc,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct OvsDbSelect {
+    table: String,
+    conditions: Vec<OvsDbCondition>,
+    columns: Option<Vec<&'static str>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct OvsDbCondition {
+    column: String,
+    function: String,
+    value: Value,
+}
+
+impl OvsDbCondition {
+    pub(crate) fn uuid_eq(uuid: &str) -> Self {
+        Self {
+            column: "_uuid".to_string(),
+            function: "==".to_string(),
+            value: gen_uuid_value(uuid),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Array(vec![
+            Value::String(self.column.to_string()),
+            Value::String(self.function.to_string()),
+            self.value.clone(),
+        ])
+    }
+}
+
+impl OvsDbSelect {
+    fn to_value(&self) -> Value {
+        let mut ret = Map::new();
+        ret.insert("op".to_string(), Value::String("select".to_string()));
+        ret.insert("table".to_string(), Value::String(self.table.clone()));
+        let condition_values: Vec<Value> =
+            self.conditions.iter().map(|c| c.to_value()).collect();
+        ret.insert("where".to_string(), Value::Array(condition_values));
+        if let Some(columns) = self.columns.as_ref() {
+            ret.insert(
+                "columns".to_string(),
+                Value::Array(
+                    columns
+                        .as_slice()
+                        .iter()
+                        .map(|c| Value::String(c.to_string()))
+                        .collect(),
+                ),
+            );
+        }
+        Value::Object(ret)
+    }
+}
+
+impl OvsDbConnection {
+    pub(crate) fn new() -> Result<Self, NipartError> {
+        Ok(Self {
+            rpc: OvsDbJsonRpc::connect(get_ovsdb_socket_path().as_str())?,
+        })
+    }
+
+    pub(crate) fn check_connection(&mut self) -> bool {
+        if let Ok(reply) = self.rpc.exec("list_dbs", &Value::Array(vec![])) {
+            if let Some(dbs) = reply.as_array() {
+                dbs.iter().any(|db| db.as_str() == Some(OVS_DB_NAME))
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    fn _get_ovs_entry(
+        &mut self,
+        table_name: &str,
+        columns: Vec<&'static str>,
+    ) -> Result<HashMap<String, OvsDbEntry>, NipartError> {
+        let select = OvsDbSelect {
+            table: table_name.to_string(),
+            conditions: vec![],
+            columns: Some(columns),
+        };
+        let mut ret: HashMap<String, OvsDbEntry> = HashMap::new();
+        match self.rpc.exec(
+            "transact",
+            &Value::Array(vec![
+                Value::String(OVS_DB_NAME.to_string()),
+                select.to_value(),
+            ]),
+        )? {
+            Value::Array(reply) => {
+                if let Some(entries) = reply
+                    .first()
+                    .and_then(|v| v.as_object())
+                    .and_then(|v| v.get("rows"))
+                    .and_then(|v| v.as_array())
+                {
+                    for entry in entries {
+                        let ovsdb_entry: OvsDbEntry = entry.try_into()?;
+                        if !ovsdb_entry.uuid.is_empty() {
+                            ret.insert(
+                                ovsdb_entry.uuid.to_string(),
+                                ovsdb_entry,
+                            );
+                        }
+                    }
+                    Ok(ret)
+                } else {
+                    let e = NipartError::new(
+                        ErrorKind::PluginFailure,
+                        format!(
+                            "Invalid reply from OVSDB for querying \
+                            {table_name} table: {reply:?}"
+                        ),
+                    );
+                    log::error!("{}", e);
+                    Err(e)
+                }
+            }
+            reply => {
+                let e = NipartError::new(
+                    ErrorKind::PluginFailure,
+                    format!(
+                        "Invalid reply from OVSDB for querying \
+                        {table_name} table: {reply:?}"
+                    ),
+                );
+                log::error!("{}", e);
+                Err(e)
+            }
+        }
+    }
+
+    pub(crate) fn get_ovs_ifaces(
+        &mut self,
+    ) -> Result<HashMap<String, OvsDbEntry>, NipartError> {
+        self._get_ovs_entry(
+            "Interface",
+            vec![
+                "external_ids",
+                "name",
+                "other_config",
+                "_uuid",
+                "type",
+                "mtu",
+                "options",
+            ],
+        )
+    }
+
+    pub(crate) fn get_ovs_ports(
+        &mut self,
+    ) -> Result<HashMap<String, OvsDbEntry>, NipartError> {
+        self._get_ovs_entry(
+            "Port",
+            vec![
+                "external_ids",
+                "name",
+                "other_config",
+                "_uuid",
+                "interfaces",
+                "vlan_mode",
+                "tag",
+                "trunks",
+                "bond_mode",
+                "bond_updelay",
+                "bond_downdelay",
+                "lacp",
+            ],
+        )
+    }
+
+    pub(crate) fn get_ovs_bridges(
+        &mut self,
+    ) -> Result<HashMap<String, OvsDbEntry>, NipartError> {
+        self._get_ovs_entry(
+            "Bridge",
+            vec![
+                "external_ids",
+                "name",
+                "other_config",
+                "_uuid",
+                "ports",
+                "stp_enable",
+                "rstp_enable",
+                "mcast_snooping_enable",
+                "fail_mode",
+                "datapath_type",
+            ],
+        )
+    }
+
+    pub(crate) fn get_ovsdb_global_conf(
+        &mut self,
+    ) -> Result<OvsDbGlobalConfig, NipartError> {
+        let select = OvsDbSelect {
+            table: GLOBAL_CONFIG_TABLE.to_string(),
+            conditions: vec![],
+            columns: Some(vec!["external_ids", "other_config"]),
+        };
+        match self.rpc.exec(
+            "transact",
+            &Value::Array(vec![
+                Value::String(OVS_DB_NAME.to_string()),
+                select.to_value(),
+            ]),
+        )? {
+            Value::Array(reply) => {
+                if let Some(global_conf) = reply
+                    .first()
+                    .and_then(|v| v.as_object())
+                    .and_then(|v| v.get("rows"))
+                    .and_then(|v| v.as_array())
+                    .and_then(|v| v.first())
+                    .and_then(|v| v.as_object())
+                {
+                    Ok(parse_ovsdb_global_conf(global_conf))
+                } else {
+                    let e = NipartError::new(
+                        ErrorKind::PluginFailure,
+                        format!(
+                            "Invalid reply from OVSDB for querying \
+                            {GLOBAL_CONFIG_TABLE} table: {reply:?}"
+                        ),
+                    );
+                    log::error!("{}", e);
+                    Err(e)
+                }
+            }
+            reply => {
+                let e = NipartError::new(
+                    ErrorKind::PluginFailure,
+                    format!(
+                        "Invalid reply from OVSDB for querying \
+                        {GLOBAL_CONFIG_TABLE} table: {reply:?}"
+                    ),
+                );
+                log::error!("{}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Run all operations in single OVSDB transaction, hence either all
+    /// succeeded or none of them take effect.
+    pub(crate) fn transact(
+        &mut self,
+        operations: &[OvsDbOperation],
+    ) -> Result<(), NipartError> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+        let mut params = vec![Value::String(OVS_DB_NAME.to_string())];
+        params.extend(operations.iter().map(|o| o.to_value()));
+        self.rpc.exec("transact", &Value::Array(params))?;
+        Ok(())
+    }
+}
+
+fn parse_ovsdb_global_conf(m: &Map<String, Value>) -> OvsDbGlobalConfig {
+    let mut ret = OvsDbGlobalConfig::default();
+    if let (Some(Value::Array(ids)), Some(Value::Array(other_cfg))) =
+        (m.get("external_ids"), m.get("other_config"))
+    {
+        ret.external_ids = Some(convert_map(parse_str_map(ids)));
+        ret.other_config = Some(convert_map(parse_str_map(other_cfg)));
+    }
+    ret
+}
+
+// Convert HashMap<String, String> to HashMap<String, Option<String>>
+pub(crate) fn convert_map(
+    mut m: HashMap<String, String>,
+) -> HashMap<String, Option<String>> {
+    let mut ret = HashMap::new();
+    for (k, v) in m.drain() {
+        ret.insert(k, Some(v));
+    }
+    ret
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct OvsDbEntry {
+    pub(crate) uuid: String,
+    pub(crate) name: String,
+    pub(crate) external_ids: HashMap<String, String>,
+    pub(crate) other_config: HashMap<String, String>,
+    pub(crate) ports: Vec<String>,
+    pub(crate) iface_type: String,
+    pub(crate) options: HashMap<String, Value>,
+}
+
+impl TryFrom<&Value> for OvsDbEntry {
+    type Error = NipartError;
+    fn try_from(v: &Value) -> Result<OvsDbEntry, Self::Error> {
+        let e = NipartError::new(
+            ErrorKind::PluginFailure,
+            format!("Failed to parse OVS Entry info from : {v:?}"),
+        );
+        let v = v.clone();
+        let mut ret = OvsDbEntry::default();
+        if let Value::Object(mut v) = v {
+            if let Some(Value::String(n)) = v.remove("name") {
+                ret.name = n;
+                if let Some(Value::Array(uuid)) = v.remove("_uuid") {
+                    if let Some(Value::String(uuid)) = uuid.get(1) {
+                        ret.uuid = uuid.to_string();
+                    }
+                }
+                if let Some(Value::String(iface_type)) = v.remove("type") {
+                    ret.iface_type = iface_type;
+                }
+                if let Some(Value::Array(ids)) = v.remove("external_ids") {
+                    ret.external_ids = parse_str_map(&ids);
+                }
+                if let Some(Value::Array(cfgs)) = v.remove("other_config") {
+                    ret.other_config = parse_str_map(&cfgs);
+                }
+                if let Some(Value::Array(ports)) = v.remove("ports") {
+                    ret.ports = parse_uuid_array(&ports);
+                }
+                if let Some(Value::Array(ports)) = v.remove("interfaces") {
+                    ret.ports = parse_uuid_array(&ports);
+                }
+                for (key, value) in v.iter() {
+                    ret.options.insert(key.to_string(), value.clone());
+                }
+
+                return Ok(ret);
+            }
+        }
+        log::error!("{}", e);
+        Err(e)
+    }
+}
+
+pub(crate) fn parse_str_map(v: &[Value]) -> HashMap<String, String> {
+    let mut ret = HashMap::new();
+    if let Some(Value::String(value_type)) = v.first() {
+        match value_type.as_str() {
+            "map" => {
+                if let Some(ids) = v.get(1).and_then(|i| i.as_array()) {
+                    for kv in ids {
+                        if let Some(kv) = kv.as_array() {
+                            if let (
+                                Some(Value::String(k)),
+                                Some(Value::String(v)),
+                            ) = (kv.first(), kv.get(1))
+                            {
+                                if k == NM_RESERVED_EXTERNAL_ID {
+                                    continue;
+                                }
+                                ret.insert(k.to_string(), v.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            t => {
+                log::warn!("Got unknown value type {t}: {v:?}");
+            }
+        }
+    }
+    ret
+}
+
+pub(crate) fn parse_uuid_array(v: &[Value]) -> Vec<String> {
+    let mut ret = Vec::new();
+    if let Some(Value::String(value_type)) = v.first() {
+        match value_type.as_str() {
+            "set" => {
+                if let Some(vs) = v.get(1).and_then(|i| i.as_array()) {
+                    for v in vs {
+                        if let Some(kv) = v.as_array() {
+                            if let (
+                                Some(Value::String(k)),
+                                Some(Value::String(v)),
+                            ) = (kv.first(), kv.get(1))
+                            {
+                                if k != "uuid" {
+                                    continue;
+                                }
+                                ret.push(v.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            "uuid" => {
+                // Single item
+                if let Some(Value::String(v)) = v.get(1) {
+                    ret.push(v.to_string());
+                }
+            }
+            t => {
+                log::warn!("Got unknown value type {t}: {v:?}");
+            }
+        }
+    }
+    ret
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum OvsDbOperation {
+    Insert {
+        table: String,
+        // The name used by other operations of the same transaction to
+        // refer this new row.
+        uuid_name: String,
+        row: HashMap<String, Value>,
+    },
+    Update {
+        table: String,
+        conditions: Vec<OvsDbCondition>,
+        row: HashMap<String, Value>,
+    },
+    Mutate {
+        table: String,
+        conditions: Vec<OvsDbCondition>,
+        // (column, mutator, value)
+        mutations: Vec<(String, String, Value)>,
+    },
+}
+
+impl OvsDbOperation {
+    fn to_value(&self) -> Value {
+        let mut ret = Map::new();
+        match self {
+            Self::Insert {
+                table,
+                uuid_name,
+                row,
+            } => {
+                ret.insert("op".to_string(), Value::String("insert".into()));
+                ret.insert("table".to_string(), Value::String(table.clone()));
+                ret.insert(
+                    "uuid-name".to_string(),
+                    Value::String(uuid_name.clone()),
+                );
+                ret.insert("row".to_string(), row_to_value(row));
+            }
+            Self::Update {
+                table,
+                conditions,
+                row,
+            } => {
+                ret.insert("op".to_string(), Value::String("update".into()));
+                ret.insert("table".to_string(), Value::String(table.clone()));
+                ret.insert(
+                    "where".to_string(),
+                    conditions_to_value(conditions),
+                );
+                ret.insert("row".to_string(), row_to_value(row));
+            }
+            Self::Mutate {
+                table,
+                conditions,
+                mutations,
+            } => {
+                ret.insert("op".to_string(), Value::String("mutate".into()));
+                ret.insert("table".to_string(), Value::String(table.clone()));
+                ret.insert(
+                    "where".to_string(),
+                    conditions_to_value(conditions),
+                );
+                ret.insert(
+                    "mutations".to_string(),
+                    Value::Array(
+                        mutations
+                            .iter()
+                            .map(|(column, mutator, value)| {
+                                Value::Array(vec![
+                                    Value::String(column.to_string()),
+                                    Value::String(mutator.to_string()),
+                                    value.clone(),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                );
+            }
+        }
+        Value::Object(ret)
+    }
+}
+
+fn row_to_value(row: &HashMap<String, Value>) -> Value {
+    let mut row_map = Map::new();
+    for (k, v) in row.iter() {
+        row_map.insert(k.to_string(), v.clone());
+    }
+    Value::Object(row_map)
+}
+
+fn conditions_to_value(conditions: &[OvsDbCondition]) -> Value {
+    Value::Array(conditions.iter().map(|c| c.to_value()).collect())
+}
+
+pub(crate) fn gen_uuid_value(uuid: &str) -> Value {
+    Value::Array(vec![
+        Value::String("uuid".to_string()),
+        Value::String(uuid.to_string()),
+    ])
+}
+
+pub(crate) fn gen_named_uuid_value(uuid_name: &str) -> Value {
+    Value::Array(vec![
+        Value::String("named-uuid".to_string()),
+        Value::String(uuid_name.to_string()),
+    ])
+}
+
+pub(crate) fn gen_set_value(items: Vec<Value>) -> Value {
+    Value::Array(vec![Value::String("set".to_string()), Value::Array(items)])
+}
+
+pub(crate) fn gen_str_map_value(
+    map: &HashMap<String, Option<String>>,
+) -> Value {
+    let mut items: Vec<Value> = map
+        .iter()
+        .filter_map(|(k, v)| {
+            v.as_ref().map(|v| {
+                Value::Array(vec![
+                    Value::String(k.to_string()),
+                    Value::String(v.to_string()),
+                ])
+            })
+        })
+        .collect();
+    items.sort_unstable_by_key(|i| i.to_string());
+    Value::Array(vec![Value::String("map".to_string()), Value::Array(items)])
+}
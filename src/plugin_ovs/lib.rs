@@ -0,0 +1,10 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod apply;
+mod db;
+mod json_rpc;
+mod monitor;
+mod plugin;
+mod show;
+
+pub use self::plugin::NipartPluginOvs;
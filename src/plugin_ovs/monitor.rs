@@ -0,0 +1,386 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use nipart::{
+    ErrorKind, NipartError, NipartEvent, NipartEventAddress,
+    NipartMonitorEvent, NipartNativePlugin, NipartOvsdbMonitorRule,
+    NipartPluginEvent, NipartUserEvent,
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::sync::mpsc::Sender;
+
+use crate::db::{get_ovsdb_socket_path, GLOBAL_CONFIG_TABLE, OVS_DB_NAME};
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+const MONITOR_REQUEST_ID: u64 = 1;
+
+// Only monitor on configuration columns, the status columns like
+// `statistics` and `link_state` change too often.
+const MONITOR_COLUMNS: [(&str, &[&str]); 4] = [
+    (
+        "Bridge",
+        &[
+            "name",
+            "ports",
+            "external_ids",
+            "other_config",
+            "stp_enable",
+            "rstp_enable",
+            "mcast_snooping_enable",
+            "fail_mode",
+            "datapath_type",
+        ],
+    ),
+    (
+        "Port",
+        &[
+            "name",
+            "interfaces",
+            "external_ids",
+            "other_config",
+            "vlan_mode",
+            "tag",
+            "trunks",
+            "bond_mode",
+            "bond_updelay",
+            "bond_downdelay",
+            "lacp",
+        ],
+    ),
+    (
+        "Interface",
+        &[
+            "name",
+            "type",
+            "options",
+            "mtu_request",
+            "external_ids",
+            "other_config",
+        ],
+    ),
+    (
+        GLOBAL_CONFIG_TABLE,
+        &["bridges", "external_ids", "other_config"],
+    ),
+];
+
+/// Long-lived OVSDB `monitor_cond` subscription running in dedicate thread,
+/// notifying requester of [NipartOvsdbMonitorRule] on OVSDB changes.
+#[derive(Debug)]
+pub(crate) struct OvsDbMonitor {
+    rules: Arc<Mutex<HashSet<NipartOvsdbMonitorRule>>>,
+    to_daemon: Sender<NipartEvent>,
+    started: bool,
+}
+
+impl OvsDbMonitor {
+    pub(crate) fn new(to_daemon: Sender<NipartEvent>) -> Self {
+        Self {
+            rules: Arc::new(Mutex::new(HashSet::new())),
+            to_daemon,
+            started: false,
+        }
+    }
+
+    pub(crate) fn add_rule(
+        &mut self,
+        rule: NipartOvsdbMonitorRule,
+    ) -> Result<(), NipartError> {
+        self.lock_rules()?.insert(rule);
+        if !self.started {
+            let rules = self.rules.clone();
+            let to_daemon = self.to_daemon.clone();
+            std::thread::Builder::new()
+                .name("ovsdb_monitor".to_string())
+                .spawn(move || monitor_thread(rules, to_daemon))
+                .map_err(|e| {
+                    NipartError::new(
+                        ErrorKind::Bug,
+                        format!("Failed to start OVSDB monitor thread: {e}"),
+                    )
+                })?;
+            self.started = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn del_rule(
+        &mut self,
+        rule: &NipartOvsdbMonitorRule,
+    ) -> Result<(), NipartError> {
+        self.lock_rules()?.remove(rule);
+        Ok(())
+    }
+
+    fn lock_rules(
+        &self,
+    ) -> Result<
+        std::sync::MutexGuard<'_, HashSet<NipartOvsdbMonitorRule>>,
+        NipartError,
+    > {
+        self.rules.lock().map_err(|e| {
+            NipartError::new(
+                ErrorKind::Bug,
+                format!("Failed to lock OVSDB monitor rules: {e}"),
+            )
+        })
+    }
+}
+
+// Reconnect when OVSDB is not running or restarted. Since we cannot tell
+// what changed during disconnection, notify global config change upon
+// reconnection.
+fn monitor_thread(
+    rules: Arc<Mutex<HashSet<NipartOvsdbMonitorRule>>>,
+    to_daemon: Sender<NipartEvent>,
+) {
+    let mut is_reconnect = false;
+    loop {
+        match OvsDbMonitorConnection::new() {
+            Ok(mut conn) => {
+                log::debug!("OVSDB monitor connected");
+                if let Err(e) = conn.run(&rules, &to_daemon, is_reconnect) {
+                    log::info!("OVSDB monitor disconnected: {e}");
+                }
+                is_reconnect = true;
+            }
+            Err(e) => {
+                log::debug!("OVSDB monitor failed to connect: {e}");
+            }
+        }
+        std::thread::sleep(RECONNECT_INTERVAL);
+    }
+}
+
+#[derive(Debug)]
+struct OvsDbMonitorConnection {
+    socket: UnixStream,
+    reader: BufReader<UnixStream>,
+    // The `update2` notification of deleted row does not contain column
+    // values, hence we track the names of rows by UUID.
+    names: HashMap<String, String>,
+}
+
+impl OvsDbMonitorConnection {
+    fn new() -> Result<Self, NipartError> {
+        let socket_path = get_ovsdb_socket_path();
+        let socket = UnixStream::connect(&socket_path).map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!("Failed to connect OVSDB socket {socket_path}: {e}"),
+            )
+        })?;
+        let reader = BufReader::new(socket.try_clone().map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!("Failed to clone OVSDB socket {socket_path}: {e}"),
+            )
+        })?);
+        Ok(Self {
+            socket,
+            reader,
+            names: HashMap::new(),
+        })
+    }
+
+    fn run(
+        &mut self,
+        rules: &Mutex<HashSet<NipartOvsdbMonitorRule>>,
+        to_daemon: &Sender<NipartEvent>,
+        is_reconnect: bool,
+    ) -> Result<(), NipartError> {
+        let mut monitor_reqs = Map::new();
+        for (table, columns) in MONITOR_COLUMNS {
+            monitor_reqs.insert(table.to_string(), json!({"columns": columns}));
+        }
+        self.send(&json!({
+            "method": "monitor_cond",
+            "params": [OVS_DB_NAME, Value::Null, monitor_reqs],
+            "id": MONITOR_REQUEST_ID,
+        }))?;
+
+        loop {
+            let msg = self.recv()?;
+            match msg.get("method").and_then(|m| m.as_str()) {
+                Some("echo") => {
+                    let params = msg.get("params").cloned().unwrap_or_default();
+                    let id = msg.get("id").cloned().unwrap_or_default();
+                    self.send(&json!({
+                        "result": params,
+                        "error": Value::Null,
+                        "id": id,
+                    }))?;
+                }
+                Some("update2") => {
+                    if let Some(updates) = msg
+                        .get("params")
+                        .and_then(|p| p.get(1))
+                        .and_then(|u| u.as_object())
+                    {
+                        let events = self.process_table_updates(updates);
+                        notify(rules, to_daemon, events);
+                    }
+                }
+                Some(method) => {
+                    log::debug!("Ignoring OVSDB notification {method}");
+                }
+                None => {
+                    if msg.get("id").and_then(|i| i.as_u64())
+                        == Some(MONITOR_REQUEST_ID)
+                    {
+                        self.process_monitor_reply(&msg)?;
+                        if is_reconnect {
+                            notify(
+                                rules,
+                                to_daemon,
+                                vec![NipartMonitorEvent::OvsGlobalConfigChange],
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, msg: &Value) -> Result<(), NipartError> {
+        let buffer = serde_json::to_string(msg)?;
+        log::trace!("OVSDB monitor: sending {buffer}");
+        self.socket.write_all(buffer.as_bytes()).map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!("OVSDB Socket error: {e}"),
+            )
+        })
+    }
+
+    // Blocking read till got a full JSON message
+    fn recv(&mut self) -> Result<Value, NipartError> {
+        let mut deserializer =
+            serde_json::Deserializer::from_reader(&mut self.reader);
+        let msg = Value::deserialize(&mut deserializer).map_err(|e| {
+            NipartError::new(
+                ErrorKind::PluginFailure,
+                format!("Failed to read OVSDB monitor message: {e}"),
+            )
+        })?;
+        log::trace!("OVSDB monitor: recv {msg}");
+        Ok(msg)
+    }
+
+    fn process_monitor_reply(
+        &mut self,
+        msg: &Value,
+    ) -> Result<(), NipartError> {
+        if let Some(error) = msg.get("error").filter(|e| !e.is_null()) {
+            let e = NipartError::new(
+                ErrorKind::PluginFailure,
+                format!("OVSDB monitor_cond failed: {error}"),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        if let Some(updates) = msg.get("result").and_then(|r| r.as_object()) {
+            // The initial content is not a change
+            self.process_table_updates(updates);
+        }
+        Ok(())
+    }
+
+    fn process_table_updates(
+        &mut self,
+        updates: &Map<String, Value>,
+    ) -> Vec<NipartMonitorEvent> {
+        let mut events: Vec<NipartMonitorEvent> = Vec::new();
+        for (table, rows) in updates.iter() {
+            let rows = if let Some(r) = rows.as_object() {
+                r
+            } else {
+                continue;
+            };
+            for (uuid, row_update) in rows.iter() {
+                let (action, row) = match row_update
+                    .as_object()
+                    .and_then(|r| r.iter().next())
+                {
+                    Some(a) => a,
+                    None => continue,
+                };
+                if let Some(name) = row.get("name").and_then(|n| n.as_str()) {
+                    self.names.insert(uuid.to_string(), name.to_string());
+                }
+                let name = if action == "delete" {
+                    self.names.remove(uuid)
+                } else {
+                    self.names.get(uuid).cloned()
+                };
+                if action == "initial" {
+                    continue;
+                }
+                let event = match (table.as_str(), name) {
+                    ("Bridge", Some(name)) => {
+                        NipartMonitorEvent::OvsBridgeChange(name)
+                    }
+                    ("Port", Some(name)) => {
+                        NipartMonitorEvent::OvsPortChange(name)
+                    }
+                    ("Interface", Some(name)) => {
+                        NipartMonitorEvent::OvsIfaceChange(name)
+                    }
+                    (GLOBAL_CONFIG_TABLE, _) => {
+                        NipartMonitorEvent::OvsGlobalConfigChange
+                    }
+                    _ => {
+                        log::debug!(
+                            "Ignoring OVSDB {action} of {table} row {uuid}"
+                        );
+                        continue;
+                    }
+                };
+                if !events.contains(&event) {
+                    events.push(event);
+                }
+            }
+        }
+        events
+    }
+}
+
+fn notify(
+    rules: &Mutex<HashSet<NipartOvsdbMonitorRule>>,
+    to_daemon: &Sender<NipartEvent>,
+    events: Vec<NipartMonitorEvent>,
+) {
+    let rules: Vec<NipartOvsdbMonitorRule> = match rules.lock() {
+        Ok(r) => r.iter().cloned().collect(),
+        Err(e) => {
+            log::error!("BUG: Failed to lock OVSDB monitor rules: {e}");
+            return;
+        }
+    };
+    for monitor_event in events {
+        log::debug!("OVSDB changed: {monitor_event}");
+        for rule in rules.as_slice() {
+            let mut event = NipartEvent::new(
+                NipartUserEvent::None,
+                NipartPluginEvent::GotMonitorEvent(Box::new(
+                    monitor_event.clone(),
+                )),
+                NipartEventAddress::Unicast(
+                    crate::NipartPluginOvs::PLUGIN_NAME.to_string(),
+                ),
+                rule.requester.clone(),
+                nipart::DEFAULT_TIMEOUT,
+            );
+            event.uuid = rule.uuid;
+            if let Err(e) = to_daemon.blocking_send(event) {
+                log::error!("Failed to send OVSDB monitor event: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use nipart::{
+    ErrorKind, MergedNetworkState, NetworkState, NipartApplyOption,
+    NipartError, NipartEvent, NipartEventAddress, NipartLogLevel,
+    NipartMonitorRule, NipartNativePlugin, NipartPluginEvent, NipartRole,
+    NipartUserEvent, NipartUuid, DEFAULT_TIMEOUT,
+};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::apply::ovs_apply;
+use crate::monitor::OvsDbMonitor;
+use crate::show::{ovsdb_is_running, ovsdb_retrieve};
+
+// Higher than nispor plugin, so OVS information overrides kernel one.
+const STATE_PRIORITY: u32 = 60;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct NipartPluginOvs {
+    log_level: NipartLogLevel,
+    to_daemon: Sender<NipartEvent>,
+    from_daemon: Receiver<NipartEvent>,
+    monitor: OvsDbMonitor,
+}
+
+impl NipartNativePlugin for NipartPluginOvs {
+    const PLUGIN_NAME: &'static str = "ovs";
+
+    fn roles() -> Vec<NipartRole> {
+        vec![NipartRole::QueryAndApply, NipartRole::Ovs]
+    }
+
+    fn recver_from_daemon(&mut self) -> &mut Receiver<NipartEvent> {
+        &mut self.from_daemon
+    }
+
+    fn sender_to_daemon(&self) -> &Sender<NipartEvent> {
+        &self.to_daemon
+    }
+
+    fn get_log_level(&self) -> NipartLogLevel {
+        self.log_level
+    }
+
+    fn set_log_level(&mut self, level: NipartLogLevel) {
+        self.log_level = level;
+    }
+
+    async fn init(
+        log_level: NipartLogLevel,
+        to_daemon: Sender<NipartEvent>,
+        from_daemon: Receiver<NipartEvent>,
+    ) -> Result<Self, NipartError> {
+        Ok(Self {
+            log_level,
+            to_daemon: to_daemon.clone(),
+            from_daemon,
+            monitor: OvsDbMonitor::new(to_daemon),
+        })
+    }
+
+    async fn handle_event(
+        &mut self,
+        event: NipartEvent,
+    ) -> Result<(), NipartError> {
+        match event.plugin {
+            NipartPluginEvent::QueryNetState(_) => {
+                let state = ovs_retrieve().await?;
+                let mut reply = NipartEvent::new(
+                    NipartUserEvent::None,
+                    NipartPluginEvent::QueryNetStateReply(
+                        Box::new(state),
+                        STATE_PRIORITY,
+                    ),
+                    NipartEventAddress::Unicast(Self::PLUGIN_NAME.to_string()),
+                    NipartEventAddress::Commander,
+                    DEFAULT_TIMEOUT,
+                );
+                reply.uuid = event.uuid;
+                self.sender_to_daemon().send(reply).await?;
+                Ok(())
+            }
+            NipartPluginEvent::QueryRelatedNetState(_) => {
+                let state = ovs_retrieve().await?;
+                let mut reply = NipartEvent::new(
+                    event.user.clone(),
+                    NipartPluginEvent::QueryNetStateReply(
+                        Box::new(state),
+                        STATE_PRIORITY,
+                    ),
+                    NipartEventAddress::Unicast(Self::PLUGIN_NAME.to_string()),
+                    NipartEventAddress::Commander,
+                    DEFAULT_TIMEOUT,
+                );
+                reply.uuid = event.uuid;
+                self.sender_to_daemon().send(reply).await?;
+                Ok(())
+            }
+            NipartPluginEvent::ApplyNetState(merged_state, opt) => {
+                // We spawn new thread for apply instead of blocking
+                // here
+                let to_daemon_clone = self.sender_to_daemon().clone();
+                tokio::spawn(async move {
+                    handle_apply(
+                        *merged_state,
+                        opt,
+                        to_daemon_clone,
+                        event.uuid,
+                    )
+                    .await
+                });
+                Ok(())
+            }
+            NipartPluginEvent::RegisterMonitorRule(rule) => {
+                log::trace!("Registering monitor rule {rule:?}");
+                if let NipartMonitorRule::Ovsdb(rule) = *rule {
+                    self.monitor.add_rule(rule)
+                } else {
+                    log::warn!(
+                        "Plugin ovs got unsupported monitor rule {rule}"
+                    );
+                    Ok(())
+                }
+            }
+            NipartPluginEvent::RemoveMonitorRule(rule) => {
+                log::trace!("Removing monitor rule {rule:?}");
+                if let NipartMonitorRule::Ovsdb(rule) = *rule {
+                    self.monitor.del_rule(&rule)
+                } else {
+                    log::warn!(
+                        "Plugin ovs got unsupported monitor rule {rule}"
+                    );
+                    Ok(())
+                }
+            }
+            _ => {
+                log::warn!("Plugin ovs got unknown event {event:?}");
+                Ok(())
+            }
+        }
+    }
+}
+
+// The OVSDB socket is blocking I/O, hence run in dedicate thread.
+// Empty state is returned when OVS daemon is not running.
+async fn ovs_retrieve() -> Result<NetworkState, NipartError> {
+    tokio::task::spawn_blocking(|| {
+        if ovsdb_is_running() {
+            ovsdb_retrieve()
+        } else {
+            log::debug!("OVSDB is not running, skipping OVS query");
+            Ok(NetworkState::new())
+        }
+    })
+    .await
+    .map_err(|e| {
+        NipartError::new(
+            ErrorKind::Bug,
+            format!("Failed to wait OVSDB query thread: {e}"),
+        )
+    })?
+}
+
+async fn handle_apply(
+    merged_state: MergedNetworkState,
+    opt: NipartApplyOption,
+    to_daemon: Sender<NipartEvent>,
+    uuid: NipartUuid,
+) {
+    let mut reply = match ovs_apply(merged_state, opt).await {
+        Ok(()) => NipartEvent::new(
+            NipartUserEvent::None,
+            NipartPluginEvent::ApplyNetStateReply,
+            NipartEventAddress::Unicast(
+                NipartPluginOvs::PLUGIN_NAME.to_string(),
+            ),
+            NipartEventAddress::Commander,
+            DEFAULT_TIMEOUT,
+        ),
+        Err(e) => NipartEvent::new(
+            NipartUserEvent::Error(e),
+            NipartPluginEvent::ApplyNetStateReply,
+            NipartEventAddress::Unicast(
+                NipartPluginOvs::PLUGIN_NAME.to_string(),
+            ),
+            NipartEventAddress::Commander,
+            DEFAULT_TIMEOUT,
+        ),
+    };
+    reply.uuid = uuid;
+    log::trace!("Sending reply {reply:?}");
+    if let Err(e) = to_daemon.send(reply).await {
+        log::error!("Failed to reply {e}")
+    }
+}